@@ -12,6 +12,7 @@ surface-export-title = Surface exportieren
 image-select-title = Bild importieren
 svg-select-title = Vektorgrafik importieren
 
+clone-graph = Duplizieren
 convert-to-graph = Zu Graph konvertieren
 exposed-parameters = Sichtbare Parameter
 exposed-field = Feld
@@ -29,9 +30,12 @@ opacity = Deckkraft
 
 renderer = Renderer
 sample-count = Sampleanzahl
+adaptive-samples = Adaptive Sampleanzahl
 shading-mode = Schattierungsmodus
 pbr = PBR
 matcap = Matcap
+subsurface = Subsurface Scattering
+background-color = Hintergrundfarbe
 tone-map = Tone Mapping Operator
 reinhard = Reinhard
 reinhard-jodie = Reinhard-Jodie
@@ -45,6 +49,8 @@ cube = Würfel
 sphere = Kugel
 cylinder = Zylinder
 infinite-cylinder = Endloser Zylinder
+capsule = Kapsel
+cone = Kegel
 displacement-amount = Displacementstärke
 tex-scale = Texturskalierung
 environment = Umgebungslicht
@@ -55,6 +61,8 @@ hdri-blur = Weichzeichnen
 hdri-rotation = Rotation
 ambient-occlusion-strength = Umgebungsverdeckungsstärke
 fog-strength = Nebelstärke
+sss-color = Streufarbe
+sss-strength = Streustärke
 light = Licht
 light-type = Lichttyp
 point-light = Punktlicht
@@ -211,6 +219,7 @@ displacement = Verschiebung
 metallic = Metallisch
 ambient-occlusion = Umgebungsverdeckung
 alpha = Alpha
+emissive = Emission
 rgb = RGB
 
 srgb = sRGB
@@ -275,3 +284,74 @@ cartesian = Kartesisch
 polar = Polar
 scale-x = Skalierung X
 scale-y = Skalierung Y
+
+gradient-type = Verlaufstyp
+radial = Radial
+angular = Konisch
+direction = Richtung
+repeat = Wiederholung
+
+sample-offset = Abtastversatz
+
+tile-count = Kachelanzahl
+blend-width = Überblendbreite
+
+clip-limit = Begrenzung
+
+red-curve = Rotkurve
+green-curve = Grünkurve
+blue-curve = Blaukurve
+master-curve = Gesamtkurve
+
+seed = Startwert
+
+lacunarity = Lakunarität
+gain = Verstärkung
+
+bias = Versatz
+ray-count = Strahlenanzahl
+max-distance = Maximale Distanz
+
+warp-strength = Warp Stärke
+warp-frequency = Warp Frequenz
+warp-octaves = Warp Oktaven
+offset = Verschiebung
+
+brick-width = Ziegelbreite
+brick-height = Ziegelhöhe
+mortar-width = Fugenbreite
+row-offset = Reihenversatz
+random-color = Zufällige Farbe
+block-size = Blockgröße
+initial-amplitude = Anfangsamplitude
+threshold-mode = Schwellenwertmodus
+single = Einfach
+hysteresis = Hysterese
+low-threshold = Unterer Schwellenwert
+high-threshold = Oberer Schwellenwert
+hdri-background = HDRi Hintergrund
+alpha-channel = Alphakanal
+red = Rot
+green = Grün
+blue = Blau
+zero = Null
+half = Halb
+one = Eins
+curvature-source = Krümmungsquelle
+gap = Abstand
+aspect = Seitenverhältnis
+wireframe-overlay = Drahtgitter-Overlay
+wire-width = Drahtstärke
+capsule-radius = Kapselradius
+capsule-length = Kapsellänge
+cone-height = Kegelhöhe
+cone-angle = Kegelwinkel
+from-color-space = Von Farbraum
+to-color-space = Zu Farbraum
+wave-type = Wellenform
+frequency = Frequenz
+phase = Phase
+sine = Sinus
+triangle = Dreieck
+square = Rechteck
+sawtooth = Sägezahn
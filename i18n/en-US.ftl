@@ -12,6 +12,7 @@ surface-export-title = Export Surface
 image-select-title = Import Image
 svg-select-title = Import SVG
 
+clone-graph = Clone
 convert-to-graph = Convert to Graph
 exposed-parameters = Exposed Parameters
 exposed-field = Field
@@ -29,9 +30,12 @@ opacity = Opacity
 
 renderer = Renderer
 sample-count = Sample Count
+adaptive-samples = Adaptive Samples
 shading-mode = Shading Mode
 pbr = PBR
 matcap = Matcap
+subsurface = Subsurface Scattering
+background-color = Background Color
 tone-map = Tone Mapping Operator
 reinhard = Reinhard
 reinhard-jodie = Reinhard-Jodie
@@ -45,6 +49,8 @@ cube = Cube
 sphere = Sphere
 cylinder = Cylinder
 infinite-cylinder = Infinite Cylinder
+capsule = Capsule
+cone = Cone
 displacement-amount = Displacement Amount
 tex-scale = Texture Scale
 environment = Environment
@@ -55,6 +61,8 @@ hdri-blur = Blur
 hdri-rotation = Rotation
 ambient-occlusion-strength = Ambient Occlusion Strength
 fog-strength = Fog Strength
+sss-color = Scatter Color
+sss-strength = Scatter Strength
 light = Light
 light-type = Light Type
 point-light = Point Light
@@ -213,6 +221,7 @@ displacement = Displacement
 metallic = Metallic
 ambient-occlusion = Ambient Occlusion
 alpha = Alpha
+emissive = Emissive
 rgb = RGB
 
 srgb = sRGB
@@ -277,3 +286,74 @@ cartesian = Cartesian
 polar = Polar
 scale-x = Scale X
 scale-y = Scale Y
+
+gradient-type = Gradient Type
+radial = Radial
+angular = Angular
+direction = Direction
+repeat = Repeat
+
+sample-offset = Sample Offset
+
+tile-count = Tile Count
+blend-width = Blend Width
+
+clip-limit = Clip Limit
+
+red-curve = Red Curve
+green-curve = Green Curve
+blue-curve = Blue Curve
+master-curve = Master Curve
+
+seed = Seed
+
+lacunarity = Lacunarity
+gain = Gain
+
+bias = Bias
+ray-count = Ray Count
+max-distance = Max Distance
+
+warp-strength = Warp Strength
+warp-frequency = Warp Frequency
+warp-octaves = Warp Octaves
+offset = Offset
+
+brick-width = Brick Width
+brick-height = Brick Height
+mortar-width = Mortar Width
+row-offset = Row Offset
+random-color = Random Color
+block-size = Block Size
+initial-amplitude = Initial Amplitude
+threshold-mode = Threshold Mode
+single = Single
+hysteresis = Hysteresis
+low-threshold = Low Threshold
+high-threshold = High Threshold
+hdri-background = HDRi Background
+alpha-channel = Alpha Channel
+red = Red
+green = Green
+blue = Blue
+zero = Zero
+half = Half
+one = One
+curvature-source = Curvature Source
+gap = Gap
+aspect = Aspect
+wireframe-overlay = Wireframe Overlay
+wire-width = Wire Width
+capsule-radius = Capsule Radius
+capsule-length = Capsule Length
+cone-height = Cone Height
+cone-angle = Cone Angle
+from-color-space = From Color Space
+to-color-space = To Color Space
+wave-type = Wave Type
+frequency = Frequency
+phase = Phase
+sine = Sine
+triangle = Triangle
+square = Square
+sawtooth = Sawtooth
@@ -12,6 +12,7 @@ layout(set = 0, binding = 1) uniform Occupancy {
     uint has_metallic;
     uint has_ao;
     uint has_alpha;
+    uint has_emissive;
     uint has_view;
     uint view_type;
 };
@@ -20,6 +21,7 @@ layout(set = 0, binding = 2) uniform Camera {
     vec2 pan;
     float zoom;
     uint channel;
+    uint show_hdri_background;
 };
 layout(set = 0, binding = 3) uniform texture2D t_Displ;
 layout(set = 0, binding = 4) uniform texture2D t_Albedo;
@@ -33,6 +35,7 @@ layout(set = 0, binding = 11) uniform textureCube irradiance_map;
 layout(set = 0, binding = 12) uniform textureCube environment_map;
 layout(set = 0, binding = 13) uniform texture2D brdf_lut;
 layout(set = 0, binding = 14) uniform texture2D matcap;
+layout(set = 0, binding = 15) uniform texture2D t_Emissive;
 
 const float PI = 3.141592654;
 
@@ -43,18 +46,30 @@ const uint CHANNEL_ROUGHNESS = 3;
 const uint CHANNEL_METALLIC = 4;
 const uint CHANNEL_ALPHA = 5;
 const uint CHANNEL_AMBIENTOCCLUSION = 6;
+const uint CHANNEL_EMISSIVE = 7;
 
 const uint VIEWTYPE_GRAYSCALE = 0;
 const uint VIEWTYPE_RGB = 1;
+const uint VIEWTYPE_RGBA = 2;
 
 const float TEX_GRID = 0.01;
 
+// Look up the environment behind the view, treating the viewport as an
+// equirectangular projection of the HDRI.
+vec3 environment_background(vec2 tex_coord) {
+    float phi = (tex_coord.x - 0.5) * 2. * PI;
+    float theta = tex_coord.y * PI;
+    vec3 d = vec3(sin(theta) * sin(phi), cos(theta), - sin(theta) * cos(phi));
+    return texture(samplerCube(irradiance_map, s_Texture), d).rgb;
+}
+
 void main() {
     vec2 uv = v_TexCoord * resolution / resolution.y;
     uv = zoom * uv - pan;
     uv.y *= - 1.0;
 
     vec3 col;
+    float alpha = 1.0;
 
     // Default to showing the view socket if available
     if (has_view != 0) {
@@ -65,6 +80,13 @@ void main() {
             case 1:
                 col = vec3(texture(sampler2D(t_View, s_Texture), uv).rgb);
                 break;
+            case 2: {
+                // Premultiplied, such that the background shows through
+                vec4 rgba = texture(sampler2D(t_View, s_Texture), uv);
+                alpha = clamp(rgba.a, 0., 1.);
+                col = rgba.rgb * alpha;
+                break;
+            }
         }
     } // Otherwise show the selected channel
     else if (channel == CHANNEL_DISPLACEMENT && has_displacement != 0) {
@@ -81,13 +103,23 @@ void main() {
         col = vec3(pow(texture(sampler2D(t_Alpha, s_Texture), uv).r, 2.2));
     } else if (channel == CHANNEL_AMBIENTOCCLUSION && has_ao != 0) {
         col = vec3(pow(texture(sampler2D(t_AO, s_Texture), uv).r, 2.2));
+    } else if (channel == CHANNEL_EMISSIVE && has_emissive != 0) {
+        col = texture(sampler2D(t_Emissive, s_Texture), uv).rgb;
     } else {
         col = vec3(0.,0.,0.);
+        alpha = 0.0;
+    }
+
+    // Composite the (premultiplied) color over the environment
+    if (show_hdri_background != 0) {
+        col += (1. - alpha) * environment_background(v_TexCoord);
+        alpha = 1.0;
     }
 
     float grid = min(sin(fract(uv.x) * PI), sin(fract(uv.y) * PI));
     grid = smoothstep(0.01, 0.0075, grid);
     col = mix(col, vec3(1.), grid);
+    alpha = mix(alpha, 1., grid);
 
-    outColor = vec4(col, 1.0);
+    outColor = vec4(col, alpha);
 }
@@ -11,11 +11,14 @@ const uint OBJECT_TYPE_CUBE = 2;
 const uint OBJECT_TYPE_SPHERE = 3;
 const uint OBJECT_TYPE_CYLINDER = 4;
 const uint OBJECT_TYPE_INFCYLINDER = 5;
+const uint OBJECT_TYPE_CAPSULE = 6;
+const uint OBJECT_TYPE_CONE = 7;
 
 layout(constant_id = 1) const uint SHADING_MODE = 0;
 
 const uint SHADING_MODE_PBR = 0;
 const uint SHADING_MODE_MATCAP = 1;
+const uint SHADING_MODE_SUBSURFACE = 2;
 
 layout(set = 0, binding = 0) uniform sampler s_Texture;
 
@@ -27,13 +30,23 @@ layout(set = 0, binding = 1) uniform Occupancy {
     uint has_metallic;
     uint has_ao;
     uint has_alpha;
+    uint has_emissive;
     uint has_view;
     uint view_type;
 };
 
+#define MAX_LIGHTS 8
+
+struct Light {
+    vec4 position;
+    vec4 color;
+    uint light_type;
+    float strength;
+    float size;
+};
+
 layout(set = 0, binding = 2) uniform Camera {
     vec4 center;
-    vec4 light_pos;
     vec2 resolution;
     float focal_length;
     float aperture_size;
@@ -53,12 +66,24 @@ layout(set = 0, binding = 2) uniform Camera {
     float environment_rotation;
     float ao_strength;
 
-    uint light_type;
-    float light_strength;
-    float light_size;
     float fog_strength;
 
     uint draw_shadow;
+
+    uint light_count;
+    uint wireframe;
+
+    vec3 sss_color;
+    float sss_strength;
+
+    Light lights[MAX_LIGHTS];
+
+    float wire_width;
+
+    float capsule_radius;
+    float capsule_length;
+    float cone_height;
+    float cone_half_angle;
 };
 
 layout(push_constant) uniform constants_t {
@@ -80,6 +105,7 @@ layout(set = 0, binding = 11) uniform textureCube irradiance_map;
 layout(set = 0, binding = 12) uniform textureCube environment_map;
 layout(set = 0, binding = 13) uniform texture2D brdf_lut;
 layout(set = 0, binding = 14) uniform texture2D matcap;
+layout(set = 0, binding = 15) uniform texture2D t_Emissive;
 
 const float PI = 3.141592654;
 
@@ -255,6 +281,29 @@ float triplanar_baked_ao(vec3 p, vec3 n, float lod) {
     return ao_front + ao_side + ao_top;
 }
 
+vec3 emissive(vec2 p, float lod) {
+    if(has_emissive != 0) {
+        return textureLod(sampler2D(t_Emissive, s_Texture), p / tex_scale, lod).rgb;
+    } else {
+        return vec3(0.);
+    }
+}
+
+vec3 triplanar_emissive(vec3 p, vec3 n, float lod) {
+    n = pow(abs(n), vec3(4.0));
+    n = n / (n.x + n.y + n.z);
+
+    vec3 col_front = emissive(-p.xy + 0.5, lod);
+    vec3 col_side = emissive(-p.zy + 0.5, lod);
+    vec3 col_top = emissive(-p.xz + 0.5, lod);
+
+    col_front *= n.b;
+    col_side *= n.r;
+    col_top *= n.g;
+
+    return col_front + col_side + col_top;
+}
+
 float alpha(vec2 p, float lod) {
     if(has_alpha != 0) {
         float alpha = textureLod(sampler2D(t_Alpha, s_Texture), p / tex_scale, lod).x;
@@ -317,6 +366,25 @@ float sdCylinder(vec3 p, vec3 c) {
   return length(p.xz - c.xy) - c.z;
 }
 
+float sdCapsule(vec3 p, vec3 a, vec3 b, float r) {
+    vec3 pa = p - a, ba = b - a;
+    float h = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+    return length(pa - ba * h) - r;
+}
+
+// Cone with its tip at the origin, opening downwards. c is the sine and cosine
+// of the half angle, h the height.
+float sdCone(vec3 p, vec2 c, float h) {
+    vec2 q = h * vec2(c.x / c.y, -1.0);
+    vec2 w = vec2(length(p.xz), p.y);
+    vec2 a = w - q * clamp(dot(w, q) / dot(q, q), 0.0, 1.0);
+    vec2 b = w - q * vec2(clamp(w.x / q.x, 0.0, 1.0), 1.0);
+    float k = sign(q.y);
+    float d = min(dot(a, a), dot(b, b));
+    float s = max(k * (w.x * q.y - w.y * q.x), k * (w.y - q.y));
+    return sqrt(d) * sign(s);
+}
+
 // Special normals function for cube. Used to get proper triplanar projection on
 // undistorted cube.
 vec3 cubeNormal(vec3 p, float s) {
@@ -359,6 +427,14 @@ float sdf(vec3 p, float lod) {
         case OBJECT_TYPE_INFCYLINDER:
             height = heightfield(cylinder_mapping(p), lod) * displacement_amount;
             return sdCylinder(p, vec3(0., 0., 2.)) - height;
+        case OBJECT_TYPE_CAPSULE:
+            height = heightfield(cylinder_mapping(p), lod) * displacement_amount;
+            vec3 capsule_end = vec3(0., capsule_length / 2., 0.);
+            return sdCapsule(p, -capsule_end, capsule_end, capsule_radius) - height;
+        case OBJECT_TYPE_CONE:
+            height = heightfield(cylinder_mapping(p), lod) * displacement_amount;
+            vec2 cone_angle = vec2(sin(cone_half_angle), cos(cone_half_angle));
+            return sdCone(p - vec3(0., cone_height / 2., 0.), cone_angle, cone_height) - height;
     }
 
     return 0.;
@@ -398,6 +474,11 @@ vec2 outer_bound(vec3 ro, vec3 rd, float d) {
             return intsBox(ro, rd, vec3(2., 2. * PI / 3., 2.) + vec3(d));
         case OBJECT_TYPE_INFCYLINDER:
             return intsBox(ro, rd, vec3(2., MAX_DIST, 2.) + vec3(d));
+        case OBJECT_TYPE_CAPSULE:
+            return intsBox(ro, rd, vec3(capsule_radius, capsule_length / 2. + capsule_radius, capsule_radius) + vec3(d));
+        case OBJECT_TYPE_CONE:
+            float cone_radius = cone_height * tan(cone_half_angle);
+            return intsBox(ro, rd, vec3(cone_radius, cone_height / 2., cone_radius) + vec3(d));
     }
 
     return vec2(0.);
@@ -529,23 +610,23 @@ float sun_light(vec3 p, vec3 lightPos, out vec3 direction) {
     return 2.0;
 }
 
-vec3 light(vec3 p, vec3 n, vec3 rd, vec3 f0, float d, vec3 albedo, float metallic, float roughness, vec3 lightColor, vec3 lightPos, float w) {
+vec3 light(vec3 p, vec3 n, vec3 rd, vec3 f0, float d, vec3 albedo, float metallic, float roughness, Light source) {
     rd *= -1;
 
     // Radiance
     vec3 l;
     float dist;
-    if (light_type == LIGHT_TYPE_POINT) {
-        dist = point_light(p, lightPos, l);
-    } else if (light_type == LIGHT_TYPE_SUN) {
-        dist = sun_light(p, lightPos, l);
+    if (source.light_type == LIGHT_TYPE_POINT) {
+        dist = point_light(p, source.position.xyz, l);
+    } else if (source.light_type == LIGHT_TYPE_SUN) {
+        dist = sun_light(p, source.position.xyz, l);
     } else {
         dist = 1.0;
         l = vec3(0., 1., 0.);
     }
     vec3 h = normalize(rd + l);
-    float attenuation = light_strength / (dist * dist);
-    vec3 radiance = lightColor * attenuation;
+    float attenuation = source.strength / (dist * dist);
+    vec3 radiance = source.color.rgb * attenuation;
 
     // Cook-Torrance BRDF
     float ndf = distributionGGX(n, h, roughness);
@@ -566,7 +647,7 @@ vec3 light(vec3 p, vec3 n, vec3 rd, vec3 f0, float d, vec3 albedo, float metalli
     // Shadow
     float shadow;
     if (draw_shadow == 1) {
-        shadow = rayShadowSoft(p, l, w);
+        shadow = rayShadowSoft(p, l, source.size);
     } else {
         shadow = 1.;
     }
@@ -574,6 +655,32 @@ vec3 light(vec3 p, vec3 n, vec3 rd, vec3 f0, float d, vec3 albedo, float metalli
     return (kD * albedo / PI + specular) * radiance * ndotl * shadow;
 }
 
+// Cheap translucency approximation. Light passing through the object is
+// scattered towards the viewer, scaled by how thin the surface is at p.
+const float SSS_DISTORTION = 0.2;
+const float SSS_POWER = 4.0;
+const float SSS_AMBIENT = 0.05;
+
+vec3 translucency(vec3 p, vec3 n, vec3 rd, float thinness, vec3 albedo, Light source) {
+    vec3 l;
+    float dist;
+    if (source.light_type == LIGHT_TYPE_POINT) {
+        dist = point_light(p, source.position.xyz, l);
+    } else if (source.light_type == LIGHT_TYPE_SUN) {
+        dist = sun_light(p, source.position.xyz, l);
+    } else {
+        dist = 1.0;
+        l = vec3(0., 1., 0.);
+    }
+    float attenuation = source.strength / (dist * dist);
+
+    vec3 lt = l + n * SSS_DISTORTION;
+    float lt_dot = pow(clamp(dot(rd, lt), 0., 1.), SSS_POWER);
+    float transmission = (lt_dot + SSS_AMBIENT) * thinness * sss_strength;
+
+    return transmission * attenuation * source.color.rgb * sss_color * albedo;
+}
+
 // Scale and bias coordinates, for correct filtered lookup
 vec2 lut_coords_ltc(float cosTheta, float roughness)
 {
@@ -700,6 +807,8 @@ vec3 render_matcap(vec3 ro, vec3 rd, vec3 look_at) {
             break;
         case OBJECT_TYPE_CYLINDER:
         case OBJECT_TYPE_INFCYLINDER:
+        case OBJECT_TYPE_CAPSULE:
+        case OBJECT_TYPE_CONE:
             alpha_ = alpha(cylinder_mapping(p), lod_by_distance(d));
             break;
     }
@@ -724,6 +833,8 @@ vec3 render_matcap(vec3 ro, vec3 rd, vec3 look_at) {
             break;
         case OBJECT_TYPE_CYLINDER:
         case OBJECT_TYPE_INFCYLINDER:
+        case OBJECT_TYPE_CAPSULE:
+        case OBJECT_TYPE_CONE:
             normal_ = normal_map(cylinder_mapping(p), lod_by_distance(d));
             break;
     }
@@ -776,6 +887,8 @@ vec3 render(vec3 ro, vec3 rd) {
             break;
         case OBJECT_TYPE_CYLINDER:
         case OBJECT_TYPE_INFCYLINDER:
+        case OBJECT_TYPE_CAPSULE:
+        case OBJECT_TYPE_CONE:
             alpha_ = alpha(cylinder_mapping(p), lod_by_distance(d));
             break;
     }
@@ -790,6 +903,7 @@ vec3 render(vec3 ro, vec3 rd) {
     float metallic_;
     float roughness_;
     float baked_ao_;
+    vec3 emissive_;
 
     switch (OBJECT_TYPE) {
         case OBJECT_TYPE_PLANE:
@@ -799,6 +913,7 @@ vec3 render(vec3 ro, vec3 rd) {
             roughness_ = roughness(plane_mapping(p), lod_by_distance(d));
             normal_ = normal_map(plane_mapping(p), lod_by_distance(d));
             baked_ao_ = baked_ao(plane_mapping(p), lod_by_distance(d));
+            emissive_ = emissive(plane_mapping(p), lod_by_distance(d));
             break;
         case OBJECT_TYPE_CUBE:
             vec3 nprime = cubeNormal(p, 0.9);
@@ -807,6 +922,7 @@ vec3 render(vec3 ro, vec3 rd) {
             roughness_ = triplanar_roughness(p / 2., nprime, lod_by_distance(d));
             normal_ = triplanar_normal_map(p / 2., nprime, lod_by_distance(d));
             baked_ao_ = triplanar_baked_ao(p / 2., nprime, lod_by_distance(d));
+            emissive_ = triplanar_emissive(p / 2., nprime, lod_by_distance(d));
             break;
         case OBJECT_TYPE_SPHERE:
             albedo_ = albedo(sphere_mapping(p), lod_by_distance(d));
@@ -814,14 +930,18 @@ vec3 render(vec3 ro, vec3 rd) {
             roughness_ = roughness(sphere_mapping(p), lod_by_distance(d));
             normal_ = normal_map(sphere_mapping(p), lod_by_distance(d));
             baked_ao_ = baked_ao(sphere_mapping(p), lod_by_distance(d));
+            emissive_ = emissive(sphere_mapping(p), lod_by_distance(d));
             break;
         case OBJECT_TYPE_CYLINDER:
         case OBJECT_TYPE_INFCYLINDER:
+        case OBJECT_TYPE_CAPSULE:
+        case OBJECT_TYPE_CONE:
             albedo_ = albedo(cylinder_mapping(p), lod_by_distance(d));
             metallic_ = metallic(cylinder_mapping(p), lod_by_distance(d));
             roughness_ = roughness(cylinder_mapping(p), lod_by_distance(d));
             normal_ = normal_map(cylinder_mapping(p), lod_by_distance(d));
             baked_ao_ = baked_ao(cylinder_mapping(p), lod_by_distance(d));
+            emissive_ = emissive(cylinder_mapping(p), lod_by_distance(d));
             break;
     }
 
@@ -831,14 +951,30 @@ vec3 render(vec3 ro, vec3 rd) {
     vec3 f0 = vec3(0.04);
     f0 = mix(f0, albedo_, metallic_);
 
-    if (light_strength > 0.) {
-        col += light(p, n, rd, f0, d, albedo_, metallic_, roughness_, vec3(1.), light_pos.xyz, light_size);
+    for (uint i = 0; i < min(light_count, uint(MAX_LIGHTS)); ++i) {
+        if (lights[i].strength > 0.) {
+            col += light(p, n, rd, f0, d, albedo_, metallic_, roughness_, lights[i]);
+        }
+    }
+
+    // Subsurface Scattering, using the baked AO as a thickness estimate.
+    // Occluded regions are considered thick and transmit less light.
+    if (SHADING_MODE == SHADING_MODE_SUBSURFACE) {
+        float thinness = clamp(baked_ao_, 0., 1.);
+        for (uint i = 0; i < min(light_count, uint(MAX_LIGHTS)); ++i) {
+            if (lights[i].strength > 0.) {
+                col += translucency(p, n, rd, thinness, albedo_, lights[i]);
+            }
+        }
     }
 
     // Ambient Light
     float ao = clamp(pow(baked_ao_, ao_strength * displacement_amount * 10.), 0., 1.);
     col += environment(n, rd, f0, albedo_, roughness_, metallic_, ao);
 
+    // Emission
+    col += emissive_;
+
     // Fog
     col += vec3(0.5,0.5,0.4) * smoothstep(2,20,d) * fog_strength;
 
@@ -851,6 +987,43 @@ vec3 render(vec3 ro, vec3 rd) {
     return col;
 }
 
+// Texture coordinates of a point on the surface of the object. For the cube,
+// the dominant planar projection is used.
+vec2 surface_uv(vec3 p) {
+    switch (OBJECT_TYPE) {
+        case OBJECT_TYPE_PLANE:
+        case OBJECT_TYPE_FINITEPLANE:
+            return plane_mapping(p) / tex_scale;
+        case OBJECT_TYPE_CUBE:
+            vec3 n = abs(cubeNormal(p, 0.9));
+            vec3 q = p / 2.;
+            if (n.z >= n.x && n.z >= n.y) {
+                return (-q.xy + 0.5) / tex_scale;
+            } else if (n.x >= n.y) {
+                return (-q.zy + 0.5) / tex_scale;
+            }
+            return (-q.xz + 0.5) / tex_scale;
+        case OBJECT_TYPE_SPHERE:
+            return sphere_mapping(p) / tex_scale;
+        case OBJECT_TYPE_CYLINDER:
+        case OBJECT_TYPE_INFCYLINDER:
+        case OBJECT_TYPE_CAPSULE:
+        case OBJECT_TYPE_CONE:
+            return cylinder_mapping(p) / tex_scale;
+    }
+
+    return vec2(0.);
+}
+
+// Coverage of grid lines at integer UV boundaries, with lines wire_width
+// pixels wide. Must be called in uniform control flow.
+float wire_grid(vec2 uv) {
+    vec2 w = max(fwidth(uv), vec2(1e-6));
+    vec2 dist = abs(fract(uv - 0.5) - 0.5) / w;
+    float d = min(dist.x, dist.y);
+    return 1. - smoothstep(wire_width * 0.5 - 0.5, wire_width * 0.5 + 0.5, d);
+}
+
 void main() {
     vec2 uv = (v_TexCoord - 0.5) * vec2(resolution.x / resolution.y, 1);
 
@@ -869,6 +1042,7 @@ void main() {
 
     switch (SHADING_MODE) {
         case SHADING_MODE_PBR:
+        case SHADING_MODE_SUBSURFACE:
             col = render(ro, rd);
             break;
         case SHADING_MODE_MATCAP:
@@ -876,5 +1050,13 @@ void main() {
             break;
     }
 
+    // Wireframe Overlay
+    if (wireframe != 0) {
+        float d = rayMarch(ro, rd);
+        bool hit = d != INFINITY;
+        vec2 suv = hit ? surface_uv(ro + rd * d) : vec2(0.);
+        col = mix(col, vec3(1.), wire_grid(suv) * float(hit));
+    }
+
     outColor = vec4(col, 1.0);
 }
@@ -0,0 +1,368 @@
+use crossbeam_channel::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod record;
+
+pub use record::{BrokerPlayback, BrokerRecorder, Record};
+
+/// A type annotated with a name.
+type Named<T> = (&'static str, T);
+
+/// A predicate deciding whether a message is delivered to a subscriber.
+type Filter<T> = Box<dyn Fn(&T) -> bool + Send>;
+
+/// Messages that can replace earlier messages of the same kind, such that the
+/// earlier message need not be delivered anymore.
+pub trait Supersede {
+    /// Returns true if and only if self makes other redundant.
+    fn supersedes(&self, other: &Self) -> bool;
+}
+
+/// What to do when a subscriber's queue reaches its high water mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Block the broker until the subscriber catches up.
+    Block,
+    /// Drop the oldest queued message superseded by the incoming one. Blocks
+    /// if there is no such message.
+    DropOldest,
+}
+
+/// Per subscriber configuration of the broker.
+#[derive(Debug, Clone, Copy)]
+pub struct BrokerConfig {
+    /// Queue length at which the drop policy comes into effect
+    pub high_water: usize,
+    /// Drop policy to apply once the high water mark is reached
+    pub drop_policy: DropPolicy,
+}
+
+impl BrokerConfig {
+    /// Configuration that blocks once the channel capacity is exhausted. This
+    /// is the behaviour of plain subscriptions.
+    pub fn blocking(capacity: usize) -> Self {
+        Self {
+            high_water: capacity,
+            drop_policy: DropPolicy::Block,
+        }
+    }
+}
+
+/// Priority of subscribers that do not request one explicitly. Lower values
+/// denote higher priority, with 1 being the highest.
+pub const DEFAULT_PRIORITY: u8 = u8::MAX;
+
+/// Time after which a subscriber holding on to a full queue without receiving
+/// any message is considered stalled. Only applies to subscribers that may
+/// drop messages, since the broker holds a receiver of their queue and thus
+/// cannot detect disconnection.
+const STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A subscriber as seen by the broker
+struct Subscriber<T> {
+    /// Sender towards the subscriber
+    sender: Sender<Arc<T>>,
+    /// Receiver of the subscriber's queue, used to drop messages. Only held
+    /// for subscribers that may drop messages. Holding it keeps the queue
+    /// connected even if the subscriber is gone, so sends to these
+    /// subscribers time out instead of blocking.
+    queue: Option<Receiver<Arc<T>>>,
+    /// Name of the subscriber
+    name: &'static str,
+    /// Aliveness status
+    alive: Arc<AtomicBool>,
+    /// Backpressure configuration
+    config: BrokerConfig,
+    /// Optional filter, messages not matching it are not delivered
+    filter: Option<Filter<T>>,
+    /// Delivery priority, lower values are delivered to first
+    priority: u8,
+}
+
+impl<T: Supersede> Subscriber<T> {
+    /// Determine whether the subscriber is interested in the message.
+    fn accepts(&self, msg: &T) -> bool {
+        self.filter.as_ref().map(|f| f(msg)).unwrap_or(true)
+    }
+
+    /// Send a message to the subscriber, respecting the drop policy. Fails
+    /// with a timeout if the subscriber has stalled.
+    fn send(&self, msg: Arc<T>) -> Result<(), SendTimeoutError<Arc<T>>> {
+        let queue = match &self.queue {
+            Some(queue) => queue,
+            None => {
+                return self
+                    .sender
+                    .send(msg)
+                    .map_err(|SendError(m)| SendTimeoutError::Disconnected(m))
+            }
+        };
+
+        if self.sender.len() >= self.config.high_water {
+            // Drain the queue, drop the oldest superseded message if any, and
+            // requeue the rest in order.
+            let mut queued: Vec<Arc<T>> = queue.try_iter().collect();
+            if let Some(pos) = queued.iter().position(|q| msg.supersedes(q)) {
+                queued.remove(pos);
+            }
+            for q in queued {
+                self.sender.send_timeout(q, STALL_TIMEOUT)?;
+            }
+        }
+
+        self.sender.send_timeout(msg, STALL_TIMEOUT)
+    }
+}
+
+pub struct Broker<T> {
+    /// Capacity of the broadcast channel
+    capacity: usize,
+
+    /// Sender towards the broker, to be cloned out to subscribers
+    sender: Sender<Named<T>>,
+
+    /// Receiver on the broker side, unique.
+    receiver: Receiver<Named<T>>,
+
+    /// List of subscribers
+    subscribers: Vec<Subscriber<T>>,
+}
+
+/// Named senders, i.e. senders that also attach their name to the message
+pub struct NamedSender<T> {
+    name: &'static str,
+    inner: Sender<Named<T>>,
+}
+
+impl<T> Clone for NamedSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> NamedSender<T> {
+    fn new(name: &'static str, inner: Sender<Named<T>>) -> Self {
+        Self { name, inner }
+    }
+
+    /// Send a message to the broker
+    pub fn send(&self, msg: T) -> Option<()> {
+        self.inner.send((self.name, msg)).ok()
+    }
+
+    /// Send a message to the broker anonymously. This will result in the
+    /// message getting echoed back at the calling thread!
+    pub fn send_anonymous(&self, msg: T) -> Option<()> {
+        self.inner.send(("", msg)).ok()
+    }
+}
+
+/// A type used to send messages to the broker, i.e. to broadcast them
+pub type BrokerSender<T> = NamedSender<T>;
+
+/// A type to read messages from the broker, i.e. from the broadcast channel
+pub type BrokerReceiver<T> = Receiver<Arc<T>>;
+
+/// A receipt attached to a message, allowing the sender to wait until the
+/// message has been handled by its consumer. Dropping a receipt without
+/// acknowledging it also releases the sender, such that messages no
+/// subscriber handles cannot block it.
+#[derive(Debug)]
+pub struct Receipt(Sender<()>);
+
+impl Receipt {
+    /// Create a new receipt, along with the waiter for it to be kept by the
+    /// sender of the message.
+    pub fn new() -> (Self, ReceiptWaiter) {
+        let (s, r) = bounded(1);
+        (Self(s), ReceiptWaiter(r))
+    }
+
+    /// Acknowledge that the message has been handled.
+    pub fn acknowledge(&self) {
+        let _ = self.0.try_send(());
+    }
+}
+
+/// The sending side of a receipt, waiting for acknowledgement.
+#[derive(Debug)]
+pub struct ReceiptWaiter(Receiver<()>);
+
+impl ReceiptWaiter {
+    /// Wait for the message to be acknowledged, or its receipt dropped.
+    pub fn wait(self) {
+        let _ = self.0.recv();
+    }
+}
+
+/// Type to control clean disconnect from broker
+pub struct BrokerDisconnect(Arc<AtomicBool>);
+
+impl BrokerDisconnect {
+    /// Disconnect from the broker
+    pub fn disconnect(self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl<T: Supersede> Broker<T> {
+    /// Create a new Broker with a given capacity.
+    pub fn new(capacity: usize) -> Self {
+        let (s, r) = bounded(capacity);
+
+        Broker {
+            capacity,
+            sender: s,
+            receiver: r,
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Obtain a sender to send towards the broker, i.e. to broadcast messages.
+    fn sender(&self) -> Sender<Named<T>> {
+        self.sender.clone()
+    }
+
+    /// Subscribe to the application bus controlled by the broker, yielding
+    /// sender, receiver, and disconnector.
+    pub fn subscribe(
+        &mut self,
+        name: &'static str,
+    ) -> (BrokerSender<T>, BrokerReceiver<T>, BrokerDisconnect) {
+        self.subscribe_with_config(name, BrokerConfig::blocking(self.capacity))
+    }
+
+    /// Subscribe to the application bus with a given backpressure
+    /// configuration. The high water mark is capped at the broker capacity.
+    pub fn subscribe_with_config(
+        &mut self,
+        name: &'static str,
+        config: BrokerConfig,
+    ) -> (BrokerSender<T>, BrokerReceiver<T>, BrokerDisconnect) {
+        self.add_subscriber(name, config, None, DEFAULT_PRIORITY)
+    }
+
+    /// Subscribe to the application bus with a given priority, where 1 is the
+    /// highest priority. Each message is sent to subscribers with higher
+    /// priority before those with lower priority, and to subscribers of equal
+    /// priority in order of subscription.
+    ///
+    /// This only orders sends into the subscriber channels. Subscribers run
+    /// on their own threads, so there is no guarantee that a higher priority
+    /// subscriber has processed a message before a lower priority one does.
+    pub fn subscribe_priority(
+        &mut self,
+        name: &'static str,
+        priority: u8,
+    ) -> (BrokerSender<T>, BrokerReceiver<T>, BrokerDisconnect) {
+        self.add_subscriber(
+            name,
+            BrokerConfig::blocking(self.capacity),
+            None,
+            priority.max(1),
+        )
+    }
+
+    /// Subscribe to the application bus, receiving only messages for which
+    /// the predicate holds. This avoids waking up subscribers for messages
+    /// they would ignore anyway.
+    pub fn subscribe_filtered<F>(
+        &mut self,
+        name: &'static str,
+        predicate: F,
+    ) -> (BrokerSender<T>, BrokerReceiver<T>, BrokerDisconnect)
+    where
+        F: Fn(&T) -> bool + Send + 'static,
+    {
+        self.add_subscriber(
+            name,
+            BrokerConfig::blocking(self.capacity),
+            Some(Box::new(predicate)),
+            DEFAULT_PRIORITY,
+        )
+    }
+
+    fn add_subscriber(
+        &mut self,
+        name: &'static str,
+        config: BrokerConfig,
+        filter: Option<Filter<T>>,
+        priority: u8,
+    ) -> (BrokerSender<T>, BrokerReceiver<T>, BrokerDisconnect) {
+        let (s, r) = bounded(self.capacity);
+        let alive = Arc::new(AtomicBool::new(true));
+
+        let subscriber = Subscriber {
+            sender: s,
+            queue: match config.drop_policy {
+                DropPolicy::Block => None,
+                DropPolicy::DropOldest => Some(r.clone()),
+            },
+            name,
+            alive: alive.clone(),
+            config: BrokerConfig {
+                high_water: config.high_water.min(self.capacity),
+                ..config
+            },
+            filter,
+            priority,
+        };
+
+        // Keep subscribers ordered by priority, such that delivery follows it
+        let pos = self
+            .subscribers
+            .iter()
+            .position(|sub| sub.priority > priority)
+            .unwrap_or(self.subscribers.len());
+        self.subscribers.insert(pos, subscriber);
+
+        (
+            NamedSender::new(name, self.sender()),
+            r,
+            BrokerDisconnect(alive),
+        )
+    }
+
+    /// Broker loop
+    pub fn run(&mut self) {
+        let mut count: usize = 0;
+        let mut stalled = false;
+        for (origin, ev) in &self.receiver {
+            count += 1;
+
+            // Purge all dead subscribers periodically
+            if count > 1024 || stalled {
+                self.subscribers
+                    .drain_filter(|sub| !sub.alive.load(Ordering::Relaxed));
+                count = 0;
+                stalled = false;
+            }
+
+            // Wrap the event and send to all live subscribers other than origin
+            let arc = Arc::new(ev);
+            for subscriber in self
+                .subscribers
+                .iter()
+                .filter(|x| x.name != origin && x.alive.load(Ordering::Relaxed) && x.accepts(&arc))
+            {
+                match subscriber.send(Arc::clone(&arc)) {
+                    Ok(()) => {}
+                    Err(SendTimeoutError::Timeout(_)) => {
+                        log::error!("Stalled Component: {}, unsubscribing", subscriber.name);
+                        subscriber.alive.store(false, Ordering::Relaxed);
+                        stalled = true;
+                    }
+                    Err(e) => {
+                        // Should only happen in case the disconnector wasn't called
+                        log::error!("Disconnected Component: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
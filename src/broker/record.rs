@@ -0,0 +1,197 @@
+use super::{Broker, BrokerDisconnect, BrokerReceiver, BrokerSender, Supersede};
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Messages that can be recorded to and replayed from a file. Not every
+/// message needs to be recorded. Typically only messages originating outside
+/// the application are, since replaying them reproduces all others.
+pub trait Record: Sized {
+    /// Serializable form of a recorded message
+    type Recorded: Serialize + DeserializeOwned;
+
+    /// Work started by messages, to be awaited at the end of playback
+    type Work: PartialEq;
+
+    /// Obtain the recorded form of this message, or None if it should not be
+    /// recorded.
+    fn record(&self) -> Option<Self::Recorded>;
+
+    /// Reconstruct a message from its recorded form.
+    fn replay(recorded: Self::Recorded) -> Self;
+
+    /// Whether this message ends the recording.
+    fn ends_recording(&self) -> bool;
+
+    /// The work started by this message that must be finished before
+    /// playback may end, e.g. saving a file, if any. Such work must
+    /// eventually be reported as finished by another message.
+    fn starts_work(&self) -> Option<Self::Work>;
+
+    /// The work reported as finished by this message, if any.
+    fn finishes_work(&self) -> Option<Self::Work>;
+
+    /// Message to send once playback has completed, if any.
+    fn end_of_playback() -> Option<Self>;
+}
+
+#[derive(Debug, Error)]
+pub enum RecordError {
+    #[error("Failed to access recording file")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to (de)serialize recorded message")]
+    Serialization(#[from] serde_cbor::Error),
+}
+
+/// A single entry of a recording, holding the time since the start of the
+/// recording in milliseconds and the recorded message.
+type Entry<R> = (u64, R);
+
+/// Maximum time to wait for a single piece of work to finish at the end of
+/// playback, before giving up on it.
+const FINISH_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Records messages on the bus to a file, as a sequence of CBOR values.
+pub struct BrokerRecorder<T> {
+    receiver: BrokerReceiver<T>,
+    disconnector: BrokerDisconnect,
+    writer: BufWriter<File>,
+}
+
+impl<T> BrokerRecorder<T>
+where
+    T: Record + Supersede,
+{
+    /// Create a new recorder writing to the file at the given path,
+    /// subscribing to the broker.
+    pub fn new<P: AsRef<Path>>(broker: &mut Broker<T>, path: P) -> Result<Self, RecordError> {
+        let writer = BufWriter::new(File::create(path)?);
+        let (_, receiver, disconnector) = broker.subscribe("recorder");
+
+        Ok(Self {
+            receiver,
+            disconnector,
+            writer,
+        })
+    }
+
+    /// Record messages until a message ending the recording is encountered
+    /// or the bus is closed.
+    pub fn run(mut self) -> Result<(), RecordError> {
+        let start = Instant::now();
+
+        for msg in &self.receiver {
+            if msg.ends_recording() {
+                break;
+            }
+
+            if let Some(recorded) = msg.record() {
+                let entry: Entry<T::Recorded> = (start.elapsed().as_millis() as u64, recorded);
+                serde_cbor::to_writer(&mut self.writer, &entry)?;
+            }
+        }
+
+        self.writer.flush()?;
+        self.disconnector.disconnect();
+
+        Ok(())
+    }
+}
+
+/// Replays a recording made by a `BrokerRecorder` onto the bus, preserving
+/// the original timing between messages. Playback ends only once all work
+/// started by replayed messages has finished.
+pub struct BrokerPlayback<T: Record> {
+    sender: BrokerSender<T>,
+    receiver: BrokerReceiver<T>,
+    disconnector: BrokerDisconnect,
+    entries: Vec<Entry<T::Recorded>>,
+}
+
+impl<T> BrokerPlayback<T>
+where
+    T: Record + Supersede,
+{
+    /// Load a recording from the given path, subscribing to the broker.
+    pub fn new<P: AsRef<Path>>(broker: &mut Broker<T>, path: P) -> Result<Self, RecordError> {
+        let reader = BufReader::new(File::open(path)?);
+        let entries = serde_cbor::Deserializer::from_reader(reader)
+            .into_iter::<Entry<T::Recorded>>()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Playback only needs to know when work has finished
+        let (sender, receiver, disconnector) =
+            broker.subscribe_filtered("playback", |msg: &T| msg.finishes_work().is_some());
+
+        Ok(Self {
+            sender,
+            receiver,
+            disconnector,
+            entries,
+        })
+    }
+
+    /// Number of messages in the recording.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the recording is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Send all recorded messages to the bus, and wait for all work started
+    /// by them to finish. Finally the end of playback message is sent, if
+    /// any.
+    pub fn run(self) {
+        let start = Instant::now();
+        let mut pending = Vec::new();
+
+        // Finishing work unrelated to playback, e.g. autosaving, is ignored
+        let finish = |pending: &mut Vec<T::Work>, msg: &T| {
+            if let Some(pos) = msg
+                .finishes_work()
+                .and_then(|work| pending.iter().position(|w| w == &work))
+            {
+                pending.remove(pos);
+            }
+        };
+
+        for (time, recorded) in self.entries {
+            let due = Duration::from_millis(time);
+            if let Some(wait) = due.checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+
+            let msg = T::replay(recorded);
+            pending.extend(msg.starts_work());
+            self.sender.send(msg);
+
+            for msg in self.receiver.try_iter() {
+                finish(&mut pending, &msg);
+            }
+        }
+
+        while !pending.is_empty() {
+            match self.receiver.recv_timeout(FINISH_TIMEOUT) {
+                Ok(msg) => finish(&mut pending, &msg),
+                Err(_) => {
+                    log::warn!("Ending playback with {} unfinished tasks", pending.len());
+                    break;
+                }
+            }
+        }
+
+        if let Some(end) = T::end_of_playback() {
+            self.sender.send(end);
+        }
+
+        self.disconnector.disconnect();
+    }
+}
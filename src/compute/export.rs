@@ -1,3 +1,4 @@
+use std::io::{Cursor, Seek, Write};
 use std::path::Path;
 
 use image::Rgb;
@@ -17,6 +18,47 @@ pub enum ExportError {
     UnsupportedBitDepthOrColorSpace,
     #[error("Unable to find compute image for export")]
     UnknownImage,
+    #[error("Packed channels differ in size")]
+    PackSizeMismatch,
+}
+
+/// Reduce raw image data from the GPU to a single value per pixel. Color
+/// images are reduced to their luminance.
+pub fn grayscale_values(raw: &[u8], ty: ImageType) -> Vec<f32> {
+    #[allow(clippy::cast_ptr_alignment)]
+    unsafe {
+        match ty {
+            ImageType::Grayscale => {
+                std::slice::from_raw_parts(raw.as_ptr() as *const f32, raw.len() / 4).to_vec()
+            }
+            ImageType::Rgb | ImageType::Rgba => {
+                std::slice::from_raw_parts(raw.as_ptr() as *const half::f16, raw.len() / 2)
+                    .chunks(4)
+                    .map(|chunk| {
+                        chunk[0].to_f32() * 0.2126
+                            + chunk[1].to_f32() * 0.7152
+                            + chunk[2].to_f32() * 0.0722
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn to_16bit(x: f32) -> u16 {
+    (x.clamp(0., 1.) * 65535.) as u16
+}
+
+fn to_16bit_gamma(x: f32) -> u16 {
+    (x.powf(1.0 / 2.2).clamp(0., 1.) * 65535.) as u16
+}
+
+fn to_8bit(x: f32) -> u8 {
+    (x.clamp(0., 1.) * 256.) as u8
+}
+
+fn to_8bit_gamma(x: f32) -> u8 {
+    (x.powf(1.0 / 2.2).clamp(0., 1.) * 256.) as u8
 }
 
 pub enum ConvertedImage {
@@ -25,6 +67,8 @@ pub enum ConvertedImage {
     R16(u32, Vec<u16>),
     Rgb16(u32, Vec<u16>),
     Rgb32(u32, Vec<Rgb<f32>>),
+    Rgba8(u32, Vec<u8>),
+    Rgba16(u32, Vec<u16>),
 }
 
 impl ConvertedImage {
@@ -38,22 +82,6 @@ impl ConvertedImage {
         bit_depth: u8,
         ty: ImageType,
     ) -> Result<Self, ExportError> {
-        fn to_16bit(x: f32) -> u16 {
-            (x.clamp(0., 1.) * 65535.) as u16
-        }
-
-        fn to_16bit_gamma(x: f32) -> u16 {
-            (x.powf(1.0 / 2.2).clamp(0., 1.) * 65535.) as u16
-        }
-
-        fn to_8bit(x: f32) -> u8 {
-            (x.clamp(0., 1.) * 256.) as u8
-        }
-
-        fn to_8bit_gamma(x: f32) -> u8 {
-            (x.powf(1.0 / 2.2).clamp(0., 1.) * 256.) as u8
-        }
-
         match (bit_depth, color_space, ty) {
             (8, ColorSpace::Linear, ImageType::Grayscale) => {
                 #[allow(clippy::cast_ptr_alignment)]
@@ -163,6 +191,46 @@ impl ConvertedImage {
                 };
                 Ok(ConvertedImage::Rgb16(size, u16s))
             }
+            (8, _, ImageType::Rgba) => {
+                let gamma = color_space == ColorSpace::Srgb;
+                #[allow(clippy::cast_ptr_alignment)]
+                let u8s: Vec<u8> = unsafe {
+                    std::slice::from_raw_parts(raw.as_ptr() as *const half::f16, raw.len() / 2)
+                        .chunks(4)
+                        .map(|chunk| {
+                            let color = if gamma { to_8bit_gamma } else { to_8bit };
+                            vec![
+                                color(chunk[0].to_f32()),
+                                color(chunk[1].to_f32()),
+                                color(chunk[2].to_f32()),
+                                to_8bit(chunk[3].to_f32()),
+                            ]
+                        })
+                        .flatten()
+                        .collect()
+                };
+                Ok(ConvertedImage::Rgba8(size, u8s))
+            }
+            (16, _, ImageType::Rgba) => {
+                let gamma = color_space == ColorSpace::Srgb;
+                #[allow(clippy::cast_ptr_alignment)]
+                let u16s: Vec<u16> = unsafe {
+                    std::slice::from_raw_parts(raw.as_ptr() as *const half::f16, raw.len() / 2)
+                        .chunks(4)
+                        .map(|chunk| {
+                            let color = if gamma { to_16bit_gamma } else { to_16bit };
+                            vec![
+                                color(chunk[0].to_f32()).to_be(),
+                                color(chunk[1].to_f32()).to_be(),
+                                color(chunk[2].to_f32()).to_be(),
+                                to_16bit(chunk[3].to_f32()).to_be(),
+                            ]
+                        })
+                        .flatten()
+                        .collect()
+                };
+                Ok(ConvertedImage::Rgba16(size, u16s))
+            }
             (32, ColorSpace::Linear, ImageType::Grayscale) => {
                 #[allow(clippy::cast_ptr_alignment)]
                 let f32s: Vec<Rgb<f32>> = unsafe {
@@ -173,7 +241,7 @@ impl ConvertedImage {
                 };
                 Ok(ConvertedImage::Rgb32(size, f32s))
             }
-            (32, ColorSpace::Linear, ImageType::Rgb) => {
+            (32, ColorSpace::Linear, ImageType::Rgb | ImageType::Rgba) => {
                 #[allow(clippy::cast_ptr_alignment)]
                 let f32s: Vec<Rgb<f32>> = unsafe {
                     std::slice::from_raw_parts(raw.as_ptr() as *const half::f16, raw.len() / 2)
@@ -187,6 +255,81 @@ impl ConvertedImage {
         }
     }
 
+    /// Pack single channel images into an RGBA image. Missing color channels
+    /// are filled with 0, a missing alpha channel with 1. Gamma correction is
+    /// applied to color channels only.
+    pub fn packed(
+        channels: &[Option<Vec<f32>>; 4],
+        inverted: [bool; 4],
+        size: u32,
+        color_space: ColorSpace,
+        bit_depth: u8,
+    ) -> Result<Self, ExportError> {
+        let pixels = (size * size) as usize;
+        let gamma = |c: usize| color_space == ColorSpace::Srgb && c < 3;
+        let value = |c: usize, i: usize| {
+            let v = channels[c]
+                .as_ref()
+                .map(|data| data[i])
+                .unwrap_or(if c == 3 { 1. } else { 0. });
+            if inverted[c] {
+                1. - v
+            } else {
+                v
+            }
+        };
+
+        if channels.iter().flatten().any(|data| data.len() != pixels) {
+            return Err(ExportError::PackSizeMismatch);
+        }
+
+        match bit_depth {
+            8 => Ok(ConvertedImage::Rgba8(
+                size,
+                (0..pixels)
+                    .flat_map(|i| (0..4).map(move |c| (i, c)))
+                    .map(|(i, c)| {
+                        let color = if gamma(c) { to_8bit_gamma } else { to_8bit };
+                        color(value(c, i))
+                    })
+                    .collect(),
+            )),
+            16 => Ok(ConvertedImage::Rgba16(
+                size,
+                (0..pixels)
+                    .flat_map(|i| (0..4).map(move |c| (i, c)))
+                    .map(|(i, c)| {
+                        let color = if gamma(c) { to_16bit_gamma } else { to_16bit };
+                        color(value(c, i)).to_be()
+                    })
+                    .collect(),
+            )),
+            _ => Err(ExportError::UnsupportedBitDepthOrColorSpace),
+        }
+    }
+
+    /// Convert an 8 bit image to RGBA, for assembly into a texture atlas.
+    /// Grayscale values are replicated across the color channels, missing
+    /// alpha is filled with 1.
+    pub fn into_rgba8(self) -> Result<image::RgbaImage, ExportError> {
+        let (size, data) = match self {
+            ConvertedImage::R8(size, data) => (
+                size,
+                data.iter().flat_map(|v| vec![*v, *v, *v, 255]).collect(),
+            ),
+            ConvertedImage::Rgb8(size, data) => (
+                size,
+                data.chunks(3)
+                    .flat_map(|c| vec![c[0], c[1], c[2], 255])
+                    .collect(),
+            ),
+            ConvertedImage::Rgba8(size, data) => (size, data),
+            _ => return Err(ExportError::UnsupportedBitDepthOrColorSpace),
+        };
+
+        image::RgbaImage::from_raw(size, size, data).ok_or(ExportError::UnsupportedExportFormat)
+    }
+
     /// Save this image to a file, using a given format. The format is *not*
     /// inferred from the path!
     pub fn save_to_file<P: AsRef<Path>>(
@@ -194,7 +337,23 @@ impl ConvertedImage {
         format: ExportFormat,
         path: P,
     ) -> Result<(), ExportError> {
-        let mut writer = std::fs::File::create(path)?;
+        let writer = std::fs::File::create(path)?;
+        self.encode(format, writer)
+    }
+
+    /// Encode this image into a buffer in memory, using a given format.
+    pub fn encode_to_vec(&self, format: ExportFormat) -> Result<Vec<u8>, ExportError> {
+        let mut buf = Cursor::new(Vec::new());
+        self.encode(format, &mut buf)?;
+        Ok(buf.into_inner())
+    }
+
+    /// Encode this image into a writer, using a given format.
+    pub fn encode<W: Write + Seek>(
+        &self,
+        format: ExportFormat,
+        mut writer: W,
+    ) -> Result<(), ExportError> {
         match (self, format) {
             (ConvertedImage::R8(size, data), ExportFormat::Png) => {
                 use image::codecs::png;
@@ -252,6 +411,29 @@ impl ConvertedImage {
                 };
                 enc.encode(u8data, *size, *size, image::ColorType::Rgb16)?;
             }
+            (ConvertedImage::Rgba8(size, data), ExportFormat::Png) => {
+                use image::codecs::png;
+                let enc = png::PngEncoder::new(writer);
+                enc.encode(data, *size, *size, image::ColorType::Rgba8)?;
+            }
+            (ConvertedImage::Rgba8(size, data), ExportFormat::Tiff) => {
+                use image::codecs::tiff;
+                let enc = tiff::TiffEncoder::new(writer);
+                enc.encode(data, *size, *size, image::ColorType::Rgba8)?;
+            }
+            (ConvertedImage::Rgba8(size, data), ExportFormat::Tga) => {
+                use image::codecs::tga;
+                let enc = tga::TgaEncoder::new(writer);
+                enc.encode(data, *size, *size, image::ColorType::Rgba8)?;
+            }
+            (ConvertedImage::Rgba16(size, data), ExportFormat::Png) => {
+                use image::codecs::png;
+                let enc = png::PngEncoder::new(writer);
+                let u8data = unsafe {
+                    std::slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * 2)
+                };
+                enc.encode(u8data, *size, *size, image::ColorType::Rgba16)?;
+            }
             (ConvertedImage::Rgb32(size, data), ExportFormat::Hdr) => {
                 use image::codecs::hdr;
                 let enc = hdr::HdrEncoder::new(writer);
@@ -131,6 +131,13 @@ where
         Ok((self.buffer.as_ref().unwrap(), self.satellite.get_extra()))
     }
 
+    /// Fill the internal buffer from raw data that was already obtained from
+    /// the source, avoiding IO on next compute.
+    pub fn preload(&mut self, raw: &[u8]) -> Result<(), ExternalError> {
+        self.buffer = Some(self.satellite.fill_buffer(raw)?);
+        Ok(())
+    }
+
     /// Get a reference to the external data's source.
     pub fn source(&self) -> &Source {
         &self.source
@@ -1,7 +1,7 @@
 use super::{
     export::*,
     external::*,
-    shaders::{BufferDim, IntermediateDataDescription, ShaderLibrary, Uniforms},
+    shaders::{BufferDim, IntermediateDataDescription, Shader, ShaderLibrary, Uniforms},
     sockets::*,
     Linearization,
 };
@@ -53,6 +53,9 @@ pub enum InterpretationError {
     /// Error occurred during export
     #[error("Error during export: {0}")]
     ExportError(#[from] ExportError),
+    /// Execution of the operator at the given node failed
+    #[error("Execution of {0} failed: {1}")]
+    OperatorFailed(Resource<Node>, Box<InterpretationError>),
 }
 
 #[derive(Debug)]
@@ -116,6 +119,21 @@ impl StackFrame {
     pub fn retention_set(&self) -> impl Iterator<Item = &Resource<Node>> {
         self.linearization.retention_set_at(self.step)
     }
+
+    /// Determine whether the given instruction operates on a node that is
+    /// known to be clean in this frame. Only toplevel frames track dirtiness,
+    /// since parameter substitutions may alter nodes in called frames.
+    pub fn is_clean(&self, instr: &Instruction) -> bool {
+        if self.caller.is_some() {
+            return false;
+        }
+
+        match instr {
+            Instruction::Execute(res, op) => op.is_cacheable() && !self.linearization.is_dirty(res),
+            Instruction::Call(res, _) => !self.linearization.is_dirty(res),
+            _ => false,
+        }
+    }
 }
 
 /// An interpreter takes a view into the current compute manager state, and runs
@@ -149,7 +167,16 @@ pub struct Interpreter<'a, B: gpu::Backend> {
     view_socket: &'a mut Option<(Resource<Socket>, u64)>,
 
     /// Export specs relevant to the interpreter
-    export_specs: &'a HashMap<Resource<Node>, &'a (ExportSpec, std::path::PathBuf)>,
+    export_specs: &'a HashMap<Resource<Node>, &'a (ExportSpec, ExportTarget)>,
+
+    /// Channel data collected so far for channel packed exports, by spec name
+    packs: HashMap<String, (u32, [Option<Vec<f32>>; 4])>,
+
+    /// Channel packed exports that failed and are to be skipped from here on
+    failed_packs: HashSet<String>,
+
+    /// Threads writing file exports
+    file_exports: &'a mut Vec<std::thread::JoinHandle<()>>,
 }
 
 impl<'a, B: gpu::Backend> Interpreter<'a, B> {
@@ -163,7 +190,8 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
         graph: &Resource<Graph>,
         parent_size: u32,
         view_socket: &'a mut Option<(Resource<Socket>, u64)>,
-        export_specs: &'a HashMap<Resource<Node>, &'a (ExportSpec, std::path::PathBuf)>,
+        export_specs: &'a HashMap<Resource<Node>, &'a (ExportSpec, ExportTarget)>,
+        file_exports: &'a mut Vec<std::thread::JoinHandle<()>>,
     ) -> Result<Self, InterpretationError> {
         let linearization = linearizations
             .get(graph)
@@ -190,6 +218,9 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
             parent_size,
             view_socket,
             export_specs,
+            packs: HashMap::new(),
+            failed_packs: HashSet::new(),
+            file_exports,
         })
     }
 
@@ -208,7 +239,7 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
 
         let thumbnail_updated = self
             .sockets
-            .get_thumbnail_updated(&node)
+            .get_thumbnail_updated(socket)
             .expect("Missing sequence for thumbnail");
         let socket_updated = self
             .sockets
@@ -222,8 +253,8 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
                 .expect("Missing output image for socket");
             let new = self
                 .sockets
-                .ensure_group_thumbnail_exists(&node, ty, &mut self.gpu);
-            let thumbnail = self.sockets.get_thumbnail(&node).unwrap();
+                .ensure_thumbnail_exists(socket, ty, &mut self.gpu);
+            let thumbnail = self.sockets.get_thumbnail(socket).unwrap();
             let image = self
                 .sockets
                 .get_output_image(socket)
@@ -233,12 +264,13 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
             if new {
                 response.push(ComputeEvent::ThumbnailCreated(
                     node.clone(),
+                    socket.clone(),
                     gpu::BrokerImageView::from::<B>(self.gpu.view_thumbnail(thumbnail)),
                 ));
             }
 
-            self.sockets.set_thumbnail_updated(&node, self.seq);
-            response.push(ComputeEvent::ThumbnailUpdated(node.clone()));
+            self.sockets.set_thumbnail_updated(socket, self.seq);
+            response.push(ComputeEvent::ThumbnailUpdated(node, socket.clone()));
         } else {
             log::trace!("Skipping thumbnail generation");
         }
@@ -250,6 +282,10 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
     /// This will recurse into the subgraph, interpret its entire linearization,
     /// and then ensure that all output sockets of the complex operator are
     /// backed by GPU images to make them ready for copying.
+    ///
+    /// The call is skipped if neither the parameter hash nor the inputs have
+    /// changed since the last call, provided the images to be copied out of
+    /// the subgraph are still allocated.
     fn execute_call(
         &mut self,
         frame_size: u32,
@@ -259,7 +295,14 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
         log::trace!("Calling complex operator of {}", res);
 
         let uniform_hash = op.parameter_hash();
-        if !self.sockets.group_requires_recompute(res, uniform_hash) {
+        let outputs_backed = op.outputs.values().all(|(_, output)| {
+            self.sockets
+                .get_input_image(&output.node_socket("data"))
+                .map(|img| img.is_backed())
+                .unwrap_or(false)
+        });
+
+        if outputs_backed && !self.sockets.group_requires_recompute(res, uniform_hash) {
             log::trace!("Reusing cached images, skipping call");
 
             let inner_seq = op
@@ -543,12 +586,13 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
         let ty = op.output_type.into();
         let new = self
             .sockets
-            .ensure_group_thumbnail_exists(&res, ty, &mut self.gpu);
+            .ensure_thumbnail_exists(&socket_res, ty, &mut self.gpu);
         let image = self.sockets.get_input_image(&socket_res).unwrap();
 
-        let thumbnail = self.sockets.get_thumbnail(&res).unwrap();
+        let thumbnail = self.sockets.get_thumbnail(&socket_res).unwrap();
         self.gpu.generate_thumbnail(image, thumbnail);
 
+        let receipt = image.hand_off();
         let mut result = vec![ComputeEvent::OutputReady(
             res.clone(),
             gpu::BrokerImage::from::<B>(image.get_raw()),
@@ -564,15 +608,17 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
                 )
                 .allocation_size(),
             output_type,
+            receipt,
         )];
 
         if new {
             result.push(ComputeEvent::ThumbnailCreated(
                 res.clone(),
+                socket_res.clone(),
                 gpu::BrokerImageView::from::<B>(self.gpu.view_thumbnail(thumbnail)),
             ));
         }
-        result.push(ComputeEvent::ThumbnailUpdated(res.clone()));
+        result.push(ComputeEvent::ThumbnailUpdated(res.clone(), socket_res));
 
         result
     }
@@ -589,7 +635,7 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
         frame_size: u32,
         op: &AtomicOperator,
         res: &Resource<Node>,
-    ) -> Result<(), InterpretationError> {
+    ) -> Result<Option<ComputeEvent>, InterpretationError> {
         log::trace!("Executing operator {:?} of {}", op, res);
 
         // Ensure socket group is well sized
@@ -620,7 +666,7 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
         let uniform_hash = op.uniform_hash();
         if !self.sockets.group_requires_recompute(res, uniform_hash) {
             log::trace!("Reusing cached image");
-            return Ok(());
+            return Ok(None);
         }
 
         let start_time = Instant::now();
@@ -675,6 +721,7 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
                         FromSocketOr::Independent(t) => *t,
                     };
                     let mut img = self.gpu.create_compute_image(size, ty, false, *mips)?;
+                    self.gpu.register_image_owner(&img, res.clone());
                     img.ensure_alloc()?;
                     intermediate_images.insert(name.clone(), img);
                 }
@@ -702,6 +749,14 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
             }
         }
 
+        // Upload data for intermediate images provided by the operator
+        for (name, data) in op.intermediate_image_data() {
+            let img = intermediate_images
+                .get(&name)
+                .expect("Intermediate image data for unknown image");
+            self.gpu.upload_image(img, &data)?;
+        }
+
         // Build input occupancy vector
         let occupancy: Vec<_> = op
             .inputs()
@@ -711,7 +766,9 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
                 |socket| match sockets.get_input_image(&res.node_socket(&socket)) {
                     Some(img) => match img.get_image_type() {
                         ImageType::Grayscale => gpu::compute::InputOccupancy::OccupiedGrayscale,
-                        ImageType::Rgb => gpu::compute::InputOccupancy::OccupiedRgb,
+                        ImageType::Rgb | ImageType::Rgba => {
+                            gpu::compute::InputOccupancy::OccupiedRgb
+                        }
                     },
                     None => gpu::compute::InputOccupancy::Unoccupied,
                 },
@@ -731,7 +788,7 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
             self.gpu.write_descriptor_sets(pass.descriptor_writers(
                 self.gpu.uniform_buffer(),
                 self.gpu.occupancy_buffer(),
-                self.gpu.sampler(),
+                self.gpu.sampler(op.wrap_mode()),
                 &inputs,
                 &outputs,
                 &intermediate_images,
@@ -739,7 +796,7 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
             ));
         }
 
-        self.gpu.run_compute(
+        let gpu_time = self.gpu.run_compute(
             sockets.get_image_size(res).allocation_size(),
             inputs.iter().map(|(a, b)| (a, *b)).unique_by(|x| x.1),
             outputs.values().copied(),
@@ -764,7 +821,7 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
         self.sockets
             .update_timing_data(res, start_time.elapsed().as_secs_f64());
 
-        Ok(())
+        Ok(gpu_time.map(|t| ComputeEvent::OperatorTiming(res.clone(), t)))
     }
 
     /// Process view socket handling, returning an event if appropriate. It will
@@ -799,6 +856,7 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
             let (image, ty) = self.sockets.get_output_image_typed(&socket).unwrap();
             *vs_seq = self.seq;
 
+            let receipt = image.hand_off();
             Ok(Some(ComputeEvent::SocketViewReady(
                 gpu::BrokerImage::from::<B>(image.get_raw()),
                 image.get_layout(),
@@ -807,19 +865,23 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
                     .get_image_size(&socket.socket_node())
                     .allocation_size(),
                 ty,
+                receipt,
             )))
         } else {
             Ok(None)
         }
     }
 
-    /// Export an image as given by the export specifications to a certain path.
+    /// Export an image as given by the export specifications to a certain
+    /// target. File exports are encoded and written in a separate thread.
+    /// Archive exports are encoded in place and the encoded data is returned
+    /// as an event for the IO component to assemble the archive.
     fn export(
         &mut self,
         spec: &ExportSpec,
-        path: std::path::PathBuf,
-    ) -> Result<(), InterpretationError> {
-        log::trace!("Exporting {} to {:?}", spec.node, path);
+        target: &ExportTarget,
+    ) -> Result<Option<ComputeEvent>, InterpretationError> {
+        log::trace!("Exporting {} to {:?}", spec.node, target);
 
         let (img, ty) = self
             .sockets
@@ -832,17 +894,193 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
         let color_space = spec.color_space;
         let bit_depth = spec.bit_depth;
 
-        std::thread::spawn(move || {
-            log::trace!("Encoding image to {:?} in thread", format);
-            match ConvertedImage::new(&raw_data, img_size, color_space, bit_depth, ty)
-                .and_then(|img| img.save_to_file(format, path))
-            {
-                Err(e) => log::error!("Failed encoding with {}", e),
-                _ => {}
+        match target {
+            ExportTarget::File(path) => {
+                let path = path.clone();
+                self.file_exports.push(std::thread::spawn(move || {
+                    log::trace!("Encoding image to {:?} in thread", format);
+                    match ConvertedImage::new(&raw_data, img_size, color_space, bit_depth, ty)
+                        .and_then(|img| img.save_to_file(format, path))
+                    {
+                        Err(e) => log::error!("Failed encoding with {}", e),
+                        _ => {}
+                    }
+                }));
+
+                Ok(None)
             }
+            ExportTarget::Archive(path, entry) => {
+                log::trace!("Encoding image to {:?} for archive", format);
+                let data = ConvertedImage::new(&raw_data, img_size, color_space, bit_depth, ty)
+                    .and_then(|img| img.encode_to_vec(format))?;
+
+                Ok(Some(ComputeEvent::ExportEncoded(
+                    path.clone(),
+                    entry.clone(),
+                    data,
+                )))
+            }
+            ExportTarget::Atlas(path, region) => {
+                let tile = ConvertedImage::new(&raw_data, img_size, color_space, bit_depth, ty)
+                    .and_then(|img| img.into_rgba8())?;
+
+                Ok(Some(ComputeEvent::AtlasTileReady(
+                    path.clone(),
+                    *region,
+                    tile,
+                )))
+            }
+        }
+    }
+
+    /// Collect the output of the given node for all channel packed exports in
+    /// its graph that contain its output type. Packs are exported as soon as
+    /// all their channels have been collected. A pack that fails is skipped
+    /// without affecting other exports.
+    fn export_packed(
+        &mut self,
+        output: &Output,
+        res: &Resource<Node>,
+    ) -> Result<Vec<ComputeEvent>, InterpretationError> {
+        let mut response = Vec::new();
+
+        let graph = res.node_graph();
+        let export_specs = self.export_specs;
+        let specs: Vec<_> = export_specs
+            .values()
+            .filter_map(|(spec, target)| {
+                spec.channel_pack
+                    .filter(|pack| {
+                        spec.node.node_graph() == graph
+                            && !self.failed_packs.contains(&spec.name)
+                            && pack
+                                .channels()
+                                .iter()
+                                .any(|c| c.map(OutputType::from) == Some(output.output_type))
+                    })
+                    .map(|pack| (spec, target, pack))
+            })
+            .collect();
+
+        if specs.is_empty() {
+            return Ok(response);
+        }
+
+        log::trace!("Collecting {} for channel packed export", res);
+
+        let (img, ty) = self
+            .sockets
+            .get_input_image_typed(&res.node_socket("data"))
+            .ok_or(ExportError::UnknownImage)?;
+        let size = img.get_size();
+        let values = grayscale_values(&self.gpu.download_image(img)?, ty);
+
+        for (spec, target, pack) in specs {
+            let (pack_size, channels) = self
+                .packs
+                .entry(spec.name.clone())
+                .or_insert_with(|| (size, [None, None, None, None]));
+
+            if *pack_size != size {
+                log::error!(
+                    "Failed channel packed export {} with {}",
+                    spec.name,
+                    ExportError::PackSizeMismatch
+                );
+                self.packs.remove(&spec.name);
+                self.failed_packs.insert(spec.name.clone());
+                continue;
+            }
+
+            for (i, chan) in pack.channels().iter().enumerate() {
+                if chan.map(OutputType::from) == Some(output.output_type) {
+                    channels[i] = Some(values.clone());
+                }
+            }
+
+            let complete = pack
+                .channels()
+                .iter()
+                .zip(channels.iter())
+                .all(|(c, v)| c.is_none() || v.is_some());
+
+            if complete {
+                let (size, channels) = self.packs.remove(&spec.name).unwrap();
+                response.extend(self.finish_pack(spec, target, size, &channels));
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Export all channel packs that are still incomplete at the end of
+    /// interpretation, because some of their channels were never computed.
+    /// The missing channels are filled as if they were unassigned.
+    fn export_incomplete_packs(&mut self) -> Vec<ComputeEvent> {
+        let mut response = Vec::new();
+        let export_specs = self.export_specs;
+
+        for (spec, target) in export_specs.values() {
+            if let Some((size, channels)) = self.packs.remove(&spec.name) {
+                log::warn!(
+                    "Channel packed export {} is missing channels, filling with defaults",
+                    spec.name
+                );
+                response.extend(self.finish_pack(spec, target, size, &channels));
+            }
+        }
+
+        response
+    }
+
+    /// Encode a channel pack and deliver it to its target. Failures are logged
+    /// and only affect this export.
+    fn finish_pack(
+        &mut self,
+        spec: &ExportSpec,
+        target: &ExportTarget,
+        size: u32,
+        channels: &[Option<Vec<f32>>; 4],
+    ) -> Option<ComputeEvent> {
+        let pack = spec.channel_pack?;
+        let result = ConvertedImage::packed(
+            channels,
+            pack.inverted,
+            size,
+            spec.color_space,
+            spec.bit_depth,
+        )
+        .and_then(|image| match target {
+            ExportTarget::File(path) => {
+                let path = path.clone();
+                let format = spec.format;
+                self.file_exports.push(std::thread::spawn(move || {
+                    if let Err(e) = image.save_to_file(format, path) {
+                        log::error!("Failed encoding with {}", e);
+                    }
+                }));
+                Ok(None)
+            }
+            ExportTarget::Archive(path, entry) => Ok(Some(ComputeEvent::ExportEncoded(
+                path.clone(),
+                entry.clone(),
+                image.encode_to_vec(spec.format)?,
+            ))),
+            ExportTarget::Atlas(path, region) => Ok(Some(ComputeEvent::AtlasTileReady(
+                path.clone(),
+                *region,
+                image.into_rgba8()?,
+            ))),
         });
 
-        Ok(())
+        match result {
+            Ok(ev) => ev,
+            Err(e) => {
+                log::error!("Failed channel packed export {} with {}", spec.name, e);
+                self.failed_packs.insert(spec.name.clone());
+                None
+            }
+        }
     }
 
     /// Clean up all image data, using the current execution stack to determine what can be cleaned up.
@@ -879,12 +1117,15 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
         }
     }
 
-    /// Interpret a single instruction, given a substitution map
+    /// Interpret a single instruction, given a substitution map. Execution
+    /// steps on clean nodes will be skipped entirely if their socket group is
+    /// still current.
     fn interpret(
         &mut self,
         frame_size: u32,
         instr: &Instruction,
         substitutions: &HashMap<Resource<Node>, Vec<ParamSubstitution>>,
+        clean: bool,
     ) -> Result<Vec<ComputeEvent>, InterpretationError> {
         let mut response = Vec::new();
 
@@ -928,12 +1169,25 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
                         for res in self.execute_output(&output, res) {
                             response.push(res);
                         }
-                        if let Some((spec, path)) = self.export_specs.get(res) {
-                            self.export(spec, path.clone())?;
+                        if let Some((spec, target)) = self
+                            .export_specs
+                            .get(res)
+                            .filter(|(spec, _)| spec.channel_pack.is_none())
+                        {
+                            if let Some(ev) = self.export(spec, target)? {
+                                response.push(ev);
+                            }
                         }
+                        response.append(&mut self.export_packed(&output, res)?);
                     }
                     _ => {
-                        self.execute_atomic_operator(frame_size, &op, res)?;
+                        if clean && self.sockets.group_is_current(res) {
+                            log::trace!("Skipping clean node {}", res);
+                        } else {
+                            if let Some(ev) = self.execute_atomic_operator(frame_size, &op, res)? {
+                                response.push(ev);
+                            }
+                        }
                     }
                 }
 
@@ -942,7 +1196,11 @@ impl<'a, B: gpu::Backend> Interpreter<'a, B> {
                 }
             }
             Instruction::Call(res, op) => {
-                self.execute_call(frame_size, res, op)?;
+                if clean && self.sockets.group_is_current(res) {
+                    log::trace!("Skipping call on clean node {}", res);
+                } else {
+                    self.execute_call(frame_size, res, op)?;
+                }
             }
             Instruction::Copy(from, to) => {
                 self.execute_copy(from, to)?;
@@ -979,24 +1237,40 @@ where
             .pop_front()
             .expect("Found empty stack frame");
         let substitutions = frame.substitutions_map.clone();
+        let clean = frame.is_clean(&instruction);
 
-        let response = match self.interpret(frame_size, &instruction, &substitutions) {
+        let response = match self.interpret(frame_size, &instruction, &substitutions, clean) {
             Ok(r) => Some(Ok((r, self.seq))),
 
             // Handle OOM
             Err(InterpretationError::AllocatorError(gpu::compute::AllocatorError::OutOfMemory)) => {
                 self.cleanup();
-                match self.interpret(frame_size, &instruction, &substitutions) {
+                match self.interpret(frame_size, &instruction, &substitutions, clean) {
                     Ok(r) => Some(Ok((r, self.seq))),
                     Err(InterpretationError::AllocatorError(
                         gpu::compute::AllocatorError::OutOfMemory,
-                    )) => Some(Err(InterpretationError::HardOOM)),
+                    )) => {
+                        for (node, bytes) in self.gpu.top_n_consumers(8) {
+                            log::debug!("{} occupies {} bytes of image memory", node, bytes);
+                        }
+                        Some(Err(InterpretationError::HardOOM))
+                    }
                     Err(e) => Some(Err(e)),
                 }
             }
             Err(e) => Some(Err(e)),
         };
 
+        // Attribute failures to the node being executed
+        let response = response.map(|r| {
+            r.map_err(|e| match &instruction {
+                Instruction::Execute(res, _) | Instruction::Call(res, _) => {
+                    InterpretationError::OperatorFailed(res.clone(), Box::new(e))
+                }
+                _ => e,
+            })
+        });
+
         if instruction.is_execution_step() {
             self.execution_stack.last_mut()?.step += 1;
         }
@@ -1011,6 +1285,17 @@ where
             }
         }
 
+        // Flush channel packs that did not receive all their channels
+        if self.execution_stack.is_empty() && !self.packs.is_empty() {
+            let mut packed = self.export_incomplete_packs();
+            return response.map(|r| {
+                r.map(|(mut evs, seq)| {
+                    evs.append(&mut packed);
+                    (evs, seq)
+                })
+            });
+        }
+
         response
     }
 }
@@ -2,7 +2,7 @@ use crate::{broker, gpu, lang::*};
 
 use strum::IntoEnumIterator;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
@@ -24,8 +24,12 @@ pub fn start_compute_thread<B: gpu::Backend>(
     config: &config::Configuration,
 ) -> thread::JoinHandle<()> {
     log::info!("Starting GPU Compute Handler");
-    let (sender, receiver, disconnector) = broker.subscribe("compute");
-    match gpu::compute::GPUCompute::new(gpu, config.compute_vram_pct) {
+    let (sender, receiver, disconnector) = broker.subscribe_priority("compute", 1);
+    match gpu::compute::GPUCompute::new(
+        gpu,
+        config.compute_vram_pct,
+        config.thumbnail_size as usize,
+    ) {
         Err(e) => {
             log::error!("Failed to initialize GPU Compute: {:?}", e);
             panic!("Critical Error");
@@ -48,13 +52,108 @@ pub fn start_compute_thread<B: gpu::Backend>(
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Linearization {
     instructions: Vec<Instruction>,
     use_points: Vec<(Resource<Node>, UsePoint)>,
+
+    /// Set of nodes that have changed since the last compute pass over this
+    /// linearization. `None` signifies that every node must be considered
+    /// dirty, e.g. because there is no previous pass to compare against.
+    dirty_set: Option<HashSet<Resource<Node>>>,
 }
 
 impl Linearization {
+    /// Construct a new linearization, determining the dirty set by comparing
+    /// against the previous linearization of the same graph, if any.
+    ///
+    /// A node is dirty if its execution step differs from the previous
+    /// linearization, or if any of its inputs has been rewired. Dirtiness is
+    /// then propagated forward along all moves, such that every node
+    /// downstream of a dirty node is also dirty.
+    pub fn new(
+        instructions: Vec<Instruction>,
+        use_points: Vec<(Resource<Node>, UsePoint)>,
+        previous: Option<&Self>,
+    ) -> Self {
+        let dirty_set = previous.map(|previous| {
+            let mut old_steps = HashMap::new();
+            let mut old_moves = HashSet::new();
+            for instr in previous.instructions.iter() {
+                match instr {
+                    Instruction::Execute(res, _) | Instruction::Call(res, _) => {
+                        old_steps.insert(res, instr);
+                    }
+                    Instruction::Move(from, to) => {
+                        old_moves.insert((from, to));
+                    }
+                    _ => {}
+                }
+            }
+
+            let mut dirty = HashSet::new();
+            for instr in instructions.iter() {
+                match instr {
+                    Instruction::Execute(res, new_op) => {
+                        let changed = match old_steps.get(res) {
+                            Some(Instruction::Execute(_, old_op)) => old_op != new_op,
+                            _ => true,
+                        };
+                        if changed || !new_op.is_cacheable() {
+                            dirty.insert(res.clone());
+                        }
+                    }
+                    Instruction::Call(res, new_op) => match old_steps.get(res) {
+                        Some(Instruction::Call(_, old_op)) if old_op == new_op => {}
+                        _ => {
+                            dirty.insert(res.clone());
+                        }
+                    },
+                    Instruction::Move(from, to) => {
+                        if !old_moves.contains(&(from, to)) || dirty.contains(&from.socket_node()) {
+                            dirty.insert(to.socket_node());
+                        }
+                    }
+                    Instruction::ClearInput(socket) => {
+                        dirty.insert(socket.socket_node());
+                    }
+                    _ => {}
+                }
+            }
+
+            dirty
+        });
+
+        Self {
+            instructions,
+            use_points,
+            dirty_set,
+        }
+    }
+
+    /// Determine whether a node is dirty in this linearization.
+    pub fn is_dirty(&self, node: &Resource<Node>) -> bool {
+        self.dirty_set
+            .as_ref()
+            .map(|d| d.contains(node))
+            .unwrap_or(true)
+    }
+
+    /// Mark the given nodes as dirty, in addition to the ones already known.
+    pub fn mark_dirty<'a, I>(&mut self, nodes: I)
+    where
+        I: IntoIterator<Item = &'a Resource<Node>>,
+    {
+        if let Some(dirty_set) = &mut self.dirty_set {
+            dirty_set.extend(nodes.into_iter().cloned());
+        }
+    }
+
+    /// Mark all nodes as clean, typically after a compute pass finished.
+    pub fn clear_dirty(&mut self) {
+        self.dirty_set = Some(HashSet::new());
+    }
+
     pub fn retention_set_at(&self, step: usize) -> impl Iterator<Item = &Resource<Node>> {
         self.use_points.iter().filter_map(move |(r, up)| {
             if up.last >= step && up.creation <= step {
@@ -93,6 +192,13 @@ struct ComputeManager<B: gpu::Backend> {
     /// A special socket that the user wants to view, with a seq number for when
     /// it was last updated
     view_socket: Option<(Resource<Socket>, u64)>,
+
+    /// Image resources currently being loaded by the IO component. Loads
+    /// completing for resources no longer in here have been cancelled.
+    pending_loads: HashSet<Resource<Img>>,
+
+    /// Threads writing file exports that have not been waited on yet
+    file_exports: Vec<thread::JoinHandle<()>>,
 }
 
 impl<B> ComputeManager<B>
@@ -112,6 +218,8 @@ where
             seq: 0,
             parent_size: 1024,
             view_socket: None,
+            pending_loads: HashSet::new(),
+            file_exports: Vec::new(),
         }
     }
 
@@ -149,6 +257,19 @@ where
                     blend_node.rename_file(&new_name);
                     self.sockets.ensure_group_exists(&blend_node, *size);
                 }
+                LayersEvent::LayersGrouped(res, _, _, _, _, size) => {
+                    // Groups only have blend nodes
+                    for channel in MaterialChannel::iter() {
+                        let mut blend_node = res.clone();
+                        let new_name = format!(
+                            "{}.blend.{}",
+                            blend_node.file().unwrap(),
+                            channel.short_name()
+                        );
+                        blend_node.rename_file(&new_name);
+                        self.sockets.ensure_group_exists(&blend_node, *size);
+                    }
+                }
                 LayersEvent::LayersAdded(_, size, outputs) => {
                     for output in outputs {
                         self.sockets.ensure_group_exists(output, *size);
@@ -176,7 +297,7 @@ where
 
                         for socket in self
                             .sockets
-                            .remove_all_for_group(res, &mut self.gpu)
+                            .remove_all_for_group(&blend_node, &mut self.gpu)
                             .drain(0..)
                         {
                             sender
@@ -216,6 +337,7 @@ where
                                     false,
                                 )
                                 .unwrap();
+                            self.gpu.register_image_owner(&img, res.socket_node());
                             self.sockets.add_output_socket(
                                 res,
                                 Some((img, *ty)),
@@ -257,15 +379,20 @@ where
                     }
                 }
                 GraphEvent::Relinearized(graph, instrs, use_points) => {
-                    self.linearizations.insert(
-                        graph.clone(),
-                        Rc::new(Linearization {
-                            instructions: instrs.clone(),
-                            use_points: use_points.clone(),
-                        }),
+                    let linearization = Linearization::new(
+                        instrs.clone(),
+                        use_points.clone(),
+                        self.linearizations.get(graph).map(|l| l.as_ref()),
                     );
+                    self.linearizations
+                        .insert(graph.clone(), Rc::new(linearization));
                 }
-                GraphEvent::Recompute(graph, export_specs) => {
+                GraphEvent::Recompute(graph, export_specs, dirty_set) => {
+                    if let (Some(dirty_set), Some(linearization)) =
+                        (dirty_set, self.linearizations.get_mut(graph))
+                    {
+                        Rc::make_mut(linearization).mark_dirty(dirty_set);
+                    }
                     self.run_interpretation(graph, export_specs, sender);
                 }
                 GraphEvent::SocketMonomorphized(res, ty) => {
@@ -280,6 +407,7 @@ where
                             .gpu
                             .create_compute_image(size, *ty, false, false)
                             .unwrap();
+                        self.gpu.register_image_owner(&img, res.socket_node());
                         // The socket is a known output, and thus the actual
                         // size should also already be known!
                         self.sockets
@@ -296,11 +424,14 @@ where
                     if self.sockets.is_known_output(res) {
                         log::trace!("Removing monomorphized socket {}", res);
                         self.sockets.remove_image(res);
-                        let node = res.socket_node();
-                        self.sockets.clear_thumbnail(&node, &mut self.gpu);
-                        sender
-                            .send(Lang::ComputeEvent(ComputeEvent::ThumbnailDestroyed(node)))
-                            .unwrap();
+                        if self.sockets.clear_thumbnail(res, &mut self.gpu) {
+                            sender
+                                .send(Lang::ComputeEvent(ComputeEvent::ThumbnailDestroyed(
+                                    res.socket_node(),
+                                    res.clone(),
+                                )))
+                                .unwrap();
+                        }
                         sender
                             .send(Lang::ComputeEvent(ComputeEvent::SocketDestroyed(
                                 res.clone(),
@@ -312,6 +443,12 @@ where
                     self.sockets.rename_graph(from, to);
                     self.linearizations.remove(to);
                 }
+                GraphEvent::Imported => {
+                    self.reset();
+                    sender
+                        .send(Lang::ComputeEvent(ComputeEvent::Cleared))
+                        .unwrap();
+                }
                 _ => {}
             },
             Lang::UserIOEvent(event) => match event {
@@ -335,9 +472,12 @@ where
                         .unwrap();
                 }
                 UserIOEvent::AddImageResource(path) => {
-                    sender.send(self.add_image_resource(path)).unwrap();
+                    if let Some(name) = path.file_name() {
+                        self.pending_loads.insert(Resource::image(name));
+                    }
                 }
                 UserIOEvent::RemoveImageResource(res) => {
+                    self.pending_loads.remove(res);
                     sender.send(self.remove_image_resource(res)).unwrap();
                 }
                 UserIOEvent::ReloadImageResource(res) => {
@@ -379,6 +519,21 @@ where
                 UserIOEvent::ReloadSvgResource(res) => {
                     self.external_data.invalidate_svg(res);
                 }
+                UserIOEvent::DefragmentVram => {
+                    match self.gpu.defragment(self.sockets.output_images_mut()) {
+                        Ok((before, after)) => {
+                            for usage in &[before, after] {
+                                sender
+                                    .send(Lang::ComputeEvent(ComputeEvent::VramUsage(
+                                        usage.vram_used(),
+                                        usage.vram_size(),
+                                    )))
+                                    .unwrap();
+                            }
+                        }
+                        Err(e) => log::error!("Error during defragmentation {}", e),
+                    }
+                }
                 _ => {}
             },
             Lang::IOEvent(IOEvent::ComputeDataLoaded(data)) => {
@@ -386,12 +541,35 @@ where
                     sender.send(ev).unwrap();
                 }
             }
+            Lang::IOEvent(IOEvent::ImageResourceLoaded(res, path, data)) => {
+                if self.pending_loads.remove(res) {
+                    if let Some(ev) = self.add_image_resource(res, path, data) {
+                        sender.send(ev).unwrap();
+                    }
+                } else {
+                    log::debug!("Discarding cancelled load of {}", res);
+                }
+            }
+            Lang::IOEvent(IOEvent::ImageResourceLoadFailed(res, ..)) => {
+                self.pending_loads.remove(res);
+            }
             Lang::UserNodeEvent(UserNodeEvent::ViewSocket(socket)) => {
                 self.view_socket = socket.as_ref().map(|s| (s.clone(), u64::MIN));
             }
             Lang::SurfaceEvent(SurfaceEvent::ParentSizeSet(size, _)) => {
                 self.parent_size = *size;
             }
+            Lang::SurfaceEvent(SurfaceEvent::ExportsQueued) => {
+                // All recomputes for the exports have been processed by now
+                for handle in self.file_exports.drain(..) {
+                    if handle.join().is_err() {
+                        log::error!("File export thread panicked");
+                    }
+                }
+                sender
+                    .send(Lang::ComputeEvent(ComputeEvent::ExportsComputed))
+                    .unwrap();
+            }
             Lang::ScheduleEvent(ScheduleEvent::VramUsage) => {
                 let usage = self.gpu.allocator_usage();
                 sender
@@ -410,7 +588,7 @@ where
     fn run_interpretation(
         &mut self,
         graph: &Resource<Graph>,
-        export_specs: &[(ExportSpec, std::path::PathBuf)],
+        export_specs: &[(ExportSpec, ExportTarget)],
         sender: &broker::BrokerSender<Lang>,
     ) {
         let export_specs: HashMap<_, _> =
@@ -427,23 +605,63 @@ where
             self.parent_size,
             &mut self.view_socket,
             &export_specs,
+            &mut self.file_exports,
         ) {
             Ok(interpreter) => {
+                let mut completed = true;
+                let mut delivered = Vec::new();
+
                 for step_response in interpreter {
                     match step_response {
                         Err(e) => {
                             log::error!("Error during compute interpretation: {:?}", e);
                             log::error!("Aborting compute!");
+                            if let interpreter::InterpretationError::OperatorFailed(res, cause) = e
+                            {
+                                sender
+                                    .send(Lang::ComputeEvent(ComputeEvent::OperatorError(
+                                        res,
+                                        cause.to_string(),
+                                    )))
+                                    .unwrap();
+                            }
+                            completed = false;
                             break;
                         }
                         Ok((r, s)) => {
                             for ev in r {
+                                if let ComputeEvent::ExportEncoded(path, entry, _) = &ev {
+                                    delivered
+                                        .push(ExportTarget::Archive(path.clone(), entry.clone()));
+                                }
+                                if let ComputeEvent::AtlasTileReady(path, region, _) = &ev {
+                                    delivered.push(ExportTarget::Atlas(path.clone(), *region));
+                                }
                                 sender.send(Lang::ComputeEvent(ev)).unwrap();
                             }
                             self.seq = s;
                         }
                     }
                 }
+
+                // Exports assembled by IO wait for all their parts, so
+                // they must learn about parts this pass failed to deliver.
+                for (_, target) in export_specs.values() {
+                    if !matches!(target, ExportTarget::File(..)) && !delivered.contains(target) {
+                        sender
+                            .send(Lang::ComputeEvent(ComputeEvent::ExportFailed(
+                                target.clone(),
+                            )))
+                            .unwrap();
+                    }
+                }
+
+                // Only a completed pass may mark the graph as clean
+                if completed {
+                    if let Some(linearization) = self.linearizations.get_mut(graph) {
+                        Rc::make_mut(linearization).clear_dirty();
+                    }
+                }
             }
             Err(e) => {
                 log::error!("Error building compute interpreter: {:?}", e);
@@ -455,21 +673,36 @@ where
     pub fn reset(&mut self) {
         self.sockets.clear(&mut self.gpu);
         self.external_data.clear();
+        self.pending_loads.clear();
     }
 
-    /// Adds an (unpacked) image resource from a path.
-    fn add_image_resource<P: AsRef<Path> + std::fmt::Debug>(&mut self, path: P) -> Lang {
-        let res = Resource::image(path.as_ref().file_name().unwrap());
+    /// Adds an (unpacked) image resource from a path, given the data already
+    /// loaded from it. Images that fail to decode are not added.
+    fn add_image_resource<P: AsRef<Path> + std::fmt::Debug>(
+        &mut self,
+        res: &Resource<Img>,
+        path: P,
+        data: &[u8],
+    ) -> Option<Lang> {
         log::debug!("Adding image resource from path {:?} as {}", path, res);
 
         self.external_data
             .insert_image(res.clone(), path, ColorSpace::Srgb);
+        if let Some(Err(e)) = self
+            .external_data
+            .get_image_mut(res)
+            .map(|img| img.preload(data))
+        {
+            log::error!("Failed to decode image {}: {}", res, e);
+            self.external_data.remove_image(res);
+            return None;
+        }
 
-        Lang::ComputeEvent(ComputeEvent::ImageResourceAdded(
-            res,
+        Some(Lang::ComputeEvent(ComputeEvent::ImageResourceAdded(
+            res.clone(),
             ColorSpace::Srgb,
             false,
-        ))
+        )))
     }
 
     /// Removes an image resource
@@ -6,9 +6,10 @@ use crate::{
 use enum_dispatch::*;
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
 use zerocopy::AsBytes;
 
-pub use gpu::Specialization;
+pub use gpu::{Specialization, WrapMode};
 
 /// Usage of a descriptor for an operator
 pub enum OperatorDescriptorUse {
@@ -230,7 +231,7 @@ pub enum OperatorPassDescription {
 pub enum OperatorPass<B: gpu::Backend> {
     RunShader {
         operator_shader: OperatorShader,
-        pipeline: gpu::compute::ComputePipeline<B>,
+        pipeline: Arc<gpu::compute::ComputePipeline<B>>,
         descriptors: B::DescriptorSet,
     },
     GenerateMips(&'static str, &'static str),
@@ -269,15 +270,15 @@ where
                     Some(descriptors),
                     &[],
                 );
+                // Image sizes need not be multiples of the local size, so the
+                // group counts are rounded up. Shaders discard invocations
+                // outside of the image.
+                let groups = |local: u8| (image_size + local as u32 - 1) / local as u32;
                 cmd_buffer.dispatch(match shape {
-                    OperatorShape::PerPixel { local_x, local_y } => [
-                        image_size / *local_x as u32,
-                        image_size / *local_y as u32,
-                        1,
-                    ],
-                    OperatorShape::PerRowOrColumn { local_size } => {
-                        [image_size / *local_size as u32, 1, 1]
+                    OperatorShape::PerPixel { local_x, local_y } => {
+                        [groups(*local_x), groups(*local_y), 1]
                     }
+                    OperatorShape::PerRowOrColumn { local_size } => [groups(*local_size), 1, 1],
                 });
             },
             Self::GenerateMips(source, target) => unsafe {
@@ -471,9 +472,8 @@ where
     ) -> Result<Self, gpu::compute::InitializationError> {
         match description {
             OperatorPassDescription::RunShader(operator_shader) => {
-                let shader: gpu::Shader<B> = gpu.create_shader(operator_shader.spirv)?;
-                let pipeline: gpu::compute::ComputePipeline<B> = gpu.create_pipeline(
-                    &shader,
+                let pipeline = gpu.create_pipeline_cached(
+                    operator_shader.spirv,
                     &operator_shader.specialization,
                     operator_shader.layout(),
                 )?;
@@ -537,6 +537,18 @@ pub trait Shader {
     fn intermediate_data(&self) -> HashMap<String, IntermediateDataDescription> {
         HashMap::new()
     }
+
+    /// Return data to be uploaded into intermediate images before running the
+    /// operator passes, by name. The data consists of half floats and must
+    /// match the size and type of the image. Defaults to empty.
+    fn intermediate_image_data(&self) -> HashMap<String, Vec<u16>> {
+        HashMap::new()
+    }
+
+    /// The wrap mode of the sampler used on input images. Defaults to tiling.
+    fn wrap_mode(&self) -> crate::gpu::WrapMode {
+        crate::gpu::WrapMode::Tile
+    }
 }
 
 /// Uniforms are structs that can be converted into plain buffers for GPU use,
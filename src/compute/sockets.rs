@@ -24,10 +24,16 @@ where
     /// Reinitialize the GPU image with a (possibly new) size. This will also
     /// force the image on the next evaluation. This image will not be
     /// immediately backed by memory.
-    pub fn reinit_image(&mut self, gpu: &gpu::compute::GPUCompute<B>, size: u32) {
+    pub fn reinit_image(
+        &mut self,
+        gpu: &gpu::compute::GPUCompute<B>,
+        size: u32,
+        owner: &Resource<Node>,
+    ) {
         self.image = gpu
             .create_compute_image(size, self.ty, self.transfer_dst, false)
             .unwrap();
+        gpu.register_image_owner(&self.image, owner.clone());
     }
 }
 
@@ -107,11 +113,9 @@ pub struct SocketGroup<B: gpu::Backend> {
     /// future. Measured in seconds, for easy conversion from Durations.
     time_ema: EMA<f64>,
 
-    /// Optional thumbnail for the "group".
-    thumbnail: Option<gpu::compute::ThumbnailIndex>,
-
-    /// Thumbnail related sequence number
-    thumbnail_seq: u64,
+    /// Thumbnails for sockets of the group, by socket name, along with the
+    /// sequence number of their last update.
+    thumbnails: HashMap<String, (gpu::compute::ThumbnailIndex, u64)>,
 }
 
 impl<B: gpu::Backend> SocketGroup<B> {
@@ -140,8 +144,7 @@ impl<B: gpu::Backend> SocketGroup<B> {
             },
             inputs: HashMap::new(),
             time_ema: EMA::new(TIMING_DECAY),
-            thumbnail: None,
-            thumbnail_seq: 0,
+            thumbnails: HashMap::new(),
         }
     }
 }
@@ -176,7 +179,7 @@ where
     /// Clear all socket information, releasing all resources.
     pub fn clear(&mut self, gpu: &mut gpu::compute::GPUCompute<B>) {
         for (_, mut socket) in self.0.drain() {
-            if let Some(thumbnail) = socket.thumbnail.take() {
+            for (_, (thumbnail, _)) in socket.thumbnails.drain() {
                 gpu.return_thumbnail(thumbnail);
             }
         }
@@ -199,7 +202,7 @@ where
 
         if let Some(mut socket) = self.0.remove(group) {
             result.extend(socket.typed_outputs.keys().map(|s| group.node_socket(s)));
-            if let Some(thumbnail) = socket.thumbnail.take() {
+            for (_, (thumbnail, _)) in socket.thumbnails.drain() {
                 gpu.return_thumbnail(thumbnail);
             }
         }
@@ -218,47 +221,74 @@ where
             .or_insert_with(|| SocketGroup::new(size))
     }
 
-    /// Ensure the group described by the resource has a thumbnail image
+    /// Ensure the socket described by the resource has a thumbnail image
     /// available, returning whether the thumbnail is newly created.
-    pub fn ensure_group_thumbnail_exists(
+    pub fn ensure_thumbnail_exists(
         &mut self,
-        res: &Resource<Node>,
+        res: &Resource<Socket>,
         ty: ImageType,
         gpu: &mut gpu::compute::GPUCompute<B>,
     ) -> bool {
-        if let Some(socket) = self.0.get_mut(&res) {
-            if socket.thumbnail.is_none() {
-                socket.thumbnail = Some(gpu.new_thumbnail(ty));
+        if let Some(group) = self.0.get_mut(&res.socket_node()) {
+            let name = res.fragment().unwrap();
+            if !group.thumbnails.contains_key(name) {
+                group
+                    .thumbnails
+                    .insert(name.to_string(), (gpu.new_thumbnail(ty), 0));
                 return true;
             }
         }
         false
     }
 
-    /// Get thumbnail sequence number for this socket group.
-    pub fn get_thumbnail_updated(&self, res: &Resource<Node>) -> Option<u64> {
-        self.0.get(&res).map(|x| x.thumbnail_seq)
+    /// Get thumbnail sequence number for this socket. Sockets without a
+    /// thumbnail report 0, as long as their group is known.
+    pub fn get_thumbnail_updated(&self, res: &Resource<Socket>) -> Option<u64> {
+        self.0.get(&res.socket_node()).map(|group| {
+            group
+                .thumbnails
+                .get(res.fragment().unwrap())
+                .map(|(_, seq)| *seq)
+                .unwrap_or(0)
+        })
     }
 
     /// Set the thumbnail seq number
-    pub fn set_thumbnail_updated(&mut self, res: &Resource<Node>, updated: u64) {
-        if let Some(s) = self.0.get_mut(&res) {
-            s.thumbnail_seq = updated;
+    pub fn set_thumbnail_updated(&mut self, res: &Resource<Socket>, updated: u64) {
+        if let Some((_, seq)) = self
+            .0
+            .get_mut(&res.socket_node())
+            .and_then(|group| group.thumbnails.get_mut(res.fragment().unwrap()))
+        {
+            *seq = updated;
         }
     }
 
-    /// Return the thumbnail for the given group
-    pub fn clear_thumbnail(&mut self, res: &Resource<Node>, gpu: &mut gpu::compute::GPUCompute<B>) {
-        if let Some(socket) = self.0.get_mut(res) {
-            if let Some(thumbnail) = socket.thumbnail.take() {
-                gpu.return_thumbnail(thumbnail);
-            }
+    /// Return the thumbnail for the given socket, returning whether a
+    /// thumbnail existed.
+    pub fn clear_thumbnail(
+        &mut self,
+        res: &Resource<Socket>,
+        gpu: &mut gpu::compute::GPUCompute<B>,
+    ) -> bool {
+        if let Some((thumbnail, _)) = self
+            .0
+            .get_mut(&res.socket_node())
+            .and_then(|group| group.thumbnails.remove(res.fragment().unwrap()))
+        {
+            gpu.return_thumbnail(thumbnail);
+            true
+        } else {
+            false
         }
     }
 
-    /// Get the thumbnail for a resource (group or socket thereof) if it exists
-    pub fn get_thumbnail(&self, res: &Resource<Node>) -> Option<&gpu::compute::ThumbnailIndex> {
-        self.0.get(res).and_then(|s| s.thumbnail.as_ref())
+    /// Get the thumbnail for a socket if it exists
+    pub fn get_thumbnail(&self, res: &Resource<Socket>) -> Option<&gpu::compute::ThumbnailIndex> {
+        self.0
+            .get(&res.socket_node())
+            .and_then(|group| group.thumbnails.get(res.fragment().unwrap()))
+            .map(|(thumbnail, _)| thumbnail)
     }
 
     /// Insert a new output socket. Will drop the old socket if it already exists.
@@ -312,7 +342,7 @@ where
             .get_mut(group)
             .expect("Trying to free images from unknown group");
         for out in sockets.typed_outputs.values_mut() {
-            out.reinit_image(gpu, sockets.size.ideal)
+            out.reinit_image(gpu, sockets.size.ideal, group)
         }
         sockets.force = true;
     }
@@ -328,11 +358,18 @@ where
             socket_data.force = true;
 
             for out in socket_data.typed_outputs.values_mut() {
-                out.reinit_image(gpu, size);
+                out.reinit_image(gpu, size, res);
             }
         }
     }
 
+    /// Obtain an iterator over all output images managed here, mutably.
+    pub fn output_images_mut(&mut self) -> impl Iterator<Item = &mut gpu::compute::Image<B>> {
+        self.0
+            .values_mut()
+            .flat_map(|group| group.typed_outputs.values_mut().map(|out| &mut out.image))
+    }
+
     /// Obtain the output image given a socket resource along with its type
     pub fn get_output_image_typed(
         &self,
@@ -421,6 +458,24 @@ where
         self.0.get_mut(group).unwrap().force = true;
     }
 
+    /// Determine whether a group is current, i.e. all its output images are
+    /// backed, it is not forced, and none of its inputs have been updated since
+    /// the group was last computed.
+    pub fn group_is_current(&self, group: &Resource<Node>) -> bool {
+        match self.0.get(group) {
+            Some(g) => {
+                !g.force
+                    && g.typed_outputs.values().all(|o| o.image.is_backed())
+                    && g.inputs.values().all(|inp| {
+                        self.get_output_images_updated(&inp.socket_node())
+                            .unwrap_or(0)
+                            <= g.seq
+                    })
+            }
+            None => false,
+        }
+    }
+
     /// Get the last known hash for a socket group
     pub fn get_last_hash(&self, group: &Resource<Node>) -> Option<u64> {
         self.0.get(group).map(|x| x.last_hash)
@@ -1,7 +1,8 @@
 use crate::gpu::{Backend, GPU};
-use crate::lang;
+use crate::{broker, lang};
 use gfx_hal as hal;
 use gfx_hal::prelude::*;
+use std::collections::HashMap;
 use std::mem::ManuallyDrop;
 use std::sync::{Arc, Mutex};
 use std::{cell::Cell, ops::Range};
@@ -10,6 +11,10 @@ use thiserror::Error;
 /// Memory allocation ID.
 type AllocId = std::num::NonZeroU64;
 
+/// Image ID, given by the address of the underlying raw image. This is stable
+/// for the lifetime of the image, including relocations.
+pub type ImageId = usize;
+
 /// A Chunk is a piece of VRAM of fixed size that can be allocated for some
 /// image. The size is hardcoded as `CHUNK_SIZE`.
 #[derive(Debug, Clone)]
@@ -45,13 +50,20 @@ pub struct ComputeAllocator<B: Backend> {
     image_mem: ManuallyDrop<B::Memory>,
     image_mem_chunks: Vec<Chunk>,
     usage: AllocatorUsage,
+    /// Images occupying allocations, along with their image type. Allocations
+    /// for buffers are not tracked here.
+    alloc_images: HashMap<AllocId, (ImageId, lang::ImageType)>,
+    /// Registry of the nodes owning images, for usage statistics
+    image_owners: HashMap<ImageId, lang::Resource<lang::Node>>,
 }
 
 /// Struct holding usage statistics for the allocator
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct AllocatorUsage {
     vram_size: usize,
     vram_used: usize,
+    largest_free: usize,
+    per_image_type: HashMap<lang::ImageType, usize>,
 }
 
 impl AllocatorUsage {
@@ -59,6 +71,8 @@ impl AllocatorUsage {
         Self {
             vram_size,
             vram_used: 0,
+            largest_free: vram_size,
+            per_image_type: HashMap::new(),
         }
     }
 
@@ -71,6 +85,17 @@ impl AllocatorUsage {
     pub fn vram_used(&self) -> usize {
         self.vram_used
     }
+
+    /// Get the size of the largest contiguous free region in bytes, as a
+    /// measure of fragmentation
+    pub fn largest_free(&self) -> usize {
+        self.largest_free
+    }
+
+    /// Get the number of bytes currently allocated to images of each type
+    pub fn per_image_type(&self) -> &HashMap<lang::ImageType, usize> {
+        &self.per_image_type
+    }
 }
 
 impl<B> ComputeAllocator<B>
@@ -118,6 +143,8 @@ where
                 })
                 .collect(),
             usage: AllocatorUsage::new(allocator_size as usize),
+            alloc_images: HashMap::new(),
+            image_owners: HashMap::new(),
         })
     }
 
@@ -144,14 +171,21 @@ where
         None
     }
 
-    /// Mark the given set of chunks as used. Assumes that the chunks were
-    /// previously free!
-    pub fn allocate_memory(&mut self, chunks: Range<usize>) -> AllocId {
+    /// Mark the given set of chunks as used, optionally by the given image.
+    /// Assumes that the chunks were previously free!
+    pub fn allocate_memory(
+        &mut self,
+        chunks: Range<usize>,
+        image: Option<(ImageId, lang::ImageType)>,
+    ) -> AllocId {
         let alloc = self.allocs.get();
         for i in chunks {
             self.image_mem_chunks[i].alloc = Some(alloc);
             self.usage.vram_used += Self::CHUNK_SIZE as usize;
         }
+        if let Some(image) = image {
+            self.alloc_images.insert(alloc, image);
+        }
         self.allocs.set(
             AllocId::new(alloc.get().wrapping_add(1))
                 .unwrap_or(unsafe { AllocId::new_unchecked(1) }),
@@ -170,11 +204,58 @@ where
             chunk.alloc = None;
             self.usage.vram_used -= Self::CHUNK_SIZE as usize;
         }
+        self.alloc_images.remove(&alloc);
+    }
+
+    /// Register the node owning an image, for the purpose of usage statistics.
+    pub fn register_image_owner(&mut self, image: ImageId, owner: lang::Resource<lang::Node>) {
+        self.image_owners.insert(image, owner);
+    }
+
+    /// Get the node owning an image, if registered.
+    pub fn image_owner(&self, image: ImageId) -> Option<&lang::Resource<lang::Node>> {
+        self.image_owners.get(&image)
+    }
+
+    /// Produce the number of bytes currently allocated to each image.
+    pub fn image_usage(&self) -> HashMap<ImageId, usize> {
+        let mut usage = HashMap::new();
+
+        for alloc in self.image_mem_chunks.iter().filter_map(|c| c.alloc) {
+            if let Some((image, _)) = self.alloc_images.get(&alloc) {
+                *usage.entry(*image).or_insert(0) += Self::CHUNK_SIZE as usize;
+            }
+        }
+
+        usage
     }
 
     /// Produce usage statistics for the allocator
     pub fn usage(&self) -> AllocatorUsage {
-        self.usage
+        let mut largest = 0;
+        let mut current = 0;
+        let mut per_image_type = HashMap::new();
+
+        for chunk in self.image_mem_chunks.iter() {
+            match chunk.alloc {
+                None => {
+                    current += 1;
+                    largest = largest.max(current);
+                }
+                Some(alloc) => {
+                    current = 0;
+                    if let Some((_, ty)) = self.alloc_images.get(&alloc) {
+                        *per_image_type.entry(*ty).or_insert(0) += Self::CHUNK_SIZE as usize;
+                    }
+                }
+            }
+        }
+
+        AllocatorUsage {
+            largest_free: largest * Self::CHUNK_SIZE as usize,
+            per_image_type,
+            ..self.usage.clone()
+        }
     }
 }
 
@@ -225,6 +306,8 @@ pub struct Image<B: Backend> {
     mip_levels: u8,
     /// Pixel width
     px_width: u8,
+    /// Usage flags the image was created with
+    usage: hal::image::Usage,
     /// The raw underlying image
     raw: ManuallyDrop<Arc<Mutex<B::Image>>>,
     /// The current layout of the underlying image
@@ -239,6 +322,8 @@ pub struct Image<B: Backend> {
     format: hal::format::Format,
     /// The type of the image, connected to the format
     image_type: lang::ImageType,
+    /// Receipt of a consumer outside of compute the image was handed off to
+    consumer: Cell<Option<broker::ReceiptWaiter>>,
 }
 
 /// Equality on images is defined as pointer equality of the underlying raw
@@ -285,8 +370,9 @@ where
             // spec does not require Rgb16 support. Many GPUs do support it but
             // some may not, and thus requiring it would impose an arbitrary
             // restriction. It might be possible to make this conditional on the
-            // specific GPU.
-            lang::ImageType::Rgb => hal::format::Format::Rgba16Sfloat,
+            // specific GPU. RGBA images share this format, since the alpha
+            // channel is already available.
+            lang::ImageType::Rgb | lang::ImageType::Rgba => hal::format::Format::Rgba16Sfloat,
         };
         let px_width = match format {
             hal::format::Format::R32Sfloat => 4,
@@ -300,6 +386,14 @@ where
             1
         };
 
+        let usage = hal::image::Usage::SAMPLED
+            | if !transfer_dst {
+                hal::image::Usage::STORAGE
+            } else {
+                hal::image::Usage::TRANSFER_DST
+            }
+            | hal::image::Usage::TRANSFER_SRC;
+
         // Create device image
         let image = unsafe {
             device.create_image(
@@ -307,13 +401,7 @@ where
                 mip_levels,
                 format,
                 hal::image::Tiling::Optimal,
-                hal::image::Usage::SAMPLED
-                    | if !transfer_dst {
-                        hal::image::Usage::STORAGE
-                    } else {
-                        hal::image::Usage::TRANSFER_DST
-                    }
-                    | hal::image::Usage::TRANSFER_SRC,
+                usage,
                 hal::image::ViewCapabilities::empty(),
             )
         }?;
@@ -326,6 +414,7 @@ where
             mip_levels,
             bytes,
             px_width,
+            usage,
             raw: ManuallyDrop::new(Arc::new(Mutex::new(image))),
             layout: Cell::new(hal::image::Layout::Undefined),
             access: Cell::new(hal::image::Access::empty()),
@@ -333,6 +422,7 @@ where
             alloc: None,
             format,
             image_type: ty,
+            consumer: Cell::new(None),
         })
     }
 
@@ -398,7 +488,7 @@ where
         let (offset, chunks) = parent_lock
             .find_free_memory(self.bytes)
             .ok_or(AllocatorError::OutOfMemory)?;
-        let alloc = parent_lock.allocate_memory(chunks);
+        let alloc = parent_lock.allocate_memory(chunks, Some((self.get_id(), self.image_type)));
 
         log::trace!(
             "Allocated memory for {}x{} image ({} bytes, id {})",
@@ -423,6 +513,210 @@ where
         Ok(())
     }
 
+    /// Relocate the image into the lowest free region of image memory that
+    /// fits it, if that region lies before its current allocation. The copy
+    /// of the image data is recorded into the given command buffer, which must
+    /// be in the recording state.
+    ///
+    /// Returns the old raw image if the image was moved. The new allocation is
+    /// in place immediately and the old memory is already freed, so the
+    /// command buffer has to be submitted and waited on before the old image
+    /// is destroyed and before any other relocation or allocation takes place.
+    pub fn relocate(
+        &mut self,
+        cmd_buffer: &mut B::CommandBuffer,
+    ) -> Result<Option<B::Image>, AllocatorError> {
+        let current = match &self.alloc {
+            Some(alloc) => alloc.offset,
+            None => return Ok(None),
+        };
+
+        self.await_consumer();
+        let mut raw_lock = self.raw.lock().unwrap();
+        let mut parent_lock = self.parent.lock().unwrap();
+
+        let (offset, chunks) = match parent_lock.find_free_memory(self.bytes) {
+            Some((offset, chunks)) if offset < current => (offset, chunks),
+            _ => return Ok(None),
+        };
+
+        let gpu = parent_lock.gpu.clone();
+        let gpu_lock = gpu.lock().unwrap();
+
+        let mut new_image = unsafe {
+            gpu_lock.device.create_image(
+                hal::image::Kind::D2(self.size, self.size, 1, 1),
+                self.mip_levels,
+                self.format,
+                hal::image::Tiling::Optimal,
+                self.usage,
+                hal::image::ViewCapabilities::empty(),
+            )
+        }?;
+
+        if let Err(e) = unsafe {
+            gpu_lock
+                .device
+                .bind_image_memory(&parent_lock.image_mem, offset, &mut new_image)
+        } {
+            unsafe { gpu_lock.device.destroy_image(new_image) };
+            return Err(e.into());
+        }
+
+        // Create the view before allocating or recording anything, such that
+        // failure leaves the image and the allocator untouched.
+        let view = match unsafe {
+            gpu_lock.device.create_image_view(
+                &new_image,
+                hal::image::ViewKind::D2,
+                self.format,
+                hal::format::Swizzle::NO,
+                hal::image::SubresourceRange::default(),
+            )
+        } {
+            Ok(view) => view,
+            Err(e) => {
+                unsafe { gpu_lock.device.destroy_image(new_image) };
+                return Err(e.into());
+            }
+        };
+
+        let alloc = parent_lock.allocate_memory(chunks, Some((self.get_id(), self.image_type)));
+
+        log::trace!(
+            "Relocating {}x{} image from offset {} to {} (id {})",
+            self.size,
+            self.size,
+            current,
+            offset,
+            alloc
+        );
+
+        // Record copy of all MIP levels
+        let subresources = (0..self.mip_levels).map(|level| hal::command::ImageCopy {
+            src_subresource: hal::image::SubresourceLayers {
+                aspects: hal::format::Aspects::COLOR,
+                level,
+                layers: 0..1,
+            },
+            src_offset: hal::image::Offset::ZERO,
+            dst_subresource: hal::image::SubresourceLayers {
+                aspects: hal::format::Aspects::COLOR,
+                level,
+                layers: 0..1,
+            },
+            dst_offset: hal::image::Offset::ZERO,
+            extent: hal::image::Extent {
+                width: (self.size >> level).max(1),
+                height: (self.size >> level).max(1),
+                depth: 1,
+            },
+        });
+
+        unsafe {
+            cmd_buffer.pipeline_barrier(
+                hal::pso::PipelineStage::COMPUTE_SHADER..hal::pso::PipelineStage::TRANSFER,
+                hal::memory::Dependencies::empty(),
+                &[
+                    self.barrier_to(
+                        &raw_lock,
+                        hal::image::Access::TRANSFER_READ,
+                        hal::image::Layout::TransferSrcOptimal,
+                    ),
+                    hal::memory::Barrier::Image {
+                        states: (hal::image::Access::empty(), hal::image::Layout::Undefined)
+                            ..(
+                                hal::image::Access::TRANSFER_WRITE,
+                                hal::image::Layout::TransferDstOptimal,
+                            ),
+                        target: &new_image,
+                        families: None,
+                        range: hal::image::SubresourceRange::default(),
+                    },
+                ],
+            );
+            cmd_buffer.copy_image(
+                &raw_lock,
+                hal::image::Layout::TransferSrcOptimal,
+                &new_image,
+                hal::image::Layout::TransferDstOptimal,
+                subresources,
+            );
+            cmd_buffer.pipeline_barrier(
+                hal::pso::PipelineStage::TRANSFER..hal::pso::PipelineStage::COMPUTE_SHADER,
+                hal::memory::Dependencies::empty(),
+                &[hal::memory::Barrier::Image {
+                    states: (
+                        hal::image::Access::TRANSFER_WRITE,
+                        hal::image::Layout::TransferDstOptimal,
+                    )
+                        ..(
+                            hal::image::Access::SHADER_READ,
+                            hal::image::Layout::ShaderReadOnlyOptimal,
+                        ),
+                    target: &new_image,
+                    families: None,
+                    range: hal::image::SubresourceRange::default(),
+                }],
+            );
+        }
+
+        // Swap in the new image and its view
+        let old_image = std::mem::replace(&mut *raw_lock, new_image);
+        self.access.set(hal::image::Access::SHADER_READ);
+        self.layout.set(hal::image::Layout::ShaderReadOnlyOptimal);
+
+        unsafe {
+            if let Some(view) = ManuallyDrop::take(&mut self.view) {
+                gpu_lock.device.destroy_image_view(view);
+            }
+        }
+        self.view = ManuallyDrop::new(Some(view));
+
+        // Drop locks before replacing the allocation, since the old allocation
+        // frees itself on drop.
+        drop(gpu_lock);
+        drop(parent_lock);
+        drop(raw_lock);
+
+        self.alloc = Some(Alloc {
+            parent: self.parent.clone(),
+            id: alloc,
+            offset,
+        });
+
+        Ok(Some(old_image))
+    }
+
+    /// Hand the image off to a consumer outside of compute, such as the
+    /// renderer. The consumer must acknowledge the returned receipt once its
+    /// GPU work on the image has completed, i.e. after waiting on its fence.
+    /// Until then, any further use of the image in compute waits.
+    pub fn hand_off(&self) -> broker::Receipt {
+        self.await_consumer();
+        let (receipt, waiter) = broker::Receipt::new();
+        self.consumer.set(Some(waiter));
+        receipt
+    }
+
+    /// Wait for the consumer this image was handed off to, if any. Consumers
+    /// lock the raw image, so this must not be called while holding the lock.
+    pub fn await_consumer(&self) {
+        if let Some(waiter) = self.consumer.take() {
+            waiter.wait();
+        }
+    }
+
+    /// Get the ID of this image
+    pub fn get_id(&self) -> ImageId {
+        Arc::as_ptr(&self.raw) as ImageId
+    }
+
+    /// Get the offset of the image in image memory, if it is backed.
+    pub fn get_offset(&self) -> Option<u64> {
+        self.alloc.as_ref().map(|a| a.offset)
+    }
+
     /// Determine whether an Image is backed by Device memory
     pub fn is_backed(&self) -> bool {
         self.alloc.is_some()
@@ -496,6 +790,9 @@ where
     /// Drop the raw resource. Any allocated memory will only be dropped when
     /// the last reference to it drops.
     fn drop(&mut self) {
+        let id = self.get_id();
+        self.await_consumer();
+
         // Spinlock to acquire the image.
         let image = {
             let mut raw = unsafe { ManuallyDrop::take(&mut self.raw) };
@@ -509,7 +806,8 @@ where
 
         // NOTE: Lock *after* having aquired the image, to avoid a deadlock
         // between here and the image copy in render
-        let parent_lock = self.parent.lock().unwrap();
+        let mut parent_lock = self.parent.lock().unwrap();
+        parent_lock.image_owners.remove(&id);
         let gpu_lock = parent_lock.gpu.lock().unwrap();
 
         unsafe {
@@ -546,7 +844,7 @@ where
             .find_free_memory(bytes)
             .ok_or(AllocatorError::OutOfMemory)?;
         let mut buffer = unsafe { device.create_buffer(bytes, hal::buffer::Usage::STORAGE) }?;
-        let alloc_id = alloc_lock.allocate_memory(chunks);
+        let alloc_id = alloc_lock.allocate_memory(chunks, None);
 
         log::trace!(
             "Allocated memory for buffer ({} bytes, id {})",
@@ -18,7 +18,8 @@ pub use allocator::{AllocatorError, Image, TempBuffer};
 pub use thumbnails::ThumbnailIndex;
 
 use super::{
-    load_shader, Backend, DownloadError, PipelineError, Shader, ShaderError, ShaderType, GPU,
+    load_shader, Backend, DownloadError, PipelineError, Shader, ShaderError, ShaderType,
+    TimerQuery, GPU,
 };
 
 #[repr(u32)]
@@ -34,12 +35,12 @@ pub struct GPUCompute<B: Backend> {
     gpu: Arc<Mutex<GPU<B>>>,
     command_pool: ManuallyDrop<B::CommandPool>,
 
-    // Uniforms and Sampler
+    // Uniforms and Samplers, one sampler per supported wrap mode
     occupancy_buf: ManuallyDrop<B::Buffer>,
     occupancy_mem: ManuallyDrop<B::Memory>,
     uniform_buf: ManuallyDrop<B::Buffer>,
     uniform_mem: ManuallyDrop<B::Memory>,
-    sampler: ManuallyDrop<B::Sampler>,
+    samplers: HashMap<hal::image::WrapMode, B::Sampler>,
 
     // Image Memory Management
     allocator: Arc<Mutex<allocator::ComputeAllocator<B>>>,
@@ -50,8 +51,47 @@ pub struct GPUCompute<B: Backend> {
     // Thumbnails
     thumbnail_cache: thumbnails::ThumbnailCache<B>,
 
+    // Pipelines
+    pipeline_cache: HashMap<PipelineKey, Arc<ComputePipeline<B>>>,
+
     // Sync
     fence: ManuallyDrop<B::Fence>,
+
+    // Profiling, if supported by the device
+    timer: Option<TimerQuery<B>>,
+}
+
+/// Key for the pipeline cache. The key holds the full SPIR-V, specialization,
+/// and bindings of the pipeline, such that lookups compare all of them and
+/// distinct pipelines can never be confused.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    spirv: &'static [u8],
+    constants: Vec<(u32, std::ops::Range<u16>)>,
+    data: Vec<u8>,
+    bindings: Vec<(u32, hal::pso::DescriptorType, usize, bool)>,
+}
+
+impl PipelineKey {
+    fn new(
+        spirv: &'static [u8],
+        specialization: &hal::pso::Specialization<'static>,
+        bindings: &[hal::pso::DescriptorSetLayoutBinding],
+    ) -> Self {
+        Self {
+            spirv,
+            constants: specialization
+                .constants
+                .iter()
+                .map(|c| (c.id, c.range.clone()))
+                .collect(),
+            data: specialization.data.to_vec(),
+            bindings: bindings
+                .iter()
+                .map(|b| (b.binding, b.ty, b.count, b.immutable_samplers))
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -79,14 +119,18 @@ where
     const OCCUPANCY_BUFFER_SIZE: u64 = 1024;
 
     /// Create a new GPUCompute instance.
-    pub fn new(gpu: Arc<Mutex<GPU<B>>>, allocator_pct: f32) -> Result<Self, InitializationError> {
+    pub fn new(
+        gpu: Arc<Mutex<GPU<B>>>,
+        allocator_pct: f32,
+        thumbnail_size: usize,
+    ) -> Result<Self, InitializationError> {
         log::info!("Obtaining GPU Compute Resources");
 
         let allocator = allocator::ComputeAllocator::new(gpu.clone(), allocator_pct)?;
 
         // Thumbnail Data. Produce this first before we lock the GPU for the
         // rest of the constructor, otherwise we get a deadlock.
-        let thumbnail_cache = thumbnails::ThumbnailCache::new(gpu.clone());
+        let thumbnail_cache = thumbnails::ThumbnailCache::new(gpu.clone(), thumbnail_size);
 
         let lock = gpu.lock().unwrap();
 
@@ -186,14 +230,23 @@ where
         }?;
 
         let fence = ManuallyDrop::new(lock.device.create_fence(false).unwrap());
-
-        // Initialize sampler
-        let sampler = unsafe {
-            lock.device.create_sampler(&hal::image::SamplerDesc::new(
-                hal::image::Filter::Linear,
-                hal::image::WrapMode::Tile,
-            ))
-        }?;
+        let timer = TimerQuery::new(&lock.device, lock.timestamps);
+
+        // Initialize samplers
+        let mut samplers = HashMap::new();
+        for wrap_mode in &[
+            hal::image::WrapMode::Tile,
+            hal::image::WrapMode::Clamp,
+            hal::image::WrapMode::Mirror,
+        ] {
+            let sampler = unsafe {
+                lock.device.create_sampler(&hal::image::SamplerDesc::new(
+                    hal::image::Filter::Linear,
+                    *wrap_mode,
+                ))
+            }?;
+            samplers.insert(*wrap_mode, sampler);
+        }
 
         Ok(GPUCompute {
             gpu: gpu.clone(),
@@ -203,14 +256,16 @@ where
             occupancy_mem: ManuallyDrop::new(occupancy_mem),
             uniform_buf: ManuallyDrop::new(uniform_buf),
             uniform_mem: ManuallyDrop::new(uniform_mem),
-            sampler: ManuallyDrop::new(sampler),
+            samplers,
 
             allocator: Arc::new(Mutex::new(allocator)),
 
             descriptor_pool: ManuallyDrop::new(descriptor_pool),
 
             thumbnail_cache,
+            pipeline_cache: HashMap::new(),
             fence,
+            timer,
         })
     }
 
@@ -220,6 +275,93 @@ where
         lock.usage()
     }
 
+    /// Register the node owning the given image, such that its memory usage
+    /// can be attributed to the node.
+    pub fn register_image_owner(&self, image: &Image<B>, owner: lang::Resource<lang::Node>) {
+        let mut lock = self.allocator.lock().unwrap();
+        lock.register_image_owner(image.get_id(), owner);
+    }
+
+    /// Find the `n` nodes consuming the most image memory, in descending
+    /// order of bytes allocated. Only images with a registered owner are
+    /// considered.
+    pub fn top_n_consumers(&self, n: usize) -> Vec<(lang::Resource<lang::Node>, usize)> {
+        let lock = self.allocator.lock().unwrap();
+        let mut consumers: HashMap<lang::Resource<lang::Node>, usize> = HashMap::new();
+
+        for (image, bytes) in lock.image_usage() {
+            if let Some(owner) = lock.image_owner(image) {
+                *consumers.entry(owner.clone()).or_insert(0) += bytes;
+            }
+        }
+
+        let mut consumers: Vec<_> = consumers.into_iter().collect();
+        consumers.sort_by(|a, b| b.1.cmp(&a.1));
+        consumers.truncate(n);
+        consumers
+    }
+
+    /// Defragment image memory by moving the given images into free regions
+    /// lower in memory, where possible. Each move is submitted and waited on
+    /// individually, such that the old memory can be reused by later moves.
+    ///
+    /// Returns allocator usage before and after defragmentation.
+    pub fn defragment<'a, I>(
+        &mut self,
+        images: I,
+    ) -> Result<(allocator::AllocatorUsage, allocator::AllocatorUsage), AllocatorError>
+    where
+        I: IntoIterator<Item = &'a mut Image<B>>,
+        B: 'a,
+    {
+        let before = self.allocator_usage();
+
+        let mut images: Vec<_> = images.into_iter().filter(|i| i.is_backed()).collect();
+        images.sort_by_key(|i| i.get_offset());
+
+        let mut moved = 0;
+
+        for image in images {
+            let mut cmd_buffer = unsafe {
+                let mut cmd_buffer = self.command_pool.allocate_one(hal::command::Level::Primary);
+                cmd_buffer.begin_primary(hal::command::CommandBufferFlags::ONE_TIME_SUBMIT);
+                cmd_buffer
+            };
+
+            let relocated = image.relocate(&mut cmd_buffer);
+
+            unsafe {
+                cmd_buffer.finish();
+
+                if let Ok(Some(_)) = &relocated {
+                    let mut lock = self.gpu.lock().unwrap();
+                    lock.device.reset_fence(&self.fence).unwrap();
+                    lock.queue_group.queues[0]
+                        .submit_without_semaphores(Some(&cmd_buffer), Some(&self.fence));
+                    lock.device.wait_for_fence(&self.fence, !0).unwrap();
+                    moved += 1;
+                }
+
+                self.command_pool.free(Some(cmd_buffer));
+            }
+
+            if let Some(old_image) = relocated? {
+                let lock = self.gpu.lock().unwrap();
+                unsafe { lock.device.destroy_image(old_image) };
+            }
+        }
+
+        let after = self.allocator_usage();
+        log::info!(
+            "Defragmented compute memory, moved {} images, largest free region {} -> {} bytes",
+            moved,
+            before.largest_free(),
+            after.largest_free(),
+        );
+
+        Ok((before, after))
+    }
+
     /// Build a new compute shader given raw SPIR-V. The resulting shader will
     /// destroy itself when dropped. The parent GPU can not be dropped before
     /// all its shaders are dropped!
@@ -259,10 +401,12 @@ where
         TempBuffer::new(&lock.device, self.allocator.clone(), bytes)
     }
 
-    /// Fill the uniform buffer with the given data. The data *must* fit into
-    /// UNIFORM_BUFFER_SIZE.
+    /// Fill the uniform buffer with the given data. Fails if the data does not
+    /// fit into UNIFORM_BUFFER_SIZE.
     pub fn fill_uniforms(&self, uniforms: &[u8]) -> Result<(), PipelineError> {
-        debug_assert!(uniforms.len() <= Self::UNIFORM_BUFFER_SIZE as usize);
+        if uniforms.len() > Self::UNIFORM_BUFFER_SIZE as usize {
+            return Err(PipelineError::UniformSize(uniforms.len()));
+        }
 
         let lock = self.gpu.lock().unwrap();
 
@@ -355,6 +499,34 @@ where
         })
     }
 
+    /// Obtain a compute pipeline for the given SPIR-V, specialization, and
+    /// bindings. Pipelines are cached, such that operators sharing a shader
+    /// and its configuration also share the pipeline, avoiding redundant shader
+    /// compilation.
+    pub fn create_pipeline_cached<I>(
+        &mut self,
+        spirv: &'static [u8],
+        specialization: &hal::pso::Specialization<'static>,
+        bindings: I,
+    ) -> Result<Arc<ComputePipeline<B>>, InitializationError>
+    where
+        I: IntoIterator<Item = hal::pso::DescriptorSetLayoutBinding>,
+    {
+        let bindings: Vec<_> = bindings.into_iter().collect();
+        let key = PipelineKey::new(spirv, specialization, &bindings);
+
+        if let Some(pipeline) = self.pipeline_cache.get(&key) {
+            log::trace!("Reusing cached compute pipeline");
+            return Ok(pipeline.clone());
+        }
+
+        let shader = self.create_shader(spirv)?;
+        let pipeline = Arc::new(self.create_pipeline(&shader, specialization, bindings)?);
+        self.pipeline_cache.insert(key, pipeline.clone());
+
+        Ok(pipeline)
+    }
+
     /// Obtain a new descriptor set from the command pool.
     pub fn allocate_descriptor_set(
         &mut self,
@@ -378,6 +550,9 @@ where
     /// output images, and a callback to fill the command buffer after
     /// initialization.
     ///
+    /// Returns the GPU time spent on the commands recorded by the callback, if
+    /// the device supports timestamp queries.
+    ///
     /// Assumes all images to be unique!
     pub fn run_compute<'a, I, O, J, F>(
         &mut self,
@@ -386,7 +561,8 @@ where
         output_images: O,
         intermediate_images: J,
         buffer_builder: F,
-    ) where
+    ) -> Option<std::time::Duration>
+    where
         I: Iterator<Item = (&'a String, &'a Image<B>)> + Clone,
         O: Iterator<Item = &'a Image<B>> + Clone,
         J: Iterator<Item = (&'a String, &'a Image<B>)> + Clone,
@@ -397,6 +573,14 @@ where
             &mut B::CommandBuffer,
         ),
     {
+        for image in input_images
+            .clone()
+            .map(|(_, i)| i)
+            .chain(output_images.clone())
+        {
+            image.await_consumer();
+        }
+
         unsafe {
             let lock = self.gpu.lock().unwrap();
             lock.device.reset_fence(&self.fence).unwrap();
@@ -450,12 +634,18 @@ where
                 hal::memory::Dependencies::empty(),
                 pre_barriers,
             );
+            if let Some(timer) = &self.timer {
+                timer.begin(&mut command_buffer);
+            }
             buffer_builder(
                 image_size,
                 &input_locks,
                 &intermediate_locks,
                 &mut command_buffer,
             );
+            if let Some(timer) = &self.timer {
+                timer.end(&mut command_buffer);
+            }
             command_buffer.finish();
             command_buffer
         };
@@ -466,6 +656,9 @@ where
                 .submit_without_semaphores(Some(&command_buffer), Some(&self.fence));
             lock.device.wait_for_fence(&self.fence, !0).unwrap();
             self.command_pool.free(Some(command_buffer));
+            self.timer
+                .as_ref()
+                .and_then(|timer| timer.elapsed(&lock.device))
         }
     }
 
@@ -479,9 +672,12 @@ where
         &self.occupancy_buf
     }
 
-    /// Borrow the sampler
-    pub fn sampler(&self) -> &B::Sampler {
-        &self.sampler
+    /// Borrow the sampler for the given wrap mode. Falls back to the tiling
+    /// sampler for unsupported wrap modes.
+    pub fn sampler(&self, wrap_mode: hal::image::WrapMode) -> &B::Sampler {
+        self.samplers
+            .get(&wrap_mode)
+            .unwrap_or_else(|| &self.samplers[&hal::image::WrapMode::Tile])
     }
 
     /// Download a raw image from the GPU by copying it into a CPU visible
@@ -491,6 +687,7 @@ where
             return Err(DownloadError::NotBacked);
         }
 
+        image.await_consumer();
         let mut lock = self.gpu.lock().unwrap();
         let bytes = image.get_bytes() as u64;
 
@@ -588,6 +785,7 @@ where
     ) -> Result<(), BasicBufferBuilderError> {
         debug_assert!(image.is_backed());
 
+        image.await_consumer();
         let mut lock = self.gpu.lock().unwrap();
         let bytes = image.get_bytes() as u64;
         let u8s: &[u8] =
@@ -672,6 +870,9 @@ where
     pub fn copy_image(&mut self, from: &Image<B>, to: &Image<B>) {
         debug_assert!(from.is_backed() && to.is_backed());
 
+        from.await_consumer();
+        to.await_consumer();
+
         let mut lock = self.gpu.lock().unwrap();
 
         unsafe { lock.device.reset_fence(&self.fence).unwrap() };
@@ -755,6 +956,7 @@ where
     /// Create a thumbnail of the given image and return it
     pub fn generate_thumbnail(&mut self, image: &Image<B>, thumbnail: &ThumbnailIndex) {
         let thumbnail_image = self.thumbnail_cache.image(thumbnail);
+        image.await_consumer();
         let image_lock = image.get_raw().lock().unwrap();
 
         let mut lock = self.gpu.lock().unwrap();
@@ -851,7 +1053,7 @@ where
     pub fn new_thumbnail(&mut self, ty: lang::ImageType) -> ThumbnailIndex {
         match ty {
             lang::ImageType::Grayscale => self.thumbnail_cache.next(true),
-            lang::ImageType::Rgb => self.thumbnail_cache.next(false),
+            lang::ImageType::Rgb | lang::ImageType::Rgba => self.thumbnail_cache.next(false),
         }
     }
 
@@ -885,12 +1087,16 @@ where
                 .free_memory(ManuallyDrop::take(&mut self.uniform_mem));
             lock.device
                 .destroy_buffer(ManuallyDrop::take(&mut self.uniform_buf));
-            lock.device
-                .destroy_sampler(ManuallyDrop::take(&mut self.sampler));
+            for (_, sampler) in self.samplers.drain() {
+                lock.device.destroy_sampler(sampler);
+            }
             lock.device
                 .destroy_command_pool(ManuallyDrop::take(&mut self.command_pool));
             lock.device
                 .destroy_descriptor_pool(ManuallyDrop::take(&mut self.descriptor_pool));
+            if let Some(timer) = self.timer.take() {
+                timer.destroy(&lock.device);
+            }
         }
     }
 }
@@ -31,8 +31,14 @@ impl Drop for ThumbnailIndex {
 ///
 /// The cache is dynamically sized. If more thumbnails are required, another
 /// memory region will be allocated for them.
+///
+/// All thumbnails share a single format, regardless of the image type they
+/// are generated from. Grayscale thumbnails differ only in their view.
 pub struct ThumbnailCache<B: Backend> {
     gpu: Arc<Mutex<GPU<B>>>,
+    thumbnail_size: usize,
+    thumbnail_stride: u64,
+    memory_type: hal::MemoryTypeId,
     memory: SmallVec<[B::Memory; 4]>,
     images: Vec<Option<B::Image>>,
     views: Vec<Option<Arc<Mutex<B::ImageView>>>>,
@@ -61,16 +67,17 @@ impl<B> ThumbnailCache<B>
 where
     B: Backend,
 {
-    /// Pixel per side
-    pub const THUMBNAIL_SIZE: usize = 128;
+    /// Format of thumbnails
+    const THUMBNAIL_FORMAT: hal::format::Format = hal::format::Format::Rgba16Sfloat;
 
-    /// Size of a single thumbnail in bytes
-    const THUMBNAIL_BYTES: usize = Self::THUMBNAIL_SIZE * Self::THUMBNAIL_SIZE * 4;
+    /// Smallest supported thumbnail size, in pixels per side
+    const MIN_THUMBNAIL_SIZE: usize = 16;
 
-    /// Format of thumbnails
-    const THUMBNAIL_FORMAT: hal::format::Format = hal::format::Format::Rgba8Unorm;
+    /// Largest supported thumbnail size, in pixels per side
+    const MAX_THUMBNAIL_SIZE: usize = 512;
 
-    /// Size of a single allocation, in number of thumbnails. 512 is roughly 32M in memory
+    /// Size of a single allocation, in number of thumbnails. 512 is roughly
+    /// 64M in memory at a thumbnail size of 128.
     const THUMBNAIL_CHUNK_LENGTH: usize = 512;
 
     /// Swizzle setting for grayscale images
@@ -81,36 +88,64 @@ where
         hal::format::Component::A,
     );
 
-    /// Create a new thumbnail cache
-    pub fn new(gpu: Arc<Mutex<GPU<B>>>) -> Self {
-        let chunk = {
+    /// Create a new thumbnail cache, holding thumbnails of the given number of
+    /// pixels per side. The size is clamped to the supported range.
+    pub fn new(gpu: Arc<Mutex<GPU<B>>>, thumbnail_size: usize) -> Self {
+        let requested_size = thumbnail_size;
+        let thumbnail_size =
+            requested_size.clamp(Self::MIN_THUMBNAIL_SIZE, Self::MAX_THUMBNAIL_SIZE);
+        if thumbnail_size != requested_size {
+            log::warn!(
+                "Unsupported thumbnail size {}, using {} instead",
+                requested_size,
+                thumbnail_size
+            );
+        }
+
+        let (thumbnail_stride, memory_type, chunk) = {
             let lock = gpu.lock().unwrap();
 
+            // Thumbnails are placed in memory according to the requirements
+            // of an image of the given size, which may exceed the pixel data.
+            let probe = Self::create_image(&lock.device, thumbnail_size);
+            let requirements = unsafe { lock.device.get_image_requirements(&probe) };
+            unsafe { lock.device.destroy_image(probe) };
+            let thumbnail_stride = ((requirements.size + requirements.alignment - 1)
+                / requirements.alignment)
+                * requirements.alignment;
+
             let memory_type = lock
                 .memory_properties
                 .memory_types
                 .iter()
-                .position(|mem_type| {
-                    mem_type
-                        .properties
-                        .contains(hal::memory::Properties::DEVICE_LOCAL)
+                .enumerate()
+                .position(|(id, mem_type)| {
+                    requirements.type_mask & (1 << id) != 0
+                        && mem_type
+                            .properties
+                            .contains(hal::memory::Properties::DEVICE_LOCAL)
                 })
                 .unwrap()
                 .into();
 
-            unsafe {
+            let chunk = unsafe {
                 lock.device.allocate_memory(
                     memory_type,
-                    Self::THUMBNAIL_CHUNK_LENGTH as u64 * Self::THUMBNAIL_BYTES as u64,
+                    Self::THUMBNAIL_CHUNK_LENGTH as u64 * thumbnail_stride,
                 )
             }
-            .expect("Error allocating thumbnail memory")
+            .expect("Error allocating thumbnail memory");
+
+            (thumbnail_stride, memory_type, chunk)
         };
 
         let memory = smallvec![chunk];
 
         Self {
             gpu,
+            thumbnail_size,
+            thumbnail_stride,
+            memory_type,
             memory,
             images: (0..Self::THUMBNAIL_CHUNK_LENGTH).map(|_| None).collect(),
             views: (0..Self::THUMBNAIL_CHUNK_LENGTH).map(|_| None).collect(),
@@ -136,12 +171,11 @@ where
         }
     }
 
-    fn new_thumbnail_at(&mut self, i: usize, grayscale: bool) {
-        let lock = self.gpu.lock().unwrap();
-
-        let mut image = unsafe {
-            lock.device.create_image(
-                hal::image::Kind::D2(Self::THUMBNAIL_SIZE as _, Self::THUMBNAIL_SIZE as _, 1, 1),
+    /// Create an unbound thumbnail image of the given size.
+    fn create_image(device: &B::Device, thumbnail_size: usize) -> B::Image {
+        unsafe {
+            device.create_image(
+                hal::image::Kind::D2(thumbnail_size as _, thumbnail_size as _, 1, 1),
                 1,
                 Self::THUMBNAIL_FORMAT,
                 hal::image::Tiling::Linear,
@@ -149,16 +183,19 @@ where
                 hal::image::ViewCapabilities::empty(),
             )
         }
-        .expect("Error creating thumbnail image");
+        .expect("Error creating thumbnail image")
+    }
+
+    fn new_thumbnail_at(&mut self, i: usize, grayscale: bool) {
+        let lock = self.gpu.lock().unwrap();
+
+        let mut image = Self::create_image(&lock.device, self.thumbnail_size);
 
         let mem = &self.memory[i / Self::THUMBNAIL_CHUNK_LENGTH];
-        let offset = (i % Self::THUMBNAIL_CHUNK_LENGTH) * Self::THUMBNAIL_BYTES;
+        let offset = (i % Self::THUMBNAIL_CHUNK_LENGTH) as u64 * self.thumbnail_stride;
 
-        unsafe {
-            lock.device
-                .bind_image_memory(mem, offset as u64, &mut image)
-        }
-        .expect("Error binding thumbnail memory");
+        unsafe { lock.device.bind_image_memory(mem, offset, &mut image) }
+            .expect("Error binding thumbnail memory");
 
         let view = unsafe {
             lock.device.create_image_view(
@@ -183,22 +220,10 @@ where
         let new_chunk = {
             let lock = self.gpu.lock().unwrap();
 
-            let memory_type = lock
-                .memory_properties
-                .memory_types
-                .iter()
-                .position(|mem_type| {
-                    mem_type
-                        .properties
-                        .contains(hal::memory::Properties::DEVICE_LOCAL)
-                })
-                .unwrap()
-                .into();
-
             unsafe {
                 lock.device.allocate_memory(
-                    memory_type,
-                    Self::THUMBNAIL_CHUNK_LENGTH as u64 * Self::THUMBNAIL_BYTES as u64,
+                    self.memory_type,
+                    Self::THUMBNAIL_CHUNK_LENGTH as u64 * self.thumbnail_stride,
                 )
             }
             .expect("Error allocating thumbnail memory")
@@ -244,6 +269,6 @@ where
 
     /// The size of a single thumbnail, measured in pixels per side.
     pub fn thumbnail_size(&self) -> usize {
-        Self::THUMBNAIL_SIZE
+        self.thumbnail_size
     }
 }
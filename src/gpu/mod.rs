@@ -8,17 +8,19 @@ use thiserror::Error;
 
 pub use gfx_hal::{spec_const_list, Backend};
 pub use hal::buffer::SubRange;
-pub use hal::image::{Access, Layout};
+pub use hal::image::{Access, Layout, WrapMode};
 pub use hal::pso::{
     BufferDescriptorFormat, BufferDescriptorType, Descriptor, DescriptorSetLayoutBinding,
     DescriptorSetWrite, DescriptorType, ImageDescriptorType, ShaderStageFlags, Specialization,
 };
 pub use hal::window::Extent2D;
 pub use hal::Instance;
+pub use timer::{TimerQuery, TimestampProperties};
 
 pub mod basic_mem;
 pub mod compute;
 pub mod render;
+pub mod timer;
 pub mod ui;
 
 pub const COLOR_RANGE: hal::image::SubresourceRange = hal::image::SubresourceRange {
@@ -35,6 +37,7 @@ pub struct GPU<B: Backend> {
     adapter: hal::adapter::Adapter<B>,
     queue_group: hal::queue::QueueGroup<B>,
     memory_properties: hal::adapter::MemoryProperties,
+    timestamps: Option<TimestampProperties>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -70,6 +73,9 @@ pub enum PipelineError {
     /// Failed to map Uniform Buffer into CPU space
     #[error("Failed to map Uniform Buffer into CPU space")]
     UniformMapping,
+    /// Uniform data exceeds the size of the uniform buffer
+    #[error("Uniform data of {0} bytes exceeds the uniform buffer")]
+    UniformSize(usize),
     /// Errors during downloading of images
     #[error("Error during image download")]
     DownloadError(#[from] DownloadError),
@@ -86,6 +92,9 @@ pub enum DownloadError {
     /// Failed to map download buffer into CPU space
     #[error("Failed to map download buffer into CPU space")]
     Map,
+    /// Cannot download an image region without extent
+    #[error("Tried to download an empty image region")]
+    Empty,
 }
 
 #[derive(Debug, Error)]
@@ -98,11 +107,31 @@ pub enum BootError {
     UnsupportedBackend,
 }
 
+/// Name of the Khronos validation layer
+const VALIDATION_LAYER: &str = "VK_LAYER_KHRONOS_validation";
+
 /// Initialize the GPU, optionally headless. When headless is specified,
-/// no graphics capable family is required.
-pub fn initialize_gpu(headless: bool) -> Result<Arc<Mutex<GPU<back::Backend>>>, BootError> {
+/// no graphics capable family is required. Validation layers are enabled on
+/// request, which requires them to be installed on the system.
+pub fn initialize_gpu(
+    headless: bool,
+    validation: bool,
+) -> Result<Arc<Mutex<GPU<back::Backend>>>, BootError> {
     log::info!("Initializing GPU");
 
+    // gfx-hal offers no way to request layers, so we ask the Vulkan loader
+    // instead, keeping any layers already requested by the environment.
+    if validation {
+        log::info!("Enabling GPU validation layers");
+        let layers = match std::env::var("VK_INSTANCE_LAYERS") {
+            Ok(existing) if !existing.is_empty() => {
+                format!("{}:{}", existing, VALIDATION_LAYER)
+            }
+            _ => VALIDATION_LAYER.to_string(),
+        };
+        std::env::set_var("VK_INSTANCE_LAYERS", layers);
+    }
+
     let instance =
         back::Instance::create("surfacelab", 1).map_err(|_| BootError::UnsupportedBackend)?;
     let adapter = instance
@@ -120,7 +149,10 @@ pub fn initialize_gpu(headless: bool) -> Result<Arc<Mutex<GPU<back::Backend>>>,
             BootError::MissingGraphicsSupport
         })?;
 
-    let gpu = GPU::new(instance, adapter, headless);
+    let mut gpu = GPU::new(instance, adapter, headless);
+    gpu.timestamps = TimestampProperties::calibrate(&gpu.device, &mut gpu.queue_group);
+    log::debug!("Timestamp properties {:?}", gpu.timestamps);
+
     Ok(Arc::new(Mutex::new(gpu)))
 }
 
@@ -161,6 +193,7 @@ where
             queue_group,
             adapter,
             memory_properties,
+            timestamps: None,
         }
     }
 }
@@ -230,7 +263,9 @@ where
                 .format(format)
                 .tiling(hal::image::Tiling::Optimal)
                 .usage(if compute_target {
-                    hal::image::Usage::SAMPLED | hal::image::Usage::STORAGE
+                    hal::image::Usage::SAMPLED
+                        | hal::image::Usage::STORAGE
+                        | hal::image::Usage::TRANSFER_SRC
                 } else {
                     hal::image::Usage::COLOR_ATTACHMENT | hal::image::Usage::SAMPLED
                 })
@@ -254,6 +289,10 @@ where
         &self.view
     }
 
+    pub fn image(&self) -> &B::Image {
+        &self.image
+    }
+
     fn barrier_to(
         &mut self,
         access: hal::image::Access,
@@ -289,6 +328,16 @@ where
         }
     }
 
+    /// Barrier for reading the render target in a transfer operation. Only
+    /// compute targets can be used as a transfer source.
+    pub fn barrier_before_transfer(&mut self) -> hal::memory::Barrier<B> {
+        debug_assert!(self.compute_target);
+        self.barrier_to(
+            hal::image::Access::TRANSFER_READ,
+            hal::image::Layout::TransferSrcOptimal,
+        )
+    }
+
     pub fn barrier_after(&mut self) -> hal::memory::Barrier<B> {
         self.barrier_to(
             hal::image::Access::SHADER_READ,
@@ -144,32 +144,91 @@ where
         })
     }
 
-    /// Create environment maps from a path to a HDRi file. Expects .hdr,
-    /// equirectangular mapping!
+    /// Read an equirectangular HDRi from a Radiance HDR file, returning its
+    /// dimensions and pixel data.
+    fn read_hdr(path: &Path) -> Result<(u32, u32, Vec<image::Rgba<f32>>), EnvironmentError> {
+        use std::fs::File;
+        use std::io::BufReader;
+
+        let reader = BufReader::new(File::open(path).map_err(|_| EnvironmentError::HDRiIOFailure)?);
+        let decoder = hdr::HdrDecoder::new(reader).map_err(|_| EnvironmentError::HDRiIOFailure)?;
+        let metadata = decoder.metadata();
+        let raw_hdri = decoder
+            .read_image_hdr()
+            .map_err(|_| EnvironmentError::HDRiIOFailure)?
+            .iter()
+            .map(|rgb| image::Rgba([rgb[0], rgb[1], rgb[2], 1.0]))
+            .collect();
+
+        Ok((metadata.width, metadata.height, raw_hdri))
+    }
+
+    /// Read an equirectangular HDRi from an OpenEXR file, returning its
+    /// dimensions and pixel data. Both scanline and tiled files are supported,
+    /// reading the first RGB(A) layer.
+    ///
+    /// EXR files may contain values that are unsuitable for convolution, such
+    /// as NaNs, negative values, or extreme highlights. Values are therefore
+    /// clamped into the range representable by half floats.
+    fn read_exr(path: &Path) -> Result<(u32, u32, Vec<image::Rgba<f32>>), EnvironmentError> {
+        use exr::prelude::*;
+
+        fn compress(x: f32) -> f32 {
+            if x.is_nan() {
+                0.0
+            } else {
+                x.max(0.0).min(half::f16::MAX.to_f32())
+            }
+        }
+
+        let image = read_first_rgba_layer_from_file(
+            path,
+            |resolution, _| {
+                (
+                    resolution.width(),
+                    vec![image::Rgba([0.0, 0.0, 0.0, 1.0]); resolution.area()],
+                )
+            },
+            |(width, pixels): &mut (usize, Vec<image::Rgba<f32>>),
+             position,
+             (r, g, b, _a): (f32, f32, f32, f32)| {
+                pixels[position.y() * *width + position.x()] =
+                    image::Rgba([compress(r), compress(g), compress(b), 1.0]);
+            },
+        )
+        .map_err(|_| EnvironmentError::HDRiIOFailure)?;
+
+        let size = image.layer_data.size;
+        let (_, raw_hdri) = image.layer_data.channel_data.pixels;
+
+        Ok((size.width() as u32, size.height() as u32, raw_hdri))
+    }
+
+    /// Create environment maps from a path to a HDRi file. Expects .hdr or
+    /// .exr, equirectangular mapping!
     pub fn from_file<P: AsRef<Path>>(
         gpu: Arc<Mutex<GPU<B>>>,
         irradiance_size: usize,
         spec_size: usize,
         path: P,
     ) -> Result<Self, EnvironmentError> {
-        use std::fs::File;
-        use std::io::BufReader;
-
         // Initialize
         let env_maps = Self::init(gpu, irradiance_size, spec_size, path.as_ref().into())?;
 
         // Read data from file
         let start_io = Instant::now();
 
-        let reader = BufReader::new(File::open(path).map_err(|_| EnvironmentError::HDRiIOFailure)?);
-        let decoder = hdr::HdrDecoder::new(reader).map_err(|_| EnvironmentError::HDRiIOFailure)?;
-        let metadata = decoder.metadata();
-        let raw_hdri: Vec<image::Rgba<f32>> = decoder
-            .read_image_hdr()
-            .map_err(|_| EnvironmentError::HDRiIOFailure)?
-            .iter()
-            .map(|rgb| image::Rgba([rgb[0], rgb[1], rgb[2], 1.0]))
-            .collect();
+        let is_exr = path
+            .as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("exr"))
+            .unwrap_or(false);
+        let (width, height, raw_hdri) = if is_exr {
+            Self::read_exr(path.as_ref())?
+        } else {
+            Self::read_hdr(path.as_ref())?
+        };
 
         let raw_hdri_u8 = unsafe {
             std::slice::from_raw_parts(raw_hdri.as_ptr() as *const u8, raw_hdri.len() * 4)
@@ -185,7 +244,7 @@ where
 
         let (staging_buffer, staging_memory) =
             BasicBufferBuilder::new(&lock.memory_properties.memory_types)
-                .bytes((metadata.width * metadata.height * 4 * 4).max(BRDF_LUT_BYTES as u32) as u64)
+                .bytes((width * height * 4 * 4).max(BRDF_LUT_BYTES as u32) as u64)
                 .usage(hal::buffer::Usage::TRANSFER_SRC)
                 .data(raw_hdri_u8)
                 .memory_type(hal::memory::Properties::CPU_VISIBLE)
@@ -195,7 +254,7 @@ where
         // Move HDRi to device only memory for the compute shader
         let (equirect_image, equirect_memory, equirect_view) =
             BasicImageBuilder::new(&lock.memory_properties.memory_types)
-                .size_2d(metadata.width, metadata.height)
+                .size_2d(width, height)
                 .format(Self::FORMAT)
                 .mip_levels(EQUIRECT_MIP_LEVELS)
                 .usage(
@@ -244,12 +303,12 @@ where
                 hal::image::Layout::TransferDstOptimal,
                 Some(hal::command::BufferImageCopy {
                     buffer_offset: 0,
-                    buffer_width: metadata.width,
-                    buffer_height: metadata.height,
+                    buffer_width: width,
+                    buffer_height: height,
                     image_offset: hal::image::Offset { x: 0, y: 0, z: 0 },
                     image_extent: hal::image::Extent {
-                        width: metadata.width,
-                        height: metadata.height,
+                        width,
+                        height,
                         depth: 1,
                     },
                     image_layers: hal::image::SubresourceLayers {
@@ -296,8 +355,8 @@ where
                             layers: 0..1,
                         },
                         src_bounds: hal::image::Offset { x: 0, y: 0, z: 0 }..hal::image::Offset {
-                            x: metadata.width as i32 >> (level - 1),
-                            y: metadata.height as i32 >> (level - 1),
+                            x: width as i32 >> (level - 1),
+                            y: height as i32 >> (level - 1),
                             z: 1,
                         },
                         dst_subresource: hal::image::SubresourceLayers {
@@ -306,8 +365,8 @@ where
                             layers: 0..1,
                         },
                         dst_bounds: hal::image::Offset { x: 0, y: 0, z: 0 }..hal::image::Offset {
-                            x: metadata.width as i32 >> level,
-                            y: metadata.height as i32 >> level,
+                            x: width as i32 >> level,
+                            y: height as i32 >> level,
                             z: 1,
                         },
                     }),
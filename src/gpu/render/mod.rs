@@ -1,7 +1,7 @@
 use super::RenderTarget;
 use crate::lang::{ImageType, ObjectType, ShadingMode, ToneMap};
 use crate::shader;
-use crate::util::HaltonSequence2D;
+use crate::util::{SamplingSequence, SamplingSequence2D};
 use crate::{
     gpu::{basic_mem::*, load_shader},
     lang::{ParamBoxDescription, RenderField},
@@ -16,7 +16,7 @@ use strum_macros::*;
 use thiserror::Error;
 use zerocopy::AsBytes;
 
-use super::{Backend, PipelineError, GPU};
+use super::{Backend, DownloadError, PipelineError, TimerQuery, GPU};
 
 pub mod brdf_lut;
 pub mod environment;
@@ -64,6 +64,13 @@ pub enum InitializationError {
     RenderTarget(#[from] super::RenderTargetError),
 }
 
+/// Paths to the assets loaded by renderers when nothing else is specified.
+#[derive(Debug, Clone)]
+pub struct DefaultAssets {
+    pub hdri: std::path::PathBuf,
+    pub matcap: std::path::PathBuf,
+}
+
 pub struct GPURender<B: Backend, U: Renderer> {
     gpu: Arc<Mutex<GPU<B>>>,
     command_pool: ManuallyDrop<B::CommandPool>,
@@ -75,6 +82,7 @@ pub struct GPURender<B: Backend, U: Renderer> {
     accum_target: RenderTarget<B>,
     current_sample: usize,
     tone_map: ToneMap,
+    background_color: [f32; 4],
 
     // Uniforms and specific/optional data
     view: U,
@@ -82,7 +90,7 @@ pub struct GPURender<B: Backend, U: Renderer> {
     shading_mode: Option<ShadingMode>,
 
     // Rendering Data
-    halton_sampler: HaltonSequence2D,
+    sample_sequence: Box<dyn SamplingSequence2D>,
     descriptor_pool: ManuallyDrop<B::DescriptorPool>,
     main_render_pass: ManuallyDrop<B::RenderPass>,
     main_pipeline: ManuallyDrop<B::GraphicsPipeline>,
@@ -104,10 +112,15 @@ pub struct GPURender<B: Backend, U: Renderer> {
     uniform_memory: ManuallyDrop<B::Memory>,
     environment_maps: EnvironmentMaps<B>,
     matcap: Matcap<B>,
+    default_assets: DefaultAssets,
 
     // Synchronization
     complete_fence: ManuallyDrop<B::Fence>,
     transfer_fence: ManuallyDrop<B::Fence>,
+
+    // Profiling, if supported by the device
+    timer: Option<TimerQuery<B>>,
+    frame_time: Option<std::time::Duration>,
 }
 
 /// Uses of an image
@@ -120,6 +133,7 @@ pub enum ImageUse {
     Metallic,
     AmbientOcclusion,
     Alpha,
+    Emissive,
     View(ImageType),
 }
 
@@ -137,6 +151,7 @@ impl std::convert::TryFrom<crate::lang::OutputType> for ImageUse {
             OutputType::Metallic => Ok(ImageUse::Metallic),
             OutputType::AmbientOcclusion => Ok(ImageUse::AmbientOcclusion),
             OutputType::Alpha => Ok(ImageUse::Alpha),
+            OutputType::Emissive => Ok(ImageUse::Emissive),
             _ => Err("Invalid OutputType for ImageUse"),
         }
     }
@@ -151,6 +166,7 @@ pub struct ImageSlots<B: Backend> {
     metallic: ImageSlot<B>,
     ao: ImageSlot<B>,
     alpha: ImageSlot<B>,
+    emissive: ImageSlot<B>,
     view: ImageSlot<B>,
     view_type: ImageType,
 }
@@ -205,6 +221,12 @@ impl<B: Backend> ImageSlots<B> {
                 hal::format::Format::R16Sfloat,
                 image_size,
             )?,
+            emissive: ImageSlot::new(
+                device,
+                memory_properties,
+                hal::format::Format::Rgba16Sfloat,
+                image_size,
+            )?,
             view: ImageSlot::new(
                 device,
                 memory_properties,
@@ -228,6 +250,7 @@ impl<B: Backend> ImageSlots<B> {
             ImageUse::Metallic => &mut self.metallic,
             ImageUse::AmbientOcclusion => &mut self.ao,
             ImageUse::Alpha => &mut self.alpha,
+            ImageUse::Emissive => &mut self.emissive,
             ImageUse::View(..) => &mut self.view,
         };
 
@@ -252,10 +275,12 @@ impl<B: Backend> ImageSlots<B> {
             metallic: from_bool(self.metallic.occupied),
             ao: from_bool(self.ao.occupied),
             alpha: from_bool(self.alpha.occupied),
+            emissive: from_bool(self.emissive.occupied),
             view: from_bool(self.view.occupied),
             view_type: match self.view_type {
                 ImageType::Grayscale => 0,
                 ImageType::Rgb => 1,
+                ImageType::Rgba => 2,
             },
         }
     }
@@ -285,6 +310,7 @@ where
         free_slot(&lock.device, &mut self.displacement);
         free_slot(&lock.device, &mut self.metallic);
         free_slot(&lock.device, &mut self.ao);
+        free_slot(&lock.device, &mut self.emissive);
         free_slot(&lock.device, &mut self.view);
     }
 }
@@ -301,6 +327,7 @@ pub struct SlotOccupancy {
     metallic: u32,
     ao: u32,
     alpha: u32,
+    emissive: u32,
     view: u32,
     view_type: u32,
 }
@@ -390,6 +417,12 @@ struct RendererSettings {
     shading_mode: Option<ShadingMode>,
     hdri_path: std::path::PathBuf,
     matcap_path: std::path::PathBuf,
+    #[serde(default = "default_background_color")]
+    background_color: [f32; 4],
+}
+
+fn default_background_color() -> [f32; 4] {
+    [0., 0., 0., 1.]
 }
 
 impl<B, U> GPURender<B, U>
@@ -405,6 +438,8 @@ where
         gpu: &Arc<Mutex<GPU<B>>>,
         monitor_dimensions: (u32, u32),
         viewport_dimensions: (u32, u32),
+        sampling: SamplingSequence,
+        default_assets: &DefaultAssets,
         view: U,
     ) -> Result<Self, InitializationError> {
         log::info!("Obtaining GPU Render Resources");
@@ -421,20 +456,10 @@ where
             gpu.clone(),
             IRRADIANCE_SIZE,
             SPECMAP_SIZE,
-            find_folder::Search::KidsThenParents(3, 5)
-                .for_folder("assets")
-                .unwrap()
-                .join("artist_workshop_2k.hdr"),
-        )
-        .unwrap();
-        let matcap = Matcap::from_file(
-            gpu.clone(),
-            find_folder::Search::KidsThenParents(3, 5)
-                .for_folder("assets")
-                .unwrap()
-                .join("matcap.png"),
+            &default_assets.hdri,
         )
         .unwrap();
+        let matcap = Matcap::from_file(gpu.clone(), &default_assets.matcap).unwrap();
 
         let lock = gpu.lock().unwrap();
         log::debug!("Using render format {:?}", Self::FINAL_FORMAT);
@@ -650,6 +675,17 @@ where
                         stage_flags: hal::pso::ShaderStageFlags::FRAGMENT,
                         immutable_samplers: false,
                     },
+                    hal::pso::DescriptorSetLayoutBinding {
+                        binding: 15,
+                        ty: hal::pso::DescriptorType::Image {
+                            ty: hal::pso::ImageDescriptorType::Sampled {
+                                with_sampler: false,
+                            },
+                        },
+                        count: 1,
+                        stage_flags: hal::pso::ShaderStageFlags::FRAGMENT,
+                        immutable_samplers: false,
+                    },
                 ],
                 &[],
             )
@@ -753,6 +789,7 @@ where
         // Synchronization primitives
         let fence = lock.device.create_fence(true).unwrap();
         let tfence = lock.device.create_fence(false).unwrap();
+        let timer = TimerQuery::new(&lock.device, lock.timestamps);
 
         Ok(GPURender {
             gpu: gpu.clone(),
@@ -767,12 +804,13 @@ where
             accum_target,
             current_sample: 0,
             tone_map: ToneMap::Reinhard,
+            background_color: default_background_color(),
 
             view,
             object_type: None,
             shading_mode: None,
 
-            halton_sampler: HaltonSequence2D::default(),
+            sample_sequence: sampling.sequence_2d(),
             descriptor_pool: ManuallyDrop::new(descriptor_pool),
             main_render_pass: ManuallyDrop::new(main_render_pass),
             main_pipeline: ManuallyDrop::new(main_pipeline),
@@ -789,6 +827,7 @@ where
 
             environment_maps,
             matcap,
+            default_assets: default_assets.clone(),
 
             occupancy_buffer: ManuallyDrop::new(occupancy_buf),
             occupancy_memory: ManuallyDrop::new(occupancy_mem),
@@ -797,6 +836,9 @@ where
 
             complete_fence: ManuallyDrop::new(fence),
             transfer_fence: ManuallyDrop::new(tfence),
+
+            timer,
+            frame_time: None,
         })
     }
 
@@ -838,6 +880,20 @@ where
                             visibility: VisibilityFunction::default(),
                             presetable: false,
                         },
+                        Parameter {
+                            name: "background-color".to_string(),
+                            control: Control::RgbColor {
+                                value: [
+                                    self.background_color[0],
+                                    self.background_color[1],
+                                    self.background_color[2],
+                                ],
+                            },
+                            transmitter: RenderField::BackgroundColor,
+                            expose_status: None,
+                            visibility: VisibilityFunction::default(),
+                            presetable: false,
+                        },
                     ],
                 },
                 ParamCategory {
@@ -902,6 +958,7 @@ where
             shading_mode: self.shading_mode,
             hdri_path: self.environment_maps.path().clone(),
             matcap_path: self.matcap.path().clone(),
+            background_color: self.background_color,
         })
     }
 
@@ -911,9 +968,32 @@ where
         self.tone_map = settings.tone_map;
         self.object_type = settings.object_type;
         self.shading_mode = settings.shading_mode;
-        self.load_environment(&settings.hdri_path)
+        self.background_color = settings.background_color;
+
+        // Settings may stem from another installation, so assets referenced in
+        // them are not guaranteed to exist.
+        let hdri_path = if settings.hdri_path.exists() {
+            settings.hdri_path
+        } else {
+            log::warn!(
+                "HDRi {:?} not found, falling back to default",
+                settings.hdri_path
+            );
+            self.default_assets.hdri.clone()
+        };
+        let matcap_path = if settings.matcap_path.exists() {
+            settings.matcap_path
+        } else {
+            log::warn!(
+                "Matcap {:?} not found, falling back to default",
+                settings.matcap_path
+            );
+            self.default_assets.matcap.clone()
+        };
+
+        self.load_environment(&hdri_path)
             .expect("Failed to load hdri");
-        self.load_matcap(&settings.matcap_path)
+        self.load_matcap(&matcap_path)
             .expect("Failed to load matcap");
         Ok(())
     }
@@ -1034,7 +1114,7 @@ where
         let pipeline_layout = unsafe {
             device.create_pipeline_layout(
                 std::iter::once(set_layout),
-                &[(hal::pso::ShaderStageFlags::COMPUTE, 0..8)],
+                &[(hal::pso::ShaderStageFlags::COMPUTE, 0..32)],
             )
         }?;
 
@@ -1136,7 +1216,7 @@ where
     /// Reset the sampling process.
     pub fn reset_sampling(&mut self) {
         self.current_sample = 0;
-        self.halton_sampler = HaltonSequence2D::default();
+        self.sample_sequence.reset();
     }
 
     /// Render a single frame
@@ -1304,6 +1384,15 @@ where
                                 hal::image::Layout::ShaderReadOnlyOptimal,
                             )),
                         },
+                        DescriptorSetWrite {
+                            set: &self.main_descriptor_set,
+                            binding: 15,
+                            array_offset: 0,
+                            descriptors: Some(Descriptor::Image(
+                                &*image_slots.emissive.view,
+                                hal::image::Layout::ShaderReadOnlyOptimal,
+                            )),
+                        },
                         DescriptorSetWrite {
                             set: &self.accum_descriptor_set,
                             binding: 0,
@@ -1339,6 +1428,9 @@ where
             let cmd_buffer = unsafe {
                 let mut cmd_buffer = self.command_pool.allocate_one(hal::command::Level::Primary);
                 cmd_buffer.begin_primary(hal::command::CommandBufferFlags::ONE_TIME_SUBMIT);
+                if let Some(timer) = &self.timer {
+                    timer.begin(&mut cmd_buffer);
+                }
                 cmd_buffer.set_viewports(0, &[self.viewport.clone()]);
                 cmd_buffer.set_scissors(0, &[self.viewport.rect]);
 
@@ -1416,6 +1508,16 @@ where
                             families: None,
                             range: IMG_SLOT_RANGE.clone(),
                         },
+                        hal::memory::Barrier::Image {
+                            states: (hal::image::Access::empty(), hal::image::Layout::Undefined)
+                                ..(
+                                    hal::image::Access::SHADER_READ,
+                                    hal::image::Layout::ShaderReadOnlyOptimal,
+                                ),
+                            target: &*image_slots.emissive.image,
+                            families: None,
+                            range: IMG_SLOT_RANGE.clone(),
+                        },
                         hal::memory::Barrier::Image {
                             states: (hal::image::Access::empty(), hal::image::Layout::Undefined)
                                 ..(
@@ -1442,7 +1544,7 @@ where
                 );
                 cmd_buffer.bind_graphics_pipeline(&self.main_pipeline);
 
-                let sample_offset = self.halton_sampler.next().unwrap();
+                let sample_offset = self.sample_sequence.next().unwrap();
                 cmd_buffer.push_graphics_constants(
                     &self.main_pipeline_layout,
                     hal::pso::ShaderStageFlags::FRAGMENT,
@@ -1459,6 +1561,9 @@ where
                     &[],
                     hal::command::SubpassContents::Inline,
                 );
+                // Samples are accumulated by additive blending, so the
+                // target is cleared to zero rather than the background color.
+                // The background is composited in the accumulation shader.
                 if self.current_sample == 0 {
                     cmd_buffer.clear_attachments(
                         &[hal::command::AttachmentClear::Color {
@@ -1493,6 +1598,12 @@ where
                     &[
                         u32::from_ne_bytes(((self.current_sample + 1) as f32).to_ne_bytes()),
                         self.tone_map as u32,
+                        0,
+                        0,
+                        u32::from_ne_bytes(self.background_color[0].to_ne_bytes()),
+                        u32::from_ne_bytes(self.background_color[1].to_ne_bytes()),
+                        u32::from_ne_bytes(self.background_color[2].to_ne_bytes()),
+                        u32::from_ne_bytes(self.background_color[3].to_ne_bytes()),
                     ],
                 );
                 cmd_buffer.dispatch([self.viewport.rect.w as u32, self.viewport.rect.h as u32, 1]);
@@ -1502,6 +1613,9 @@ where
                     hal::memory::Dependencies::empty(),
                     &[self.accum_target.barrier_after()],
                 );
+                if let Some(timer) = &self.timer {
+                    timer.end(&mut cmd_buffer);
+                }
                 cmd_buffer.finish();
 
                 cmd_buffer
@@ -1519,6 +1633,10 @@ where
                 lock.device
                     .wait_for_fence(&self.complete_fence, 10_000_000_000)
                     .map_err(|_| RenderError::FenceTimeout)?;
+                self.frame_time = self
+                    .timer
+                    .as_ref()
+                    .and_then(|timer| timer.elapsed(&lock.device));
             }
 
             unsafe {
@@ -1532,11 +1650,107 @@ where
         Ok(())
     }
 
+    /// Take the GPU time spent on the last rendered frame, if it was measured
+    /// and has not been taken yet.
+    pub fn take_frame_time(&mut self) -> Option<std::time::Duration> {
+        self.frame_time.take()
+    }
+
     /// Obtain an image view for the render target
     pub fn target_view(&self) -> &Arc<Mutex<B::ImageView>> {
         self.accum_target.image_view()
     }
 
+    /// Download the current render result, i.e. the tone mapped contents of
+    /// the accumulation target in the viewport region. Returns the width and
+    /// height of the frame along with the raw RGBA data in half precision.
+    pub fn download_accum(&mut self) -> Result<(u32, u32, Vec<u8>), DownloadError> {
+        let width = self.viewport.rect.w as u32;
+        let height = self.viewport.rect.h as u32;
+        if width == 0 || height == 0 {
+            return Err(DownloadError::Empty);
+        }
+        let bytes = width as u64 * height as u64 * 8;
+
+        let mut lock = self.gpu.lock().unwrap();
+
+        let (buf, mem) = BasicBufferBuilder::new(&lock.memory_properties.memory_types)
+            .bytes(bytes)
+            .usage(hal::buffer::Usage::TRANSFER_DST)
+            .memory_type(hal::memory::Properties::CPU_VISIBLE)
+            .expect("Failed to build CPU visible download buffer")
+            .build::<B>(&lock.device)?;
+
+        unsafe {
+            let mut cmd_buffer = self.command_pool.allocate_one(hal::command::Level::Primary);
+            cmd_buffer.begin_primary(hal::command::CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buffer.pipeline_barrier(
+                hal::pso::PipelineStage::COMPUTE_SHADER..hal::pso::PipelineStage::TRANSFER,
+                hal::memory::Dependencies::empty(),
+                std::iter::once(self.accum_target.barrier_before_transfer()),
+            );
+            cmd_buffer.copy_image_to_buffer(
+                self.accum_target.image(),
+                hal::image::Layout::TransferSrcOptimal,
+                &buf,
+                Some(hal::command::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_width: width,
+                    buffer_height: height,
+                    image_offset: hal::image::Offset { x: 0, y: 0, z: 0 },
+                    image_extent: hal::image::Extent {
+                        width,
+                        height,
+                        depth: 1,
+                    },
+                    image_layers: hal::image::SubresourceLayers {
+                        aspects: hal::format::Aspects::COLOR,
+                        level: 0,
+                        layers: 0..1,
+                    },
+                }),
+            );
+            cmd_buffer.pipeline_barrier(
+                hal::pso::PipelineStage::TRANSFER..hal::pso::PipelineStage::FRAGMENT_SHADER,
+                hal::memory::Dependencies::empty(),
+                std::iter::once(self.accum_target.barrier_after()),
+            );
+            cmd_buffer.finish();
+
+            lock.device.reset_fence(&*self.transfer_fence).unwrap();
+            lock.queue_group.queues[0]
+                .submit_without_semaphores(Some(&cmd_buffer), Some(&self.transfer_fence));
+            lock.device
+                .wait_for_fence(&*self.transfer_fence, 5_000_000_000)
+                .unwrap();
+            self.command_pool.free(Some(cmd_buffer));
+        }
+
+        let res = unsafe {
+            let mapping = lock
+                .device
+                .map_memory(
+                    &mem,
+                    hal::memory::Segment {
+                        offset: 0,
+                        size: Some(bytes),
+                    },
+                )
+                .map_err(|_| DownloadError::Map)?;
+            let slice = std::slice::from_raw_parts::<u8>(mapping as *const u8, bytes as usize);
+            let owned = slice.to_owned();
+            lock.device.unmap_memory(&mem);
+            owned
+        };
+
+        unsafe {
+            lock.device.free_memory(mem);
+            lock.device.destroy_buffer(buf);
+        }
+
+        Ok((width, height, res))
+    }
+
     /// Transfer an external (usually compute) image to an image slot in the
     /// ImageSlots struct passed in. This allows hijacking the command buffers
     /// of this renderer to fulfil this task on externally held images.
@@ -1565,6 +1779,7 @@ where
             ImageUse::Metallic => &mut image_slots.metallic,
             ImageUse::AmbientOcclusion => &mut image_slots.ao,
             ImageUse::Alpha => &mut image_slots.alpha,
+            ImageUse::Emissive => &mut image_slots.emissive,
             ImageUse::View(..) => &mut image_slots.view,
         };
 
@@ -1710,6 +1925,12 @@ where
     pub fn set_tone_map(&mut self, tone_map: ToneMap) {
         self.tone_map = tone_map;
     }
+
+    /// Set the background color, shown wherever the rendered image is not
+    /// fully opaque.
+    pub fn set_background_color(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.background_color = [r, g, b, a];
+    }
 }
 
 impl<B, U> Drop for GPURender<B, U>
@@ -1763,6 +1984,9 @@ where
                 .destroy_fence(ManuallyDrop::take(&mut self.complete_fence));
             lock.device
                 .destroy_fence(ManuallyDrop::take(&mut self.transfer_fence));
+            if let Some(timer) = self.timer.take() {
+                timer.destroy(&lock.device);
+            }
         }
     }
 }
@@ -1,7 +1,8 @@
-use super::{GPURender, InitializationError, Renderer};
+use super::{DefaultAssets, GPURender, InitializationError, Renderer};
 use crate::{
     gpu::{Backend, GPU},
-    lang::{ParamBoxDescription, RenderField},
+    lang::{ParamBoxDescription, ParameterBool, RenderField},
+    util::SamplingSequence,
 };
 use crate::{lang::MaterialChannel, shader};
 use serde_derive::{Deserialize, Serialize};
@@ -22,6 +23,8 @@ pub struct Uniforms {
     pan: [f32; 2],
     zoom: f32,
     channel: MaterialChannel,
+    #[serde(default)]
+    show_hdri_background: ParameterBool,
 }
 
 impl Default for Uniforms {
@@ -31,6 +34,7 @@ impl Default for Uniforms {
             pan: [0., 0.],
             zoom: 1.,
             channel: MaterialChannel::Displacement,
+            show_hdri_background: 0,
         }
     }
 }
@@ -63,7 +67,26 @@ impl Renderer for Uniforms {
 
     fn parameters(&self) -> ParamBoxDescription<RenderField> {
         use crate::lang::parameters::*;
-        ParamBoxDescription::empty()
+
+        ParamBoxDescription {
+            box_title: "renderer".to_string(),
+            preset_tag: Some("renderer".to_string()),
+            categories: vec![ParamCategory {
+                name: "environment",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![Parameter {
+                    name: "hdri-background".to_string(),
+                    control: Control::Toggle {
+                        def: self.show_hdri_background == 1,
+                    },
+                    transmitter: RenderField::ShowHdriBackground,
+                    expose_status: None,
+                    visibility: VisibilityFunction::default(),
+                    presetable: false,
+                }],
+            }],
+        }
     }
 }
 
@@ -75,11 +98,15 @@ where
         gpu: &Arc<Mutex<GPU<B>>>,
         monitor_dimensions: (u32, u32),
         viewport_dimensions: (u32, u32),
+        sampling: SamplingSequence,
+        default_assets: &DefaultAssets,
     ) -> Result<Self, InitializationError> {
         let mut renderer = Self::new(
             gpu,
             monitor_dimensions,
             viewport_dimensions,
+            sampling,
+            default_assets,
             Uniforms::default(),
         )?;
 
@@ -108,4 +135,9 @@ where
     pub fn set_channel(&mut self, channel: MaterialChannel) {
         self.view.channel = channel;
     }
+
+    /// Set whether the HDRI should be drawn behind the material
+    pub fn set_show_hdri_background(&mut self, show: bool) {
+        self.view.show_hdri_background = show as ParameterBool;
+    }
 }
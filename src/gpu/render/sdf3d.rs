@@ -1,9 +1,10 @@
-use super::{GPURender, InitializationError, Renderer};
-use crate::lang::{LightType, ObjectType, ParameterBool, ShadingMode};
+use super::{DefaultAssets, GPURender, InitializationError, Renderer};
+use crate::lang::{Light, LightType, ObjectType, ParameterBool, ShadingMode};
 use crate::shader;
 use crate::{
     gpu::{Backend, GPU},
     lang::{ParamBoxDescription, RenderField},
+    util::SamplingSequence,
 };
 use gfx_hal as hal;
 use gfx_hal::prelude::*;
@@ -19,12 +20,46 @@ static MAIN_FRAGMENT_SHADER_3D: &[u8] = shader!("sdf3d");
 /// with real displacement. Designed for temporal multisampling
 pub type RendererSDF3D<B> = GPURender<B, Uniforms>;
 
+/// Maximum number of lights supported by the renderer. Must match the constant
+/// in the fragment shader.
+pub const MAX_LIGHTS: usize = 8;
+
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize)]
+#[repr(C)]
+/// GPU side representation of a light, laid out according to std140.
+pub struct LightData {
+    position: [f32; 4],
+    color: [f32; 4],
+    light_type: LightType,
+    strength: f32,
+    size: f32,
+    padding: f32,
+}
+
+impl From<Light> for LightData {
+    fn from(light: Light) -> Self {
+        Self {
+            position: [light.position[0], light.position[1], light.position[2], 0.],
+            color: [light.color[0], light.color[1], light.color[2], 1.],
+            light_type: light.light_type,
+            strength: light.strength,
+            size: light.size,
+            padding: 0.,
+        }
+    }
+}
+
+impl Default for LightData {
+    fn default() -> Self {
+        Light::default().into()
+    }
+}
+
 #[derive(AsBytes, Debug, Serialize, Deserialize)]
 #[repr(C)]
 /// Uniforms for a 3D Renderer
 pub struct Uniforms {
     center: [f32; 4],
-    light_pos: [f32; 4],
     resolution: [f32; 2],
     focal_length: f32,
     aperture_size: f32,
@@ -44,11 +79,93 @@ pub struct Uniforms {
     environment_rotation: f32,
     ambient_occlusion_strength: f32,
 
-    light_type: LightType,
-    light_strength: f32,
-    light_size: f32,
     fog_strength: f32,
     shadow: ParameterBool,
+
+    #[serde(default = "default_light_count")]
+    light_count: u32,
+    #[serde(default)]
+    wireframe: ParameterBool,
+
+    #[serde(default = "default_sss_color")]
+    sss_color: [f32; 3],
+    #[serde(default = "default_sss_strength")]
+    sss_strength: f32,
+
+    #[serde(default = "default_lights")]
+    lights: [LightData; MAX_LIGHTS],
+
+    #[serde(default = "default_wire_width")]
+    wire_width: f32,
+    #[serde(default = "default_capsule_radius")]
+    capsule_radius: f32,
+    #[serde(default = "default_capsule_length")]
+    capsule_length: f32,
+    #[serde(default = "default_cone_height")]
+    cone_height: f32,
+    #[serde(default = "default_cone_angle")]
+    cone_angle: f32,
+    #[serde(skip)]
+    padding: [f32; 3],
+}
+
+fn default_light_count() -> u32 {
+    1
+}
+
+fn default_lights() -> [LightData; MAX_LIGHTS] {
+    [LightData::default(); MAX_LIGHTS]
+}
+
+fn default_sss_color() -> [f32; 3] {
+    [0.8, 0.2, 0.1]
+}
+
+fn default_sss_strength() -> f32 {
+    0.5
+}
+
+fn default_wire_width() -> f32 {
+    1.0
+}
+
+fn default_capsule_radius() -> f32 {
+    1.0
+}
+
+/// Distance between the centers of the two capsule caps
+fn default_capsule_length() -> f32 {
+    2.0
+}
+
+fn default_cone_height() -> f32 {
+    2.0
+}
+
+/// Half angle of the cone at its tip, in radians
+fn default_cone_angle() -> f32 {
+    std::f32::consts::FRAC_PI_6
+}
+
+impl Uniforms {
+    /// Radius of a sphere around the origin containing the undisplaced
+    /// object, following the signed distance functions in `sdf3d.frag`.
+    /// Unbounded objects are covered up to the region shown in full.
+    fn bounding_radius(&self, object_type: ObjectType) -> f32 {
+        match object_type {
+            ObjectType::Plane | ObjectType::FinitePlane => 2. * std::f32::consts::SQRT_2,
+            ObjectType::Cube => 3f32.sqrt(),
+            ObjectType::Sphere => 1.,
+            ObjectType::Cylinder => {
+                (2f32.powi(2) + (2. * std::f32::consts::PI / 3.).powi(2)).sqrt()
+            }
+            ObjectType::InfiniteCylinder => 2. * std::f32::consts::SQRT_2,
+            ObjectType::Capsule => self.capsule_length / 2. + self.capsule_radius,
+            ObjectType::Cone => ((self.cone_height / 2.).powi(2)
+                + (self.cone_height * self.cone_angle.tan()).powi(2))
+            .sqrt(),
+        }
+    }
 }
 
 impl Default for Uniforms {
@@ -56,7 +173,6 @@ impl Default for Uniforms {
         Self {
             resolution: [1024.0, 1024.0],
             center: [0., 0., 0., 0.],
-            light_pos: [0., 3., 0., 0.],
             focal_length: 1.0,
             aperture_size: 0.0,
             aperture_blades: 6,
@@ -71,11 +187,19 @@ impl Default for Uniforms {
             environment_blur: 3.0,
             environment_rotation: 0.,
             ambient_occlusion_strength: 0.5,
-            light_type: LightType::PointLight,
-            light_strength: 100.0,
-            light_size: 1.0,
             fog_strength: 0.0,
             shadow: 1,
+            light_count: default_light_count(),
+            wireframe: 0,
+            sss_color: default_sss_color(),
+            sss_strength: default_sss_strength(),
+            lights: default_lights(),
+            wire_width: default_wire_width(),
+            capsule_radius: default_capsule_radius(),
+            capsule_length: default_capsule_length(),
+            cone_height: default_cone_height(),
+            cone_angle: default_cone_angle(),
+            padding: [0.; 3],
         }
     }
 }
@@ -156,6 +280,79 @@ impl Renderer for Uniforms {
                             visibility: VisibilityFunction::default(),
                             presetable: false,
                         },
+                        Parameter {
+                            name: "wireframe-overlay".to_string(),
+                            control: Control::Toggle {
+                                def: self.wireframe == 1,
+                            },
+                            transmitter: RenderField::WireframeOverlay,
+                            expose_status: None,
+                            visibility: VisibilityFunction::default(),
+                            presetable: false,
+                        },
+                        Parameter {
+                            name: "wire-width".to_string(),
+                            control: Control::Slider {
+                                value: self.wire_width,
+                                min: 0.5,
+                                max: 8.0,
+                            },
+                            transmitter: RenderField::WireWidth,
+                            expose_status: None,
+                            visibility: VisibilityFunction::on_parameter(
+                                "wireframe-overlay",
+                                |c| matches!(c, Control::Toggle { def: true }),
+                            ),
+                            presetable: false,
+                        },
+                        Parameter {
+                            name: "capsule-radius".to_string(),
+                            control: Control::Slider {
+                                value: self.capsule_radius,
+                                min: 0.1,
+                                max: 2.0,
+                            },
+                            transmitter: RenderField::CapsuleRadius,
+                            expose_status: None,
+                            visibility: VisibilityFunction::default(),
+                            presetable: false,
+                        },
+                        Parameter {
+                            name: "capsule-length".to_string(),
+                            control: Control::Slider {
+                                value: self.capsule_length,
+                                min: 0.0,
+                                max: 4.0,
+                            },
+                            transmitter: RenderField::CapsuleLength,
+                            expose_status: None,
+                            visibility: VisibilityFunction::default(),
+                            presetable: false,
+                        },
+                        Parameter {
+                            name: "cone-height".to_string(),
+                            control: Control::Slider {
+                                value: self.cone_height,
+                                min: 0.1,
+                                max: 4.0,
+                            },
+                            transmitter: RenderField::ConeHeight,
+                            expose_status: None,
+                            visibility: VisibilityFunction::default(),
+                            presetable: false,
+                        },
+                        Parameter {
+                            name: "cone-angle".to_string(),
+                            control: Control::Slider {
+                                value: self.cone_angle,
+                                min: 0.05,
+                                max: 1.5,
+                            },
+                            transmitter: RenderField::ConeAngle,
+                            expose_status: None,
+                            visibility: VisibilityFunction::default(),
+                            presetable: false,
+                        },
                     ],
                 },
                 ParamCategory {
@@ -208,14 +405,10 @@ impl Renderer for Uniforms {
                             },
                             transmitter: RenderField::AoStrength,
                             expose_status: None,
-                            visibility: VisibilityFunction::on_parameter("shading-mode", |c| {
-                                if let Control::Enum { selected, .. } = c {
-                                    unsafe { ShadingMode::from_unchecked(*selected as u32) }
-                                        .has_lights()
-                                } else {
-                                    false
-                                }
-                            }),
+                            visibility: VisibilityFunction::on_parameter_enum(
+                                "shading-mode",
+                                ShadingMode::has_lights,
+                            ),
                             presetable: false,
                         },
                         Parameter {
@@ -235,30 +428,56 @@ impl Renderer for Uniforms {
                 ParamCategory {
                     name: "matcap",
                     is_open: true,
-                    visibility: VisibilityFunction::on_parameter("shading-mode", |c| {
-                        if let Control::Enum { selected, .. } = c {
-                            unsafe { ShadingMode::from_unchecked(*selected as u32) }.has_matcap()
-                        } else {
-                            false
-                        }
-                    }),
+                    visibility: VisibilityFunction::on_parameter_enum(
+                        "shading-mode",
+                        ShadingMode::has_matcap,
+                    ),
                     parameters: vec![],
                 },
+                ParamCategory {
+                    name: "subsurface",
+                    is_open: true,
+                    visibility: VisibilityFunction::on_parameter_enum(
+                        "shading-mode",
+                        ShadingMode::has_subsurface,
+                    ),
+                    parameters: vec![
+                        Parameter {
+                            name: "sss-color".to_string(),
+                            control: Control::RgbColor {
+                                value: self.sss_color,
+                            },
+                            transmitter: RenderField::SssColor,
+                            expose_status: None,
+                            visibility: VisibilityFunction::default(),
+                            presetable: false,
+                        },
+                        Parameter {
+                            name: "sss-strength".to_string(),
+                            control: Control::Slider {
+                                value: self.sss_strength,
+                                min: 0.0,
+                                max: 4.0,
+                            },
+                            transmitter: RenderField::SssStrength,
+                            expose_status: None,
+                            visibility: VisibilityFunction::default(),
+                            presetable: false,
+                        },
+                    ],
+                },
                 ParamCategory {
                     name: "light",
                     is_open: true,
-                    visibility: VisibilityFunction::on_parameter("shading-mode", |c| {
-                        if let Control::Enum { selected, .. } = c {
-                            unsafe { ShadingMode::from_unchecked(*selected as u32) }.has_lights()
-                        } else {
-                            false
-                        }
-                    }),
+                    visibility: VisibilityFunction::on_parameter_enum(
+                        "shading-mode",
+                        ShadingMode::has_lights,
+                    ),
                     parameters: vec![
                         Parameter {
                             name: "light-type".to_string(),
                             control: Control::Enum {
-                                selected: self.light_type as usize,
+                                selected: self.lights[0].light_type as usize,
                                 variants: LightType::VARIANTS
                                     .iter()
                                     .map(|x| x.to_string())
@@ -272,7 +491,7 @@ impl Renderer for Uniforms {
                         Parameter {
                             name: "light-strength".to_string(),
                             control: Control::Slider {
-                                value: self.light_strength,
+                                value: self.lights[0].strength,
                                 min: 0.0,
                                 max: 1000.0,
                             },
@@ -284,7 +503,7 @@ impl Renderer for Uniforms {
                         Parameter {
                             name: "light-size".to_string(),
                             control: Control::Slider {
-                                value: self.light_size,
+                                value: self.lights[0].size,
                                 min: 0.01,
                                 max: 2.0,
                             },
@@ -386,11 +605,15 @@ where
         gpu: &Arc<Mutex<GPU<B>>>,
         monitor_dimensions: (u32, u32),
         viewport_dimensions: (u32, u32),
+        sampling: SamplingSequence,
+        default_assets: &DefaultAssets,
     ) -> Result<Self, InitializationError> {
         let mut renderer = Self::new(
             gpu,
             monitor_dimensions,
             viewport_dimensions,
+            sampling,
+            default_assets,
             Uniforms::default(),
         )?;
 
@@ -485,6 +708,27 @@ where
         self.view.center[2] = y;
     }
 
+    /// Reset the camera to its default orientation, looking at the origin
+    /// from a distance at which the entire object is in view.
+    pub fn center_camera(&mut self) {
+        let default = Uniforms::default();
+
+        let radius = self
+            .view
+            .bounding_radius(self.object_type.unwrap_or(ObjectType::Cube))
+            + self.view.displacement.abs();
+
+        // Half the field of view along the narrower image axis, as set up by
+        // the camera in the fragment shader
+        let aspect = self.view.resolution[0] / self.view.resolution[1];
+        let half_fov = (0.5 * aspect.min(1.) / self.view.focal_length).atan();
+
+        self.view.center = default.center;
+        self.view.phi = default.phi;
+        self.view.theta = default.theta;
+        self.view.rad = 1.2 * radius / half_fov.sin();
+    }
+
     /// Pan the camera given screen space input deltas
     pub fn pan_camera(&mut self, x: f32, y: f32) {
         let point = (self.view.theta.cos(), self.view.theta.sin());
@@ -501,10 +745,36 @@ where
         self.view.rad += z;
     }
 
-    /// Move the light given screen space input deltas
+    /// Move the primary light given screen space input deltas
     pub fn move_light(&mut self, x: f32, y: f32) {
-        self.view.light_pos[0] += x;
-        self.view.light_pos[2] += y;
+        self.view.lights[0].position[0] += x;
+        self.view.lights[0].position[2] += y;
+    }
+
+    /// Add a new light with default settings, if the maximum number of lights
+    /// has not yet been reached.
+    pub fn add_light(&mut self) {
+        if (self.view.light_count as usize) < MAX_LIGHTS {
+            self.view.lights[self.view.light_count as usize] = LightData::default();
+            self.view.light_count += 1;
+        }
+    }
+
+    /// Remove the light at the given index, shifting all following lights
+    /// down.
+    pub fn remove_light(&mut self, index: usize) {
+        let count = self.view.light_count as usize;
+        if index < count {
+            self.view.lights[index..count].rotate_left(1);
+            self.view.light_count -= 1;
+        }
+    }
+
+    /// Update the light at the given index.
+    pub fn update_light(&mut self, index: usize, light: Light) {
+        if index < self.view.light_count as usize {
+            self.view.lights[index] = light.into();
+        }
     }
 
     /// Update the displacement amount to be renderered
@@ -517,19 +787,19 @@ where
         self.view.tex_scale = scale;
     }
 
-    /// Set the light type to be rendered
+    /// Set the type of the primary light to be rendered
     pub fn set_light_type(&mut self, light_type: LightType) {
-        self.view.light_type = light_type;
+        self.view.lights[0].light_type = light_type;
     }
 
-    /// Set the light strength
+    /// Set the primary light strength
     pub fn set_light_strength(&mut self, strength: f32) {
-        self.view.light_strength = strength;
+        self.view.lights[0].strength = strength;
     }
 
-    /// Set the light size
+    /// Set the primary light size
     pub fn set_light_size(&mut self, size: f32) {
-        self.view.light_size = size;
+        self.view.lights[0].size = size;
     }
 
     /// Set the fog strength
@@ -537,6 +807,16 @@ where
         self.view.fog_strength = strength;
     }
 
+    /// Set the subsurface scattering color
+    pub fn set_sss_color(&mut self, color: [f32; 3]) {
+        self.view.sss_color = color;
+    }
+
+    /// Set the subsurface scattering strength
+    pub fn set_sss_strength(&mut self, strength: f32) {
+        self.view.sss_strength = strength;
+    }
+
     /// Set the strength of environment lighting (IBL)
     pub fn set_environment_strength(&mut self, strength: f32) {
         self.view.environment_strength = strength;
@@ -557,6 +837,36 @@ where
         self.view.shadow = shadow;
     }
 
+    /// Set whether a grid of UV isocurves should be drawn over the object
+    pub fn set_wireframe(&mut self, wireframe: ParameterBool) {
+        self.view.wireframe = wireframe;
+    }
+
+    /// Set the width of wireframe lines, in pixels
+    pub fn set_wire_width(&mut self, wire_width: f32) {
+        self.view.wire_width = wire_width;
+    }
+
+    /// Set the radius of the capsule object
+    pub fn set_capsule_radius(&mut self, radius: f32) {
+        self.view.capsule_radius = radius;
+    }
+
+    /// Set the distance between the cap centers of the capsule object
+    pub fn set_capsule_length(&mut self, length: f32) {
+        self.view.capsule_length = length;
+    }
+
+    /// Set the height of the cone object
+    pub fn set_cone_height(&mut self, height: f32) {
+        self.view.cone_height = height;
+    }
+
+    /// Set the half angle at the tip of the cone object, in radians
+    pub fn set_cone_angle(&mut self, angle: f32) {
+        self.view.cone_angle = angle;
+    }
+
     /// Set the strength of AO to be rendered
     pub fn set_ao_strength(&mut self, ao_strength: f32) {
         self.view.ambient_occlusion_strength = ao_strength;
@@ -0,0 +1,209 @@
+/// GPU side timing using timestamp queries.
+use gfx_hal as hal;
+use gfx_hal::prelude::*;
+use std::mem::ManuallyDrop;
+use std::time::{Duration, Instant};
+
+/// Time between the two timestamps written during calibration. Longer
+/// intervals reduce the influence of submission latency on the result.
+const CALIBRATION_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Device properties required to interpret timestamp query results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimestampProperties {
+    /// Nanoseconds per timestamp tick
+    pub period_ns: f32,
+}
+
+impl TimestampProperties {
+    /// Measure the timestamp period on the given queue group, by writing two
+    /// timestamps some time apart and comparing their difference to the time
+    /// elapsed on the CPU. gfx-hal does not expose the timestamp period of
+    /// the device, nor whether the queue family supports timestamps.
+    ///
+    /// Returns None if the timestamps do not advance, which is the case on
+    /// queue families without timestamp support.
+    pub fn calibrate<B: hal::Backend>(
+        device: &B::Device,
+        queue_group: &mut hal::queue::QueueGroup<B>,
+    ) -> Option<Self> {
+        let mut command_pool = unsafe {
+            device.create_command_pool(
+                queue_group.family,
+                hal::pool::CommandPoolCreateFlags::TRANSIENT,
+            )
+        }
+        .ok()?;
+        let query_pool = unsafe { device.create_query_pool(hal::query::Type::Timestamp, 1) };
+        let fence = device.create_fence(false);
+
+        let properties = match (&query_pool, &fence) {
+            (Ok(query_pool), Ok(fence)) => {
+                let first = Self::sample(device, queue_group, &mut command_pool, query_pool, fence);
+                std::thread::sleep(CALIBRATION_INTERVAL);
+                let second =
+                    Self::sample(device, queue_group, &mut command_pool, query_pool, fence);
+
+                first.zip(second).and_then(|((t0, ticks0), (t1, ticks1))| {
+                    let ticks = ticks1.checked_sub(ticks0).filter(|t| *t > 0)?;
+                    Some(Self {
+                        period_ns: (t1 - t0).as_nanos() as f32 / ticks as f32,
+                    })
+                })
+            }
+            _ => None,
+        };
+
+        unsafe {
+            if let Ok(fence) = fence {
+                device.destroy_fence(fence);
+            }
+            if let Ok(query_pool) = query_pool {
+                device.destroy_query_pool(query_pool);
+            }
+            device.destroy_command_pool(command_pool);
+        }
+
+        properties
+    }
+
+    /// Write a single timestamp, returning it along with the CPU time at
+    /// which the write was submitted.
+    fn sample<B: hal::Backend>(
+        device: &B::Device,
+        queue_group: &mut hal::queue::QueueGroup<B>,
+        command_pool: &mut B::CommandPool,
+        query_pool: &B::QueryPool,
+        fence: &B::Fence,
+    ) -> Option<(Instant, u64)> {
+        unsafe {
+            let mut cmd_buffer = command_pool.allocate_one(hal::command::Level::Primary);
+            cmd_buffer.begin_primary(hal::command::CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buffer.reset_query_pool(query_pool, 0..1);
+            cmd_buffer.write_timestamp(
+                hal::pso::PipelineStage::TOP_OF_PIPE,
+                hal::query::Query {
+                    pool: query_pool,
+                    id: 0,
+                },
+            );
+            cmd_buffer.finish();
+
+            device.reset_fence(fence).ok()?;
+            let submitted = Instant::now();
+            queue_group.queues[0].submit_without_semaphores(Some(&cmd_buffer), Some(fence));
+            let completed = device.wait_for_fence(fence, !0);
+            command_pool.free(Some(cmd_buffer));
+            completed.ok()?;
+
+            let mut data = [0u8; 8];
+            device
+                .get_query_pool_results(
+                    query_pool,
+                    0..1,
+                    &mut data,
+                    8,
+                    hal::query::ResultFlags::BITS_64 | hal::query::ResultFlags::WAIT,
+                )
+                .ok()?;
+
+            Some((submitted, u64::from_ne_bytes(data)))
+        }
+    }
+}
+
+/// A pair of timestamp queries bracketing a section of a command buffer.
+pub struct TimerQuery<B: hal::Backend> {
+    pool: ManuallyDrop<B::QueryPool>,
+    properties: TimestampProperties,
+}
+
+impl<B> TimerQuery<B>
+where
+    B: hal::Backend,
+{
+    /// Create a new timer query. Returns None if the device does not support
+    /// timestamp queries, or the queue family does not write valid timestamps.
+    pub fn new(device: &B::Device, properties: Option<TimestampProperties>) -> Option<Self> {
+        let properties = properties.filter(|p| p.period_ns > 0.)?;
+        let pool = unsafe { device.create_query_pool(hal::query::Type::Timestamp, 2) }.ok()?;
+
+        Some(Self {
+            pool: ManuallyDrop::new(pool),
+            properties,
+        })
+    }
+
+    /// Record the start timestamp. This also resets the query pool and must
+    /// therefore be recorded outside of render passes.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer must be in the recording state.
+    pub unsafe fn begin(&self, cmd_buffer: &mut B::CommandBuffer) {
+        cmd_buffer.reset_query_pool(&self.pool, 0..2);
+        cmd_buffer.write_timestamp(
+            hal::pso::PipelineStage::TOP_OF_PIPE,
+            hal::query::Query {
+                pool: &self.pool,
+                id: 0,
+            },
+        );
+    }
+
+    /// Record the end timestamp.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer must be in the recording state, after `begin`.
+    pub unsafe fn end(&self, cmd_buffer: &mut B::CommandBuffer) {
+        cmd_buffer.write_timestamp(
+            hal::pso::PipelineStage::BOTTOM_OF_PIPE,
+            hal::query::Query {
+                pool: &self.pool,
+                id: 1,
+            },
+        );
+    }
+
+    /// Read back the elapsed time between the two timestamps. Must only be
+    /// called after the command buffer has completed execution. Returns None
+    /// if the timestamp counter wrapped around in between the two queries.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer the queries were recorded to must have been
+    /// submitted, otherwise this blocks forever.
+    pub unsafe fn elapsed(&self, device: &B::Device) -> Option<Duration> {
+        let mut data = [0u8; 16];
+        device
+            .get_query_pool_results(
+                &self.pool,
+                0..2,
+                &mut data,
+                8,
+                hal::query::ResultFlags::BITS_64 | hal::query::ResultFlags::WAIT,
+            )
+            .ok()?;
+
+        let mut start = [0u8; 8];
+        let mut end = [0u8; 8];
+        start.copy_from_slice(&data[0..8]);
+        end.copy_from_slice(&data[8..16]);
+
+        let ticks = u64::from_ne_bytes(end).checked_sub(u64::from_ne_bytes(start))?;
+
+        Some(Duration::from_nanos(
+            (ticks as f64 * self.properties.period_ns as f64) as u64,
+        ))
+    }
+
+    /// Destroy the query pool.
+    ///
+    /// # Safety
+    ///
+    /// No command buffer using the timer may still be executing.
+    pub unsafe fn destroy(mut self, device: &B::Device) {
+        device.destroy_query_pool(ManuallyDrop::take(&mut self.pool));
+    }
+}
@@ -1,8 +1,44 @@
 use serde_derive::{Deserialize, Serialize};
 use std::fs::File;
+use std::io::{Read, Write};
 use std::path::Path;
 use thiserror::Error;
 
+/// Magic bytes identifying a versioned surface file. Files without them are
+/// assumed to be of version 1, which had no header.
+const SURFACE_FILE_MAGIC: &[u8; 4] = b"SURF";
+
+/// The current version of the surface file format.
+pub const SURFACE_FILE_VERSION: u32 = 2;
+
+/// Header prepended to surface files, following the magic bytes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SurfaceFileHeader {
+    /// File format version
+    pub version: u32,
+    /// Creation time in seconds since the UNIX epoch
+    pub created_at: u64,
+}
+
+impl SurfaceFileHeader {
+    /// Create a header for the current version, timestamped now.
+    pub fn new() -> Self {
+        Self {
+            version: SURFACE_FILE_VERSION,
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl Default for SurfaceFileHeader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Struct defining a .surf file.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SurfaceFile {
@@ -17,22 +53,53 @@ pub enum SurfaceIOError {
     FileIO(#[from] std::io::Error),
     #[error("Error during file serialization")]
     Serialization(#[from] serde_cbor::Error),
+    #[error("Unsupported surface file version {0}")]
+    UnsupportedVersion(u32),
+}
+
+/// Migrate the body of a surface file from the given version to the current
+/// schema, returning the migrated bytes.
+///
+/// **Errors** if the version is unknown, e.g. because the file was written by
+/// a newer version of the application.
+pub fn migrate(version: u32, data: &[u8]) -> Result<Vec<u8>, SurfaceIOError> {
+    match version {
+        // Version 2 only introduced the header, the body is unchanged.
+        1 => migrate(2, data),
+        SURFACE_FILE_VERSION => Ok(data.to_vec()),
+        v => Err(SurfaceIOError::UnsupportedVersion(v)),
+    }
 }
 
 impl SurfaceFile {
     pub fn save<P: AsRef<Path> + std::fmt::Debug>(&self, path: P) -> Result<(), SurfaceIOError> {
         log::info!("Saving surface to {:?}", path);
 
-        let output_file = File::create(path)?;
-        serde_cbor::to_writer(output_file, &self)?;
+        let mut output_file = File::create(path)?;
+        output_file.write_all(SURFACE_FILE_MAGIC)?;
+        serde_cbor::to_writer(&mut output_file, &SurfaceFileHeader::new())?;
+        serde_cbor::to_writer(&mut output_file, &self)?;
 
         Ok(())
     }
 
     pub fn open<P: AsRef<Path> + std::fmt::Debug>(path: P) -> Result<Self, SurfaceIOError> {
         log::info!("Loading surface from file {:?}", path);
-        let input_file = File::open(path)?;
-        let surface_file: Self = serde_cbor::from_reader(input_file)?;
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+
+        let (version, body) = match data.strip_prefix(SURFACE_FILE_MAGIC) {
+            Some(rest) => {
+                let mut de = serde_cbor::Deserializer::from_slice(rest);
+                let header: SurfaceFileHeader = serde::Deserialize::deserialize(&mut de)?;
+                log::debug!("Surface file header {:?}", header);
+                (header.version, &rest[de.byte_offset()..])
+            }
+            None => (1, &data[..]),
+        };
+
+        let body = migrate(version, body)?;
+        let surface_file: Self = serde_cbor::from_slice(&body)?;
 
         Ok(surface_file)
     }
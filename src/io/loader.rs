@@ -0,0 +1,114 @@
+use crate::{broker, lang::*};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+/// Size of the chunks in which files are read. Cancellation is checked
+/// between chunks.
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Loads image resources from disk in the background. Each load runs on its
+/// own thread and broadcasts `IOEvent::ImageResourceLoaded` on completion, or
+/// `IOEvent::ImageResourceLoadFailed` if the file could not be read. Loads in
+/// flight can be cancelled, in which case no event is sent. A load
+/// may complete while its cancellation is in flight, so consumers must
+/// discard completions for loads they consider cancelled.
+#[derive(Default)]
+pub struct ImageLoader {
+    pending: HashMap<Resource<Img>, Arc<AtomicBool>>,
+}
+
+impl ImageLoader {
+    /// Start loading an image resource from the given path. Returns the
+    /// resource the image will be known as.
+    pub fn load(&mut self, sender: broker::BrokerSender<Lang>, path: PathBuf) -> Resource<Img> {
+        let res = Resource::image(path.file_name().unwrap());
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        if let Some(previous) = self.pending.insert(res.clone(), cancel.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+
+        log::debug!("Loading image resource from path {:?} as {}", path, res);
+
+        let thread_res = res.clone();
+        let spawned = thread::Builder::new()
+            .name("image-loader".to_string())
+            .spawn(move || match read_cancellable(&path, &cancel) {
+                Ok(Some(data)) if !cancel.load(Ordering::Relaxed) => {
+                    // Sent anonymously such that the IO component can clear
+                    // the pending load.
+                    sender.send_anonymous(Lang::IOEvent(IOEvent::ImageResourceLoaded(
+                        thread_res, path, data,
+                    )));
+                }
+                Ok(_) => log::debug!("Loading of image resource {} cancelled", thread_res),
+                Err(e) => {
+                    log::error!("Failed to load image from {:?}: {}", path, e);
+                    sender.send_anonymous(Lang::IOEvent(IOEvent::ImageResourceLoadFailed(
+                        thread_res,
+                        path,
+                        e.to_string(),
+                    )));
+                }
+            });
+
+        if let Err(e) = spawned {
+            log::error!("Failed to spawn image loader thread: {}", e);
+        }
+
+        res
+    }
+
+    /// Mark a load as finished, successfully or not.
+    pub fn finish(&mut self, res: &Resource<Img>) {
+        self.pending.remove(res);
+    }
+
+    /// Cancel a pending load, if any. Returns whether a load was cancelled.
+    pub fn cancel(&mut self, res: &Resource<Img>) -> bool {
+        match self.pending.remove(res) {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel all pending loads.
+    pub fn cancel_all(&mut self) {
+        for (_, cancel) in self.pending.drain() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Read a file to memory, returning `None` if cancelled before completion.
+fn read_cancellable(path: &Path, cancel: &AtomicBool) -> std::io::Result<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let mut data = Vec::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&chunk[..n]);
+    }
+
+    Ok(Some(data))
+}
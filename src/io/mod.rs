@@ -1,11 +1,14 @@
 use crate::{broker, lang::config::Configuration, lang::*};
 use std::{
+    collections::HashMap,
     fmt::Debug,
     path::{Path, PathBuf},
     thread,
 };
 
 pub mod file;
+pub mod loader;
+pub mod watcher;
 
 pub fn start_io_thread(
     broker: &mut broker::Broker<Lang>,
@@ -17,10 +20,17 @@ pub fn start_io_thread(
         .spawn(move || {
             log::info!("Starting IO manager");
 
-            let mut io_manager = IOManager::new(config);
-            let _scheduler = scheduler_setup(sender.clone());
+            let mut scheduler = scheduler_setup(sender.clone(), &config);
+            let mut io_manager = IOManager::new(config, sender.clone());
 
             for event in receiver {
+                if let Lang::UserIOEvent(UserIOEvent::SetAutosaveInterval(interval)) = &*event {
+                    io_manager.set_autosave_interval(*interval);
+                    scheduler.stop();
+                    scheduler = scheduler_setup(sender.clone(), &io_manager.config);
+                    continue;
+                }
+
                 match io_manager.process_event(&event) {
                     Some(res) => {
                         for r in res {
@@ -39,7 +49,10 @@ pub fn start_io_thread(
         .expect("Failed to start IO manager thread!")
 }
 
-fn scheduler_setup(sender: broker::BrokerSender<Lang>) -> clokwerk::ScheduleHandle {
+fn scheduler_setup(
+    sender: broker::BrokerSender<Lang>,
+    config: &Configuration,
+) -> clokwerk::ScheduleHandle {
     use clokwerk::*;
     use enclose::*;
 
@@ -48,36 +61,81 @@ fn scheduler_setup(sender: broker::BrokerSender<Lang>) -> clokwerk::ScheduleHand
 
     // The scheduler should send messages anonymously such that they can also be
     // intercepted by the IO component, since they share a sender.
+    if config.autosave_interval > 0 {
+        scheduler
+            .every(config.autosave_interval.minutes())
+            .run(enclose!((sender_arc => sender) move ||
+                          sender.send_anonymous(Lang::ScheduleEvent(ScheduleEvent::Autosave)).unwrap()));
+    }
     scheduler
-        .every(2.minutes())
-        .run(enclose!((sender_arc => sender) move ||
-                      sender.send_anonymous(Lang::ScheduleEvent(ScheduleEvent::Autosave)).unwrap()));
-    scheduler
-        .every(5.seconds())
+        .every(config.vram_usage_interval.max(1).seconds())
         .run(enclose!((sender_arc => sender) move ||
                       sender.send_anonymous(Lang::ScheduleEvent(ScheduleEvent::VramUsage)).unwrap()));
+    if config.watch_resources {
+        scheduler
+            .every(1.seconds())
+            .run(enclose!((sender_arc => sender) move ||
+                          sender.send_anonymous(Lang::ScheduleEvent(ScheduleEvent::WatchResources)).unwrap()));
+    }
 
     scheduler.watch_thread(std::time::Duration::from_secs(1))
 }
 
+/// An export assembled from multiple parts in progress, holding the number of
+/// parts expected, the number of parts that failed, and the parts received so
+/// far.
+struct PendingExport<T> {
+    expected: usize,
+    failed: usize,
+    parts: Vec<T>,
+}
+
+impl<T> PendingExport<T> {
+    fn new(expected: usize) -> Self {
+        Self {
+            expected,
+            failed: 0,
+            parts: Vec::with_capacity(expected),
+        }
+    }
+
+    /// An export is complete once all expected parts have either been
+    /// received or failed.
+    fn is_complete(&self) -> bool {
+        self.parts.len() + self.failed >= self.expected
+    }
+}
+
+/// An archive export in progress, collecting encoded entries by name.
+type PendingArchive = PendingExport<(String, Vec<u8>)>;
+
+/// An atlas export in progress, collecting tiles by region.
+type PendingAtlas = PendingExport<(AtlasRegion, image::RgbaImage)>;
+
 pub struct IOManager {
     file_builder: Option<file::SurfaceFileBuilder>,
     save_path: Option<PathBuf>,
+    surface_path: Option<PathBuf>,
     config: Configuration,
-}
-
-impl Default for IOManager {
-    fn default() -> Self {
-        Self::new(Configuration::default())
-    }
+    archives: HashMap<PathBuf, PendingArchive>,
+    atlases: HashMap<PathBuf, PendingAtlas>,
+    images: loader::ImageLoader,
+    watcher: watcher::ResourceWatcher,
+    sender: broker::BrokerSender<Lang>,
 }
 
 impl IOManager {
-    pub fn new(config: Configuration) -> Self {
+    pub fn new(config: Configuration, sender: broker::BrokerSender<Lang>) -> Self {
         Self {
             file_builder: None,
             save_path: None,
+            surface_path: None,
             config,
+            archives: HashMap::new(),
+            atlases: HashMap::new(),
+            images: loader::ImageLoader::default(),
+            watcher: watcher::ResourceWatcher::default(),
+            sender,
         }
     }
 
@@ -87,27 +145,107 @@ impl IOManager {
         }
     }
 
+    /// Set the autosave interval. The interval is stored in the
+    /// configuration at minute granularity, where 0 disables autosaving.
+    pub fn set_autosave_interval(&mut self, interval: std::time::Duration) {
+        self.config.autosave_interval = (interval.as_secs() / 60) as u32;
+    }
+
     pub fn process_event(&mut self, event: &Lang) -> Option<Vec<Lang>> {
         let mut response = Vec::new();
 
         match event {
             Lang::UserIOEvent(UserIOEvent::OpenSurface(path)) => {
+                self.images.cancel_all();
+                self.watcher.clear();
+                self.surface_path = Some(path.clone());
                 response.append(&mut self.open_surface(path))
             }
-            Lang::UserIOEvent(UserIOEvent::SaveSurface(path)) => self.save_surface(path),
+            Lang::UserIOEvent(UserIOEvent::SaveSurface(path)) => {
+                self.surface_path = Some(path.clone());
+                self.save_surface(path)
+            }
+            Lang::UserIOEvent(UserIOEvent::NewSurface) => {
+                self.images.cancel_all();
+                self.watcher.clear();
+                self.surface_path = None;
+            }
+            Lang::UserIOEvent(UserIOEvent::AddImageResource(path)) => {
+                self.images.load(self.sender.clone(), path.clone());
+            }
+            Lang::UserIOEvent(UserIOEvent::RemoveImageResource(res)) => {
+                if self.images.cancel(res) {
+                    log::debug!("Cancelled pending load of {}", res);
+                }
+                self.watcher.unwatch(res);
+            }
+            Lang::IOEvent(IOEvent::ImageResourceLoaded(res, path, _)) => {
+                self.images.finish(res);
+                if self.config.watch_resources {
+                    self.watcher.watch(res.clone(), path.clone());
+                }
+            }
+            Lang::IOEvent(IOEvent::ImageResourceLoadFailed(res, ..)) => {
+                self.images.finish(res);
+            }
+            Lang::ComputeEvent(ComputeEvent::ImagePacked(res)) => {
+                self.watcher.unwatch(res);
+            }
             Lang::UserIOEvent(UserIOEvent::Quit) => return None,
             Lang::UserIOEvent(UserIOEvent::ResizeWindow(w, h)) => {
                 self.config.window_size.0 = *w;
                 self.config.window_size.1 = *h;
             }
-            Lang::GraphEvent(GraphEvent::Serialized(data)) => self.write_graph_data(data),
-            Lang::ComputeEvent(ComputeEvent::Serialized(data)) => self.write_compute_data(data),
-            Lang::RenderEvent(RenderEvent::Serialized(data)) => self.write_render_settings(data),
+            Lang::GraphEvent(GraphEvent::Serialized(data)) => {
+                response.extend(self.write_graph_data(data))
+            }
+            Lang::GraphEvent(GraphEvent::DotExported(path, dot)) => {
+                if let Err(e) = std::fs::write(path, dot) {
+                    log::error!("Failed to write DOT file {:?}: {}", path, e);
+                }
+            }
+            Lang::ComputeEvent(ComputeEvent::Serialized(data)) => {
+                response.extend(self.write_compute_data(data))
+            }
+            Lang::RenderEvent(RenderEvent::Serialized(data)) => {
+                response.extend(self.write_render_settings(data))
+            }
+            Lang::SurfaceEvent(SurfaceEvent::ArchiveExportStarted(path, expected)) => {
+                self.archives
+                    .insert(path.clone(), PendingExport::new(*expected));
+            }
+            Lang::ComputeEvent(ComputeEvent::ExportEncoded(path, entry, data)) => {
+                self.add_archive_entry(path, entry, data)
+            }
+            Lang::ComputeEvent(ComputeEvent::ExportFailed(ExportTarget::Archive(path, entry))) => {
+                self.fail_archive_entry(path, entry)
+            }
+            Lang::SurfaceEvent(SurfaceEvent::AtlasExportStarted(path, expected)) => {
+                self.atlases
+                    .insert(path.clone(), PendingExport::new(*expected));
+            }
+            Lang::ComputeEvent(ComputeEvent::AtlasTileReady(path, region, tile)) => {
+                self.add_atlas_tile(path, *region, tile)
+            }
+            Lang::ComputeEvent(ComputeEvent::ExportFailed(ExportTarget::Atlas(path, region))) => {
+                self.fail_atlas_tile(path, *region)
+            }
+            Lang::ComputeEvent(ComputeEvent::ExportsComputed) => {
+                // Archives and atlases are written as soon as their last part
+                // arrives, which precedes this event.
+                response.push(Lang::IOEvent(IOEvent::ExportsFinished));
+            }
+            Lang::ScheduleEvent(ScheduleEvent::WatchResources) => {
+                for res in self.watcher.poll() {
+                    log::debug!("Image resource {} changed on disk", res);
+                    response.push(Lang::UserIOEvent(UserIOEvent::ReloadImageResource(res)));
+                }
+            }
             Lang::ScheduleEvent(ScheduleEvent::Autosave) => {
                 log::debug!("Autosave requested by schedule");
-                response.push(Lang::UserIOEvent(UserIOEvent::SaveSurface(
-                    "autosave.surf".into(),
-                )));
+                let path = self.autosave_path();
+                self.save_surface(&path);
+                response.push(Lang::UserIOEvent(UserIOEvent::SaveSurface(path)));
             }
             _ => {}
         }
@@ -136,36 +274,130 @@ impl IOManager {
         response
     }
 
+    /// The path to autosave to, derived from the path of the current surface
+    /// by appending an `.autosave` suffix, such that autosaves never
+    /// overwrite an explicitly saved file.
+    fn autosave_path(&self) -> PathBuf {
+        match &self.surface_path {
+            Some(path) => {
+                let mut autosave = path.clone().into_os_string();
+                autosave.push(".autosave");
+                PathBuf::from(autosave)
+            }
+            None => PathBuf::from("untitled.surf.autosave"),
+        }
+    }
+
     fn save_surface<P: AsRef<Path>>(&mut self, path: P) {
         self.save_path = Some(PathBuf::from(path.as_ref()));
         self.file_builder = Some(file::SurfaceFileBuilder::new());
     }
 
-    fn write_graph_data(&mut self, data: &[u8]) {
+    fn write_graph_data(&mut self, data: &[u8]) -> Option<Lang> {
         if let Some(fb) = &mut self.file_builder {
             fb.node_data(data);
         }
 
-        self.attempt_write().unwrap();
+        self.attempt_write().unwrap()
     }
 
-    fn write_compute_data(&mut self, data: &[u8]) {
+    fn write_compute_data(&mut self, data: &[u8]) -> Option<Lang> {
         if let Some(fb) = &mut self.file_builder {
             fb.compute_data(data);
         }
 
-        self.attempt_write().unwrap();
+        self.attempt_write().unwrap()
     }
 
-    fn write_render_settings(&mut self, data: &[u8]) {
+    fn write_render_settings(&mut self, data: &[u8]) -> Option<Lang> {
         if let Some(fb) = &mut self.file_builder {
             fb.render_settings(data);
         }
 
-        self.attempt_write().unwrap();
+        self.attempt_write().unwrap()
+    }
+
+    fn add_archive_entry(&mut self, path: &Path, entry: &str, data: &[u8]) {
+        match self.archives.get_mut(path) {
+            Some(archive) => archive.parts.push((entry.to_string(), data.to_vec())),
+            None => log::warn!("Received entry {} for unknown archive {:?}", entry, path),
+        }
+
+        self.finish_archive(path);
+    }
+
+    fn fail_archive_entry(&mut self, path: &Path, entry: &str) {
+        match self.archives.get_mut(path) {
+            Some(archive) => archive.failed += 1,
+            None => log::warn!("Failed entry {} for unknown archive {:?}", entry, path),
+        }
+
+        log::error!("Failed exporting entry {} for archive {:?}", entry, path);
+        self.finish_archive(path);
+    }
+
+    /// Write the archive at the given path if all its entries have been
+    /// accounted for. The archive is dropped if any entry failed.
+    fn finish_archive(&mut self, path: &Path) {
+        if !self.archives.get(path).map_or(false, |a| a.is_complete()) {
+            return;
+        }
+
+        let archive = self.archives.remove(path).unwrap();
+        if archive.failed > 0 {
+            log::error!(
+                "Aborted archive export {:?}, {} of {} entries failed",
+                path,
+                archive.failed,
+                archive.expected
+            );
+        } else if let Err(e) = write_zip_archive(path, &archive.parts) {
+            log::error!("Failed writing archive {:?}: {}", path, e);
+        }
+    }
+
+    fn add_atlas_tile(&mut self, path: &Path, region: AtlasRegion, tile: &image::RgbaImage) {
+        match self.atlases.get_mut(path) {
+            Some(atlas) => atlas.parts.push((region, tile.clone())),
+            None => log::warn!("Received tile for unknown atlas {:?}", path),
+        }
+
+        self.finish_atlas(path);
+    }
+
+    fn fail_atlas_tile(&mut self, path: &Path, region: AtlasRegion) {
+        match self.atlases.get_mut(path) {
+            Some(atlas) => atlas.failed += 1,
+            None => log::warn!("Failed tile for unknown atlas {:?}", path),
+        }
+
+        log::error!("Failed exporting tile {:?} for atlas {:?}", region, path);
+        self.finish_atlas(path);
     }
 
-    fn attempt_write(&mut self) -> Result<(), file::SurfaceIOError> {
+    /// Write the atlas at the given path if all its tiles have been accounted
+    /// for. The atlas is dropped if any tile failed.
+    fn finish_atlas(&mut self, path: &Path) {
+        if !self.atlases.get(path).map_or(false, |a| a.is_complete()) {
+            return;
+        }
+
+        let atlas = self.atlases.remove(path).unwrap();
+        if atlas.failed > 0 {
+            log::error!(
+                "Aborted atlas export {:?}, {} of {} tiles failed",
+                path,
+                atlas.failed,
+                atlas.expected
+            );
+        } else if let Err(e) = assemble_atlas(&atlas.parts).save(path) {
+            log::error!("Failed writing atlas {:?}: {}", path, e);
+        }
+    }
+
+    /// Write the surface file once all its parts have been collected,
+    /// returning an event announcing the save.
+    fn attempt_write(&mut self) -> Result<Option<Lang>, file::SurfaceIOError> {
         if let Some(path) = self.save_path.as_ref() {
             if self
                 .file_builder
@@ -176,9 +408,54 @@ impl IOManager {
                 let fb = self.file_builder.take().unwrap();
                 let file = fb.build().unwrap();
                 file.save(path)?;
+                return Ok(Some(Lang::IOEvent(IOEvent::SurfaceSaved(path.clone()))));
             }
         }
 
-        Ok(())
+        Ok(None)
+    }
+}
+
+/// Assemble tiles into a square atlas. The grid size is inferred from the
+/// regions in use, the cell size from the largest tile.
+fn assemble_atlas(tiles: &[(AtlasRegion, image::RgbaImage)]) -> image::RgbaImage {
+    let grid = tiles
+        .iter()
+        .map(|(region, _)| region.row.max(region.column) + 1)
+        .max()
+        .unwrap_or(1);
+    let cell = tiles
+        .iter()
+        .map(|(_, tile)| tile.width().max(tile.height()))
+        .max()
+        .unwrap_or(1);
+
+    let mut atlas = image::RgbaImage::new(grid * cell, grid * cell);
+    for (region, tile) in tiles {
+        image::imageops::replace(&mut atlas, tile, region.column * cell, region.row * cell);
     }
+
+    atlas
+}
+
+/// Write a ZIP archive to the given path, containing the given named entries.
+fn write_zip_archive<P: AsRef<Path>>(
+    path: P,
+    entries: &[(String, Vec<u8>)],
+) -> zip::result::ZipResult<()> {
+    use std::io::Write;
+
+    let file = std::fs::File::create(path)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, data) in entries {
+        zip.start_file(name, options)?;
+        zip.write_all(data)?;
+    }
+
+    zip.finish()?;
+
+    Ok(())
 }
@@ -0,0 +1,58 @@
+use crate::lang::*;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Watches external image resources for changes on disk. Changes are
+/// detected by polling the modification times of the watched files, which
+/// is cheap enough at the rate of the scheduler and works on every platform
+/// and file system.
+#[derive(Default)]
+pub struct ResourceWatcher {
+    watched: HashMap<Resource<Img>, (PathBuf, Option<SystemTime>)>,
+}
+
+impl ResourceWatcher {
+    /// Start watching the file at the given path for the resource. Replaces
+    /// any previous path for the same resource.
+    pub fn watch(&mut self, res: Resource<Img>, path: PathBuf) {
+        log::debug!("Watching {:?} for changes to {}", path, res);
+        let modified = modified(&path);
+        self.watched.insert(res, (path, modified));
+    }
+
+    /// Stop watching the resource. Returns whether it was watched.
+    pub fn unwatch(&mut self, res: &Resource<Img>) -> bool {
+        self.watched.remove(res).is_some()
+    }
+
+    /// Stop watching all resources.
+    pub fn clear(&mut self) {
+        self.watched.clear();
+    }
+
+    /// Determine all resources whose files have been modified since they
+    /// were last checked. Files that can not be accessed, for instance
+    /// because they are currently being replaced, are skipped until they are
+    /// accessible again.
+    pub fn poll(&mut self) -> Vec<Resource<Img>> {
+        let mut changed = Vec::new();
+
+        for (res, (path, last)) in self.watched.iter_mut() {
+            let current = modified(path);
+            if current.is_some() && current != *last {
+                *last = current;
+                changed.push(res.clone());
+            }
+        }
+
+        changed
+    }
+}
+
+/// Modification time of the file at the given path, if available.
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
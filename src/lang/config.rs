@@ -1,5 +1,6 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::util::SamplingSequence;
 use serde_derive::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -19,8 +20,28 @@ pub struct Configuration {
     pub window_size: (u32, u32),
     #[serde(default = "default_language")]
     pub language: String,
-    #[serde(default = "default_compute_vram_pct")]
+    #[serde(default = "default_compute_vram_pct", alias = "allocator_percent")]
     pub compute_vram_pct: f32,
+    #[serde(default = "default_undo_limit", alias = "max_undo_steps")]
+    pub undo_limit: usize,
+    #[serde(default = "default_vram_usage_interval")]
+    pub vram_usage_interval: u32,
+    #[serde(default)]
+    pub gpu_validation: bool,
+    #[serde(default = "default_sample_budget_ms")]
+    pub sample_budget_ms: u64,
+    #[serde(default = "default_autosave_interval")]
+    pub autosave_interval: u32,
+    #[serde(default)]
+    pub sampling_sequence: SamplingSequence,
+    #[serde(default = "default_hdri_path")]
+    pub hdri_default_path: PathBuf,
+    #[serde(default = "default_matcap_path")]
+    pub matcap_default_path: PathBuf,
+    #[serde(default)]
+    pub watch_resources: bool,
+    #[serde(default = "default_thumbnail_size")]
+    pub thumbnail_size: u32,
 }
 
 fn default_size() -> (u32, u32) {
@@ -32,7 +53,57 @@ fn default_language() -> String {
 }
 
 fn default_compute_vram_pct() -> f32 {
-    0.5
+    0.7
+}
+
+fn default_undo_limit() -> usize {
+    128
+}
+
+/// Interval in seconds between VRAM usage reports
+fn default_vram_usage_interval() -> u32 {
+    5
+}
+
+/// Time budget in milliseconds for adaptive sampling in renderers
+fn default_sample_budget_ms() -> u64 {
+    500
+}
+
+/// Interval in minutes between autosaves, 0 disables autosaving
+fn default_autosave_interval() -> u32 {
+    2
+}
+
+/// HDRi loaded by renderers unless another one is chosen
+fn default_hdri_path() -> PathBuf {
+    asset_dir().join("artist_workshop_2k.hdr")
+}
+
+/// Matcap loaded by renderers unless another one is chosen
+fn default_matcap_path() -> PathBuf {
+    asset_dir().join("matcap.png")
+}
+
+/// Size of node thumbnails in pixels per side, between 16 and 512
+fn default_thumbnail_size() -> u32 {
+    128
+}
+
+/// Locate the directory of bundled assets. This is the first `assets`
+/// directory found next to the executable or in one of its parent
+/// directories, such that both installed and development builds are covered.
+/// Falls back to `assets` in the working directory.
+pub fn asset_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            exe.ancestors()
+                .skip(1)
+                .map(|dir| dir.join("assets"))
+                .find(|dir| dir.is_dir())
+        })
+        .unwrap_or_else(|| PathBuf::from("assets"))
 }
 
 impl Default for Configuration {
@@ -41,6 +112,16 @@ impl Default for Configuration {
             window_size: default_size(),
             language: default_language(),
             compute_vram_pct: default_compute_vram_pct(),
+            undo_limit: default_undo_limit(),
+            vram_usage_interval: default_vram_usage_interval(),
+            gpu_validation: false,
+            sample_budget_ms: default_sample_budget_ms(),
+            autosave_interval: default_autosave_interval(),
+            sampling_sequence: SamplingSequence::default(),
+            hdri_default_path: default_hdri_path(),
+            matcap_default_path: default_matcap_path(),
+            watch_resources: false,
+            thumbnail_size: default_thumbnail_size(),
         }
     }
 }
@@ -10,11 +10,11 @@ pub mod socketed;
 
 use enum_dispatch::*;
 use enumset::{EnumSet, EnumSetType};
-use num_enum::UnsafeFromPrimitive;
+use num_enum::TryFromPrimitive;
 
 use serde_derive::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::path::*;
 use strum_macros::*;
@@ -29,25 +29,41 @@ pub use socketed::*;
 /// Atomic Operators are operators that can not be decomposed into smaller
 /// parts.
 #[enum_dispatch(Socketed, Parameters, Uniforms, Shader, OperatorParamBox)]
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Display)]
 pub enum AtomicOperator {
+    AlphaBlend,
     AlphaExtract,
     AmbientOcclusion,
     Blend,
     Blur,
+    Brick,
     Checker,
     ColorAdjust,
+    ConvertColorSpace,
     CoordinateTransform,
+    Curvature,
+    CurveAdjust,
+    Dilate,
     Distance,
+    EdgeDetect,
+    Erode,
+    FractalNoise,
+    Gradient,
+    GradientMap,
     Grayscale,
+    HeightToNormal,
+    HexTile,
+    HistogramEqualize,
     Image,
     Input,
     Merge,
+    NoiseFbm,
     NoiseSpread,
     NormalBlend,
     NormalMap,
     Output,
     PerlinNoise,
+    Pixelate,
     Ramp,
     Range,
     Rgb,
@@ -56,10 +72,16 @@ pub enum AtomicOperator {
     Shape,
     Split,
     Svg(operators::Svg),
+    Swizzle,
+    TextureTransform,
+    Threshold,
+    Tile,
     Transform,
     Value,
     Voronoi,
     Warp,
+    WarpedNoise,
+    Wave,
 }
 
 impl AtomicOperator {
@@ -67,23 +89,39 @@ impl AtomicOperator {
     /// for frontends to present a list of all operators.
     pub fn all_default() -> Vec<Self> {
         vec![
+            Self::AlphaBlend(AlphaBlend::default()),
             Self::AlphaExtract(AlphaExtract::default()),
             Self::AmbientOcclusion(AmbientOcclusion::default()),
             Self::Blend(Blend::default()),
             Self::Blur(Blur::default()),
+            Self::Brick(Brick::default()),
             Self::Checker(Checker::default()),
             Self::ColorAdjust(ColorAdjust::default()),
+            Self::ConvertColorSpace(ConvertColorSpace::default()),
             Self::CoordinateTransform(CoordinateTransform::default()),
+            Self::Curvature(Curvature::default()),
+            Self::CurveAdjust(CurveAdjust::default()),
+            Self::Dilate(Dilate::default()),
             Self::Distance(Distance::default()),
+            Self::EdgeDetect(EdgeDetect::default()),
+            Self::Erode(Erode::default()),
+            Self::FractalNoise(FractalNoise::default()),
+            Self::Gradient(Gradient::default()),
+            Self::GradientMap(GradientMap::default()),
             Self::Grayscale(Grayscale::default()),
+            Self::HeightToNormal(HeightToNormal::default()),
+            Self::HexTile(HexTile::default()),
+            Self::HistogramEqualize(HistogramEqualize::default()),
             Self::Image(Image::default()),
             Self::Input(Input::default()),
             Self::Merge(Merge::default()),
+            Self::NoiseFbm(NoiseFbm::default()),
             Self::NoiseSpread(NoiseSpread::default()),
             Self::NormalBlend(NormalBlend::default()),
             Self::NormalMap(NormalMap::default()),
             Self::Output(Output::default()),
             Self::PerlinNoise(PerlinNoise::default()),
+            Self::Pixelate(Pixelate::default()),
             Self::Ramp(Ramp::default()),
             Self::Range(Range::default()),
             Self::Rgb(Rgb::default()),
@@ -92,18 +130,37 @@ impl AtomicOperator {
             Self::Shape(Shape::default()),
             Self::Split(Split::default()),
             Self::Svg(operators::Svg::default()),
+            Self::Swizzle(Swizzle::default()),
+            Self::TextureTransform(TextureTransform::default()),
+            Self::Threshold(Threshold::default()),
+            Self::Tile(Tile::default()),
             Self::Transform(Transform::default()),
             Self::Value(Value::default()),
             Self::Voronoi(Voronoi::default()),
             Self::Warp(Warp::default()),
+            Self::WarpedNoise(WarpedNoise::default()),
+            Self::Wave(Wave::default()),
         ]
     }
+
+    /// Whether the result of this operator depends only on its parameters and
+    /// inputs. Operators reading external data or interacting with the outside
+    /// world are never cacheable and must be considered on every compute pass.
+    pub fn is_cacheable(&self) -> bool {
+        !matches!(
+            self,
+            Self::Image(..) | Self::Svg(..) | Self::Input(..) | Self::Output(..)
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum OperatorSize {
     RelativeToParent(i32),
     AbsoluteSize(u32),
+    /// An absolute size that is not snapped to a "normal" texture size, for
+    /// sizes such as 1920 that are neither powers of two nor multiples of 3k.
+    NonPow2(u32),
 }
 
 impl Default for OperatorSize {
@@ -116,11 +173,25 @@ impl OperatorSize {
     /// Create an absolute size from a float by snapping to the nearest "normal"
     /// texture size. Normal here means powers of two *or* multiples of 3k up to
     /// 12k.
+    ///
+    /// If the value is integral and not itself a normal size, it is returned
+    /// unchanged as a `NonPow2` size.
     pub fn abs_nearest(val: f32) -> Self {
         let pow2 = 2_u32.pow(val.log(2.).floor().max(5.) as u32);
         let mul3k = (val as u32 / 3072).min(4) * 3072;
+        let nearest = pow2.max(mul3k);
 
-        Self::AbsoluteSize(pow2.max(mul3k))
+        if val.fract() == 0. && val as u32 != nearest {
+            Self::NonPow2(val as u32)
+        } else {
+            Self::AbsoluteSize(nearest)
+        }
+    }
+
+    /// Determine whether this size is absolute, i.e. independent of the
+    /// parent size.
+    pub fn is_absolute(self) -> bool {
+        !matches!(self, Self::RelativeToParent(..))
     }
 
     /// Convert relative size to absolute, given a parent size.
@@ -139,7 +210,7 @@ impl OperatorSize {
     /// and 16384.
     pub fn absolute(self, parent_size: u32) -> u32 {
         match self.to_abs(parent_size) {
-            OperatorSize::AbsoluteSize(s) => s,
+            OperatorSize::AbsoluteSize(s) | OperatorSize::NonPow2(s) => s,
             _ => unreachable!(),
         }
         .clamp(32, 16384)
@@ -277,10 +348,12 @@ impl Operator {
     /// outputs greater than 0 that can be interpreted as grayscale images.
     pub fn is_mask(&self) -> bool {
         self.inputs().len() <= 1
-            && self
-                .outputs()
-                .values()
-                .any(|t| !matches!(t, OperatorType::Monomorphic(ImageType::Rgb)))
+            && self.outputs().values().any(|t| {
+                !matches!(
+                    t,
+                    OperatorType::Monomorphic(ImageType::Rgb | ImageType::Rgba)
+                )
+            })
     }
 
     /// Obtain the monomorphic type of a socket if possible.
@@ -308,6 +381,21 @@ impl Operator {
     pub fn scalable(&self) -> bool {
         self.size_request().is_none() && !self.is_output() && !self.is_input()
     }
+
+    /// Obtain the current value of a parameter in the form expected by
+    /// `set_parameter`, if the operator has such a parameter.
+    pub fn parameter_value(&self, field: &str) -> Option<Vec<u8>> {
+        match self {
+            Operator::AtomicOperator(op) => op
+                .param_box_description()
+                .parameters_mut()
+                .find(|p| p.transmitter.0 == field)
+                .map(|p| p.control.value()),
+            Operator::ComplexOperator(co) => {
+                co.parameters.get(field).map(|p| p.get_value().to_vec())
+            }
+        }
+    }
 }
 
 /// A linearization is an executable form of a graph, typically some sort of
@@ -356,9 +444,11 @@ impl Instruction {
     }
 }
 
-/// Enum describing the types of images in the system. Images can be either RGB
-/// or Grayscale. Without further information as to where this is used, no
-/// assumptions should be made about representation!
+/// Enum describing the types of images in the system. Images can be either RGB,
+/// RGBA, or Grayscale. RGB and RGBA images may share a representation, the
+/// distinction being whether the alpha channel carries meaning. Without
+/// further information as to where this is used, no assumptions should be
+/// made about representation!
 #[repr(C)]
 #[derive(
     AsBytes,
@@ -378,6 +468,7 @@ impl Instruction {
 pub enum ImageType {
     Grayscale,
     Rgb,
+    Rgba,
 }
 
 impl Default for ImageType {
@@ -387,6 +478,15 @@ impl Default for ImageType {
     }
 }
 
+impl ImageType {
+    /// Determine whether a socket of this type can be connected to a socket
+    /// of the other type. RGB and RGBA share their representation, so they
+    /// can be connected to each other.
+    pub fn connects_to(self, other: Self) -> bool {
+        self == other || (self != ImageType::Grayscale && other != ImageType::Grayscale)
+    }
+}
+
 impl From<OutputType> for ImageType {
     fn from(source: OutputType) -> Self {
         match source {
@@ -397,6 +497,7 @@ impl From<OutputType> for ImageType {
             OutputType::Metallic => ImageType::Grayscale,
             OutputType::AmbientOcclusion => ImageType::Grayscale,
             OutputType::Alpha => ImageType::Grayscale,
+            OutputType::Emissive => ImageType::Rgb,
             OutputType::Value => ImageType::Grayscale,
             OutputType::Rgb => ImageType::Rgb,
         }
@@ -413,6 +514,7 @@ impl From<MaterialChannel> for ImageType {
             MaterialChannel::Metallic => ImageType::Grayscale,
             MaterialChannel::Alpha => ImageType::Grayscale,
             MaterialChannel::AmbientOcclusion => ImageType::Grayscale,
+            MaterialChannel::Emissive => ImageType::Rgb,
         }
     }
 }
@@ -437,7 +539,7 @@ impl OperatorType {
     /// forbidden!
     pub fn can_unify(&self, other: &OperatorType) -> bool {
         match (self, other) {
-            (OperatorType::Monomorphic(t), OperatorType::Monomorphic(q)) => t == q,
+            (OperatorType::Monomorphic(t), OperatorType::Monomorphic(q)) => t.connects_to(*q),
             (OperatorType::Monomorphic(_), OperatorType::Polymorphic(_)) => true,
             (OperatorType::Polymorphic(_), OperatorType::Monomorphic(_)) => true,
             (OperatorType::Polymorphic(_), OperatorType::Polymorphic(_)) => false,
@@ -454,7 +556,7 @@ impl OperatorType {
         ty_vars_other: &HashMap<TypeVariable, ImageType>,
     ) -> bool {
         match (self, other) {
-            (OperatorType::Monomorphic(t), OperatorType::Monomorphic(q)) => t == q,
+            (OperatorType::Monomorphic(t), OperatorType::Monomorphic(q)) => t.connects_to(*q),
             (OperatorType::Monomorphic(t), OperatorType::Polymorphic(q)) => {
                 ty_vars_other.get(q).map(|z| t == z).unwrap_or(true)
             }
@@ -535,10 +637,57 @@ impl TryFrom<OperatorType> for TypeVariable {
     }
 }
 
+/// A query for searching nodes across all graphs. Filters that are `None` are
+/// not applied, such that the default query matches every node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeQuery {
+    /// Only match nodes whose operator has this default name, e.g. `blend`
+    pub operator: Option<String>,
+    /// Only match nodes whose name starts with this prefix
+    pub name_prefix: Option<String>,
+}
+
+impl NodeQuery {
+    /// Determine whether a node with the given resource and operator is
+    /// matched by this query.
+    pub fn matches(&self, node: &Resource<Node>, operator: &Operator) -> bool {
+        let operator_matches = self
+            .operator
+            .as_ref()
+            .map(|o| operator.default_name() == o)
+            .unwrap_or(true);
+        let name_matches = self
+            .name_prefix
+            .as_ref()
+            .map(|p| {
+                node.file()
+                    .map(|n| n.starts_with(p.as_str()))
+                    .unwrap_or(false)
+            })
+            .unwrap_or(true);
+
+        operator_matches && name_matches
+    }
+}
+
+/// Complexity metrics of a node collection.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphStatistics {
+    /// Number of nodes in the collection
+    pub node_count: usize,
+    /// Number of connections in the collection
+    pub edge_count: usize,
+    /// Length of the longest chain of dependent nodes
+    pub max_depth: usize,
+    /// Number of occurrences of each operator. Atomic operators are keyed by
+    /// their variant name, complex operators by their graph.
+    pub operator_histogram: HashMap<String, usize>,
+}
+
 /// Events concerning node operation triggered by the user, such as adding,
 /// removing, etc. These events should be treated as unsanitized, since they are
 /// user generated.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UserNodeEvent {
     /// The user requests creation of a new node inside a given graph, using
     /// this operator, at layout position. The optional socket specifies an
@@ -552,6 +701,9 @@ pub enum UserNodeEvent {
         Option<Resource<Socket>>,
         Option<String>,
     ),
+    /// The user requests duplication of the given node, with all its current
+    /// parameters, at the layout position. Connections are not duplicated.
+    DuplicateNode(Resource<Node>, (f64, f64)),
     /// The user requests the removal of a given node.
     RemoveNode(Resource<Node>),
     /// The user requests dissolving of the given node.
@@ -576,12 +728,14 @@ pub enum UserNodeEvent {
     OutputSizeChange(Resource<Node>, OperatorSize),
     /// The user requests display of the given socket or disabling it
     ViewSocket(Option<Resource<Socket>>),
+    /// The user searches for nodes matching the query across all graphs.
+    SearchNodes(NodeQuery),
 }
 
 /// Events concerning graph operation triggered by the user, such as adding,
 /// removing, etc. These events should be treated as unsanitized, since they are
 /// user generated.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UserGraphEvent {
     /// The user adds a new graph.
     AddGraph,
@@ -591,6 +745,9 @@ pub enum UserGraphEvent {
     RenameGraph(Resource<Graph>, Resource<Graph>),
     /// The user seeks to delete a graph.
     DeleteGraph(Resource<Graph>),
+    /// The user seeks to create a copy of a graph or layer stack under the
+    /// given name.
+    CloneGraph(Resource<Graph>, String),
     /// The user asks for exposure of a given parameter, where the two strings
     /// represent (in order) the *graph field*, i.e. the field name to be used
     /// for the exposed parameter, and the *title*, i.e. the human readable name
@@ -611,6 +768,13 @@ pub enum UserGraphEvent {
     /// the node. The bool determines whether injected nodes should be
     /// repositioned.
     Inject(Resource<Node>, Resource<Graph>, bool),
+    /// The user requests automatic layouting of all nodes in the graph.
+    AutoLayout(Resource<Graph>),
+    /// The user requests complexity statistics for the graph.
+    RequestStatistics(Resource<Graph>),
+    /// The user requests writing the graph in Graphviz DOT format to the
+    /// given path, for debugging purposes.
+    ExportDot(Resource<Graph>, PathBuf),
 }
 
 /// Events concerning graphs, not directly coming from user input.
@@ -652,6 +816,9 @@ pub enum GraphEvent {
         ComplexOperator,
         ParamBoxDescription<MessageWriters>,
     ),
+    /// A parameter substitution on a complex operator has been removed,
+    /// because the called graph no longer exposes the parameter.
+    StaleParameterRemoved(Resource<Node>, String),
     /// Two sockets have been connected. The first socket is the source, the
     /// second the sink.
     ConnectedSockets(Resource<Socket>, Resource<Socket>),
@@ -660,8 +827,25 @@ pub enum GraphEvent {
     /// A graph has been relinearized, resulting in the new linearization data
     /// supplied.
     Relinearized(Resource<Graph>, Linearization, UsePoints),
-    /// A graph needs to be recomputed.
-    Recompute(Resource<Graph>, Vec<(ExportSpec, PathBuf)>),
+    /// A graph has been validated after relinearization. Contains the nodes
+    /// affected by problems, along with a description of each problem. An
+    /// empty list means the graph is valid.
+    ValidationReport(Resource<Graph>, Vec<(Resource<Node>, String)>),
+    /// Results of a node search, in response to a search request.
+    SearchResults(Vec<Resource<Node>>),
+    /// Complexity statistics of a graph, in response to a statistics request.
+    Statistics(Resource<Graph>, GraphStatistics),
+    /// A graph has been exported in Graphviz DOT format, to be written to the
+    /// given path.
+    DotExported(PathBuf, String),
+    /// A graph needs to be recomputed, exporting to the given targets.
+    /// Optionally carries a set of nodes known to be dirty, in addition to
+    /// those determined by compute itself.
+    Recompute(
+        Resource<Graph>,
+        Vec<(ExportSpec, ExportTarget)>,
+        Option<HashSet<Resource<Node>>>,
+    ),
     /// A sockets type has been monomorphized to the given image type.
     SocketMonomorphized(Resource<Socket>, ImageType),
     /// A sockets type is no longer monomorphic.
@@ -675,6 +859,9 @@ pub enum GraphEvent {
     OutputRemoved(Resource<Node>, OutputType),
     /// *All* graphs have been cleared in the system.
     Cleared,
+    /// Graph data has been imported successfully, replacing all graphs in
+    /// the system. Precedes the events describing the imported graphs.
+    Imported,
     /// Loaded graphs have been serialized
     Serialized(Vec<u8>),
 }
@@ -686,25 +873,10 @@ pub enum LayerType {
     Fx,
 }
 
-#[derive(Clone, Debug)]
-pub enum LayerDropTarget {
-    Below(Resource<Node>),
-    Above(Resource<Node>),
-}
-
-impl LayerDropTarget {
-    pub fn target(&self) -> &Resource<Node> {
-        match self {
-            LayerDropTarget::Below(t) => t,
-            LayerDropTarget::Above(t) => t,
-        }
-    }
-}
-
 /// Events concerning layer operation triggered by the user, such as adding,
 /// removing, reordering, etc. These events should be treated as unsanitized,
 /// since they are user generated.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UserLayersEvent {
     /// The user requests a new layer stack.
     AddLayers,
@@ -718,10 +890,21 @@ pub enum UserLayersEvent {
     PushMask(Resource<Node>, Operator),
     /// The user requests removal of a layer.
     RemoveLayer(Resource<Node>),
+    /// The user requests duplication of a layer. The copy is placed directly
+    /// above the source layer.
+    DuplicateLayer(Resource<Node>),
+    /// The user requests combining the given layers into a group. The layers
+    /// must be adjacent siblings in the same stack.
+    GroupLayers(Vec<Resource<Node>>),
+    /// The user requests dissolving a group, moving its contents into its
+    /// place in the stack.
+    UngroupLayers(Resource<Node>),
     /// The user requests removal of a mask.
     RemoveMask(Resource<Node>),
-    /// The user requests moving a layer (or mask) to a position in the stack
-    PositionLayer(Resource<Node>, LayerDropTarget),
+    /// The user requests moving a layer (or mask) to the given index in its
+    /// stack. Layers are indexed from the bottom of the layer stack, masks
+    /// from the bottom of the mask stack of their layer.
+    PositionLayer(Resource<Node>, usize),
     /// The user requests setting the output of a layer to the given material
     /// channel to the specified output as enumerated. The boolean denotes
     /// whether the channel is enabled or not.
@@ -791,7 +974,30 @@ pub enum LayersEvent {
         ParamBoxDescription<MessageWriters>,
         u32,
     ),
-    LayerPositioned(Resource<Node>, LayerDropTarget),
+    /// A layer (or mask) was moved from the first to the second index in its
+    /// stack.
+    LayerPositioned(Resource<Node>, usize, usize),
+    /// The given layers have been combined into a group. The fields describe
+    /// the following, in order
+    ///
+    /// 1. The resource of the new group
+    /// 2. The human readable title
+    /// 3. The grouped layers, from the bottom of the group upwards
+    /// 4. The blend mode of the group
+    /// 5. The opacity of the group
+    /// 6. The image size
+    LayersGrouped(
+        Resource<Node>,
+        String,
+        Vec<Resource<Node>>,
+        BlendMode,
+        f32,
+        u32,
+    ),
+    /// The given group has been dissolved, with its contents moved into its
+    /// place. Lists the formerly grouped layers from the bottom upwards. The
+    /// group itself is subsequently removed with `LayerRemoved`.
+    LayersUngrouped(Resource<Node>, Vec<Resource<Node>>),
 }
 
 /// Events concerning surfaces, not directly coming from user input.
@@ -808,6 +1014,14 @@ pub enum SurfaceEvent {
     ParentSizeSet(u32, bool),
     /// The export size has been set.
     ExportSizeSet(OperatorSize),
+    /// An export into an archive at the given path has been started, with the
+    /// given number of entries to be expected.
+    ArchiveExportStarted(PathBuf, usize),
+    /// An export into a texture atlas at the given path has been started,
+    /// with the given number of tiles to be expected.
+    AtlasExportStarted(PathBuf, usize),
+    /// All recomputes required for a requested export have been queued.
+    ExportsQueued,
 }
 
 /// Renderers are indexed by an ID, internally merely a `u64`.
@@ -822,6 +1036,28 @@ pub enum LightType {
     SunLight = 1,
 }
 
+/// A single light source, as used by renderers supporting multiple lights.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Light {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub light_type: LightType,
+    pub strength: f32,
+    pub size: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            position: [0., 3., 0.],
+            color: [1., 1., 1.],
+            light_type: LightType::PointLight,
+            strength: 100.0,
+            size: 1.0,
+        }
+    }
+}
+
 /// Object types supported by the SDF 3D renderer
 #[derive(AsBytes, Copy, Clone, Debug, Serialize, EnumVariantNames, Deserialize)]
 #[repr(u32)]
@@ -833,27 +1069,34 @@ pub enum ObjectType {
     Sphere = 3,
     Cylinder = 4,
     InfiniteCylinder = 5,
+    Capsule = 6,
+    Cone = 7,
 }
 
 /// Shading modes supported by the SDF 3D renderer
 #[derive(
-    AsBytes, Copy, Clone, Debug, Serialize, EnumVariantNames, Deserialize, UnsafeFromPrimitive,
+    AsBytes, Copy, Clone, Debug, Serialize, EnumVariantNames, Deserialize, TryFromPrimitive,
 )]
 #[repr(u32)]
 #[strum(serialize_all = "kebab_case")]
 pub enum ShadingMode {
     Pbr = 0,
     Matcap = 1,
+    Subsurface = 2,
 }
 
 impl ShadingMode {
     pub fn has_lights(self) -> bool {
-        matches!(self, Self::Pbr)
+        matches!(self, Self::Pbr | Self::Subsurface)
     }
 
     pub fn has_matcap(self) -> bool {
         matches!(self, Self::Matcap)
     }
+
+    pub fn has_subsurface(self) -> bool {
+        matches!(self, Self::Subsurface)
+    }
 }
 
 /// Tonemapping operators for renderer
@@ -868,7 +1111,7 @@ pub enum ToneMap {
 }
 
 /// Events concerning renderer operation triggered by the user.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UserRenderEvent {
     /// The user requests rotation of the view by angles theta and phi.
     Rotate(RendererID, f32, f32),
@@ -896,8 +1139,18 @@ pub enum UserRenderEvent {
     LightStrength(RendererID, f32),
     /// The user requests setting the light size
     LightSize(RendererID, f32),
+    /// The user requests adding a new light to the renderer.
+    AddLight(RendererID),
+    /// The user requests removal of the light with the given index.
+    RemoveLight(RendererID, usize),
+    /// The user requests updating the light with the given index.
+    UpdateLight(RendererID, usize, Light),
     /// The user requests setting the fog strength
     FogStrength(RendererID, f32),
+    /// The user requests setting the subsurface scattering color
+    SssColor(RendererID, [f32; 3]),
+    /// The user requests setting the subsurface scattering strength
+    SssStrength(RendererID, f32),
     /// The user requests setting the focal length
     FocalLength(RendererID, f32),
     /// The user requests setting the aperture size
@@ -912,6 +1165,20 @@ pub enum UserRenderEvent {
     SetShadow(RendererID, ParameterBool),
     /// The user requests setting the ambient occlusion strength
     AoStrength(RendererID, f32),
+    /// The user requests enabling/disabling the wireframe overlay, drawing
+    /// UV isocurves on the rendered object
+    WireframeOverlay(RendererID, ParameterBool),
+    /// The user requests setting the width of wireframe lines in pixels
+    WireWidth(RendererID, f32),
+    /// The user requests setting the radius of the capsule object
+    CapsuleRadius(RendererID, f32),
+    /// The user requests setting the distance between the cap centers of the
+    /// capsule object
+    CapsuleLength(RendererID, f32),
+    /// The user requests setting the height of the cone object
+    ConeHeight(RendererID, f32),
+    /// The user requests setting the half angle of the cone object in radians
+    ConeAngle(RendererID, f32),
     /// The user seeks to load a new HDRI from file
     LoadHdri(RendererID, Option<PathBuf>),
     /// The user seeks to load a new matcap from file
@@ -924,13 +1191,26 @@ pub enum UserRenderEvent {
     ToneMap(RendererID, ToneMap),
     /// The user requests setting the sample count
     SampleCount(RendererID, u32),
+    /// The user requests adapting the sample count to the render time budget,
+    /// or keeping the current sample count fixed
+    AdaptiveSamples(RendererID, bool),
+    /// The user requests setting the background color, as RGBA
+    BackgroundColor(RendererID, [f32; 4]),
+    /// The user requests showing or hiding the HDRI behind the material in
+    /// a 2D renderer
+    ShowHdriBackground(RendererID, bool),
     /// The user requests resetting of the camera position
     CenterCamera(RendererID),
+    /// The user requests saving the current render result to the given path.
+    /// The format is determined by the file extension, either PNG or EXR.
+    ExportFrame(RendererID, PathBuf),
 }
 
 /// Supported color spaces for (external) images.
 #[repr(u32)]
-#[derive(Debug, EnumIter, ToString, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Hash)]
+#[derive(
+    Debug, EnumIter, ToString, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Hash, AsBytes,
+)]
 #[strum(serialize_all = "kebab_case")]
 pub enum ColorSpace {
     Srgb,
@@ -959,6 +1239,71 @@ impl ExportFormat {
     }
 }
 
+/// Target of a single export.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportTarget {
+    /// Export directly to a file at the given path.
+    File(PathBuf),
+    /// Export as an entry with the given name into an archive at the given
+    /// path. The archive is assembled by the IO component.
+    Archive(PathBuf, String),
+    /// Export as a tile in the given region of a texture atlas at the given
+    /// path. The atlas is assembled by the IO component.
+    Atlas(PathBuf, AtlasRegion),
+}
+
+/// Position of a tile in a square texture atlas. The size of the atlas grid
+/// is inferred from the largest row and column in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasRegion {
+    pub row: u32,
+    pub column: u32,
+}
+
+/// Packing of material channels into the channels of a single RGBA image, as
+/// commonly used by game engines. Unassigned color channels are filled with 0,
+/// an unassigned alpha channel with 1.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ChannelPack {
+    pub r: Option<MaterialChannel>,
+    pub g: Option<MaterialChannel>,
+    pub b: Option<MaterialChannel>,
+    pub a: Option<MaterialChannel>,
+    /// Per channel inversion, in RGBA order, e.g. to store smoothness rather
+    /// than roughness.
+    pub inverted: [bool; 4],
+}
+
+impl ChannelPack {
+    /// Unity HDRP mask map, packing metallic, ambient occlusion, detail mask
+    /// and smoothness. The detail mask is left empty.
+    pub fn unity_hdrp() -> Self {
+        Self {
+            r: Some(MaterialChannel::Metallic),
+            g: Some(MaterialChannel::AmbientOcclusion),
+            b: None,
+            a: Some(MaterialChannel::Roughness),
+            inverted: [false, false, false, true],
+        }
+    }
+
+    /// Unreal ORM map, packing ambient occlusion, roughness and metallic.
+    pub fn unreal_orm() -> Self {
+        Self {
+            r: Some(MaterialChannel::AmbientOcclusion),
+            g: Some(MaterialChannel::Roughness),
+            b: Some(MaterialChannel::Metallic),
+            a: None,
+            inverted: [false; 4],
+        }
+    }
+
+    /// The packed channels in RGBA order.
+    pub fn channels(&self) -> [Option<MaterialChannel>; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
 /// Export specifications
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ExportSpec {
@@ -967,6 +1312,10 @@ pub struct ExportSpec {
     pub color_space: ColorSpace,
     pub bit_depth: u8,
     pub format: ExportFormat,
+    /// When set, the outputs for the packed channels in the graph of the node
+    /// are combined into a single RGBA image instead.
+    #[serde(default)]
+    pub channel_pack: Option<ChannelPack>,
 }
 
 impl From<&Resource<Node>> for ExportSpec {
@@ -977,15 +1326,26 @@ impl From<&Resource<Node>> for ExportSpec {
             color_space: ColorSpace::Srgb,
             bit_depth: 8,
             format: ExportFormat::Png,
+            channel_pack: None,
         }
     }
 }
 
 impl ExportSpec {
-    fn legal(color_space: ColorSpace, format: ExportFormat, bit_depth: u8) -> bool {
+    fn legal(&self, color_space: ColorSpace, format: ExportFormat, bit_depth: u8) -> bool {
         use ColorSpace::*;
         use ExportFormat::*;
 
+        // Channel packs are always RGBA images, which only some formats support
+        if self.channel_pack.is_some()
+            && !matches!(
+                (format, bit_depth),
+                (Png, 8) | (Png, 16) | (Tiff, 8) | (Tga, 8)
+            )
+        {
+            return false;
+        }
+
         match (color_space, format, bit_depth) {
             (Srgb, Png, 8) => true,
             (Linear, Png, 8) => true,
@@ -1004,17 +1364,17 @@ impl ExportSpec {
 
     /// Determine whether a color space is legal for this spec
     pub fn color_space_legal(&self, color_space: ColorSpace) -> bool {
-        Self::legal(color_space, self.format, self.bit_depth)
+        self.legal(color_space, self.format, self.bit_depth)
     }
 
     /// Determine whether a format is legal for this spec
     pub fn format_legal(&self, format: ExportFormat) -> bool {
-        Self::legal(self.color_space, format, self.bit_depth)
+        self.legal(self.color_space, format, self.bit_depth)
     }
 
     /// Determine whether a bit depth is legal for this spec
     pub fn bit_depth_legal(&self, bit_depth: u8) -> bool {
-        Self::legal(self.color_space, self.format, bit_depth)
+        self.legal(self.color_space, self.format, bit_depth)
     }
 
     /// Sanitize this spec such that all entries are legal for the color space
@@ -1025,7 +1385,7 @@ impl ExportSpec {
         if !self.color_space_legal(self.color_space) {
             let (format, bit_depth) = ExportFormat::iter()
                 .cartesian_product([8, 16, 32].iter().copied())
-                .find(|(f, b)| Self::legal(self.color_space, *f, *b))
+                .find(|(f, b)| self.legal(self.color_space, *f, *b))
                 .unwrap();
             self.format = format;
             self.bit_depth = bit_depth;
@@ -1040,13 +1400,24 @@ impl ExportSpec {
         if !self.format_legal(self.format) {
             let (color_space, bit_depth) = ColorSpace::iter()
                 .cartesian_product([8, 16, 32].iter().copied())
-                .find(|(c, b)| Self::legal(*c, self.format, *b))
+                .find(|(c, b)| self.legal(*c, self.format, *b))
                 .unwrap();
             self.color_space = color_space;
             self.bit_depth = bit_depth;
         }
     }
 
+    /// Sanitize this spec such that all of its entries are legal together,
+    /// keeping the color space where possible.
+    pub fn sanitize(&mut self) {
+        if !self.legal(self.color_space, self.format, self.bit_depth) {
+            self.sanitize_for_color_space();
+        }
+        if !self.legal(self.color_space, self.format, self.bit_depth) {
+            self.sanitize_for_format();
+        }
+    }
+
     /// Sanitize this spec such that all entries are legal for the bit depth
     pub fn sanitize_for_bit_depth(&mut self) {
         use itertools::Itertools;
@@ -1055,7 +1426,7 @@ impl ExportSpec {
         if !self.bit_depth_legal(self.bit_depth) {
             let (color_space, format) = ColorSpace::iter()
                 .cartesian_product(ExportFormat::iter())
-                .find(|(c, f)| Self::legal(*c, *f, self.bit_depth))
+                .find(|(c, f)| self.legal(*c, *f, self.bit_depth))
                 .unwrap();
             self.color_space = color_space;
             self.format = format;
@@ -1063,14 +1434,38 @@ impl ExportSpec {
     }
 }
 
+/// Logical actions triggered by keyboard shortcuts. The UI translates raw key
+/// presses into these events, such that other components can respond to
+/// shortcuts without depending on the widget toolkit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UserShortcutEvent {
+    /// Undo the last action
+    Undo,
+    /// Redo the last undone action
+    Redo,
+}
+
+/// A material preset is a set of parameter values to be applied at once,
+/// keyed by the parameters they are applied to.
+pub type MaterialPreset = HashMap<Resource<Param>, Vec<u8>>;
+
 /// IO related events triggered by the user. Should be treated as unsanitized
 /// because they are usually user generated.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UserIOEvent {
     /// The user requests loading a surface from file to replace the current.
     OpenSurface(PathBuf),
     /// The user requests saving the current surface to file.
     SaveSurface(PathBuf),
+    /// The user requests exporting the node data of the current surface to a
+    /// human readable JSON file.
+    ExportGraphJson(PathBuf),
+    /// The user requests importing node data from a JSON file, replacing the
+    /// current node data.
+    ImportGraphJson(PathBuf),
+    /// The user requests applying all parameter values of a material preset
+    /// to the given graph, with a single recompute at the end.
+    ApplyMaterialPreset(Resource<Graph>, MaterialPreset),
     /// The user seeks to add an image resource from a file.
     AddImageResource(PathBuf),
     /// The user seeks to set the colorspace of an image.
@@ -1103,8 +1498,17 @@ pub enum UserIOEvent {
     RemoveExportSpec(String),
     /// The user requests export according to existing specification.
     RunExports(PathBuf),
+    /// The user requests export according to existing specification, packing
+    /// all exported images into a single ZIP archive.
+    RunExportsZip(PathBuf),
+    /// The user requests exporting the given output nodes into a single
+    /// texture atlas, placing each output in the given region.
+    ExportAtlas(PathBuf, Vec<(Resource<Node>, AtlasRegion)>),
     /// The user requests a new surface file.
     NewSurface,
+    /// The user requests setting the interval between autosaves. A zero
+    /// duration disables autosaving.
+    SetAutosaveInterval(std::time::Duration),
     /// The user requests quitting the application.
     Quit,
     /// The user requests an undo
@@ -1113,12 +1517,16 @@ pub enum UserIOEvent {
     Redo,
     /// The user resized the window
     ResizeWindow(u32, u32),
+    /// The user requests defragmentation of compute memory.
+    DefragmentVram,
 }
 
 /// Events triggered during computation or setup thereof
 #[derive(Debug)]
 pub enum ComputeEvent {
-    /// The system has computed an output image.
+    /// The system has computed an output image. The receipt is to be
+    /// acknowledged once the image has been consumed, since compute will not
+    /// touch the image until then.
     OutputReady(
         Resource<Node>,
         crate::gpu::BrokerImage,
@@ -1126,25 +1534,36 @@ pub enum ComputeEvent {
         crate::gpu::Access,
         u32,
         OutputType,
+        crate::broker::Receipt,
     ),
-    /// The system has computed a socket for viewing
+    /// The system has computed a socket for viewing. The receipt is to be
+    /// acknowledged once the image has been consumed.
     SocketViewReady(
         crate::gpu::BrokerImage,
         crate::gpu::Layout,
         crate::gpu::Access,
         u32,
         ImageType,
+        crate::broker::Receipt,
     ),
     /// The system has created a compute socket with a fixed type.
     SocketCreated(Resource<Socket>, ImageType),
     /// The system has destroyed a compute socket.
     SocketDestroyed(Resource<Socket>),
-    /// The system has created and filled a thumbnail.
-    ThumbnailCreated(Resource<Node>, crate::gpu::BrokerImageView),
-    /// The system has destroyed a thumbnail.
-    ThumbnailDestroyed(Resource<Node>),
-    /// The system has the given thumbnail for the given node.
-    ThumbnailUpdated(Resource<Node>),
+    /// The system has created and filled a thumbnail for the given socket of
+    /// a node.
+    ThumbnailCreated(
+        Resource<Node>,
+        Resource<Socket>,
+        crate::gpu::BrokerImageView,
+    ),
+    /// The system has destroyed the thumbnail for the given socket of a node.
+    ThumbnailDestroyed(Resource<Node>, Resource<Socket>),
+    /// The system has updated the thumbnail for the given socket of a node.
+    ThumbnailUpdated(Resource<Node>, Resource<Socket>),
+    /// Executing the given node failed, aborting the current compute pass.
+    /// Contains a human readable description of the failure.
+    OperatorError(Resource<Node>, String),
     /// An image resource has been registered. The bool describes whether the resource is packed.
     ImageResourceAdded(Resource<Img>, ColorSpace, bool),
     /// An image resource has been unregistered.
@@ -1165,6 +1584,20 @@ pub enum ComputeEvent {
     Cleared,
     /// System compiled VRAM usage report, bytes used and total bytes in managed region
     VramUsage(usize, usize),
+    /// GPU time spent executing the given operator.
+    OperatorTiming(Resource<Node>, std::time::Duration),
+    /// An image has been encoded for an archive export. Contains the archive
+    /// path, the entry name, and the encoded data.
+    ExportEncoded(PathBuf, String, Vec<u8>),
+    /// An image has been converted for a texture atlas export. Contains the
+    /// atlas path, the region of the tile, and the tile itself.
+    AtlasTileReady(PathBuf, AtlasRegion, image::RgbaImage),
+    /// An export to the given target was not delivered by the compute pass
+    /// responsible for it.
+    ExportFailed(ExportTarget),
+    /// All exports queued so far have been computed. File exports have been
+    /// written, all other exports have been delivered to IO.
+    ExportsComputed,
 }
 
 /// Type of renderer.
@@ -1186,6 +1619,7 @@ pub enum MaterialChannel {
     Metallic = 4,
     Alpha = 5,
     AmbientOcclusion = 6,
+    Emissive = 7,
 }
 
 impl MaterialChannel {
@@ -1206,6 +1640,7 @@ impl MaterialChannel {
             MaterialChannel::Displacement => "dsp",
             MaterialChannel::Alpha => "alpha",
             MaterialChannel::AmbientOcclusion => "ao",
+            MaterialChannel::Emissive => "emi",
         }
     }
 }
@@ -1263,6 +1698,11 @@ pub enum RenderEvent {
     SettingsUpdated(RendererID, ParamBoxDescription<RenderField>),
     /// Render settings have been serialized.
     Serialized(Vec<u8>),
+    /// GPU time spent rendering the last frame of the given renderer.
+    FrameTiming(RendererID, std::time::Duration),
+    /// The current render result of the given renderer has been saved to the
+    /// given path.
+    FrameExported(RendererID, PathBuf),
 }
 
 /// Events from the IO component
@@ -1274,6 +1714,16 @@ pub enum IOEvent {
     ComputeDataLoaded(Vec<u8>),
     /// Render Settings have been loaded by the IO component
     RenderSettingsLoaded(Vec<u8>),
+    /// The data of an image resource has been loaded from the given path by
+    /// the IO component
+    ImageResourceLoaded(Resource<Img>, PathBuf, Vec<u8>),
+    /// Loading an image resource from the given path has failed with the
+    /// given error message
+    ImageResourceLoadFailed(Resource<Img>, PathBuf, String),
+    /// The surface has been saved to the given path
+    SurfaceSaved(PathBuf),
+    /// All exports requested so far have been written, or have failed
+    ExportsFinished,
 }
 
 /// Events from the scheduler
@@ -1283,6 +1733,8 @@ pub enum ScheduleEvent {
     Autosave,
     /// VRAM statistics scheduled
     VramUsage,
+    /// Watched resources should be checked for changes
+    WatchResources,
 }
 
 /// Master event type used by the application bus. This defines the common
@@ -1294,6 +1746,7 @@ pub enum Lang {
     UserLayersEvent(UserLayersEvent),
     UserRenderEvent(UserRenderEvent),
     UserIOEvent(UserIOEvent),
+    UserShortcutEvent(UserShortcutEvent),
     IOEvent(IOEvent),
     UIEvent(UIEvent),
     GraphEvent(GraphEvent),
@@ -1303,3 +1756,98 @@ pub enum Lang {
     RenderEvent(RenderEvent),
     ScheduleEvent(ScheduleEvent),
 }
+
+/// The recorded form of `Lang`. Only user events are recorded, since all
+/// other events follow from them.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    UserNodeEvent(UserNodeEvent),
+    UserGraphEvent(UserGraphEvent),
+    UserLayersEvent(UserLayersEvent),
+    UserRenderEvent(UserRenderEvent),
+    UserIOEvent(UserIOEvent),
+}
+
+/// Work started by recorded events, that has to finish before playback ends.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedWork {
+    /// Saving the surface to the given path
+    Save(PathBuf),
+    /// Running exports
+    Export,
+}
+
+impl crate::broker::Record for Lang {
+    type Recorded = RecordedEvent;
+    type Work = RecordedWork;
+
+    /// Undo and redo, including their shortcuts, are not recorded, because the
+    /// events performing them are recorded instead. Quitting is left to the end
+    /// of playback.
+    fn record(&self) -> Option<RecordedEvent> {
+        match self {
+            Lang::UserIOEvent(UserIOEvent::Undo)
+            | Lang::UserIOEvent(UserIOEvent::Redo)
+            | Lang::UserIOEvent(UserIOEvent::Quit)
+            | Lang::UserShortcutEvent(_) => None,
+            Lang::UserNodeEvent(ev) => Some(RecordedEvent::UserNodeEvent(ev.clone())),
+            Lang::UserGraphEvent(ev) => Some(RecordedEvent::UserGraphEvent(ev.clone())),
+            Lang::UserLayersEvent(ev) => Some(RecordedEvent::UserLayersEvent(ev.clone())),
+            Lang::UserRenderEvent(ev) => Some(RecordedEvent::UserRenderEvent(ev.clone())),
+            Lang::UserIOEvent(ev) => Some(RecordedEvent::UserIOEvent(ev.clone())),
+            _ => None,
+        }
+    }
+
+    fn replay(recorded: RecordedEvent) -> Self {
+        match recorded {
+            RecordedEvent::UserNodeEvent(ev) => Lang::UserNodeEvent(ev),
+            RecordedEvent::UserGraphEvent(ev) => Lang::UserGraphEvent(ev),
+            RecordedEvent::UserLayersEvent(ev) => Lang::UserLayersEvent(ev),
+            RecordedEvent::UserRenderEvent(ev) => Lang::UserRenderEvent(ev),
+            RecordedEvent::UserIOEvent(ev) => Lang::UserIOEvent(ev),
+        }
+    }
+
+    fn ends_recording(&self) -> bool {
+        matches!(self, Lang::UserIOEvent(UserIOEvent::Quit))
+    }
+
+    fn starts_work(&self) -> Option<RecordedWork> {
+        match self {
+            Lang::UserIOEvent(UserIOEvent::SaveSurface(path)) => {
+                Some(RecordedWork::Save(path.clone()))
+            }
+            Lang::UserIOEvent(UserIOEvent::RunExports(..))
+            | Lang::UserIOEvent(UserIOEvent::RunExportsZip(..))
+            | Lang::UserIOEvent(UserIOEvent::ExportAtlas(..)) => Some(RecordedWork::Export),
+            _ => None,
+        }
+    }
+
+    fn finishes_work(&self) -> Option<RecordedWork> {
+        match self {
+            Lang::IOEvent(IOEvent::SurfaceSaved(path)) => Some(RecordedWork::Save(path.clone())),
+            Lang::IOEvent(IOEvent::ExportsFinished) => Some(RecordedWork::Export),
+            _ => None,
+        }
+    }
+
+    fn end_of_playback() -> Option<Self> {
+        Some(Lang::UserIOEvent(UserIOEvent::Quit))
+    }
+}
+
+impl crate::broker::Supersede for Lang {
+    /// Parameter changes supersede earlier changes to the same parameter, since
+    /// they carry the full new value.
+    fn supersedes(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Lang::UserNodeEvent(UserNodeEvent::ParameterChange(r1, ..)),
+                Lang::UserNodeEvent(UserNodeEvent::ParameterChange(r2, ..)),
+            ) => r1 == r2,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Composite the foreground over the background according to their alpha
+/// channels, following the Porter-Duff over operator for straight alpha. The
+/// opacity scales the alpha of the foreground.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct AlphaBlend {
+    pub opacity: f32,
+}
+
+impl Default for AlphaBlend {
+    fn default() -> Self {
+        Self { opacity: 1.0 }
+    }
+}
+
+impl Socketed for AlphaBlend {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "bg".to_string() => (OperatorType::Monomorphic(ImageType::Rgba), false),
+            "fg".to_string() => (OperatorType::Monomorphic(ImageType::Rgba), false),
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "color".to_string() => OperatorType::Monomorphic(ImageType::Rgba),
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "alpha_blend"
+    }
+
+    fn title(&self) -> &str {
+        "Alpha Blend"
+    }
+}
+
+impl Shader for AlphaBlend {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("alpha_blend"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::InputImage("bg"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::InputImage("fg"),
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 4,
+                    descriptor: OperatorDescriptorUse::OutputImage("color"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for AlphaBlend {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("alpha_blend".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![Parameter {
+                    name: "opacity".to_string(),
+                    transmitter: Field(AlphaBlend::OPACITY.to_string()),
+                    control: Control::Slider {
+                        value: self.opacity,
+                        min: 0.,
+                        max: 1.,
+                    },
+                    expose_status: Some(ExposeStatus::Unexposed),
+                    visibility: VisibilityFunction::default(),
+                    presetable: true,
+                }],
+            }],
+        }
+    }
+}
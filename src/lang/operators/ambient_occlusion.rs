@@ -34,6 +34,7 @@ pub enum AmbientOcclusionQuality {
 
 #[repr(C)]
 #[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+#[serde(default)]
 pub struct AmbientOcclusion {
     pub quality: AmbientOcclusionQuality,
     pub jitter: ParameterBool,
@@ -41,6 +42,12 @@ pub struct AmbientOcclusion {
     pub falloff: f32,
     pub depth: f32,
     pub albedo: f32,
+    pub bias: f32,
+    /// Number of directions to search for horizons in. Zero uses the number
+    /// given by the quality setting.
+    pub ray_count: u32,
+    /// Distance beyond which heightfield samples no longer occlude
+    pub max_distance: f32,
 }
 
 impl Default for AmbientOcclusion {
@@ -52,6 +59,9 @@ impl Default for AmbientOcclusion {
             falloff: 0.5,
             depth: 1.,
             albedo: 0.5,
+            bias: 0.,
+            ray_count: 0,
+            max_distance: 8.,
         }
     }
 }
@@ -191,6 +201,42 @@ impl OperatorParamBox for AmbientOcclusion {
                         visibility: VisibilityFunction::default(),
                         presetable: true,
                     },
+                    Parameter {
+                        name: "bias".to_string(),
+                        transmitter: Field(AmbientOcclusion::BIAS.to_string()),
+                        control: Control::Slider {
+                            value: self.bias,
+                            min: 0.,
+                            max: 0.5,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "ray-count".to_string(),
+                        transmitter: Field(AmbientOcclusion::RAY_COUNT.to_string()),
+                        control: Control::DiscreteSlider {
+                            value: self.ray_count as i32,
+                            min: 0,
+                            max: 64,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "max-distance".to_string(),
+                        transmitter: Field(AmbientOcclusion::MAX_DISTANCE.to_string()),
+                        control: Control::Slider {
+                            value: self.max_distance,
+                            min: 0.,
+                            max: 8.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
                 ],
             }],
         }
@@ -173,10 +173,10 @@ impl OperatorParamBox for Blur {
                     Parameter {
                         name: "sigma".to_string(),
                         transmitter: Field(Blur::SIGMA.to_string()),
-                        control: Control::Slider {
+                        control: Control::LogSlider {
                             value: self.sigma,
                             min: 1.,
-                            max: 256.,
+                            max: 1024.,
                         },
                         expose_status: Some(ExposeStatus::Unexposed),
                         visibility: VisibilityFunction::default(),
@@ -0,0 +1,160 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Brick pattern with offset rows. Brick sizes are given relative to the image
+/// size and rounded such that an integer number of bricks fits, keeping the
+/// pattern tileable. The number of rows is additionally rounded to be even,
+/// since alternate rows are offset.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct Brick {
+    pub brick_width: f32,
+    pub brick_height: f32,
+    pub mortar_width: f32,
+    pub offset: f32,
+    pub random_color: f32,
+}
+
+impl Default for Brick {
+    fn default() -> Self {
+        Self {
+            brick_width: 0.25,
+            brick_height: 0.125,
+            mortar_width: 0.01,
+            offset: 0.5,
+            random_color: 0.2,
+        }
+    }
+}
+
+impl Socketed for Brick {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {}
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "mask".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
+            "height".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "brick"
+    }
+
+    fn title(&self) -> &str {
+        "Brick"
+    }
+}
+
+impl Shader for Brick {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("brick"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::OutputImage("mask"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::OutputImage("height"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for Brick {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("brick".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "brick-width".to_string(),
+                        transmitter: Field(Brick::BRICK_WIDTH.to_string()),
+                        control: Control::Slider {
+                            value: self.brick_width,
+                            min: 0.01,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "brick-height".to_string(),
+                        transmitter: Field(Brick::BRICK_HEIGHT.to_string()),
+                        control: Control::Slider {
+                            value: self.brick_height,
+                            min: 0.01,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "mortar-width".to_string(),
+                        transmitter: Field(Brick::MORTAR_WIDTH.to_string()),
+                        control: Control::Slider {
+                            value: self.mortar_width,
+                            min: 0.,
+                            max: 0.1,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "row-offset".to_string(),
+                        transmitter: Field(Brick::OFFSET.to_string()),
+                        control: Control::Slider {
+                            value: self.offset,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "random-color".to_string(),
+                        transmitter: Field(Brick::RANDOM_COLOR.to_string()),
+                        control: Control::Slider {
+                            value: self.random_color,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use super::super::ColorSpace;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Convert color images between sRGB and linear color spaces, using the
+/// transfer function of IEC 61966-2-1.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct ConvertColorSpace {
+    pub from: ColorSpace,
+    pub to: ColorSpace,
+}
+
+impl Default for ConvertColorSpace {
+    fn default() -> Self {
+        Self {
+            from: ColorSpace::Srgb,
+            to: ColorSpace::Linear,
+        }
+    }
+}
+
+impl Socketed for ConvertColorSpace {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "in".to_string() => (OperatorType::Monomorphic(ImageType::Rgb), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "out".to_string() => OperatorType::Monomorphic(ImageType::Rgb)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "convert_color_space"
+    }
+
+    fn title(&self) -> &str {
+        "Convert Color Space"
+    }
+}
+
+impl Shader for ConvertColorSpace {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("convert_color_space"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::InputImage("in"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::OutputImage("out"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for ConvertColorSpace {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("convert_color_space".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "from-color-space".to_string(),
+                        transmitter: Field(ConvertColorSpace::FROM.to_string()),
+                        control: Control::Enum {
+                            selected: self.from as usize,
+                            variants: ColorSpace::iter().map(|x| x.to_string()).collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "to-color-space".to_string(),
+                        transmitter: Field(ConvertColorSpace::TO.to_string()),
+                        control: Control::Enum {
+                            selected: self.to as usize,
+                            variants: ColorSpace::iter().map(|x| x.to_string()).collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::VariantNames;
+use strum_macros::*;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// How to interpret the input of the curvature operator.
+#[repr(u32)]
+#[derive(
+    AsBytes,
+    Clone,
+    Copy,
+    Debug,
+    EnumIter,
+    EnumVariantNames,
+    EnumString,
+    Serialize,
+    Deserialize,
+    PartialEq,
+)]
+#[strum(serialize_all = "kebab_case")]
+pub enum CurvatureSource {
+    Normal = 0,
+    Displacement = 1,
+}
+
+/// Extract surface curvature from a normal or displacement map. The output is
+/// centered around 0.5, with convex regions brighter and concave regions
+/// darker.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct Curvature {
+    pub source: CurvatureSource,
+    pub strength: f32,
+    pub clamp_output: ParameterBool,
+}
+
+impl Default for Curvature {
+    fn default() -> Self {
+        Self {
+            source: CurvatureSource::Normal,
+            strength: 1.0,
+            clamp_output: 1,
+        }
+    }
+}
+
+impl Socketed for Curvature {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "in".to_string() => (OperatorType::Polymorphic(0), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "out".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "curvature"
+    }
+
+    fn title(&self) -> &str {
+        "Curvature"
+    }
+}
+
+impl Shader for Curvature {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("curvature"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::InputImage("in"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::OutputImage("out"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for Curvature {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("curvature".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "curvature-source".to_string(),
+                        transmitter: Field(Curvature::SOURCE.to_string()),
+                        control: Control::Enum {
+                            selected: self.source as usize,
+                            variants: CurvatureSource::VARIANTS
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "strength".to_string(),
+                        transmitter: Field(Curvature::STRENGTH.to_string()),
+                        control: Control::Slider {
+                            value: self.strength,
+                            min: 0.,
+                            max: 16.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "clamp".to_string(),
+                        transmitter: Field(Curvature::CLAMP_OUTPUT.to_string()),
+                        control: Control::Toggle {
+                            def: self.clamp_output == 1,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
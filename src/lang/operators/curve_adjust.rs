@@ -0,0 +1,267 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Number of LUT entries per curve
+const LUT_SIZE: usize = 256;
+
+/// Side length of the LUT texture, holding `LUT_SIZE` entries
+const LUT_DIM: usize = 16;
+
+/// Curve based color adjustment, with separate curves for the red, green, and
+/// blue channels, and a master curve applied to all channels afterwards.
+///
+/// Curves are edited as ramps, where the position of each step is the input
+/// value and the luminance of its color gives the output value. Each curve is
+/// baked into a half float LUT, occupying one channel of the LUT texture
+/// passed to the shader.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CurveAdjust {
+    pub red: Vec<(f32, f32)>,
+    pub green: Vec<(f32, f32)>,
+    pub blue: Vec<(f32, f32)>,
+    pub master: Vec<(f32, f32)>,
+}
+
+impl Default for CurveAdjust {
+    fn default() -> Self {
+        let identity = vec![(0.0, 0.0), (1.0, 1.0)];
+        Self {
+            red: identity.clone(),
+            green: identity.clone(),
+            blue: identity.clone(),
+            master: identity,
+        }
+    }
+}
+
+impl CurveAdjust {
+    pub const LUT_R: &'static str = "lut_r";
+    pub const LUT_G: &'static str = "lut_g";
+    pub const LUT_B: &'static str = "lut_b";
+    pub const LUT_MASTER: &'static str = "lut_master";
+
+    /// The points of a curve in the format used by ramp controls, i.e. gray
+    /// with the position in the last component.
+    fn ramp_steps(curve: &[(f32, f32)]) -> Vec<[f32; 4]> {
+        curve.iter().map(|(x, y)| [*y, *y, *y, *x]).collect()
+    }
+
+    /// The curves in order of their channel in the LUT texture.
+    fn curves(&self) -> [&[(f32, f32)]; 4] {
+        [&self.red, &self.green, &self.blue, &self.master]
+    }
+}
+
+/// Obtain a curve from ramp steps, sorted by input value.
+fn curve_from_data(data: &[u8]) -> Vec<(f32, f32)> {
+    let mut curve: Vec<_> = <Vec<[f32; 4]>>::from_data(data)
+        .into_iter()
+        .map(|[r, g, b, x]| (x, r * 0.2126 + g * 0.7152 + b * 0.0722))
+        .collect();
+    curve.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    curve
+}
+
+/// Evaluate a curve at the given input value by linear interpolation between
+/// its points. Curves are kept sorted by input value.
+fn evaluate_curve(curve: &[(f32, f32)], x: f32) -> f32 {
+    match curve {
+        [] => x,
+        [(pos, first), ..] if x <= *pos => *first,
+        [.., (pos, last)] if x >= *pos => *last,
+        _ => {
+            let ((low_pos, low), (high_pos, high)) = curve
+                .windows(2)
+                .map(|w| (w[0], w[1]))
+                .find(|((low, _), (high, _))| *low <= x && x <= *high)
+                .unwrap();
+            let interval = high_pos - low_pos;
+            let t = if interval > 0.0 {
+                (x - low_pos) / interval
+            } else {
+                0.0
+            };
+            low + (high - low) * t
+        }
+    }
+}
+
+/// Curves are transmitted from ramp controls, and therefore need to be
+/// converted on setting.
+impl Parameters for CurveAdjust {
+    fn set_parameter(&mut self, field: &str, data: &[u8]) {
+        match field {
+            Self::LUT_R => self.red = curve_from_data(data),
+            Self::LUT_G => self.green = curve_from_data(data),
+            Self::LUT_B => self.blue = curve_from_data(data),
+            Self::LUT_MASTER => self.master = curve_from_data(data),
+            _ => panic!("Unknown field {}", field),
+        }
+    }
+}
+
+/// The curves are passed to the shader as a LUT texture, so there are no
+/// uniforms. The hash is computed from the curves such that the LUT gets
+/// uploaded again on parameter change.
+impl Uniforms for CurveAdjust {
+    fn uniforms(&self) -> Cow<[u8]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn uniform_hash(&self) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let mut hasher = DefaultHasher::new();
+        for curve in self.curves().iter() {
+            hasher.write_usize(curve.len());
+            for (x, y) in curve.iter() {
+                hasher.write_u32(x.to_bits());
+                hasher.write_u32(y.to_bits());
+            }
+        }
+        hasher.finish()
+    }
+}
+
+impl Socketed for CurveAdjust {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "color_in".to_string() => (OperatorType::Monomorphic(ImageType::Rgb), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "color_out".to_string() => OperatorType::Monomorphic(ImageType::Rgb)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "curve_adjust"
+    }
+
+    fn title(&self) -> &str {
+        "Curve Adjust"
+    }
+}
+
+impl Shader for CurveAdjust {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("curve_adjust"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::IntermediateImage("lut", false),
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::InputImage("color_in"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::OutputImage("color_out"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+
+    fn intermediate_data(&self) -> HashMap<String, IntermediateDataDescription> {
+        hashmap! {
+            "lut".to_string() => IntermediateDataDescription::Image {
+                size: FromSocketOr::Independent(LUT_DIM as u32),
+                ty: FromSocketOr::Independent(ImageType::Rgb),
+                mips: false,
+            },
+        }
+    }
+
+    fn intermediate_image_data(&self) -> HashMap<String, Vec<u16>> {
+        let curves = self.curves();
+        let lut = (0..LUT_SIZE)
+            .flat_map(|i| {
+                let x = i as f32 / (LUT_SIZE - 1) as f32;
+                curves
+                    .iter()
+                    .map(move |curve| half::f16::from_f32(evaluate_curve(curve, x)).to_bits())
+            })
+            .collect();
+
+        hashmap! {
+            "lut".to_string() => lut,
+        }
+    }
+}
+
+impl OperatorParamBox for CurveAdjust {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("curve_adjust".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "red-curve".to_string(),
+                        transmitter: Field(CurveAdjust::LUT_R.to_string()),
+                        control: Control::Ramp {
+                            steps: Self::ramp_steps(&self.red),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "green-curve".to_string(),
+                        transmitter: Field(CurveAdjust::LUT_G.to_string()),
+                        control: Control::Ramp {
+                            steps: Self::ramp_steps(&self.green),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "blue-curve".to_string(),
+                        transmitter: Field(CurveAdjust::LUT_B.to_string()),
+                        control: Control::Ramp {
+                            steps: Self::ramp_steps(&self.blue),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "master-curve".to_string(),
+                        transmitter: Field(CurveAdjust::LUT_MASTER.to_string()),
+                        control: Control::Ramp {
+                            steps: Self::ramp_steps(&self.master),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
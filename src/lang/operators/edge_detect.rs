@@ -0,0 +1,105 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct EdgeDetect {
+    pub sigma: f32,
+}
+
+impl Default for EdgeDetect {
+    fn default() -> Self {
+        Self { sigma: 1.0 }
+    }
+}
+
+impl Socketed for EdgeDetect {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "in".to_string() => (OperatorType::Polymorphic(0), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "out".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "edge_detect"
+    }
+
+    fn title(&self) -> &str {
+        "Edge Detect"
+    }
+}
+
+impl Shader for EdgeDetect {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("edge_detect"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::Occupancy,
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::InputImage("in"),
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 4,
+                    descriptor: OperatorDescriptorUse::OutputImage("out"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for EdgeDetect {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("edge_detect".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![Parameter {
+                    name: "sigma".to_string(),
+                    transmitter: Field(EdgeDetect::SIGMA.to_string()),
+                    control: Control::Slider {
+                        value: self.sigma,
+                        min: 0.5,
+                        max: 2.,
+                    },
+                    expose_status: Some(ExposeStatus::Unexposed),
+                    visibility: VisibilityFunction::default(),
+                    presetable: true,
+                }],
+            }],
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct FractalNoise {
+    pub scale: f32,
+    pub octaves: u32,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl Default for FractalNoise {
+    fn default() -> Self {
+        Self {
+            scale: 3.0,
+            octaves: 6,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+}
+
+impl Socketed for FractalNoise {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {}
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "noise".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
+            "warp".to_string() => OperatorType::Monomorphic(ImageType::Rgb),
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "fractal_noise"
+    }
+
+    fn title(&self) -> &str {
+        "Fractal Noise"
+    }
+}
+
+impl Shader for FractalNoise {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("fractal_noise"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::OutputImage("noise"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::OutputImage("warp"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for FractalNoise {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("fractal_noise".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "scale".to_string(),
+                        transmitter: Field(FractalNoise::SCALE.to_string()),
+                        control: Control::Slider {
+                            value: self.scale,
+                            min: 0.,
+                            max: 16.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "octaves".to_string(),
+                        transmitter: Field(FractalNoise::OCTAVES.to_string()),
+                        control: Control::DiscreteSlider {
+                            value: self.octaves as i32,
+                            min: 1,
+                            max: 16,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "lacunarity".to_string(),
+                        transmitter: Field(FractalNoise::LACUNARITY.to_string()),
+                        control: Control::Slider {
+                            value: self.lacunarity,
+                            min: 1.,
+                            max: 4.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "gain".to_string(),
+                        transmitter: Field(FractalNoise::GAIN.to_string()),
+                        control: Control::Slider {
+                            value: self.gain,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
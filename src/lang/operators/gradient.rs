@@ -0,0 +1,158 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use num_enum::TryFromPrimitive;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::VariantNames;
+use strum_macros::*;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+#[repr(u32)]
+#[derive(
+    AsBytes,
+    Clone,
+    Copy,
+    Debug,
+    EnumIter,
+    EnumVariantNames,
+    EnumString,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    TryFromPrimitive,
+)]
+#[strum(serialize_all = "kebab_case")]
+pub enum GradientType {
+    Linear = 0,
+    Radial = 1,
+    Angular = 2,
+}
+
+impl GradientType {
+    pub fn has_direction(self) -> bool {
+        matches!(self, Self::Linear | Self::Angular)
+    }
+}
+
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct Gradient {
+    pub gradient_type: GradientType,
+    pub direction: f32,
+    pub repeat: u32,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self {
+            gradient_type: GradientType::Linear,
+            direction: 0.,
+            repeat: 1,
+        }
+    }
+}
+
+impl Socketed for Gradient {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {}
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "gradient".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "gradient"
+    }
+
+    fn title(&self) -> &str {
+        "Gradient"
+    }
+}
+
+impl Shader for Gradient {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("gradient"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::OutputImage("gradient"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for Gradient {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("gradient".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "gradient-type".to_string(),
+                        transmitter: Field(Gradient::GRADIENT_TYPE.to_string()),
+                        control: Control::Enum {
+                            selected: self.gradient_type as usize,
+                            variants: GradientType::VARIANTS
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "direction".to_string(),
+                        transmitter: Field(Gradient::DIRECTION.to_string()),
+                        control: Control::Slider {
+                            value: self.direction,
+                            min: 0.,
+                            max: 360.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::on_parameter_enum(
+                            "gradient-type",
+                            |t: GradientType| t.has_direction(),
+                        ),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "repeat".to_string(),
+                        transmitter: Field(Gradient::REPEAT.to_string()),
+                        control: Control::DiscreteSlider {
+                            value: self.repeat as i32,
+                            min: 1,
+                            max: 32,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
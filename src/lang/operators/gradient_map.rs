@@ -0,0 +1,211 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Side length of the LUT texture. The LUT holds the square of this many
+/// entries.
+const LUT_DIM: usize = 16;
+
+/// Maps grayscale values to colors through a multi stop gradient. The stops
+/// are kept sorted by position and baked into a lookup table texture for the
+/// shader.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct GradientMap {
+    pub stops: Vec<(f32, [f32; 4])>,
+}
+
+impl GradientMap {
+    pub const STOPS: &'static str = "stops";
+
+    /// Evaluate the gradient at the given position by linear interpolation
+    /// between its stops.
+    fn evaluate(&self, x: f32) -> [f32; 4] {
+        match self.stops.as_slice() {
+            [] => [x, x, x, 1.0],
+            [(pos, first), ..] if x <= *pos => *first,
+            [.., (pos, last)] if x >= *pos => *last,
+            _ => {
+                let ((low_pos, low), (high_pos, high)) = self
+                    .stops
+                    .windows(2)
+                    .map(|w| (w[0], w[1]))
+                    .find(|((low, _), (high, _))| *low <= x && x <= *high)
+                    .unwrap();
+                let interval = high_pos - low_pos;
+                let t = if interval > 0.0 {
+                    (x - low_pos) / interval
+                } else {
+                    0.0
+                };
+                let mut result = [0.0; 4];
+                for (i, r) in result.iter_mut().enumerate() {
+                    *r = low[i] + (high[i] - low[i]) * t;
+                }
+                result
+            }
+        }
+    }
+
+    /// The stops of this gradient in the format used by ramp controls, i.e.
+    /// RGB color with the position in the last component.
+    fn ramp_steps(&self) -> Vec<[f32; 4]> {
+        self.stops
+            .iter()
+            .map(|(pos, [r, g, b, _])| [*r, *g, *b, *pos])
+            .collect()
+    }
+}
+
+impl Default for GradientMap {
+    fn default() -> Self {
+        Self {
+            stops: vec![(0.0, [0.0, 0.0, 0.0, 1.0]), (1.0, [1.0, 1.0, 1.0, 1.0])],
+        }
+    }
+}
+
+/// Stops are transmitted from ramp controls, and therefore need to be
+/// converted on setting.
+impl Parameters for GradientMap {
+    fn set_parameter(&mut self, field: &str, data: &[u8]) {
+        match field {
+            Self::STOPS => {
+                self.stops = <Vec<[f32; 4]>>::from_data(data)
+                    .into_iter()
+                    .map(|[r, g, b, pos]| (pos, [r, g, b, 1.0]))
+                    .collect();
+                self.stops
+                    .sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            }
+            _ => panic!("Unknown field {}", field),
+        }
+    }
+}
+
+/// The gradient is passed to the shader as a LUT texture, so there are no
+/// uniforms. The hash is computed from the stops such that the LUT gets
+/// uploaded again on parameter change.
+impl Uniforms for GradientMap {
+    fn uniforms(&self) -> Cow<[u8]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn uniform_hash(&self) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let mut hasher = DefaultHasher::new();
+        for (pos, color) in &self.stops {
+            hasher.write_u32(pos.to_bits());
+            for c in color {
+                hasher.write_u32(c.to_bits());
+            }
+        }
+        hasher.finish()
+    }
+}
+
+impl Socketed for GradientMap {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "factor".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "color".to_string() => OperatorType::Monomorphic(ImageType::Rgb),
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "gradient_map"
+    }
+
+    fn title(&self) -> &str {
+        "Gradient Map"
+    }
+}
+
+impl Shader for GradientMap {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("gradient_map"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::IntermediateImage("lut", false),
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::InputImage("factor"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::OutputImage("color"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+
+    fn intermediate_data(&self) -> HashMap<String, IntermediateDataDescription> {
+        hashmap! {
+            "lut".to_string() => IntermediateDataDescription::Image {
+                size: FromSocketOr::Independent(LUT_DIM as u32),
+                ty: FromSocketOr::Independent(ImageType::Rgb),
+                mips: false,
+            },
+        }
+    }
+
+    fn intermediate_image_data(&self) -> HashMap<String, Vec<u16>> {
+        let entries = LUT_DIM * LUT_DIM;
+        let lut = (0..entries)
+            .flat_map(|i| self.evaluate(i as f32 / (entries - 1) as f32).to_vec())
+            .map(|x| half::f16::from_f32(x).to_bits())
+            .collect();
+
+        hashmap! {
+            "lut".to_string() => lut,
+        }
+    }
+}
+
+impl OperatorParamBox for GradientMap {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("gradient_map".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![Parameter {
+                    name: "gradient".to_string(),
+                    transmitter: Field(GradientMap::STOPS.to_string()),
+                    control: Control::Ramp {
+                        steps: self.ramp_steps(),
+                    },
+                    expose_status: Some(ExposeStatus::Unexposed),
+                    visibility: VisibilityFunction::default(),
+                    presetable: true,
+                }],
+            }],
+        }
+    }
+}
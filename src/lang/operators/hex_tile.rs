@@ -0,0 +1,131 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Hexagonal tile pattern, producing a cell mask and a random value per cell.
+/// The scale is the number of cells across the image, rounded such that the
+/// pattern stays tileable.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct HexTile {
+    pub scale: f32,
+    pub gap: f32,
+    pub aspect: f32,
+}
+
+impl Default for HexTile {
+    fn default() -> Self {
+        Self {
+            scale: 8.0,
+            gap: 0.05,
+            aspect: 1.0,
+        }
+    }
+}
+
+impl Socketed for HexTile {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {}
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "mask".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
+            "id".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "hex_tile"
+    }
+
+    fn title(&self) -> &str {
+        "Hexagonal Tile"
+    }
+}
+
+impl Shader for HexTile {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("hex_tile"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::OutputImage("mask"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::OutputImage("id"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for HexTile {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("hex_tile".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "scale".to_string(),
+                        transmitter: Field(HexTile::SCALE.to_string()),
+                        control: Control::Slider {
+                            value: self.scale,
+                            min: 1.,
+                            max: 64.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "gap".to_string(),
+                        transmitter: Field(HexTile::GAP.to_string()),
+                        control: Control::Slider {
+                            value: self.gap,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "aspect".to_string(),
+                        transmitter: Field(HexTile::ASPECT.to_string()),
+                        control: Control::Slider {
+                            value: self.aspect,
+                            min: 0.25,
+                            max: 4.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Number of bins in the histogram. Must match the shader.
+const HISTOGRAM_BINS: usize = 256;
+
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct HistogramEqualize {
+    /// Clip limit for histogram bins, in multiples of the average bin height.
+    /// Excess counts are redistributed evenly. A limit of 0 disables clipping.
+    pub clip_limit: f32,
+}
+
+impl Default for HistogramEqualize {
+    fn default() -> Self {
+        Self { clip_limit: 0.0 }
+    }
+}
+
+impl Socketed for HistogramEqualize {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "in".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "out".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "histogram_equalize"
+    }
+
+    fn title(&self) -> &str {
+        "Histogram Equalize"
+    }
+}
+
+const HISTOGRAM_EQUALIZE_DESCRIPTORS: &'static [OperatorDescriptor] = &[
+    OperatorDescriptor {
+        binding: 0,
+        descriptor: OperatorDescriptorUse::Uniforms,
+    },
+    OperatorDescriptor {
+        binding: 1,
+        descriptor: OperatorDescriptorUse::InputImage("in"),
+    },
+    OperatorDescriptor {
+        binding: 2,
+        descriptor: OperatorDescriptorUse::Sampler,
+    },
+    OperatorDescriptor {
+        binding: 3,
+        descriptor: OperatorDescriptorUse::IntermediateBuffer("histogram"),
+    },
+    OperatorDescriptor {
+        binding: 4,
+        descriptor: OperatorDescriptorUse::IntermediateBuffer("cdf"),
+    },
+    OperatorDescriptor {
+        binding: 5,
+        descriptor: OperatorDescriptorUse::OutputImage("out"),
+    },
+];
+
+impl Shader for HistogramEqualize {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![
+            // Clear the histogram
+            OperatorPassDescription::SynchronizeBuffer(&[SynchronizeDescription::ToWrite(
+                "histogram",
+            )]),
+            OperatorPassDescription::RunShader(OperatorShader {
+                spirv: shader!("histogram_equalize"),
+                descriptors: HISTOGRAM_EQUALIZE_DESCRIPTORS,
+                specialization: gfx_hal::spec_const_list!(0u32),
+                shape: OperatorShape::PerRowOrColumn { local_size: 8 },
+            }),
+            // Accumulate histogram
+            OperatorPassDescription::SynchronizeBuffer(&[SynchronizeDescription::ToReadWrite(
+                "histogram",
+            )]),
+            OperatorPassDescription::RunShader(OperatorShader {
+                spirv: shader!("histogram_equalize"),
+                descriptors: HISTOGRAM_EQUALIZE_DESCRIPTORS,
+                specialization: gfx_hal::spec_const_list!(1u32),
+                shape: OperatorShape::PerPixel {
+                    local_x: 8,
+                    local_y: 8,
+                },
+            }),
+            // Clip histogram and build the cumulative distribution
+            OperatorPassDescription::SynchronizeBuffer(&[
+                SynchronizeDescription::ToRead("histogram"),
+                SynchronizeDescription::ToWrite("cdf"),
+            ]),
+            OperatorPassDescription::RunShader(OperatorShader {
+                spirv: shader!("histogram_equalize"),
+                descriptors: HISTOGRAM_EQUALIZE_DESCRIPTORS,
+                specialization: gfx_hal::spec_const_list!(2u32),
+                shape: OperatorShape::PerRowOrColumn { local_size: 8 },
+            }),
+            // Remap
+            OperatorPassDescription::SynchronizeBuffer(&[SynchronizeDescription::ToRead("cdf")]),
+            OperatorPassDescription::RunShader(OperatorShader {
+                spirv: shader!("histogram_equalize"),
+                descriptors: HISTOGRAM_EQUALIZE_DESCRIPTORS,
+                specialization: gfx_hal::spec_const_list!(3u32),
+                shape: OperatorShape::PerPixel {
+                    local_x: 8,
+                    local_y: 8,
+                },
+            }),
+        ]
+    }
+
+    fn intermediate_data(&self) -> HashMap<String, IntermediateDataDescription> {
+        hashmap! {
+            "histogram".to_string() => IntermediateDataDescription::Buffer {
+                dim: BufferDim::Vector(FromSocketOr::Independent(HISTOGRAM_BINS)),
+                element_width: std::mem::size_of::<u32>(),
+            },
+            "cdf".to_string() => IntermediateDataDescription::Buffer {
+                dim: BufferDim::Vector(FromSocketOr::Independent(HISTOGRAM_BINS)),
+                element_width: std::mem::size_of::<f32>(),
+            },
+        }
+    }
+}
+
+impl OperatorParamBox for HistogramEqualize {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("histogram_equalize".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![Parameter {
+                    name: "clip-limit".to_string(),
+                    transmitter: Field(HistogramEqualize::CLIP_LIMIT.to_string()),
+                    control: Control::Slider {
+                        value: self.clip_limit,
+                        min: 0.,
+                        max: 16.,
+                    },
+                    expose_status: Some(ExposeStatus::Unexposed),
+                    visibility: VisibilityFunction::default(),
+                    presetable: true,
+                }],
+            }],
+        }
+    }
+}
@@ -13,19 +13,34 @@ use strum::VariantNames;
 use strum_macros::*;
 use surfacelab_derive::*;
 
+pub mod alpha_blend;
 pub mod alpha_extract;
 pub mod ambient_occlusion;
 pub mod blend;
 pub mod blur;
+pub mod brick;
 pub mod checker;
 pub mod color_adjust;
+pub mod convert_color_space;
 pub mod coordinate_transform;
+pub mod curvature;
+pub mod curve_adjust;
 pub mod distance;
+pub mod edge_detect;
+pub mod fractal_noise;
+pub mod gradient;
+pub mod gradient_map;
 pub mod grayscale;
+pub mod height_to_normal;
+pub mod hex_tile;
+pub mod histogram_equalize;
+pub mod morphology;
+pub mod noise_fbm;
 pub mod noise_spread;
 pub mod normal_blend;
 pub mod normal_map;
 pub mod perlin_noise;
+pub mod pixelate;
 pub mod ramp;
 pub mod range;
 pub mod rgb;
@@ -34,24 +49,45 @@ pub mod select;
 pub mod shape;
 pub mod split_merge;
 pub mod svg;
+pub mod swizzle;
+pub mod texture_transform;
+pub mod threshold;
+pub mod tile;
 pub mod transform;
 pub mod value;
 pub mod voronoi;
 pub mod warp;
+pub mod warped_noise;
+pub mod wave;
 
+pub use alpha_blend::*;
 pub use alpha_extract::*;
 pub use ambient_occlusion::*;
 pub use blend::*;
 pub use blur::*;
+pub use brick::*;
 pub use checker::*;
 pub use color_adjust::*;
+pub use convert_color_space::*;
 pub use coordinate_transform::*;
+pub use curvature::*;
+pub use curve_adjust::*;
 pub use distance::*;
+pub use edge_detect::*;
+pub use fractal_noise::*;
+pub use gradient::*;
+pub use gradient_map::*;
 pub use grayscale::*;
+pub use height_to_normal::*;
+pub use hex_tile::*;
+pub use histogram_equalize::*;
+pub use morphology::*;
+pub use noise_fbm::*;
 pub use noise_spread::*;
 pub use normal_blend::*;
 pub use normal_map::*;
 pub use perlin_noise::*;
+pub use pixelate::*;
 pub use ramp::*;
 pub use range::*;
 pub use rgb::*;
@@ -60,10 +96,16 @@ pub use select::*;
 pub use shape::*;
 pub use split_merge::*;
 pub use svg::*;
+pub use swizzle::*;
+pub use texture_transform::*;
+pub use threshold::*;
+pub use tile::*;
 pub use transform::*;
 pub use value::*;
 pub use voronoi::*;
 pub use warp::*;
+pub use warped_noise::*;
+pub use wave::*;
 
 /// Image operator to include external images into a node graph
 #[derive(Clone, Debug, Serialize, Deserialize, Parameters, PartialEq)]
@@ -170,6 +212,7 @@ pub enum OutputType {
     Metallic,
     AmbientOcclusion,
     Alpha,
+    Emissive,
     Value,
     Rgb,
 }
@@ -179,6 +222,7 @@ impl From<ImageType> for OutputType {
         match t {
             ImageType::Grayscale => OutputType::Value,
             ImageType::Rgb => OutputType::Rgb,
+            ImageType::Rgba => OutputType::Rgb,
         }
     }
 }
@@ -202,6 +246,7 @@ impl From<MaterialChannel> for OutputType {
             MaterialChannel::Metallic => OutputType::Metallic,
             MaterialChannel::Alpha => OutputType::Alpha,
             MaterialChannel::AmbientOcclusion => OutputType::AmbientOcclusion,
+            MaterialChannel::Emissive => OutputType::Emissive,
         }
     }
 }
@@ -389,3 +434,41 @@ impl OperatorParamBox for Input {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::*;
+
+    /// Serializing and deserializing an operator as done when saving a
+    /// surface must yield the same operator.
+    #[test]
+    fn serde_roundtrip() {
+        for op in AtomicOperator::all_default() {
+            let data = serde_cbor::to_vec(&op).unwrap();
+            let decoded: AtomicOperator = serde_cbor::from_slice(&data).unwrap();
+            assert_eq!(op, decoded, "serde round trip failed for {}", op);
+        }
+    }
+
+    /// Setting each parameter to the value held by its control in the
+    /// parameter box must leave the operator unchanged. This verifies that
+    /// the data produced by controls is decoded correctly by the derived
+    /// `set_parameter`.
+    #[test]
+    fn parameter_roundtrip() {
+        for op in AtomicOperator::all_default() {
+            let mut decoded = op.clone();
+
+            for category in op.param_box_description().categories {
+                for parameter in category.parameters {
+                    decoded.set_parameter(&parameter.transmitter.0, &parameter.control.value());
+                    assert_eq!(
+                        op, decoded,
+                        "parameter round trip failed for {} on {}",
+                        parameter.transmitter.0, op
+                    );
+                }
+            }
+        }
+    }
+}
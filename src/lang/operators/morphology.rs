@@ -0,0 +1,204 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+const MORPHOLOGY_DESCRIPTORS: &[OperatorDescriptor] = &[
+    OperatorDescriptor {
+        binding: 0,
+        descriptor: OperatorDescriptorUse::Uniforms,
+    },
+    OperatorDescriptor {
+        binding: 1,
+        descriptor: OperatorDescriptorUse::InputImage("in"),
+    },
+    OperatorDescriptor {
+        binding: 2,
+        descriptor: OperatorDescriptorUse::Sampler,
+    },
+    OperatorDescriptor {
+        binding: 3,
+        descriptor: OperatorDescriptorUse::IntermediateImage("tmp", false),
+    },
+    OperatorDescriptor {
+        binding: 4,
+        descriptor: OperatorDescriptorUse::OutputImage("out"),
+    },
+];
+
+const MORPHOLOGY_DILATE: u32 = 0;
+const MORPHOLOGY_ERODE: u32 = 1;
+
+/// The separable max/min filter is run as a row pass into an intermediate
+/// image, followed by a column pass into the output.
+fn morphology_passes(mode: u32) -> Vec<OperatorPassDescription> {
+    vec![
+        OperatorPassDescription::SynchronizeImage(&[SynchronizeDescription::ToReadWrite("tmp")]),
+        OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("morphology"),
+            descriptors: MORPHOLOGY_DESCRIPTORS,
+            specialization: gfx_hal::spec_const_list!(0u32, mode),
+            shape: OperatorShape::PerRowOrColumn { local_size: 64 },
+        }),
+        OperatorPassDescription::SynchronizeImage(&[SynchronizeDescription::ToReadWrite("tmp")]),
+        OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("morphology"),
+            descriptors: MORPHOLOGY_DESCRIPTORS,
+            specialization: gfx_hal::spec_const_list!(1u32, mode),
+            shape: OperatorShape::PerRowOrColumn { local_size: 64 },
+        }),
+    ]
+}
+
+fn morphology_intermediate_data() -> HashMap<String, IntermediateDataDescription> {
+    hashmap! {
+        "tmp".to_string() => IntermediateDataDescription::Image {
+            size: FromSocketOr::FromSocket("out"),
+            ty: FromSocketOr::FromSocket("out"),
+            mips: false,
+        },
+    }
+}
+
+fn morphology_inputs() -> HashMap<String, (OperatorType, bool)> {
+    hashmap! {
+        "in".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), false)
+    }
+}
+
+fn morphology_outputs() -> HashMap<String, OperatorType> {
+    hashmap! {
+        "out".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
+    }
+}
+
+fn radius_parameter(radius: u32, field: &str) -> Parameter<Field> {
+    Parameter {
+        name: "radius".to_string(),
+        transmitter: Field(field.to_string()),
+        control: Control::DiscreteSlider {
+            value: radius as i32,
+            min: 1,
+            max: 32,
+        },
+        expose_status: Some(ExposeStatus::Unexposed),
+        visibility: VisibilityFunction::default(),
+        presetable: true,
+    }
+}
+
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct Dilate {
+    pub radius: u32,
+}
+
+impl Default for Dilate {
+    fn default() -> Self {
+        Self { radius: 1 }
+    }
+}
+
+impl Socketed for Dilate {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        morphology_inputs()
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        morphology_outputs()
+    }
+
+    fn default_name(&self) -> &str {
+        "dilate"
+    }
+
+    fn title(&self) -> &str {
+        "Dilate"
+    }
+}
+
+impl Shader for Dilate {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        morphology_passes(MORPHOLOGY_DILATE)
+    }
+
+    fn intermediate_data(&self) -> HashMap<String, IntermediateDataDescription> {
+        morphology_intermediate_data()
+    }
+}
+
+impl OperatorParamBox for Dilate {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("dilate".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![radius_parameter(self.radius, Dilate::RADIUS)],
+            }],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct Erode {
+    pub radius: u32,
+}
+
+impl Default for Erode {
+    fn default() -> Self {
+        Self { radius: 1 }
+    }
+}
+
+impl Socketed for Erode {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        morphology_inputs()
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        morphology_outputs()
+    }
+
+    fn default_name(&self) -> &str {
+        "erode"
+    }
+
+    fn title(&self) -> &str {
+        "Erode"
+    }
+}
+
+impl Shader for Erode {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        morphology_passes(MORPHOLOGY_ERODE)
+    }
+
+    fn intermediate_data(&self) -> HashMap<String, IntermediateDataDescription> {
+        morphology_intermediate_data()
+    }
+}
+
+impl OperatorParamBox for Erode {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("erode".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![radius_parameter(self.radius, Erode::RADIUS)],
+            }],
+        }
+    }
+}
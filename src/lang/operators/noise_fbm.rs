@@ -0,0 +1,168 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Fractal Brownian motion over an arbitrary base noise. The input is sampled
+/// at increasing frequencies and the octaves are summed up. With tiling
+/// enabled, the frequency of each octave is rounded to an integer such that
+/// a tileable input yields a tileable output for any lacunarity.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct NoiseFbm {
+    pub octaves: u32,
+    pub lacunarity: f32,
+    pub gain: f32,
+    pub initial_amplitude: f32,
+    #[serde(default = "default_tiling")]
+    pub tiling: ParameterBool,
+}
+
+fn default_tiling() -> ParameterBool {
+    1
+}
+
+impl Default for NoiseFbm {
+    fn default() -> Self {
+        Self {
+            octaves: 6,
+            lacunarity: 2.0,
+            gain: 0.5,
+            initial_amplitude: 0.5,
+            tiling: default_tiling(),
+        }
+    }
+}
+
+impl Socketed for NoiseFbm {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "noise".to_string() => (OperatorType::Polymorphic(0), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "out".to_string() => OperatorType::Polymorphic(0)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "noise_fbm"
+    }
+
+    fn title(&self) -> &str {
+        "Noise FBm"
+    }
+}
+
+impl Shader for NoiseFbm {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("noise_fbm"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::InputImage("noise"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::OutputImage("out"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for NoiseFbm {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("noise_fbm".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "octaves".to_string(),
+                        transmitter: Field(NoiseFbm::OCTAVES.to_string()),
+                        control: Control::DiscreteSlider {
+                            value: self.octaves as i32,
+                            min: 1,
+                            max: 16,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "lacunarity".to_string(),
+                        transmitter: Field(NoiseFbm::LACUNARITY.to_string()),
+                        control: Control::Slider {
+                            value: self.lacunarity,
+                            min: 1.,
+                            max: 4.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "gain".to_string(),
+                        transmitter: Field(NoiseFbm::GAIN.to_string()),
+                        control: Control::Slider {
+                            value: self.gain,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "initial-amplitude".to_string(),
+                        transmitter: Field(NoiseFbm::INITIAL_AMPLITUDE.to_string()),
+                        control: Control::Slider {
+                            value: self.initial_amplitude,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "tiling".to_string(),
+                        transmitter: Field(NoiseFbm::TILING.to_string()),
+                        control: Control::Toggle {
+                            def: self.tiling == 1,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
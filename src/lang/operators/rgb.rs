@@ -6,19 +6,49 @@ use crate::shader;
 use maplit::hashmap;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
-use surfacelab_derive::*;
 use zerocopy::AsBytes;
 
+/// A constant color with alpha. The color is edited as a whole, with the
+/// alpha stored separately such that files predating it remain loadable.
 #[repr(C)]
-#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Rgb {
     pub rgb: [f32; 3],
+    #[serde(default = "default_alpha")]
+    pub alpha: f32,
+}
+
+fn default_alpha() -> f32 {
+    1.0
+}
+
+impl Rgb {
+    pub const RGB: &'static str = "rgb";
 }
 
 impl Default for Rgb {
     fn default() -> Self {
         Self {
             rgb: [0.5, 0.7, 0.3],
+            alpha: default_alpha(),
+        }
+    }
+}
+
+/// The color is transmitted as a `ColorParam`. Exposed parameters created
+/// before alpha support still transmit plain RGB, which is accepted as well.
+impl Parameters for Rgb {
+    fn set_parameter(&mut self, field: &str, data: &[u8]) {
+        match field {
+            Self::RGB if data.len() == std::mem::size_of::<[f32; 3]>() => {
+                self.rgb = <[f32; 3]>::from_data(data);
+            }
+            Self::RGB => {
+                let ColorParam([r, g, b, a]) = ColorParam::from_data(data);
+                self.rgb = [r, g, b];
+                self.alpha = a;
+            }
+            _ => panic!("Unknown field {}", field),
         }
     }
 }
@@ -30,7 +60,7 @@ impl Socketed for Rgb {
 
     fn outputs(&self) -> HashMap<String, OperatorType> {
         hashmap! {
-            "color".to_string() => OperatorType::Monomorphic(ImageType::Rgb)
+            "color".to_string() => OperatorType::Monomorphic(ImageType::Rgba)
         }
     }
 
@@ -82,7 +112,9 @@ impl OperatorParamBox for Rgb {
                 parameters: vec![Parameter {
                     name: "color".to_string(),
                     transmitter: Field(Rgb::RGB.to_string()),
-                    control: Control::RgbColor { value: self.rgb },
+                    control: Control::Color {
+                        value: [self.rgb[0], self.rgb[1], self.rgb[2], self.alpha],
+                    },
                     expose_status: Some(ExposeStatus::Unexposed),
                     visibility: VisibilityFunction::default(),
                     presetable: true,
@@ -80,6 +80,7 @@ impl BlendMode {
 
 #[repr(C)]
 #[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+#[serde(default)]
 pub struct Scatter {
     edge_mode: EdgeMode,
     blend_mode: BlendMode,
@@ -95,6 +96,7 @@ pub struct Scatter {
     random_size: f32,
     random_intensity: f32,
     random_offset: f32,
+    seed: u32,
 }
 
 impl Default for Scatter {
@@ -114,6 +116,7 @@ impl Default for Scatter {
             random_size: 0.,
             random_intensity: 0.,
             random_offset: 0.5,
+            seed: 0,
         }
     }
 }
@@ -390,6 +393,18 @@ impl OperatorParamBox for Scatter {
                         ),
                         presetable: true,
                     },
+                    Parameter {
+                        name: "seed".to_string(),
+                        transmitter: Field(Scatter::SEED.to_string()),
+                        control: Control::DiscreteSlider {
+                            value: self.seed as i32,
+                            min: 0,
+                            max: 1024,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
                 ],
             }],
         }
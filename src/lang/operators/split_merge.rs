@@ -9,6 +9,7 @@ use std::collections::HashMap;
 use surfacelab_derive::*;
 use zerocopy::AsBytes;
 
+/// Split a color image into its channels, one grayscale output per channel.
 #[repr(C)]
 #[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
 pub struct Split {}
@@ -31,6 +32,7 @@ impl Socketed for Split {
             "x/r".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
             "y/g".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
             "z/b".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
+            "alpha".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
         }
     }
 
@@ -68,6 +70,10 @@ impl Shader for Split {
                     binding: 4,
                     descriptor: OperatorDescriptorUse::OutputImage("z/b"),
                 },
+                OperatorDescriptor {
+                    binding: 5,
+                    descriptor: OperatorDescriptorUse::OutputImage("alpha"),
+                },
             ],
             specialization: Specialization::default(),
             shape: OperatorShape::PerPixel {
@@ -88,6 +94,8 @@ impl OperatorParamBox for Split {
     }
 }
 
+/// Merge grayscale images into a color image. The blue channel defaults to 0
+/// and alpha defaults to 1 when left unconnected.
 #[repr(C)]
 #[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
 pub struct Merge {}
@@ -103,7 +111,8 @@ impl Socketed for Merge {
         hashmap! {
             "x/r".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), false),
             "y/g".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), false),
-            "z/b".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), false),
+            "z/b".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), true),
+            "alpha".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), true),
         }
     }
 
@@ -129,22 +138,30 @@ impl Shader for Merge {
             descriptors: &[
                 OperatorDescriptor {
                     binding: 0,
-                    descriptor: OperatorDescriptorUse::InputImage("x/r"),
+                    descriptor: OperatorDescriptorUse::Occupancy,
                 },
                 OperatorDescriptor {
                     binding: 1,
-                    descriptor: OperatorDescriptorUse::InputImage("y/g"),
+                    descriptor: OperatorDescriptorUse::InputImage("x/r"),
                 },
                 OperatorDescriptor {
                     binding: 2,
-                    descriptor: OperatorDescriptorUse::InputImage("z/b"),
+                    descriptor: OperatorDescriptorUse::InputImage("y/g"),
                 },
                 OperatorDescriptor {
                     binding: 3,
-                    descriptor: OperatorDescriptorUse::Sampler,
+                    descriptor: OperatorDescriptorUse::InputImage("z/b"),
                 },
                 OperatorDescriptor {
                     binding: 4,
+                    descriptor: OperatorDescriptorUse::InputImage("alpha"),
+                },
+                OperatorDescriptor {
+                    binding: 5,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 6,
                     descriptor: OperatorDescriptorUse::OutputImage("color"),
                 },
             ],
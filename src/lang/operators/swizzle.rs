@@ -0,0 +1,186 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use num_enum::TryFromPrimitive;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::VariantNames;
+use strum_macros::*;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Source of a single output channel, either an input channel or a constant.
+#[repr(u32)]
+#[derive(
+    AsBytes,
+    Clone,
+    Copy,
+    Debug,
+    EnumIter,
+    EnumVariantNames,
+    EnumString,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    TryFromPrimitive,
+)]
+#[strum(serialize_all = "kebab_case")]
+pub enum SwizzleSource {
+    Red = 0,
+    Green = 1,
+    Blue = 2,
+    Alpha = 3,
+    Zero = 4,
+    Half = 5,
+    One = 6,
+}
+
+/// Remap the channels of a color image. Each output channel is taken from an
+/// arbitrary input channel or set to a constant.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct Swizzle {
+    pub red: SwizzleSource,
+    pub green: SwizzleSource,
+    pub blue: SwizzleSource,
+    pub alpha: SwizzleSource,
+}
+
+impl Default for Swizzle {
+    fn default() -> Self {
+        Self {
+            red: SwizzleSource::Red,
+            green: SwizzleSource::Green,
+            blue: SwizzleSource::Blue,
+            alpha: SwizzleSource::Alpha,
+        }
+    }
+}
+
+impl Socketed for Swizzle {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "in".to_string() => (OperatorType::Monomorphic(ImageType::Rgb), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "out".to_string() => OperatorType::Monomorphic(ImageType::Rgb)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "swizzle"
+    }
+
+    fn title(&self) -> &str {
+        "Swizzle"
+    }
+}
+
+impl Shader for Swizzle {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("swizzle"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::InputImage("in"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::OutputImage("out"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for Swizzle {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("swizzle".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "red-channel".to_string(),
+                        transmitter: Field(Swizzle::RED.to_string()),
+                        control: Control::Enum {
+                            selected: self.red as usize,
+                            variants: SwizzleSource::VARIANTS
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "green-channel".to_string(),
+                        transmitter: Field(Swizzle::GREEN.to_string()),
+                        control: Control::Enum {
+                            selected: self.green as usize,
+                            variants: SwizzleSource::VARIANTS
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "blue-channel".to_string(),
+                        transmitter: Field(Swizzle::BLUE.to_string()),
+                        control: Control::Enum {
+                            selected: self.blue as usize,
+                            variants: SwizzleSource::VARIANTS
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "alpha-channel".to_string(),
+                        transmitter: Field(Swizzle::ALPHA.to_string()),
+                        control: Control::Enum {
+                            selected: self.alpha as usize,
+                            variants: SwizzleSource::VARIANTS
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
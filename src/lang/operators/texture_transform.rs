@@ -0,0 +1,154 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use super::transform::Transform;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use surfacelab_derive::*;
+
+/// Standard UV transformation of a texture, applying tiling, offset and
+/// rotation at once. This is a restricted form of `Transform` with tiling
+/// always enabled and parameters suitable for texturing.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct TextureTransform {
+    pub tile: [f32; 2],
+    pub offset: [f32; 2],
+    pub rotation: f32,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            tile: [1., 1.],
+            offset: [0., 0.],
+            rotation: 0.,
+        }
+    }
+}
+
+impl Socketed for TextureTransform {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "in".to_string() => (OperatorType::Polymorphic(0), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "out".to_string() => OperatorType::Polymorphic(0)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "texture_transform"
+    }
+
+    fn title(&self) -> &str {
+        "Texture Transform"
+    }
+}
+
+/// The uniforms are those of the equivalent `Transform`, such that the
+/// transform shader can be reused.
+impl Uniforms for TextureTransform {
+    fn uniforms(&self) -> Cow<[u8]> {
+        let transform = Transform {
+            translation: self.offset,
+            scale: self.tile,
+            shear: [0., 0.],
+            rotation: self.rotation,
+            tiling: 1,
+            mirror_x: 0,
+            mirror_y: 0,
+        };
+
+        Cow::Owned(transform.uniforms().into_owned())
+    }
+}
+
+impl Shader for TextureTransform {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("transform"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::InputImage("in"),
+                },
+                OperatorDescriptor {
+                    binding: 2,
+                    descriptor: OperatorDescriptorUse::Sampler,
+                },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::OutputImage("out"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for TextureTransform {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("texture_transform".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "tile-count".to_string(),
+                        transmitter: Field(TextureTransform::TILE.to_string()),
+                        control: Control::XYPad {
+                            value: self.tile,
+                            min: [0., 0.],
+                            max: [16., 16.],
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "offset".to_string(),
+                        transmitter: Field(TextureTransform::OFFSET.to_string()),
+                        control: Control::XYPad {
+                            value: self.offset,
+                            min: [-1., -1.],
+                            max: [1., 1.],
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "rotation".to_string(),
+                        transmitter: Field(TextureTransform::ROTATION.to_string()),
+                        control: Control::Slider {
+                            value: self.rotation,
+                            min: 0.,
+                            max: 360.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use num_enum::TryFromPrimitive;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::VariantNames;
+use strum_macros::*;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Maximum number of column/row sweep iterations used to propagate foreground
+/// through connected weak regions in hysteresis mode. Each sweep propagates
+/// along an entire column or row, so this bounds the number of turns a
+/// connecting path may take rather than its length. Iterations after
+/// convergence return immediately.
+const MAX_PROPAGATION_ITERATIONS: usize = 64;
+
+#[repr(u32)]
+#[derive(
+    AsBytes,
+    Clone,
+    Copy,
+    Debug,
+    EnumIter,
+    EnumVariantNames,
+    EnumString,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    TryFromPrimitive,
+)]
+#[strum(serialize_all = "kebab_case")]
+pub enum ThresholdMode {
+    Single = 0,
+    Hysteresis = 1,
+}
+
+impl ThresholdMode {
+    fn is_single(self) -> bool {
+        matches!(self, Self::Single)
+    }
+
+    fn is_hysteresis(self) -> bool {
+        matches!(self, Self::Hysteresis)
+    }
+}
+
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct Threshold {
+    mode: ThresholdMode,
+    threshold: f32,
+    low_threshold: f32,
+    high_threshold: f32,
+}
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Self {
+            mode: ThresholdMode::Single,
+            threshold: 0.5,
+            low_threshold: 0.3,
+            high_threshold: 0.7,
+        }
+    }
+}
+
+impl Socketed for Threshold {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {
+            "in".to_string() => (OperatorType::Monomorphic(ImageType::Grayscale), false)
+        }
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "out".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "threshold"
+    }
+
+    fn title(&self) -> &str {
+        "Threshold"
+    }
+}
+
+/// Descriptors for passes reading the classification from `c` and writing it
+/// to `d`.
+const THRESHOLD_DESCRIPTORS_CD: &[OperatorDescriptor] = &[
+    OperatorDescriptor {
+        binding: 0,
+        descriptor: OperatorDescriptorUse::Uniforms,
+    },
+    OperatorDescriptor {
+        binding: 1,
+        descriptor: OperatorDescriptorUse::InputImage("in"),
+    },
+    OperatorDescriptor {
+        binding: 2,
+        descriptor: OperatorDescriptorUse::Sampler,
+    },
+    OperatorDescriptor {
+        binding: 3,
+        descriptor: OperatorDescriptorUse::IntermediateImage("c", false),
+    },
+    OperatorDescriptor {
+        binding: 4,
+        descriptor: OperatorDescriptorUse::IntermediateImage("d", false),
+    },
+    OperatorDescriptor {
+        binding: 5,
+        descriptor: OperatorDescriptorUse::OutputImage("out"),
+    },
+    OperatorDescriptor {
+        binding: 6,
+        descriptor: OperatorDescriptorUse::IntermediateBuffer("changed"),
+    },
+];
+
+/// Descriptors for passes reading the classification from `d` and writing it
+/// to `c`.
+const THRESHOLD_DESCRIPTORS_DC: &[OperatorDescriptor] = &[
+    OperatorDescriptor {
+        binding: 0,
+        descriptor: OperatorDescriptorUse::Uniforms,
+    },
+    OperatorDescriptor {
+        binding: 1,
+        descriptor: OperatorDescriptorUse::InputImage("in"),
+    },
+    OperatorDescriptor {
+        binding: 2,
+        descriptor: OperatorDescriptorUse::Sampler,
+    },
+    OperatorDescriptor {
+        binding: 3,
+        descriptor: OperatorDescriptorUse::IntermediateImage("d", false),
+    },
+    OperatorDescriptor {
+        binding: 4,
+        descriptor: OperatorDescriptorUse::IntermediateImage("c", false),
+    },
+    OperatorDescriptor {
+        binding: 5,
+        descriptor: OperatorDescriptorUse::OutputImage("out"),
+    },
+    OperatorDescriptor {
+        binding: 6,
+        descriptor: OperatorDescriptorUse::IntermediateBuffer("changed"),
+    },
+];
+
+const SYNC_ALL: &[SynchronizeDescription] = &[
+    SynchronizeDescription::ToReadWrite("c"),
+    SynchronizeDescription::ToReadWrite("d"),
+];
+
+impl Shader for Threshold {
+    /// Thresholding runs in three stages. First all pixels are classified
+    /// into background, weak, and strong foreground. Strong foreground is
+    /// then propagated into connected weak pixels by alternating column and
+    /// row sweeps. Finally the classification is written to the output,
+    /// keeping only strong foreground.
+    ///
+    /// Sweeps ping-pong between `c` and `d`, such that lines neighbouring
+    /// the swept line are only ever read from the image not written in the
+    /// same pass. Each iteration records whether it changed anything in the
+    /// `changed` buffer, and once an iteration made no change the remaining
+    /// ones return immediately.
+    ///
+    /// Passes are shared by all instances of the operator, so the sweeps are
+    /// always run. In single threshold mode there are no weak pixels and they
+    /// return immediately.
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        let mut passes = vec![
+            OperatorPassDescription::SynchronizeImage(SYNC_ALL),
+            OperatorPassDescription::SynchronizeBuffer(&[SynchronizeDescription::ToReadWrite(
+                "changed",
+            )]),
+            OperatorPassDescription::RunShader(OperatorShader {
+                spirv: shader!("threshold"),
+                descriptors: THRESHOLD_DESCRIPTORS_CD,
+                specialization: gfx_hal::spec_const_list!(0u32, 0u32),
+                shape: OperatorShape::PerPixel {
+                    local_x: 8,
+                    local_y: 8,
+                },
+            }),
+        ];
+
+        for iteration in 0..MAX_PROPAGATION_ITERATIONS {
+            // The flag index cycles through the changed buffer, see shader
+            let flag = (iteration % 3) as u32;
+            for (pass, descriptors) in [
+                (1u32, THRESHOLD_DESCRIPTORS_CD),
+                (2u32, THRESHOLD_DESCRIPTORS_DC),
+            ]
+            .iter()
+            {
+                passes.push(OperatorPassDescription::SynchronizeImage(SYNC_ALL));
+                passes.push(OperatorPassDescription::SynchronizeBuffer(&[
+                    SynchronizeDescription::ToReadWrite("changed"),
+                ]));
+                passes.push(OperatorPassDescription::RunShader(OperatorShader {
+                    spirv: shader!("threshold"),
+                    descriptors: *descriptors,
+                    specialization: gfx_hal::spec_const_list!(*pass, flag),
+                    shape: OperatorShape::PerRowOrColumn { local_size: 8 },
+                }));
+            }
+        }
+
+        passes.push(OperatorPassDescription::SynchronizeImage(&[
+            SynchronizeDescription::ToRead("c"),
+        ]));
+        passes.push(OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("threshold"),
+            descriptors: THRESHOLD_DESCRIPTORS_CD,
+            specialization: gfx_hal::spec_const_list!(3u32, 0u32),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        }));
+
+        passes
+    }
+
+    fn intermediate_data(&self) -> HashMap<String, IntermediateDataDescription> {
+        hashmap! {
+            "c".to_string() => IntermediateDataDescription::Image {
+                size: FromSocketOr::FromSocket("out"),
+                ty: FromSocketOr::FromSocket("out"),
+                mips: false,
+            },
+            "d".to_string() => IntermediateDataDescription::Image {
+                size: FromSocketOr::FromSocket("out"),
+                ty: FromSocketOr::FromSocket("out"),
+                mips: false,
+            },
+            "changed".to_string() => IntermediateDataDescription::Buffer {
+                dim: BufferDim::Vector(FromSocketOr::Independent(3)),
+                element_width: std::mem::size_of::<u32>(),
+            },
+        }
+    }
+}
+
+impl OperatorParamBox for Threshold {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("threshold".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "threshold-mode".to_string(),
+                        transmitter: Field(Threshold::MODE.to_string()),
+                        control: Control::Enum {
+                            selected: self.mode as usize,
+                            variants: ThresholdMode::VARIANTS
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "threshold".to_string(),
+                        transmitter: Field(Threshold::THRESHOLD.to_string()),
+                        control: Control::Slider {
+                            value: self.threshold,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::on_parameter_enum(
+                            "threshold-mode",
+                            |t: ThresholdMode| t.is_single(),
+                        ),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "low-threshold".to_string(),
+                        transmitter: Field(Threshold::LOW_THRESHOLD.to_string()),
+                        control: Control::Slider {
+                            value: self.low_threshold,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::on_parameter_enum(
+                            "threshold-mode",
+                            |t: ThresholdMode| t.is_hysteresis(),
+                        ),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "high-threshold".to_string(),
+                        transmitter: Field(Threshold::HIGH_THRESHOLD.to_string()),
+                        control: Control::Slider {
+                            value: self.high_threshold,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::on_parameter_enum(
+                            "threshold-mode",
+                            |t: ThresholdMode| t.is_hysteresis(),
+                        ),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
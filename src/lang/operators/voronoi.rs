@@ -131,7 +131,8 @@ impl Socketed for Voronoi {
     fn outputs(&self) -> HashMap<String, OperatorType> {
         hashmap! {
             "distance".to_string() => OperatorType::Monomorphic(ImageType::Grayscale),
-            "random".to_string() => OperatorType::Monomorphic(ImageType::Rgb)
+            "random".to_string() => OperatorType::Monomorphic(ImageType::Rgb),
+            "border".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
         }
     }
 
@@ -161,6 +162,10 @@ impl Shader for Voronoi {
                     binding: 2,
                     descriptor: OperatorDescriptorUse::OutputImage("random"),
                 },
+                OperatorDescriptor {
+                    binding: 3,
+                    descriptor: OperatorDescriptorUse::OutputImage("border"),
+                },
             ],
             specialization: Specialization::default(),
             shape: OperatorShape::PerPixel {
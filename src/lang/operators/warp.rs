@@ -152,6 +152,12 @@ impl Shader for Warp {
             },
         })]
     }
+
+    /// Warping samples far outside of the texel, which produces artifacts at
+    /// the edges when tiling.
+    fn wrap_mode(&self) -> WrapMode {
+        WrapMode::Clamp
+    }
 }
 
 impl OperatorParamBox for Warp {
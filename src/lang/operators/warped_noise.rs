@@ -0,0 +1,181 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Domain warped Perlin noise. The sampling coordinates are displaced by a
+/// second noise field before sampling, all within a single shader pass.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct WarpedNoise {
+    pub scale: f32,
+    pub octaves: u32,
+    pub roughness: f32,
+    pub warp_strength: f32,
+    pub warp_frequency: f32,
+    pub warp_octaves: u32,
+    pub offset: [f32; 2],
+}
+
+impl Default for WarpedNoise {
+    fn default() -> Self {
+        Self {
+            scale: 3.0,
+            octaves: 4,
+            roughness: 0.5,
+            warp_strength: 0.1,
+            warp_frequency: 2.0,
+            warp_octaves: 2,
+            offset: [0., 0.],
+        }
+    }
+}
+
+impl Socketed for WarpedNoise {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {}
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "noise".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "warped_noise"
+    }
+
+    fn title(&self) -> &str {
+        "Warped Noise"
+    }
+}
+
+impl Shader for WarpedNoise {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("warped_noise"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::OutputImage("noise"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for WarpedNoise {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("warped_noise".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "scale".to_string(),
+                        transmitter: Field(WarpedNoise::SCALE.to_string()),
+                        control: Control::Slider {
+                            value: self.scale,
+                            min: 0.,
+                            max: 16.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "octaves".to_string(),
+                        transmitter: Field(WarpedNoise::OCTAVES.to_string()),
+                        control: Control::DiscreteSlider {
+                            value: self.octaves as i32,
+                            min: 1,
+                            max: 16,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "roughness".to_string(),
+                        transmitter: Field(WarpedNoise::ROUGHNESS.to_string()),
+                        control: Control::Slider {
+                            value: self.roughness,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "offset".to_string(),
+                        transmitter: Field(WarpedNoise::OFFSET.to_string()),
+                        control: Control::XYPad {
+                            value: self.offset,
+                            min: [-1., -1.],
+                            max: [1., 1.],
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "warp-strength".to_string(),
+                        transmitter: Field(WarpedNoise::WARP_STRENGTH.to_string()),
+                        control: Control::Slider {
+                            value: self.warp_strength,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "warp-frequency".to_string(),
+                        transmitter: Field(WarpedNoise::WARP_FREQUENCY.to_string()),
+                        control: Control::Slider {
+                            value: self.warp_frequency,
+                            min: 0.,
+                            max: 16.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "warp-octaves".to_string(),
+                        transmitter: Field(WarpedNoise::WARP_OCTAVES.to_string()),
+                        control: Control::DiscreteSlider {
+                            value: self.warp_octaves as i32,
+                            min: 1,
+                            max: 8,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use super::super::parameters::*;
+use super::super::socketed::*;
+use crate::compute::shaders::*;
+use crate::shader;
+
+use maplit::hashmap;
+use num_enum::TryFromPrimitive;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::VariantNames;
+use strum_macros::*;
+use surfacelab_derive::*;
+use zerocopy::AsBytes;
+
+/// Shape of a single period of the wave.
+#[repr(u32)]
+#[derive(
+    AsBytes,
+    Clone,
+    Copy,
+    Debug,
+    EnumIter,
+    EnumVariantNames,
+    EnumString,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    TryFromPrimitive,
+)]
+#[strum(serialize_all = "kebab_case")]
+pub enum WaveType {
+    Sine = 0,
+    Triangle = 1,
+    Square = 2,
+    Sawtooth = 3,
+}
+
+/// Periodic stripe pattern along a direction, with the frequency given in
+/// periods per image and the phase as a fraction of a period.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, Parameters, PartialEq)]
+pub struct Wave {
+    pub wave_type: WaveType,
+    pub frequency: f32,
+    pub direction: f32,
+    pub phase: f32,
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self {
+            wave_type: WaveType::Sine,
+            frequency: 4.,
+            direction: 0.,
+            phase: 0.,
+        }
+    }
+}
+
+impl Socketed for Wave {
+    fn inputs(&self) -> HashMap<String, (OperatorType, bool)> {
+        hashmap! {}
+    }
+
+    fn outputs(&self) -> HashMap<String, OperatorType> {
+        hashmap! {
+            "pattern".to_string() => OperatorType::Monomorphic(ImageType::Grayscale)
+        }
+    }
+
+    fn default_name(&self) -> &str {
+        "wave"
+    }
+
+    fn title(&self) -> &str {
+        "Wave"
+    }
+}
+
+impl Shader for Wave {
+    fn operator_passes(&self) -> Vec<OperatorPassDescription> {
+        vec![OperatorPassDescription::RunShader(OperatorShader {
+            spirv: shader!("wave"),
+            descriptors: &[
+                OperatorDescriptor {
+                    binding: 0,
+                    descriptor: OperatorDescriptorUse::Uniforms,
+                },
+                OperatorDescriptor {
+                    binding: 1,
+                    descriptor: OperatorDescriptorUse::OutputImage("pattern"),
+                },
+            ],
+            specialization: Specialization::default(),
+            shape: OperatorShape::PerPixel {
+                local_x: 8,
+                local_y: 8,
+            },
+        })]
+    }
+}
+
+impl OperatorParamBox for Wave {
+    fn param_box_description(&self) -> ParamBoxDescription<Field> {
+        ParamBoxDescription {
+            box_title: self.title().to_string(),
+            preset_tag: Some("wave".to_string()),
+            categories: vec![ParamCategory {
+                name: "basic-parameters",
+                is_open: true,
+                visibility: VisibilityFunction::default(),
+                parameters: vec![
+                    Parameter {
+                        name: "wave-type".to_string(),
+                        transmitter: Field(Wave::WAVE_TYPE.to_string()),
+                        control: Control::Enum {
+                            selected: self.wave_type as usize,
+                            variants: WaveType::VARIANTS.iter().map(|x| x.to_string()).collect(),
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "frequency".to_string(),
+                        transmitter: Field(Wave::FREQUENCY.to_string()),
+                        control: Control::Slider {
+                            value: self.frequency,
+                            min: 1.,
+                            max: 64.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "direction".to_string(),
+                        transmitter: Field(Wave::DIRECTION.to_string()),
+                        control: Control::Slider {
+                            value: self.direction,
+                            min: 0.,
+                            max: 360.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                    Parameter {
+                        name: "phase".to_string(),
+                        transmitter: Field(Wave::PHASE.to_string()),
+                        control: Control::Slider {
+                            value: self.phase,
+                            min: 0.,
+                            max: 1.,
+                        },
+                        expose_status: Some(ExposeStatus::Unexposed),
+                        visibility: VisibilityFunction::default(),
+                        presetable: true,
+                    },
+                ],
+            }],
+        }
+    }
+}
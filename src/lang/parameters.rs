@@ -4,6 +4,7 @@ use serde_derive::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::{collections::HashMap, fmt::Debug, sync::Arc};
 use thiserror::Error;
+use zerocopy::AsBytes;
 
 /// A trait for things that have parameters. Parameters can be set from a field
 /// descriptor and some plain data. It is up to the implementation to interpret
@@ -21,6 +22,12 @@ pub trait Parameters {
 /// shader.
 pub type ParameterBool = u32;
 
+/// A ColorParam is an RGBA color in linear space, transmitted as 4 floats.
+/// It is edited with a `Control::Color`.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ColorParam(pub [f32; 4]);
+
 /// A ParameterField is a type that can be converted from/to data, with a given
 /// fixed size. Specifically, anything that can be serialized and deserialized
 /// can be used as a parameter field using bincode.
@@ -302,8 +309,16 @@ pub enum RenderField {
     LightStrength,
     LightSize,
     FogStrength,
+    SssColor,
+    SssStrength,
     Shadow,
     AoStrength,
+    WireframeOverlay,
+    WireWidth,
+    CapsuleRadius,
+    CapsuleLength,
+    ConeHeight,
+    ConeAngle,
     EnvironmentStrength,
     EnvironmentBlur,
     EnvironmentRotation,
@@ -318,6 +333,9 @@ pub enum RenderField {
     ShadingMode,
     ToneMap,
     SampleCount,
+    AdaptiveSamples,
+    BackgroundColor,
+    ShowHdriBackground,
 }
 
 impl MessageWriter for RenderField {
@@ -343,6 +361,12 @@ impl MessageWriter for RenderField {
             RenderField::FogStrength => super::Lang::UserRenderEvent(
                 super::UserRenderEvent::FogStrength(*renderer, f32::from_data(data)),
             ),
+            RenderField::SssColor => super::Lang::UserRenderEvent(
+                super::UserRenderEvent::SssColor(*renderer, <[f32; 3]>::from_data(data)),
+            ),
+            RenderField::SssStrength => super::Lang::UserRenderEvent(
+                super::UserRenderEvent::SssStrength(*renderer, f32::from_data(data)),
+            ),
             RenderField::EnvironmentStrength => super::Lang::UserRenderEvent(
                 super::UserRenderEvent::EnvironmentStrength(*renderer, f32::from_data(data)),
             ),
@@ -359,6 +383,24 @@ impl MessageWriter for RenderField {
             RenderField::AoStrength => super::Lang::UserRenderEvent(
                 super::UserRenderEvent::AoStrength(*renderer, f32::from_data(data)),
             ),
+            RenderField::WireframeOverlay => super::Lang::UserRenderEvent(
+                super::UserRenderEvent::WireframeOverlay(*renderer, ParameterBool::from_data(data)),
+            ),
+            RenderField::WireWidth => super::Lang::UserRenderEvent(
+                super::UserRenderEvent::WireWidth(*renderer, f32::from_data(data)),
+            ),
+            RenderField::CapsuleRadius => super::Lang::UserRenderEvent(
+                super::UserRenderEvent::CapsuleRadius(*renderer, f32::from_data(data)),
+            ),
+            RenderField::CapsuleLength => super::Lang::UserRenderEvent(
+                super::UserRenderEvent::CapsuleLength(*renderer, f32::from_data(data)),
+            ),
+            RenderField::ConeHeight => super::Lang::UserRenderEvent(
+                super::UserRenderEvent::ConeHeight(*renderer, f32::from_data(data)),
+            ),
+            RenderField::ConeAngle => super::Lang::UserRenderEvent(
+                super::UserRenderEvent::ConeAngle(*renderer, f32::from_data(data)),
+            ),
             RenderField::Hdri => super::Lang::UserRenderEvent(super::UserRenderEvent::LoadHdri(
                 *renderer,
                 <Option<PathBuf>>::from_data(data),
@@ -394,6 +436,25 @@ impl MessageWriter for RenderField {
             RenderField::SampleCount => super::Lang::UserRenderEvent(
                 super::UserRenderEvent::SampleCount(*renderer, u32::from_data(data)),
             ),
+            RenderField::AdaptiveSamples => {
+                super::Lang::UserRenderEvent(super::UserRenderEvent::AdaptiveSamples(
+                    *renderer,
+                    ParameterBool::from_data(data) == 1,
+                ))
+            }
+            RenderField::BackgroundColor => {
+                let [r, g, b] = <[f32; 3]>::from_data(data);
+                super::Lang::UserRenderEvent(super::UserRenderEvent::BackgroundColor(
+                    *renderer,
+                    [r, g, b, 1.],
+                ))
+            }
+            RenderField::ShowHdriBackground => {
+                super::Lang::UserRenderEvent(super::UserRenderEvent::ShowHdriBackground(
+                    *renderer,
+                    ParameterBool::from_data(data) == 1,
+                ))
+            }
         }
     }
 }
@@ -1104,6 +1165,13 @@ pub enum Control {
         min: f32,
         max: f32,
     },
+    /// A slider on a logarithmic scale. Only the knob position is
+    /// logarithmic, the value is always linear. Requires `min > 0`.
+    LogSlider {
+        value: f32,
+        min: f32,
+        max: f32,
+    },
     DiscreteSlider {
         value: i32,
         min: i32,
@@ -1117,6 +1185,9 @@ pub enum Control {
     RgbColor {
         value: [f32; 3],
     },
+    Color {
+        value: [f32; 4],
+    },
     Enum {
         selected: usize,
         variants: Vec<String>,
@@ -1152,12 +1223,14 @@ pub enum Control {
 }
 
 impl Control {
-    fn value(&self) -> Vec<u8> {
+    pub fn value(&self) -> Vec<u8> {
         match self {
             Self::Slider { value, .. } => value.to_data(),
+            Self::LogSlider { value, .. } => value.to_data(),
             Self::DiscreteSlider { value, .. } => value.to_data(),
             Self::XYPad { value, .. } => value.to_data(),
             Self::RgbColor { value, .. } => value.to_data(),
+            Self::Color { value } => ColorParam(*value).to_data(),
             Self::Enum { selected, .. } => (*selected as u32).to_data(),
             Self::File { selected } => selected.to_data(),
             Self::ImageResource { selected } => selected.to_data(),
@@ -1175,9 +1248,11 @@ impl Control {
     pub fn set_value(&mut self, data: &[u8]) {
         match self {
             Self::Slider { value, .. } => *value = f32::from_data(data),
+            Self::LogSlider { value, .. } => *value = f32::from_data(data),
             Self::DiscreteSlider { value, .. } => *value = i32::from_data(data),
             Self::XYPad { value, .. } => *value = <[f32; 2]>::from_data(data),
             Self::RgbColor { value } => *value = <[f32; 3]>::from_data(data),
+            Self::Color { value } => *value = ColorParam::from_data(data).0,
             Self::Enum { selected, .. } => *selected = u32::from_data(data) as usize,
             Self::File { selected } => *selected = <Option<PathBuf>>::from_data(data),
             Self::ImageResource { selected } => {
@@ -1,5 +1,5 @@
 use serde_derive::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 pub type ResourcePart = String;
 
@@ -71,14 +71,50 @@ impl Scheme for Svg {
     }
 }
 
+/// Normalize a path purely lexically, i.e. without accessing the file system.
+/// Redundant separators and `.` components are removed, and `..` components
+/// are resolved against their preceding component where possible.
+fn canonical_path(path: &Path) -> PathBuf {
+    let mut canonical = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match canonical.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    canonical.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => canonical.push(component),
+            },
+            _ => canonical.push(component),
+        }
+    }
+
+    canonical
+}
+
+/// Deserialize a path, canonicalizing it in the process.
+fn deserialize_canonical<'de, D>(deserializer: D) -> Result<PathBuf, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let path: PathBuf = serde::Deserialize::deserialize(deserializer)?;
+    Ok(canonical_path(&path))
+}
+
 /// A Resource describes some thing in the system. This could refer to a node,
 /// or to a graph/layer stack, or to a socket of a node, or to a parameter, etc.
 ///
 /// The type parameter narrows down the type of resource that can be described.
 ///
 /// A resource has a schema, depending on the type of resource.
+///
+/// Resource paths are canonicalized on construction and deserialization,
+/// such that different spellings of the same path compare and hash equally.
 #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Resource<S> {
+    #[serde(deserialize_with = "deserialize_canonical")]
     resource_path: PathBuf,
     fragment: Option<String>,
     phantom_data: std::marker::PhantomData<S>,
@@ -122,7 +158,7 @@ impl Resource<Node> {
     /// Constructor for a node resource
     pub fn node<P: AsRef<Path>>(path: P) -> Self {
         Self {
-            resource_path: path.as_ref().to_path_buf(),
+            resource_path: canonical_path(path.as_ref()),
             fragment: None,
             phantom_data: std::marker::PhantomData,
         }
@@ -167,7 +203,7 @@ impl Resource<Graph> {
     /// Constructor for a graph resource
     pub fn graph<P: AsRef<Path>>(path: P) -> Self {
         Self {
-            resource_path: path.as_ref().to_path_buf(),
+            resource_path: canonical_path(path.as_ref()),
             fragment: None,
             phantom_data: std::marker::PhantomData,
         }
@@ -179,7 +215,7 @@ impl Resource<Graph> {
         path.push(node);
 
         Resource {
-            resource_path: path,
+            resource_path: canonical_path(&path),
             fragment: None,
             phantom_data: std::marker::PhantomData,
         }
@@ -190,7 +226,7 @@ impl Resource<Param> {
     /// Constructor for a parameter resource
     pub fn parameter<P: AsRef<Path>>(path: P, fragment: &str) -> Self {
         Self {
-            resource_path: path.as_ref().to_path_buf(),
+            resource_path: canonical_path(path.as_ref()),
             fragment: Some(fragment.to_string()),
             phantom_data: std::marker::PhantomData,
         }
@@ -215,7 +251,7 @@ impl Resource<Socket> {
     /// Constructor for a socket resource
     pub fn socket<P: AsRef<Path>>(path: P, fragment: &str) -> Self {
         Self {
-            resource_path: path.as_ref().to_path_buf(),
+            resource_path: canonical_path(path.as_ref()),
             fragment: Some(fragment.to_string()),
             phantom_data: std::marker::PhantomData,
         }
@@ -240,7 +276,7 @@ impl Resource<Img> {
     /// Constructor for an image resource
     pub fn image<P: AsRef<Path>>(path: P) -> Self {
         Self {
-            resource_path: path.as_ref().to_path_buf(),
+            resource_path: canonical_path(path.as_ref()),
             fragment: None,
             phantom_data: std::marker::PhantomData,
         }
@@ -251,7 +287,7 @@ impl Resource<Svg> {
     /// Constructor for an SVG resource
     pub fn svg<P: AsRef<Path>>(path: P) -> Self {
         Self {
-            resource_path: path.as_ref().to_path_buf(),
+            resource_path: canonical_path(path.as_ref()),
             fragment: None,
             phantom_data: std::marker::PhantomData,
         }
@@ -294,6 +330,17 @@ impl<S> Resource<S> {
         self.path().parent().and_then(|x| x.to_str())
     }
 
+    /// Obtain a canonical version of this resource, with redundant separators
+    /// and `.` components removed and `..` components resolved. This does not
+    /// touch the file system.
+    pub fn canonicalize(&self) -> Self {
+        Self {
+            resource_path: canonical_path(&self.resource_path),
+            fragment: self.fragment.clone(),
+            phantom_data: std::marker::PhantomData,
+        }
+    }
+
     /// Cast between resource types. Note that this does not perform *any*
     /// checks. Usually it is wiser to use one of the specialized casting
     /// functions. Handle with care!
@@ -312,6 +359,6 @@ impl<S: InGraph> Resource<S> {
         let file = self.resource_path.file_name().unwrap();
         let mut path = graph.as_ref().to_path_buf();
         path.push(file);
-        self.resource_path = path;
+        self.resource_path = canonical_path(&path);
     }
 }
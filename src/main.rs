@@ -1,39 +1,119 @@
+use std::path::PathBuf;
 use std::thread;
 use surfacelab::{
     broker, gpu,
     lang::{self, config::Configuration},
 };
 
+/// Modes of running the application, as selected on the command line.
+enum Mode {
+    /// Regular interactive session
+    Interactive,
+    /// Interactive session, recording all user events to the given file
+    Record(PathBuf),
+    /// Headless session, replaying user events from the given file
+    Playback(PathBuf),
+}
+
+impl Mode {
+    /// Parse the mode from command line arguments. Accepts either
+    /// `--record <file>` or `--playback <file>`.
+    fn from_args() -> Result<Self, String> {
+        let mut args = std::env::args().skip(1);
+
+        match (args.next().as_deref(), args.next()) {
+            (None, _) => Ok(Mode::Interactive),
+            (Some("--record"), Some(path)) => Ok(Mode::Record(PathBuf::from(path))),
+            (Some("--playback"), Some(path)) => Ok(Mode::Playback(PathBuf::from(path))),
+            (Some(arg), _) => Err(format!(
+                "Invalid argument {}, expected --record <file> or --playback <file>",
+                arg
+            )),
+        }
+    }
+
+    fn is_headless(&self) -> bool {
+        matches!(self, Mode::Playback(..))
+    }
+}
+
 fn main() {
     env_logger::init();
 
+    let mode = match Mode::from_args() {
+        Ok(mode) => mode,
+        Err(err) => {
+            log::error!("{}", err);
+            return;
+        }
+    };
+
+    // read config file from known location or use default
+    let config =
+        Configuration::load_from_file("config.toml").unwrap_or_else(|_| Configuration::default());
+
     // initialize GPU before proceeding
-    match gpu::initialize_gpu(true) {
+    match gpu::initialize_gpu(true, config.gpu_validation) {
         Ok(gpu) => {
             // initialize the bus
             let mut broker: broker::Broker<lang::Lang> = broker::Broker::new(1024);
 
-            // read config file from known location or use default
-            let config = Configuration::load_from_file("config.toml")
-                .unwrap_or_else(|_| Configuration::default());
+            // start recording or playback
+            let session_thread = match &mode {
+                Mode::Interactive => None,
+                Mode::Record(path) => match broker::BrokerRecorder::new(&mut broker, path) {
+                    Ok(recorder) => Some(thread::spawn(move || {
+                        if let Err(e) = recorder.run() {
+                            log::error!("Recording failed: {}", e);
+                        }
+                    })),
+                    Err(e) => {
+                        log::error!("Failed to start recording: {}", e);
+                        return;
+                    }
+                },
+                Mode::Playback(path) => match broker::BrokerPlayback::new(&mut broker, path) {
+                    Ok(playback) => {
+                        log::info!("Replaying {} recorded events", playback.len());
+                        Some(thread::spawn(move || playback.run()))
+                    }
+                    Err(e) => {
+                        log::error!("Failed to load recording: {}", e);
+                        return;
+                    }
+                },
+            };
 
             // start threads
-            let ui_thread = surfacelab::ui::start_ui_thread(&mut broker, gpu.clone(), &config);
+            let ui_thread = if mode.is_headless() {
+                None
+            } else {
+                Some(surfacelab::ui::start_ui_thread(
+                    &mut broker,
+                    gpu.clone(),
+                    &config,
+                ))
+            };
             let compute_thread =
                 surfacelab::compute::start_compute_thread(&mut broker, gpu.clone(), &config);
+            let undo_thread = surfacelab::undo::start_undo_thread(&mut broker, &config);
+            let render_thread = surfacelab::render::start_render_thread(&mut broker, gpu, &config);
             let io_thread = surfacelab::io::start_io_thread(&mut broker, config);
-            let undo_thread = surfacelab::undo::start_undo_thread(&mut broker);
             let nodes_thread = surfacelab::nodes::start_nodes_thread(&mut broker);
-            let render_thread = surfacelab::render::start_render_thread(&mut broker, gpu);
             let _broker_runner = thread::spawn(move || broker.run());
 
             // wait for threads
-            ui_thread.join().unwrap();
+            if let Some(ui_thread) = ui_thread {
+                ui_thread.join().unwrap();
+            }
             io_thread.join().unwrap();
             undo_thread.join().unwrap();
             nodes_thread.join().unwrap();
             compute_thread.join().unwrap();
             render_thread.join().unwrap();
+            if let Some(session_thread) = session_thread {
+                session_thread.join().unwrap();
+            }
         }
         Err(err) => log::error!("{:?}", err),
     }
@@ -29,14 +29,47 @@ impl NodeManager {
         serde_cbor::ser::to_vec_packed(&surf)
     }
 
+    /// Serialize contained data into human readable JSON
+    pub fn serialize_json(&self) -> Result<String, serde_json::Error> {
+        log::info!("Serializing node data to JSON");
+        let surf = NodeData {
+            parent_size: self.parent_size,
+            export_size: self.export_size,
+            export_specs: Cow::Borrowed(&self.export_specs),
+            graphs: Cow::Borrowed(&self.graphs),
+        };
+
+        serde_json::to_string_pretty(&surf)
+    }
+
     /// Deserialize plain old data into self
     pub fn deserialize(&mut self, data: &[u8]) -> Result<Vec<Lang>, serde_cbor::Error> {
         log::info!("Deserializing node data");
         let node_data: NodeData<'_> = serde_cbor::de::from_slice(data)?;
+        Ok(self.load_node_data(node_data))
+    }
 
+    /// Deserialize JSON data into self
+    pub fn deserialize_json(&mut self, data: &str) -> Result<Vec<Lang>, serde_json::Error> {
+        log::info!("Deserializing node data from JSON");
+        let node_data: NodeData<'_> = serde_json::from_str(data)?;
+        Ok(self.load_node_data(node_data))
+    }
+
+    /// Replace internal structures with the given node data, returning all
+    /// events required to rebuild the state in other components.
+    fn load_node_data(&mut self, node_data: NodeData<'_>) -> Vec<Lang> {
         // Rebuilding internal structures
         self.graphs = node_data.graphs.into_owned();
+        for graph in self.graphs.values_mut() {
+            if let ManagedNodeCollection::LayerStack(ls) = graph {
+                ls.migrate();
+            }
+        }
         self.export_specs = node_data.export_specs.into_owned();
+        for spec in self.export_specs.iter_mut() {
+            spec.sanitize();
+        }
         self.parent_size = node_data.parent_size;
         self.export_size = node_data.export_size;
 
@@ -64,8 +97,14 @@ impl NodeManager {
             events.append(&mut graph.rebuild_events(self.parent_size));
             if let Some((instrs, last_use)) = graph.linearize(LinearizationMode::TopoSort) {
                 events.push(Lang::GraphEvent(GraphEvent::Relinearized(
-                    res, instrs, last_use,
-                )))
+                    res.clone(),
+                    instrs,
+                    last_use,
+                )));
+                events.push(Lang::GraphEvent(GraphEvent::ValidationReport(
+                    res,
+                    crate::nodes::nodegraph::ValidationError::report(&graph.validate()),
+                )));
             }
         }
 
@@ -97,6 +136,111 @@ impl NodeManager {
             Resource::graph("base"),
         )));
 
-        Ok(events)
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::*;
+    use crate::nodes::layers::{Layer, LayerStack};
+
+    fn checker() -> Layer {
+        Layer::from(Operator::from(AtomicOperator::Checker(Checker::default())))
+    }
+
+    fn blur() -> Layer {
+        Layer::from(Operator::from(AtomicOperator::Blur(Blur::default())))
+    }
+
+    /// Instructions of the linearization of a collection, in a comparable form
+    fn instructions(collection: &ManagedNodeCollection) -> Option<String> {
+        collection
+            .linearize(LinearizationMode::TopoSort)
+            .map(|(instructions, _)| format!("{:?}", instructions))
+    }
+
+    /// Saving and loading a surface containing a layer stack with groups must
+    /// yield the same layer stack.
+    #[test]
+    fn layer_stack_roundtrip() {
+        let mut stack = LayerStack::new("stack");
+        stack.push_layer(checker(), LayerType::Fill, "checker");
+        let blur_a = stack.push_layer(blur(), LayerType::Fx, "blur");
+        let blur_b = stack.push_layer(blur(), LayerType::Fx, "blur");
+        stack
+            .group_layers(&[blur_a, blur_b], "group", "Group")
+            .expect("Grouping failed");
+
+        let mut manager = NodeManager::new();
+        manager.graphs.insert(
+            "stack".to_string(),
+            ManagedNodeCollection::LayerStack(stack),
+        );
+        let before = instructions(&manager.graphs["stack"]);
+
+        let data = manager.serialize().unwrap();
+        let mut loaded = NodeManager::new();
+        let events = loaded.deserialize(&data).unwrap();
+
+        assert_eq!(before, instructions(&loaded.graphs["stack"]));
+        assert!(events
+            .iter()
+            .any(|ev| matches!(ev, Lang::LayersEvent(LayersEvent::LayersGrouped(..)))));
+    }
+
+    /// Layer stacks as saved before the introduction of groups
+    #[derive(Serialize)]
+    struct LegacyLayerStack {
+        name: String,
+        layers: Vec<(Resource<Node>, Layer)>,
+        parameters: HashMap<String, GraphParameter>,
+    }
+
+    #[derive(Serialize)]
+    enum LegacyNodeCollection {
+        #[allow(dead_code)]
+        NodeGraph(crate::nodes::nodegraph::NodeGraph),
+        LayerStack(LegacyLayerStack),
+    }
+
+    #[derive(Serialize)]
+    struct LegacyNodeData {
+        parent_size: u32,
+        export_size: OperatorSize,
+        export_specs: Vec<ExportSpec>,
+        graphs: HashMap<String, LegacyNodeCollection>,
+    }
+
+    /// Surfaces saved before the introduction of groups must load, with their
+    /// layers migrated into the stack.
+    #[test]
+    fn legacy_layer_stack() {
+        let legacy = LegacyNodeData {
+            parent_size: 1024,
+            export_size: OperatorSize::RelativeToParent(0),
+            export_specs: Vec::new(),
+            graphs: maplit::hashmap! {
+                "stack".to_string() => LegacyNodeCollection::LayerStack(LegacyLayerStack {
+                    name: "stack".to_string(),
+                    layers: vec![
+                        (Resource::node("stack/checker.1"), checker()),
+                        (Resource::node("stack/blur.1"), blur()),
+                    ],
+                    parameters: HashMap::new(),
+                })
+            },
+        };
+
+        let data = serde_cbor::ser::to_vec_packed(&legacy).unwrap();
+        let mut loaded = NodeManager::new();
+        let events = loaded.deserialize(&data).unwrap();
+
+        let pushed = events
+            .iter()
+            .filter(|ev| matches!(ev, Lang::LayersEvent(LayersEvent::LayerPushed(..))))
+            .count();
+        assert_eq!(pushed, 2);
     }
 }
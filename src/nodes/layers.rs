@@ -252,18 +252,21 @@ impl MaskStack {
     }
 
     /// Insert this mask stack into a graph, for use in converting layer stacks
-    /// to graphs.
+    /// to graphs. The names of the inserted mask nodes are recorded in
+    /// `nodes`, keyed by mask resource.
     pub fn insert_into_graph(
         &self,
         graph: &mut super::nodegraph::NodeGraph,
         mut x: f64,
         parent_size: u32,
+        nodes: &mut Vec<(Resource<Node>, String)>,
     ) -> Option<(String, String)> {
         let mut last_socket: Option<(String, String)> = None;
 
-        for (_, mask) in self.stack.iter().filter(|m| m.1.blend_options.enabled) {
+        for (resource, mask) in self.stack.iter().filter(|m| m.1.blend_options.enabled) {
             let mask_node = graph.new_node(&mask.operator, parent_size, None).0;
             graph.position_node(&mask_node, x, -SLICE_WIDTH);
+            nodes.push((resource.clone(), mask_node.clone()));
             x += SLICE_WIDTH;
 
             // Since this is a mask, there is always 0 or 1 input
@@ -302,6 +305,13 @@ impl MaskStack {
         last_socket
     }
 
+    /// Move all masks in the stack to the given layer stack.
+    fn set_graph(&mut self, graph: &str) {
+        for (res, _) in self.stack.iter_mut() {
+            res.set_graph(graph);
+        }
+    }
+
     /// Push a new mask operator onto the mask stack
     pub fn push(&mut self, mask: Mask, resource: Resource<Node>) -> Option<()> {
         if !mask.operator.inputs().is_empty() && self.stack.is_empty() {
@@ -318,6 +328,14 @@ impl MaskStack {
         self.stack.iter().map(|x| &x.1)
     }
 
+    /// Iterator over all enabled masks in the stack
+    pub fn iter_enabled(&self) -> impl Iterator<Item = &Mask> + Clone {
+        self.stack
+            .iter()
+            .map(|x| &x.1)
+            .filter(|m| m.blend_options.enabled)
+    }
+
     /// Get size of the mask stack
     pub fn len(&self) -> usize {
         self.stack.len()
@@ -342,6 +360,11 @@ impl MaskStack {
         }
     }
 
+    pub fn get_mask_parameter(&self, param: &Resource<Param>) -> Option<Vec<u8>> {
+        let (_, mask) = self.stack.iter().find(|(r, _)| param.is_param_of(r))?;
+        mask.operator.parameter_value(param.fragment()?)
+    }
+
     pub fn set_mask_opacity(&mut self, mask: &Resource<Node>, opacity: f32) {
         if let Some((_, mask)) = self.stack.iter_mut().find(|(r, _)| r == mask) {
             mask.blend_options.opacity = opacity;
@@ -367,26 +390,18 @@ impl MaskStack {
             .map(|p| self.stack.remove(p).1)
     }
 
-    pub fn position_mask(
-        &mut self,
-        res: &Resource<Node>,
-        position: &LayerDropTarget,
-    ) -> Option<()> {
-        let mask = self
-            .stack
-            .remove(self.stack.iter().position(|(r, _)| r == res)?);
-        let mut target = self
-            .stack
-            .iter()
-            .position(|(r, _)| position.target() == r)?;
-
-        if let LayerDropTarget::Above(_) = position {
-            target += 1
+    /// Move a mask to the given index in the stack, by removing it and
+    /// reinserting it at the index. Returns the previous index of the mask.
+    pub fn position_mask(&mut self, res: &Resource<Node>, index: usize) -> Option<usize> {
+        let from = self.stack.iter().position(|(r, _)| r == res)?;
+        if index >= self.stack.len() {
+            return None;
         }
 
-        self.stack.insert(target, mask);
+        let mask = self.stack.remove(from);
+        self.stack.insert(index, mask);
 
-        Some(())
+        Some(from)
     }
 }
 
@@ -429,13 +444,15 @@ impl LayerBlendOptions {
         mask_resource: F,
         blend_resource: G,
     ) -> Option<Resource<Socket>> {
-        match self.mask.stack.len() {
+        // Disabled masks are skipped during linearization
+        let enabled = self.mask.iter_enabled();
+        match enabled.clone().count() {
             0 => None,
             1 => {
-                let mask = self.mask.iter().last().unwrap();
+                let mask = enabled.last().unwrap();
                 Some(mask_resource(mask).node_socket(&mask.output_socket))
             }
-            _ => Some(blend_resource(self.mask.iter().last().unwrap()).node_socket("color")),
+            _ => Some(blend_resource(enabled.last().unwrap()).node_socket("color")),
         }
     }
 }
@@ -555,6 +572,10 @@ impl Layer {
         self.blend_options.mask.set_mask_parameter(mask, data);
     }
 
+    pub fn get_mask_parameter(&self, mask: &Resource<Param>) -> Option<Vec<u8>> {
+        self.blend_options.mask.get_mask_parameter(mask)
+    }
+
     pub fn set_mask_opacity(&mut self, mask: &Resource<Node>, opacity: f32) {
         self.blend_options.mask.set_mask_opacity(mask, opacity);
     }
@@ -650,27 +671,307 @@ impl Layer {
     }
 }
 
+/// A group of layers with its own nested stack. The layers in a group are
+/// applied on top of the material underneath the group, and the result is
+/// blended onto that material as a whole, using the blend mode and opacity of
+/// the group.
+///
+/// Groups have no masks and no channel selection of their own. Every channel
+/// written by one of the contained layers is blended.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GroupLayer {
+    /// Internal name of the group
+    name: String,
+    /// Human readable name of the group
+    title: String,
+    /// Nested layer stack, indexed from the bottom
+    layers: Vec<(Resource<Node>, StackElement)>,
+    /// Blend opacity
+    opacity: f32,
+    /// Blend mode to use, uniform across all channels
+    blend_mode: BlendMode,
+    /// Whether this group is enabled at all
+    enabled: bool,
+}
+
+impl GroupLayer {
+    pub fn new(name: &str, title: &str, layers: Vec<(Resource<Node>, StackElement)>) -> Self {
+        Self {
+            name: name.to_owned(),
+            title: title.to_owned(),
+            layers,
+            opacity: 1.0,
+            blend_mode: BlendMode::Mix,
+            enabled: true,
+        }
+    }
+
+    /// Create a blend operator for blending the group onto the underlying
+    /// stack. The output will *always* be clamped!
+    pub fn blend_operator(&self) -> AtomicOperator {
+        AtomicOperator::Blend(Blend {
+            blend_mode: self.blend_mode,
+            mix: self.opacity,
+            sharpness: 16.0,
+            clamp_output: 1,
+        })
+    }
+
+    /// The layers contained directly in this group, from the bottom up.
+    pub fn layers(&self) -> &[(Resource<Node>, StackElement)] {
+        &self.layers
+    }
+
+    /// Resources of all elements contained in this group, recursively. Nested
+    /// elements precede the groups containing them.
+    pub fn nested_resources(&self) -> Vec<Resource<Node>> {
+        let mut resources: Vec<_> = iter_elements(&self.layers)
+            .map(|(res, _)| res.clone())
+            .collect();
+        resources.reverse();
+        resources
+    }
+}
+
+/// An element of a layer stack, either a single layer or a group of layers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StackElement {
+    Layer(Layer),
+    Group(GroupLayer),
+}
+
+impl StackElement {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Layer(l) => &l.name,
+            Self::Group(g) => &g.name,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        match self {
+            Self::Layer(l) => &l.title,
+            Self::Group(g) => &g.title,
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        match self {
+            Self::Layer(l) => l.set_title(title),
+            Self::Group(g) => g.title = title.to_owned(),
+        }
+    }
+
+    pub fn get_opacity(&self) -> f32 {
+        match self {
+            Self::Layer(l) => l.get_opacity(),
+            Self::Group(g) => g.opacity,
+        }
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        match self {
+            Self::Layer(l) => l.set_opacity(opacity),
+            Self::Group(g) => g.opacity = opacity,
+        }
+    }
+
+    pub fn get_blend_mode(&self) -> BlendMode {
+        match self {
+            Self::Layer(l) => l.get_blend_mode(),
+            Self::Group(g) => g.blend_mode,
+        }
+    }
+
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        match self {
+            Self::Layer(l) => l.set_blend_mode(blend_mode),
+            Self::Group(g) => g.blend_mode = blend_mode,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Self::Layer(l) => l.blend_options.enabled,
+            Self::Group(g) => g.enabled,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        match self {
+            Self::Layer(l) => l.set_enabled(enabled),
+            Self::Group(g) => g.enabled = enabled,
+        }
+    }
+
+    /// Channels written by this element. For groups these are all channels
+    /// written by any enabled layer in the group.
+    pub fn get_output_channels(&self) -> EnumSet<MaterialChannel> {
+        match self {
+            Self::Layer(l) => l.get_output_channels(),
+            Self::Group(g) => g
+                .layers
+                .iter()
+                .filter(|(_, e)| e.is_enabled())
+                .map(|(_, e)| e.get_output_channels())
+                .fold(EnumSet::empty(), |z, c| z.union(c)),
+        }
+    }
+
+    /// Determine whether this element takes part in the stack at all.
+    fn is_active(&self) -> bool {
+        self.is_enabled() && !self.get_output_channels().is_empty()
+    }
+
+    /// Determine the number of graph "layers" taken up by this element, see
+    /// `Layer::graph_width`. Groups take up the width of their contents, plus
+    /// one slice for blending.
+    pub fn graph_width(&self) -> usize {
+        match self {
+            Self::Layer(l) => l.graph_width(),
+            Self::Group(g) => {
+                1 + g
+                    .layers
+                    .iter()
+                    .filter(|(_, e)| e.is_active())
+                    .map(|(_, e)| e.graph_width())
+                    .sum::<usize>()
+            }
+        }
+    }
+
+    /// Move this element and all elements contained in it to the given layer
+    /// stack.
+    fn set_graph(&mut self, graph: &str) {
+        match self {
+            Self::Layer(l) => l.blend_options.mask.set_graph(graph),
+            Self::Group(g) => {
+                for (res, element) in g.layers.iter_mut() {
+                    res.set_graph(graph);
+                    element.set_graph(graph);
+                }
+            }
+        }
+    }
+}
+
+/// Iterate over all elements of a stack, descending into groups. Groups are
+/// yielded before their contents.
+fn iter_elements(
+    elements: &[(Resource<Node>, StackElement)],
+) -> Box<dyn Iterator<Item = (&Resource<Node>, &StackElement)> + '_> {
+    Box::new(elements.iter().flat_map(|(res, element)| {
+        let contents = match element {
+            StackElement::Layer(_) => Box::new(std::iter::empty()),
+            StackElement::Group(g) => iter_elements(&g.layers),
+        };
+        std::iter::once((res, element)).chain(contents)
+    }))
+}
+
+/// Iterate over all layers of a stack, descending into groups.
+fn iter_layers(
+    elements: &[(Resource<Node>, StackElement)],
+) -> impl Iterator<Item = (&Resource<Node>, &Layer)> {
+    iter_elements(elements).filter_map(|(res, element)| match element {
+        StackElement::Layer(l) => Some((res, l)),
+        StackElement::Group(_) => None,
+    })
+}
+
+/// Iterate mutably over all layers of a stack, descending into groups.
+fn iter_layers_mut(
+    elements: &mut [(Resource<Node>, StackElement)],
+) -> Box<dyn Iterator<Item = (&Resource<Node>, &mut Layer)> + '_> {
+    Box::new(
+        elements
+            .iter_mut()
+            .flat_map(|(res, element)| match element {
+                StackElement::Layer(l) => Box::new(std::iter::once((&*res, l)))
+                    as Box<dyn Iterator<Item = (&Resource<Node>, &mut Layer)>>,
+                StackElement::Group(g) => iter_layers_mut(&mut g.layers),
+            }),
+    )
+}
+
+/// Find the (sub)stack directly containing the element with the given
+/// resource, descending into groups.
+fn find_container_mut<'a>(
+    elements: &'a mut Vec<(Resource<Node>, StackElement)>,
+    resource: &Resource<Node>,
+) -> Option<&'a mut Vec<(Resource<Node>, StackElement)>> {
+    if elements.iter().any(|(r, _)| r == resource) {
+        return Some(elements);
+    }
+
+    elements.iter_mut().find_map(|(_, element)| match element {
+        StackElement::Group(g) => find_container_mut(&mut g.layers, resource),
+        StackElement::Layer(_) => None,
+    })
+}
+
 /// A stack of layers, equivalent to a graph of a specific form, that can be
-/// linearized or converted.
+/// linearized or converted. Layers may be combined into groups, which nest
+/// arbitrarily deep.
 ///
 /// Contrary to graphs, layer stacks have a well defined set of outputs, one per
 /// PBR channel.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LayerStack {
     name: String,
-    layers: Vec<(Resource<Node>, Layer)>,
+    /// Layers as stored by files predating groups. This is only ever filled
+    /// when loading such a file, and emptied again by `migrate`. The field
+    /// keeps its position, since packed serialization identifies fields by
+    /// index. Skipping conditionally is required to retain the index of the
+    /// following fields.
+    #[serde(rename = "layers", default, skip_serializing_if = "Vec::is_empty")]
+    legacy_layers: Vec<(Resource<Node>, Layer)>,
     parameters: HashMap<String, GraphParameter>,
+    #[serde(rename = "elements", default)]
+    layers: Vec<(Resource<Node>, StackElement)>,
 }
 
 impl LayerStack {
     pub fn new(name: &str) -> Self {
         LayerStack {
             name: name.to_owned(),
-            layers: Vec::new(),
+            legacy_layers: Vec::new(),
             parameters: HashMap::new(),
+            layers: Vec::new(),
         }
     }
 
+    /// Move layers loaded from a file predating groups into the stack. Must
+    /// be called after deserialization.
+    pub fn migrate(&mut self) {
+        if !self.legacy_layers.is_empty() {
+            log::info!("Migrating layer stack {} to stack elements", self.name);
+            self.layers = self
+                .legacy_layers
+                .drain(0..)
+                .map(|(res, layer)| (res, StackElement::Layer(layer)))
+                .collect();
+        }
+    }
+
+    /// Create a deep copy of this layer stack under a new name, rewriting all
+    /// contained resources.
+    pub fn clone_as(&self, name: &str) -> Self {
+        let mut new = self.clone();
+        new.name = name.to_owned();
+
+        for (res, element) in new.layers.iter_mut() {
+            res.set_graph(name);
+            element.set_graph(name);
+        }
+
+        for param in new.parameters.values_mut() {
+            param.parameter.set_graph(name);
+        }
+
+        new
+    }
+
     /// Obtain the next free resource name given a base, see node graph for
     /// analogous function
     fn next_free_name(&self, base_name: &str) -> String {
@@ -678,9 +979,7 @@ impl LayerStack {
 
         let mut resource = String::new();
 
-        let knowns: HashSet<String> = self
-            .layers
-            .iter()
+        let knowns: HashSet<String> = iter_elements(&self.layers)
             .map(|x| x.0.file().unwrap().to_string())
             .collect();
 
@@ -711,19 +1010,42 @@ impl LayerStack {
         Resource::node(&format!("{}/{}.blend", self.name, mask.name))
     }
 
-    /// Obtain the resource name of a layer blend
-    pub fn blend_resource(&self, layer: &Layer, channel: MaterialChannel) -> Resource<Node> {
+    /// Obtain the resource name of a layer or group blend, given the name of
+    /// the layer or group
+    pub fn blend_resource(&self, layer: &str, channel: MaterialChannel) -> Resource<Node> {
         Resource::node(&format!(
             "{}/{}.blend.{}",
             self.name,
-            layer.name(),
+            layer,
             channel.short_name()
         ))
     }
 
+    /// Find a layer by resource, descending into groups.
+    fn find_layer(&self, resource: &Resource<Node>) -> Option<&Layer> {
+        iter_layers(&self.layers)
+            .find(|(r, _)| r == &resource)
+            .map(|(_, l)| l)
+    }
+
+    /// Find a layer by resource mutably, descending into groups.
+    fn find_layer_mut(&mut self, resource: &Resource<Node>) -> Option<&mut Layer> {
+        iter_layers_mut(&mut self.layers)
+            .find(|(r, _)| r == &resource)
+            .map(|(_, l)| l)
+    }
+
+    /// Find a layer or group by resource mutably, descending into groups.
+    fn find_element_mut(&mut self, resource: &Resource<Node>) -> Option<&mut StackElement> {
+        find_container_mut(&mut self.layers, resource)?
+            .iter_mut()
+            .find(|(r, _)| r == resource)
+            .map(|(_, e)| e)
+    }
+
     /// Push a new layer onto the stack.
     fn push(&mut self, layer: Layer, resource: Resource<Node>) {
-        self.layers.push((resource, layer));
+        self.layers.push((resource, StackElement::Layer(layer)));
     }
 
     /// Push a new layer onto the stack
@@ -740,6 +1062,32 @@ impl LayerStack {
         resource
     }
 
+    /// Duplicate a layer, including its operator and blend options, placing
+    /// the copy directly above the source layer. Masks are not duplicated.
+    /// Returns the resource of the new layer along with the layer itself, as
+    /// well as the index it was pushed to and the index it was moved to.
+    ///
+    /// Only layers outside of groups can be duplicated.
+    pub fn duplicate_layer(
+        &mut self,
+        resource: &Resource<Node>,
+    ) -> Option<(Resource<Node>, Layer, usize, usize)> {
+        let index = self.layers.iter().position(|(r, _)| r == resource)?;
+        let mut layer = match &self.layers[index].1 {
+            StackElement::Layer(l) => l.clone(),
+            StackElement::Group(_) => return None,
+        };
+        layer.blend_options.mask = MaskStack::new();
+
+        let layer_type = layer.layer_type;
+        let base_name = layer.operator.default_name().to_owned();
+        let new_resource = self.push_layer(layer, layer_type, &base_name);
+        let from = self.position_layer(&new_resource, index + 1)?;
+
+        let layer = self.find_layer(&new_resource)?.clone();
+        Some((new_resource, layer, from, index + 1))
+    }
+
     /// Push a new mask onto the mask stack for a given layer
     pub fn push_mask(
         &mut self,
@@ -750,10 +1098,10 @@ impl LayerStack {
         let base_name = format!("{}.mask.{}", for_layer.file().unwrap(), base_name);
         let name = self.next_free_name(&base_name);
 
-        if let Some((_, layer)) = self.layers.iter_mut().find(|(r, _)| r == for_layer) {
-            mask.name = name.to_owned();
+        let resource = Resource::node(&format!("{}/{}", self.name, name));
 
-            let resource = Resource::node(&format!("{}/{}", self.name, name));
+        if let Some(layer) = self.find_layer_mut(for_layer) {
+            mask.name = name.to_owned();
             layer.push_mask(mask, resource.clone())?;
             Some(resource)
         } else {
@@ -761,24 +1109,74 @@ impl LayerStack {
         }
     }
 
-    /// Remove a layer by resource
-    pub fn remove_layer(&mut self, resource: &Resource<Node>) -> Option<Layer> {
-        self.layers
+    /// Combine the given layers into a new group, which takes their place in
+    /// the stack. The layers must form a contiguous range in the same (sub)stack.
+    /// Returns the resource of the new group and the grouped layers, ordered
+    /// from the bottom of the group upwards.
+    pub fn group_layers(
+        &mut self,
+        layers: &[Resource<Node>],
+        base_name: &str,
+        title: &str,
+    ) -> Option<(Resource<Node>, Vec<Resource<Node>>)> {
+        let name = self.next_free_name(base_name);
+        let resource = Resource::node(&format!("{}/{}", self.name, name));
+        let container = find_container_mut(&mut self.layers, layers.first()?)?;
+
+        let indices = layers
+            .iter()
+            .map(|layer| container.iter().position(|(r, _)| r == layer))
+            .collect::<Option<Vec<_>>>()?;
+        let (&bottom, &top) = (indices.iter().min()?, indices.iter().max()?);
+        if indices.iter().unique().count() != top - bottom + 1 {
+            return None;
+        }
+
+        let grouped: Vec<_> = container.drain(bottom..=top).collect();
+        let members = grouped.iter().map(|(r, _)| r.clone()).collect();
+        container.insert(
+            bottom,
+            (
+                resource.clone(),
+                StackElement::Group(GroupLayer::new(&name, title, grouped)),
+            ),
+        );
+
+        Some((resource, members))
+    }
+
+    /// Dissolve a group, moving its contents into its place in the stack.
+    /// Returns the formerly grouped layers, ordered from the bottom upwards.
+    pub fn ungroup_layers(&mut self, group: &Resource<Node>) -> Option<Vec<Resource<Node>>> {
+        let container = find_container_mut(&mut self.layers, group)?;
+        let index = container.iter().position(|(r, _)| r == group)?;
+
+        let contents = match container.remove(index).1 {
+            StackElement::Group(g) => g.layers,
+            element => {
+                container.insert(index, (group.clone(), element));
+                return None;
+            }
+        };
+        let members = contents.iter().map(|(r, _)| r.clone()).collect();
+        container.splice(index..index, contents);
+
+        Some(members)
+    }
+
+    /// Remove a layer or group by resource
+    pub fn remove_layer(&mut self, resource: &Resource<Node>) -> Option<StackElement> {
+        let container = find_container_mut(&mut self.layers, resource)?;
+        container
             .iter()
             .position(|(r, _)| r == resource)
-            .map(|p| self.layers.remove(p).1)
+            .map(|p| container.remove(p).1)
     }
 
     /// Remove a mask by resource
     pub fn remove_mask(&mut self, resource: &Resource<Node>) -> Option<Mask> {
         let parent_resource = layer_resource_from_mask_resource(resource);
-        self.layers.iter_mut().find_map(|(r, l)| {
-            if r == &parent_resource {
-                l.remove_mask(resource)
-            } else {
-                None
-            }
-        })
+        self.find_layer_mut(&parent_resource)?.remove_mask(resource)
     }
 
     /// Reset the layer stack, removing all layers
@@ -803,7 +1201,7 @@ impl LayerStack {
         &self,
         layer: &Resource<Node>,
     ) -> Vec<(Resource<Socket>, OperatorType, bool)> {
-        if let Some((_, l)) = self.layers.iter().find(|(r, _)| r == layer) {
+        if let Some(l) = self.find_layer(layer) {
             l.operator
                 .outputs()
                 .iter()
@@ -829,7 +1227,7 @@ impl LayerStack {
         layer: &Resource<Node>,
         mask: &Resource<Node>,
     ) -> Vec<(Resource<Socket>, OperatorType, bool)> {
-        if let Some((_, l)) = self.layers.iter().find(|(r, _)| r == layer) {
+        if let Some(l) = self.find_layer(layer) {
             let op = l.get_masks().get_operator(mask).expect("Unknown mask");
             op.outputs()
                 .iter()
@@ -854,13 +1252,14 @@ impl LayerStack {
         )]
     }
 
-    /// Return all blend sockets of the given layer
+    /// Return all blend sockets of the given layer or group
     pub fn blend_sockets(&self, layer: &Resource<Node>) -> Vec<(Resource<Socket>, OperatorType)> {
-        if let Some((_, layer)) = self.layers.iter().find(|(r, _)| r == layer) {
+        if let Some((_, element)) = iter_elements(&self.layers).find(|(r, _)| r == &layer) {
             MaterialChannel::iter()
                 .map(|channel| {
                     (
-                        self.blend_resource(layer, channel).node_socket("color"),
+                        self.blend_resource(element.name(), channel)
+                            .node_socket("color"),
                         OperatorType::Monomorphic(ImageType::from(channel)),
                     )
                 })
@@ -870,10 +1269,10 @@ impl LayerStack {
         }
     }
 
-    /// Set the title, i.e. human readable name, of a layer
+    /// Set the title, i.e. human readable name, of a layer or group
     pub fn set_title(&mut self, layer: &Resource<Node>, title: &str) {
-        if let Some((_, layer)) = self.layers.iter_mut().find(|(r, _)| r == layer) {
-            layer.set_title(title);
+        if let Some(element) = self.find_element_mut(layer) {
+            element.set_title(title);
         }
     }
 
@@ -884,7 +1283,7 @@ impl LayerStack {
         channel: MaterialChannel,
         socket_index: usize,
     ) {
-        if let Some((_, layer)) = self.layers.iter_mut().find(|(r, _)| r == layer) {
+        if let Some(layer) = self.find_layer_mut(layer) {
             let socket = layer
                 .operator
                 .outputs()
@@ -904,11 +1303,7 @@ impl LayerStack {
         layer_socket: &Resource<Socket>,
         channel: MaterialChannel,
     ) -> Vec<(Resource<Socket>, ImageType)> {
-        if let Some((_, l)) = self
-            .layers
-            .iter_mut()
-            .find(|(r, _)| layer_socket.is_socket_of(r))
-        {
+        if let Some(l) = self.find_layer_mut(&layer_socket.socket_node()) {
             if let LayerType::Fx = l.layer_type {
                 l.input_sockets
                     .insert(layer_socket.fragment().unwrap().to_owned(), channel);
@@ -925,7 +1320,7 @@ impl LayerStack {
     }
 
     pub fn type_sanitize_layer(&mut self, layer: &Resource<Node>) -> Vec<MaterialChannel> {
-        if let Some((_, l)) = self.layers.iter_mut().find(|(r, _)| r == layer) {
+        if let Some(l) = self.find_layer_mut(layer) {
             l.type_sanitize_outputs()
         } else {
             vec![]
@@ -939,7 +1334,7 @@ impl LayerStack {
         channel: MaterialChannel,
         visibility: bool,
     ) {
-        if let Some((_, l)) = self.layers.iter_mut().find(|(r, _)| r == layer) {
+        if let Some(l) = self.find_layer_mut(layer) {
             if visibility {
                 l.blend_options.channels.insert(channel);
             } else {
@@ -948,12 +1343,12 @@ impl LayerStack {
         }
     }
 
-    /// Set the opacity of a layer
+    /// Set the opacity of a layer or group
     pub fn set_layer_opacity(&mut self, layer: &Resource<Node>, opacity: f32) {
         if layer.path_str().unwrap().contains("mask") {
             self.set_mask_opacity(layer, opacity);
-        } else if let Some((_, layer)) = self.layers.iter_mut().find(|(r, _)| r == layer) {
-            layer.set_opacity(opacity);
+        } else if let Some(element) = self.find_element_mut(layer) {
+            element.set_opacity(opacity);
         }
     }
 
@@ -961,17 +1356,17 @@ impl LayerStack {
     fn set_mask_opacity(&mut self, mask: &Resource<Node>, opacity: f32) {
         let parent_resource = layer_resource_from_mask_resource(mask);
 
-        if let Some((_, l)) = self.layers.iter_mut().find(|(r, _)| r == &parent_resource) {
+        if let Some(l) = self.find_layer_mut(&parent_resource) {
             l.set_mask_opacity(mask, opacity);
         }
     }
 
-    /// Set the blend mode of a layer
+    /// Set the blend mode of a layer or group
     pub fn set_layer_blend_mode(&mut self, layer: &Resource<Node>, blend_mode: BlendMode) {
         if layer.path_str().unwrap().contains("mask") {
             self.set_mask_blend_mode(layer, blend_mode);
-        } else if let Some((_, layer)) = self.layers.iter_mut().find(|(r, _)| r == layer) {
-            layer.set_blend_mode(blend_mode)
+        } else if let Some(element) = self.find_element_mut(layer) {
+            element.set_blend_mode(blend_mode)
         }
     }
 
@@ -979,17 +1374,17 @@ impl LayerStack {
     fn set_mask_blend_mode(&mut self, mask: &Resource<Node>, blend_mode: BlendMode) {
         let parent_resource = layer_resource_from_mask_resource(mask);
 
-        if let Some((_, l)) = self.layers.iter_mut().find(|(r, _)| r == &parent_resource) {
+        if let Some(l) = self.find_layer_mut(&parent_resource) {
             l.set_mask_blend_mode(mask, blend_mode);
         }
     }
 
-    /// Enable or disable a layer
+    /// Enable or disable a layer or group
     pub fn set_layer_enabled(&mut self, layer: &Resource<Node>, enabled: bool) {
         if layer.path_str().unwrap().contains("mask") {
             self.set_mask_enabled(layer, enabled);
-        } else if let Some((_, layer)) = self.layers.iter_mut().find(|(r, _)| r == layer) {
-            layer.set_enabled(enabled);
+        } else if let Some(element) = self.find_element_mut(layer) {
+            element.set_enabled(enabled);
         }
     }
 
@@ -997,7 +1392,7 @@ impl LayerStack {
     fn set_mask_enabled(&mut self, mask: &Resource<Node>, enabled: bool) {
         let parent_resource = layer_resource_from_mask_resource(mask);
 
-        if let Some((_, l)) = self.layers.iter_mut().find(|(r, _)| r == &parent_resource) {
+        if let Some(l) = self.find_layer_mut(&parent_resource) {
             l.set_mask_enabled(mask, enabled);
         }
     }
@@ -1009,68 +1404,188 @@ impl LayerStack {
         self.linearize(super::LinearizationMode::TopoSort).is_some()
     }
 
-    /// Attempt moving a layer (or mask) to a specified position in the stack's
-    /// canonical order.
-    pub fn position_layer(
-        &mut self,
-        res: &Resource<Node>,
-        position: &LayerDropTarget,
-    ) -> Option<()> {
-        // First, ensure that this is a proper move, otherwise the reordering
-        // algorithm will mess up the stack, fail later, and everything is
-        // terrible.
-        if res == position.target() {
-            return None;
-        }
-
+    /// Attempt moving a layer (or mask) to the given index in its stack, by
+    /// removing it and reinserting it at the index. Layers are indexed from
+    /// the bottom of the layer stack or group containing them, masks from the
+    /// bottom of the mask stack of their layer. Returns the previous index of
+    /// the layer or mask.
+    pub fn position_layer(&mut self, res: &Resource<Node>, index: usize) -> Option<usize> {
         if res.path_str().unwrap().contains("mask") {
             let parent_resource = layer_resource_from_mask_resource(res);
 
-            self.layers
-                .iter_mut()
-                .find(|(r, _)| r == &parent_resource)
-                .and_then(|l| l.1.get_masks_mut().position_mask(res, position))
+            self.find_layer_mut(&parent_resource)
+                .and_then(|l| l.get_masks_mut().position_mask(res, index))
         } else {
-            let layer = self
-                .layers
-                .remove(self.layers.iter().position(|(r, _)| r == res)?);
-            let mut target = self
-                .layers
-                .iter()
-                .position(|(r, _)| position.target() == r)?;
-
-            if let LayerDropTarget::Above(_) = position {
-                target += 1
+            let container = find_container_mut(&mut self.layers, res)?;
+            let from = container.iter().position(|(r, _)| r == res)?;
+            if index >= container.len() {
+                return None;
             }
 
-            self.layers.insert(target, layer);
+            let layer = container.remove(from);
+            container.insert(index, layer);
 
-            Some(())
+            Some(from)
         }
     }
 
     /// Convert this layer stack into a node graph, if it is valid.
+    ///
+    /// The conversion mirrors the linearization of the stack, such that the
+    /// resulting graph computes the same outputs. Disabled layers and masks
+    /// are omitted, and exposed parameters are carried over to the nodes
+    /// they refer to. Groups are flattened, with one additional blend per
+    /// channel for each group.
     pub fn to_graph(&self, parent_size: u32) -> Option<super::nodegraph::NodeGraph> {
-        use super::nodegraph::*;
+        use super::{nodegraph::*, ExposedParameters, NodeCollection};
 
         let mut x = -(self
             .layers
             .iter()
-            .map(|(_, l)| l.graph_width())
+            .filter(|(_, e)| e.is_active())
+            .map(|(_, e)| e.graph_width())
             .sum::<usize>() as f64
             / 2.0)
             * SLICE_WIDTH;
 
         let mut last_socket: HashMap<MaterialChannel, (String, String)> = HashMap::new();
-        let mut last_mask: Option<(String, String)>;
+        let mut nodes = Vec::new();
         let mut graph = NodeGraph::new(&format!("{}_graph", self.name));
 
-        for (_, layer) in self.layers.iter() {
+        self.insert_into_graph(
+            &self.layers,
+            &mut graph,
+            &mut x,
+            parent_size,
+            &mut last_socket,
+            &mut nodes,
+        )?;
+
+        x += SLICE_WIDTH;
+        for channel in MaterialChannel::iter().filter(|channel| last_socket.contains_key(channel)) {
+            let output_op = Operator::from(AtomicOperator::Output(Output {
+                output_type: OutputType::from(channel),
+            }));
+            let output_node = graph
+                .new_node(
+                    &output_op,
+                    parent_size,
+                    Some(&format!("output.{}", channel.short_name())),
+                )
+                .0;
+            graph.position_node(&output_node, x, 0.0);
+
+            let (node, socket) = last_socket.get(&channel)?;
+            graph
+                .connect_sockets(node, socket, &output_node, "data")
+                .ok()?;
+        }
+
+        let graph_res = graph.graph_resource();
+        for (field, param) in self.parameters.iter() {
+            let node = nodes
+                .iter()
+                .find(|(res, _)| &param.parameter.parameter_node() == res);
+            let fragment = param.parameter.fragment();
+
+            if let Some(((_, node), fragment)) = node.zip(fragment) {
+                graph.expose_parameter(
+                    graph_res.graph_node(node).node_parameter(fragment),
+                    field,
+                    &param.title,
+                    param.control.clone(),
+                );
+            }
+        }
+
+        Some(graph)
+    }
+
+    /// Insert the given stack elements into a graph, for use in converting
+    /// layer stacks to graphs. The last socket written for each channel is
+    /// tracked in `last_socket`, and the names of inserted layer nodes are
+    /// recorded in `nodes`, keyed by layer resource.
+    fn insert_into_graph(
+        &self,
+        elements: &[(Resource<Node>, StackElement)],
+        graph: &mut super::nodegraph::NodeGraph,
+        x: &mut f64,
+        parent_size: u32,
+        last_socket: &mut HashMap<MaterialChannel, (String, String)>,
+        nodes: &mut Vec<(Resource<Node>, String)>,
+    ) -> Option<()> {
+        for (resource, element) in elements.iter().filter(|(_, e)| e.is_active()) {
+            let layer = match element {
+                StackElement::Layer(layer) => layer,
+                StackElement::Group(group) => {
+                    // Groups start from the material underneath them
+                    let mut group_socket = last_socket.clone();
+                    self.insert_into_graph(
+                        &group.layers,
+                        graph,
+                        x,
+                        parent_size,
+                        &mut group_socket,
+                        nodes,
+                    )?;
+
+                    // Blend all channels changed by the group
+                    let changed: Vec<_> = MaterialChannel::iter()
+                        .filter_map(|channel| {
+                            let socket = group_socket.get(&channel)?;
+                            if last_socket.get(&channel) == Some(socket) {
+                                None
+                            } else {
+                                Some((channel, socket.clone()))
+                            }
+                        })
+                        .collect();
+
+                    for (i, (channel, (group_node, group_output))) in
+                        changed.into_iter().enumerate()
+                    {
+                        if let Some((background_node, background_socket)) =
+                            last_socket.get(&channel)
+                        {
+                            let blend_op = Operator::from(group.blend_operator());
+                            let blend_node = graph.new_node(&blend_op, parent_size, None).0;
+                            graph.position_node(&blend_node, *x, (i + 1) as f64 * SLICE_WIDTH);
+
+                            graph
+                                .connect_sockets(
+                                    background_node,
+                                    background_socket,
+                                    &blend_node,
+                                    "background",
+                                )
+                                .ok()?;
+                            graph
+                                .connect_sockets(
+                                    &group_node,
+                                    &group_output,
+                                    &blend_node,
+                                    "foreground",
+                                )
+                                .ok()?;
+
+                            last_socket.insert(channel, (blend_node, "color".to_owned()));
+                        } else {
+                            last_socket
+                                .insert(channel, (group_node.to_owned(), group_output.to_owned()));
+                        }
+                    }
+
+                    *x += SLICE_WIDTH;
+                    continue;
+                }
+            };
+
             let op = layer.operator();
 
             let layer_node = graph.new_node(op, parent_size, None).0;
-            graph.position_node(&layer_node, x, 0.0);
-            x += SLICE_WIDTH;
+            graph.position_node(&layer_node, *x, 0.0);
+            nodes.push((resource.clone(), layer_node.clone()));
+            *x += SLICE_WIDTH;
 
             if let Some(inputs) = layer.inputs() {
                 for (input, channel) in inputs.iter() {
@@ -1081,15 +1596,15 @@ impl LayerStack {
                 }
             }
 
-            if layer.has_masks() {
-                let masks = layer.get_masks();
+            let masks = layer.get_masks();
+            let last_mask = if masks.iter_enabled().next().is_some() {
                 let (mask_node, mask_socket) =
-                    masks.insert_into_graph(&mut graph, x - SLICE_WIDTH, parent_size)?;
-                last_mask = Some((mask_node, mask_socket));
-                x += masks.len() as f64 * SLICE_WIDTH;
+                    masks.insert_into_graph(graph, *x - SLICE_WIDTH, parent_size, nodes)?;
+                *x += masks.len() as f64 * SLICE_WIDTH;
+                Some((mask_node, mask_socket))
             } else {
-                last_mask = None
-            }
+                None
+            };
 
             let output_channels = layer.get_output_channels();
             for (i, (channel, socket)) in layer
@@ -1101,7 +1616,7 @@ impl LayerStack {
                 if let Some((background_node, background_socket)) = last_socket.get(channel) {
                     let blend_op = Operator::from(layer.get_blend_options().blend_operator());
                     let blend_node = graph.new_node(&blend_op, parent_size, None).0;
-                    graph.position_node(&blend_node, x, (i + 1) as f64 * SLICE_WIDTH);
+                    graph.position_node(&blend_node, *x, (i + 1) as f64 * SLICE_WIDTH);
 
                     graph
                         .connect_sockets(
@@ -1127,98 +1642,103 @@ impl LayerStack {
             }
         }
 
-        x += SLICE_WIDTH;
-        for channel in MaterialChannel::iter().filter(|channel| last_socket.contains_key(channel)) {
-            let output_op = Operator::from(AtomicOperator::Output(Output {
-                output_type: OutputType::from(channel),
-            }));
-            let output_node = graph.new_node(&output_op, parent_size, None).0;
-            graph.position_node(&output_node, x, 0.0);
-
-            let (node, socket) = last_socket.get(&channel)?;
-            graph
-                .connect_sockets(node, socket, &output_node, "data")
-                .ok()?;
-        }
-
-        Some(graph)
-    }
-}
-
-impl super::ExposedParameters for LayerStack {
-    fn exposed_parameters(&self) -> &HashMap<String, GraphParameter> {
-        &self.parameters
-    }
-
-    fn exposed_parameters_mut(&mut self) -> &mut HashMap<String, GraphParameter> {
-        &mut self.parameters
-    }
-}
-
-impl super::NodeCollection for LayerStack {
-    /// Layer stacks do not have inputs, so this always returns an empty HashMap.
-    fn inputs(&self) -> HashMap<String, (OperatorType, Resource<Node>)> {
-        HashMap::new()
-    }
-
-    /// Layer stacks always have the same set of outputs, one per possible material channel.
-    fn outputs(&self) -> HashMap<String, (OperatorType, Resource<Node>)> {
-        let channels = self
-            .layers
-            .iter()
-            .map(|(_, l)| l.get_output_channels())
-            .fold(EnumSet::empty(), |z, c| z.union(c));
-
-        HashMap::from_iter(channels.iter().map(|channel| {
-            (
-                channel.short_name().to_string(),
-                (
-                    OperatorType::Monomorphic(ImageType::from(channel)),
-                    self.output_resource(channel),
-                ),
-            )
-        }))
-    }
-
-    fn output_type(&self, node: &Resource<Node>) -> Option<OutputType> {
-        let channels = self
-            .layers
-            .iter()
-            .map(|(_, l)| l.get_output_channels())
-            .fold(EnumSet::empty(), |z, c| z.union(c));
-        channels
-            .iter()
-            .find(|chan| &self.output_resource(*chan) == node)
-            .map(|chan| OutputType::from(chan))
-    }
-
-    fn graph_resource(&self) -> Resource<Graph> {
-        Resource::graph(self.name.clone())
-    }
-
-    fn rename(&mut self, name: &str) {
-        self.name = name.to_string();
+        Some(())
     }
 
-    /// Linearize this layer stack into a vector of instructions to be
-    /// interpreted by the compute backend. Analogous to the similarly named
-    /// function in the NodeGraph.
+    /// Linearize the given stack elements, for use in linearizing the layer
+    /// stack. The last socket written for each channel is tracked in
+    /// `last_socket`, the remaining linearization structures are passed as
+    /// in `MaskStack::linearize_into`.
     ///
-    /// The linearization mode is ignored for layer stacks.
-    fn linearize(&self, _mode: super::LinearizationMode) -> Option<(Linearization, UsePoints)> {
-        let mut linearization = Vec::new();
-        let mut use_points: HashMap<Resource<Node>, UsePoint> = HashMap::new();
-        let mut step = 0;
-
-        let mut last_socket: HashMap<MaterialChannel, Resource<Socket>> = HashMap::new();
-
-        for (resource, layer) in self.layers.iter() {
+    /// Groups are flattened recursively. Each group is linearized starting
+    /// from the material underneath it, followed by a blend per channel
+    /// written in the group, much like a layer with a complex operator.
+    ///
+    /// Will return None if an error occurs.
+    fn linearize_into(
+        &self,
+        elements: &[(Resource<Node>, StackElement)],
+        linearization: &mut Linearization,
+        use_points: &mut HashMap<Resource<Node>, UsePoint>,
+        step: &mut usize,
+        last_socket: &mut HashMap<MaterialChannel, Resource<Socket>>,
+    ) -> Option<()> {
+        for (resource, element) in elements.iter() {
             // Skip if disabled
-            if !layer.blend_options.enabled || layer.blend_options.channels.is_empty() {
+            if !element.is_active() {
                 continue;
             }
 
-            step += 1;
+            let layer = match element {
+                StackElement::Layer(layer) => layer,
+                StackElement::Group(group) => {
+                    let mut group_socket = last_socket.clone();
+                    self.linearize_into(
+                        &group.layers,
+                        linearization,
+                        use_points,
+                        step,
+                        &mut group_socket,
+                    )?;
+
+                    // Blend all channels changed by the group
+                    for channel in MaterialChannel::iter() {
+                        let foreground = match group_socket.get(&channel) {
+                            Some(socket) if last_socket.get(&channel) != Some(socket) => {
+                                socket.clone()
+                            }
+                            _ => continue,
+                        };
+
+                        if let Some(background) = last_socket.get(&channel).cloned() {
+                            *step += 1;
+
+                            let blend_res = self.blend_resource(&group.name, channel);
+
+                            for socket in &[&background, &foreground] {
+                                use_points
+                                    .entry(socket.socket_node())
+                                    .and_modify(|e| e.last = *step)
+                                    .or_insert(UsePoint {
+                                        last: *step,
+                                        creation: usize::MIN,
+                                    });
+                            }
+
+                            linearization.push(Instruction::Move(
+                                background,
+                                blend_res.node_socket("background"),
+                            ));
+                            linearization.push(Instruction::Move(
+                                foreground,
+                                blend_res.node_socket("foreground"),
+                            ));
+                            linearization
+                                .push(Instruction::ClearInput(blend_res.node_socket("mask")));
+                            linearization.push(Instruction::Execute(
+                                blend_res.clone(),
+                                group.blend_operator(),
+                            ));
+
+                            use_points
+                                .entry(blend_res.clone())
+                                .and_modify(|e| e.creation = *step)
+                                .or_insert(UsePoint {
+                                    last: usize::MAX,
+                                    creation: *step,
+                                });
+
+                            last_socket.insert(channel, blend_res.node_socket("color"));
+                        } else {
+                            last_socket.insert(channel, foreground);
+                        }
+                    }
+
+                    continue;
+                }
+            };
+
+            *step += 1;
 
             // Clear all optional sockets that have no inputs
             for socket in layer
@@ -1244,9 +1764,9 @@ impl super::NodeCollection for LayerStack {
                         let input_resource = last_socket.get(channel)?.clone();
                         use_points
                             .entry(input_resource.socket_node())
-                            .and_modify(|e| e.last = step)
+                            .and_modify(|e| e.last = *step)
                             .or_insert(UsePoint {
-                                last: step,
+                                last: *step,
                                 creation: usize::MIN,
                             });
 
@@ -1270,9 +1790,9 @@ impl super::NodeCollection for LayerStack {
                         let input_resource = last_socket.get(channel)?.clone();
                         use_points
                             .entry(input_resource.socket_node())
-                            .and_modify(|e| e.last = step)
+                            .and_modify(|e| e.last = *step)
                             .or_insert(UsePoint {
-                                last: step,
+                                last: *step,
                                 creation: usize::MIN,
                             });
 
@@ -1302,18 +1822,18 @@ impl super::NodeCollection for LayerStack {
 
             use_points
                 .entry(resource.clone())
-                .and_modify(|e| e.creation = step)
+                .and_modify(|e| e.creation = *step)
                 .or_insert(UsePoint {
                     last: usize::MAX,
-                    creation: step,
+                    creation: *step,
                 });
 
             if layer.blend_options.has_masks() {
                 layer.blend_options.mask.linearize_into(
                     |mask| self.mask_blend_resource(mask),
-                    &mut linearization,
-                    &mut use_points,
-                    &mut step,
+                    linearization,
+                    use_points,
+                    step,
                 );
             }
 
@@ -1324,22 +1844,22 @@ impl super::NodeCollection for LayerStack {
                 }
 
                 if let Some(background) = last_socket.get(channel).cloned() {
-                    step += 1;
+                    *step += 1;
 
-                    let blend_res = self.blend_resource(layer, *channel);
+                    let blend_res = self.blend_resource(layer.name(), *channel);
 
                     use_points
                         .entry(background.socket_node())
-                        .and_modify(|e| e.last = step)
+                        .and_modify(|e| e.last = *step)
                         .or_insert(UsePoint {
-                            last: step,
+                            last: *step,
                             creation: usize::MIN,
                         });
                     use_points
                         .entry(resource.clone())
-                        .and_modify(|e| e.last = step)
+                        .and_modify(|e| e.last = *step)
                         .or_insert(UsePoint {
-                            last: step,
+                            last: *step,
                             creation: usize::MIN,
                         });
 
@@ -1374,10 +1894,10 @@ impl super::NodeCollection for LayerStack {
 
                     use_points
                         .entry(blend_res.clone())
-                        .and_modify(|e| e.creation = step)
+                        .and_modify(|e| e.creation = *step)
                         .or_insert(UsePoint {
                             last: usize::MAX,
-                            creation: step,
+                            creation: *step,
                         });
 
                     last_socket.insert(*channel, blend_res.node_socket("color"));
@@ -1387,6 +1907,83 @@ impl super::NodeCollection for LayerStack {
             }
         }
 
+        Some(())
+    }
+}
+
+impl super::ExposedParameters for LayerStack {
+    fn exposed_parameters(&self) -> &HashMap<String, GraphParameter> {
+        &self.parameters
+    }
+
+    fn exposed_parameters_mut(&mut self) -> &mut HashMap<String, GraphParameter> {
+        &mut self.parameters
+    }
+}
+
+impl super::NodeCollection for LayerStack {
+    /// Layer stacks do not have inputs, so this always returns an empty HashMap.
+    fn inputs(&self) -> HashMap<String, (OperatorType, Resource<Node>)> {
+        HashMap::new()
+    }
+
+    /// Layer stacks always have the same set of outputs, one per possible material channel.
+    fn outputs(&self) -> HashMap<String, (OperatorType, Resource<Node>)> {
+        let channels = iter_layers(&self.layers)
+            .map(|(_, l)| l.get_output_channels())
+            .fold(EnumSet::empty(), |z, c| z.union(c));
+
+        HashMap::from_iter(channels.iter().map(|channel| {
+            (
+                channel.short_name().to_string(),
+                (
+                    OperatorType::Monomorphic(ImageType::from(channel)),
+                    self.output_resource(channel),
+                ),
+            )
+        }))
+    }
+
+    /// Output types are reported only for material channels that are written
+    /// by at least one layer, since other channels have no output to export.
+    fn output_type(&self, node: &Resource<Node>) -> Option<OutputType> {
+        self.layers
+            .iter()
+            .map(|(_, l)| l.get_output_channels())
+            .fold(EnumSet::empty(), |z, c| z.union(c))
+            .iter()
+            .find(|chan| &self.output_resource(*chan) == node)
+            .map(OutputType::from)
+    }
+
+    fn graph_resource(&self) -> Resource<Graph> {
+        Resource::graph(self.name.clone())
+    }
+
+    fn rename(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    /// Linearize this layer stack into a vector of instructions to be
+    /// interpreted by the compute backend. Analogous to the similarly named
+    /// function in the NodeGraph.
+    ///
+    /// The linearization mode is ignored for layer stacks.
+    fn linearize(&self, _mode: super::LinearizationMode) -> Option<(Linearization, UsePoints)> {
+        let mut linearization = Vec::new();
+        let mut use_points: HashMap<Resource<Node>, UsePoint> = HashMap::new();
+        let mut step = 0;
+
+        let mut last_socket: HashMap<MaterialChannel, Resource<Socket>> = HashMap::new();
+
+        self.linearize_into(
+            &self.layers,
+            &mut linearization,
+            &mut use_points,
+            &mut step,
+            &mut last_socket,
+        )?;
+
         // Finally process the (virtual) output operators
         for channel in MaterialChannel::iter() {
             step += 1;
@@ -1423,17 +2020,13 @@ impl super::NodeCollection for LayerStack {
             parent_resource.rename_file(&res_file[..pos]);
             let parent_node = parent_resource.parameter_node();
 
-            if let Some((_, layer)) = self.layers.iter_mut().find(|(r, _)| r == &parent_node) {
+            if let Some(layer) = self.find_layer_mut(&parent_node) {
                 layer.set_mask_parameter(resource, data);
             }
         } else {
             let field = resource.fragment().unwrap();
 
-            if let Some((_, layer)) = self
-                .layers
-                .iter_mut()
-                .find(|(r, _)| resource.is_param_of(r))
-            {
+            if let Some(layer) = self.find_layer_mut(&resource.parameter_node()) {
                 layer.operator.set_parameter(field, data);
             }
         }
@@ -1441,6 +2034,19 @@ impl super::NodeCollection for LayerStack {
         None
     }
 
+    fn parameter_value(&self, resource: &Resource<Param>) -> Option<Vec<u8>> {
+        let field = resource.fragment()?;
+
+        if resource.path_str()?.contains("mask") {
+            let parent_node = layer_resource_from_mask_resource(&resource.parameter_node());
+            self.find_layer(&parent_node)?.get_mask_parameter(resource)
+        } else {
+            self.find_layer(&resource.parameter_node())?
+                .operator
+                .parameter_value(field)
+        }
+    }
+
     fn update_complex_operators(
         &mut self,
         _parent_size: u32,
@@ -1448,8 +2054,9 @@ impl super::NodeCollection for LayerStack {
         new: &ComplexOperator,
     ) -> (Vec<super::ComplexOperatorUpdate>, Vec<GraphEvent>) {
         let mut updated = Vec::new();
+        let mut evs = Vec::new();
 
-        for (_, layer) in self.layers.iter_mut() {
+        for (res, layer) in iter_layers_mut(&mut self.layers) {
             let complex = match &mut layer.operator {
                 Operator::ComplexOperator(co) if &co.graph == graph => co,
                 _ => continue,
@@ -1470,22 +2077,44 @@ impl super::NodeCollection for LayerStack {
                 subs.resource_mut().set_graph(new.graph.path())
             }
 
+            // Purge substitutions that no longer correspond to any exposed
+            // parameter, e.g. after renaming a parameter in the called graph.
+            let stale: Vec<String> = complex
+                .parameters
+                .keys()
+                .filter(|field| !new.parameters.contains_key(*field))
+                .cloned()
+                .collect();
+            for field in stale {
+                complex.parameters.remove(&field);
+                evs.push(GraphEvent::StaleParameterRemoved(res.clone(), field));
+            }
+
             let params = complex.parameters.clone();
-            updated.push((layer.to_owned(), params));
+            updated.push((res.clone(), params));
         }
 
-        (
-            updated
-                .drain(0..)
-                .map(|(l, p)| (self.layer_resource(&l), p))
-                .collect(),
-            vec![],
-        )
+        (updated, evs)
     }
 
     fn resize_all(&mut self, parent_size: u32) -> Vec<Lang> {
         let mut evs = vec![];
-        for (res, layer) in self.layers.iter() {
+        for (res, element) in iter_elements(&self.layers) {
+            let layer = match element {
+                StackElement::Layer(layer) => layer,
+                StackElement::Group(group) => {
+                    // Groups only have blends
+                    for channel in MaterialChannel::iter() {
+                        evs.push(Lang::GraphEvent(GraphEvent::NodeResized(
+                            self.blend_resource(&group.name, channel),
+                            parent_size,
+                            true,
+                        )));
+                    }
+                    continue;
+                }
+            };
+
             // Main Layer
             evs.push(Lang::GraphEvent(GraphEvent::NodeResized(
                 res.clone(),
@@ -1501,7 +2130,7 @@ impl super::NodeCollection for LayerStack {
             // Blends
             for channel in layer.get_blend_options().channels.iter() {
                 evs.push(Lang::GraphEvent(GraphEvent::NodeResized(
-                    self.blend_resource(layer, channel),
+                    self.blend_resource(layer.name(), channel),
                     parent_size,
                     true,
                 )));
@@ -1530,9 +2159,17 @@ impl super::NodeCollection for LayerStack {
         evs
     }
 
+    /// Rebuilding pushes all layers in stack order first, and then groups
+    /// them, with nested groups preceding the groups containing them.
     fn rebuild_events(&self, parent_size: u32) -> Vec<Lang> {
-        self.layers
-            .iter()
+        let groups: Vec<_> = iter_elements(&self.layers)
+            .filter_map(|(res, element)| match element {
+                StackElement::Group(group) => Some((res, group)),
+                StackElement::Layer(_) => None,
+            })
+            .collect();
+
+        iter_layers(&self.layers)
             .map(|(res, layer)| {
                 let mut evs = Vec::new();
 
@@ -1584,12 +2221,57 @@ impl super::NodeCollection for LayerStack {
                 evs
             })
             .flatten()
+            .chain(groups.iter().rev().flat_map(|(res, group)| {
+                std::iter::once(Lang::LayersEvent(LayersEvent::LayersGrouped(
+                    (*res).clone(),
+                    group.title.clone(),
+                    group.layers.iter().map(|(r, _)| r.clone()).collect(),
+                    group.blend_mode,
+                    group.opacity,
+                    parent_size,
+                )))
+                .chain(self.blend_sockets(res).into_iter().map(|(s, t)| {
+                    Lang::GraphEvent(GraphEvent::OutputSocketAdded(s, t, false, parent_size))
+                }))
+            }))
             .collect()
     }
 
+    /// Layer stacks are valid by construction, since sockets are only ever
+    /// connected within layers of matching types.
+    fn validate(&self) -> Vec<super::nodegraph::ValidationError> {
+        Vec::new()
+    }
+
+    fn find_nodes(&self, query: &NodeQuery) -> Vec<Resource<Node>> {
+        iter_layers(&self.layers)
+            .filter(|(res, layer)| query.matches(res, &layer.operator))
+            .map(|(res, _)| res.clone())
+            .collect()
+    }
+
+    /// Layer stacks are linear, with each layer depending on the layer below
+    /// it and on its own masks. Groups are counted as if they were flattened.
+    fn statistics(&self) -> GraphStatistics {
+        let layers: Vec<&Layer> = iter_layers(&self.layers).map(|(_, l)| l).collect();
+        let masks: Vec<&Mask> = layers.iter().flat_map(|l| l.get_masks().iter()).collect();
+
+        GraphStatistics {
+            node_count: layers.len() + masks.len(),
+            edge_count: layers.len().saturating_sub(1) + masks.len(),
+            max_depth: layers.len(),
+            operator_histogram: super::operator_histogram(
+                layers
+                    .iter()
+                    .map(|l| &l.operator)
+                    .chain(masks.iter().map(|m| &m.operator)),
+            ),
+        }
+    }
+
     fn element_param_box(&self, element: &Resource<Node>) -> ParamBoxDescription<MessageWriters> {
-        match self.layers.iter().find(|(r, _)| r == element) {
-            Some((_, l)) => match l.layer_type {
+        match self.find_layer(element) {
+            Some(l) => match l.layer_type {
                 LayerType::Fill => {
                     ParamBoxDescription::fill_layer_parameters(&l.operator, &l.output_sockets)
                         .transmitters_into()
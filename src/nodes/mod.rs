@@ -1,7 +1,8 @@
 use crate::{broker, lang, lang::OperatorParamBox, lang::*};
 
 use serde_derive::*;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::thread;
 
@@ -152,6 +153,10 @@ trait NodeCollection {
     /// return an event or fail silently.
     fn parameter_change(&mut self, resource: &Resource<Param>, data: &[u8]) -> Option<Lang>;
 
+    /// Obtain the current value of a parameter in a resource in this node
+    /// collection, if it exists.
+    fn parameter_value(&self, resource: &Resource<Param>) -> Option<Vec<u8>>;
+
     /// Update all the complex operators matching a call to the old graph.
     /// Returns a vector of all node resources that have been updated.
     fn update_complex_operators(
@@ -173,6 +178,31 @@ trait NodeCollection {
     /// collection. E.g. nodes in the case of graphs or layers/masks in the case
     /// of layer stacks.
     fn element_param_box(&self, element: &Resource<Node>) -> ParamBoxDescription<MessageWriters>;
+
+    /// Validate the collection, checking all connections for type
+    /// compatibility. Returns a list of all problems found.
+    fn validate(&self) -> Vec<nodegraph::ValidationError>;
+
+    /// Find all nodes in the collection matching the query.
+    fn find_nodes(&self, query: &NodeQuery) -> Vec<Resource<Node>>;
+
+    /// Compute complexity metrics for this collection.
+    fn statistics(&self) -> GraphStatistics;
+}
+
+/// Count occurrences of operators for use in graph statistics.
+fn operator_histogram<'a, I: Iterator<Item = &'a Operator>>(
+    operators: I,
+) -> HashMap<String, usize> {
+    let mut histogram = HashMap::new();
+    for op in operators {
+        let key = match op {
+            Operator::AtomicOperator(aop) => aop.to_string(),
+            Operator::ComplexOperator(cop) => cop.graph.to_string(),
+        };
+        *histogram.entry(key).or_insert(0) += 1;
+    }
+    histogram
 }
 
 /// A node collection that can be stored and managed by the node manager.
@@ -190,6 +220,16 @@ impl Default for ManagedNodeCollection {
     }
 }
 
+impl ManagedNodeCollection {
+    /// Create a deep copy of this collection under a new name.
+    pub fn clone_as(&self, new_name: &str) -> ManagedNodeCollection {
+        match self {
+            Self::NodeGraph(graph) => Self::NodeGraph(graph.clone_as(new_name)),
+            Self::LayerStack(stack) => Self::LayerStack(stack.clone_as(new_name)),
+        }
+    }
+}
+
 /// The node manager is responsible for storing and modifying the node networks
 /// in the current surface file.
 struct NodeManager {
@@ -246,6 +286,58 @@ impl NodeManager {
         elbox.merge(opbox.transmitters_into())
     }
 
+    /// Parameter boxes of complex operators added during loading are built
+    /// from the defaults of their graph. Construct update events carrying the
+    /// parameter substitutions of each such operator, to be sent after the
+    /// events adding them.
+    fn loaded_complex_operator_updates(&self, events: &[Lang]) -> Vec<Lang> {
+        events
+            .iter()
+            .filter_map(|ev| match ev {
+                Lang::GraphEvent(GraphEvent::NodeAdded(res, op, ..))
+                | Lang::LayersEvent(LayersEvent::LayerPushed(res, _, _, op, ..))
+                | Lang::LayersEvent(LayersEvent::MaskPushed(_, res, _, op, ..)) => match op {
+                    Operator::ComplexOperator(co) => Some((res, co)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .map(|(res, co)| {
+                let mut pbox = self.operator_param_box(&Operator::ComplexOperator(co.clone()));
+
+                for param in pbox.parameters_mut() {
+                    if let Some(subs) = co.parameters.get(&param.transmitter.0) {
+                        param.control.set_value(subs.get_value());
+                    }
+                }
+
+                let elbox = self
+                    .graphs
+                    .get(res.directory().unwrap())
+                    .expect("Unknown node collection")
+                    .element_param_box(res);
+
+                Lang::GraphEvent(GraphEvent::ComplexOperatorUpdated(
+                    res.clone(),
+                    co.clone(),
+                    elbox.merge(pbox.transmitters_into()),
+                ))
+            })
+            .collect()
+    }
+
+    /// Find all nodes matching the query across all node collections. Results
+    /// are sorted by resource for a stable order.
+    pub fn find_nodes(&self, query: &NodeQuery) -> Vec<Resource<Node>> {
+        let mut results: Vec<_> = self
+            .graphs
+            .values()
+            .flat_map(|g| g.find_nodes(query))
+            .collect();
+        results.sort_by(|a, b| a.path().cmp(b.path()));
+        results
+    }
+
     /// Process an event from the application bus and dispatch the necessary
     /// operations to respond.
     ///
@@ -260,8 +352,10 @@ impl NodeManager {
             Lang::UserIOEvent(event) => self.process_user_io_event(event),
             Lang::IOEvent(IOEvent::NodeDataLoaded(data)) => {
                 let mut evs = self.deserialize(data).ok()?;
+                let mut updates = self.loaded_complex_operator_updates(&evs);
                 let mut response = vec![lang::Lang::GraphEvent(lang::GraphEvent::Cleared)];
                 response.append(&mut evs);
+                response.append(&mut updates);
                 Some(response)
             }
             _ => Some(vec![]),
@@ -347,6 +441,57 @@ impl NodeManager {
                     self.relinearize(&mut response, &graph_res, Some(&self.active_graph));
                 }
             }
+            UserNodeEvent::DuplicateNode(res, pos) => {
+                let node = res.file().unwrap();
+                let graph_name = res.directory().unwrap();
+                let mut update_co = None;
+
+                if let Some(ManagedNodeCollection::NodeGraph(graph)) =
+                    self.graphs.get_mut(graph_name)
+                {
+                    match graph.duplicate_node(node, self.parent_size, *pos) {
+                        Ok((node_id, op, size)) => {
+                            let resource = Resource::node(
+                                [graph_name, &node_id]
+                                    .iter()
+                                    .collect::<std::path::PathBuf>(),
+                            );
+
+                            match op {
+                                Operator::AtomicOperator(AtomicOperator::Output(..))
+                                | Operator::AtomicOperator(AtomicOperator::Input(..)) => {
+                                    update_co = Some((
+                                        graph.graph_resource(),
+                                        graph.complex_operator_stub(),
+                                    ));
+                                }
+                                _ => {}
+                            }
+
+                            response.push(Lang::GraphEvent(GraphEvent::NodeAdded(
+                                resource.clone(),
+                                op.clone(),
+                                self.element_param_box(&op, &resource),
+                                Some(*pos),
+                                size,
+                            )));
+                            for (socket, imgtype) in op.outputs().iter() {
+                                response.push(Lang::GraphEvent(GraphEvent::OutputSocketAdded(
+                                    resource.node_socket(socket),
+                                    *imgtype,
+                                    op.external_data(),
+                                    size,
+                                )));
+                            }
+                        }
+                        Err(e) => log::error!("{}", e),
+                    }
+                }
+
+                if let Some((graph_res, co_stub)) = update_co {
+                    response.append(&mut self.update_complex_operators(&graph_res, &co_stub));
+                }
+            }
             UserNodeEvent::RemoveNode(res) => {
                 let node = res.file().unwrap();
                 let graph = res.directory().unwrap();
@@ -402,10 +547,15 @@ impl NodeManager {
                     match graph.connect_sockets(from_node, from_socket, to_node, to_socket) {
                         Ok(mut res) => {
                             response.append(&mut res);
-                            self.relinearize(
+                            let dirty_set = self.downstream_dirty_set(
+                                &from.socket_node().node_graph(),
+                                &[to.socket_node()],
+                            );
+                            self.relinearize_dirty(
                                 &mut response,
                                 &from.socket_node().node_graph(),
                                 Some(&self.active_graph),
+                                dirty_set,
                             );
                         }
                         Err(e) => log::error!("{}", e),
@@ -464,6 +614,7 @@ impl NodeManager {
                         Ok(mut res) => {
                             let g_res = graph.graph_resource();
                             let g_instrs = graph.linearize(LinearizationMode::TopoSort);
+                            let g_validation = graph.validate();
 
                             // Rebuild parameter boxes for node added events before publishing
                             for ev in res.iter_mut() {
@@ -483,11 +634,18 @@ impl NodeManager {
 
                             if let Some(instrs) = g_instrs {
                                 response.push(Lang::GraphEvent(GraphEvent::Relinearized(
-                                    g_res, instrs.0, instrs.1,
+                                    g_res.clone(),
+                                    instrs.0,
+                                    instrs.1,
+                                )));
+                                response.push(Lang::GraphEvent(GraphEvent::ValidationReport(
+                                    g_res,
+                                    nodegraph::ValidationError::report(&g_validation),
                                 )));
                                 response.push(Lang::GraphEvent(GraphEvent::Recompute(
                                     self.active_graph.clone(),
                                     Vec::new(),
+                                    None,
                                 )));
                             }
                         }
@@ -500,10 +658,13 @@ impl NodeManager {
                     if let Some(side_effect) = graph.parameter_change(res, data) {
                         response.push(side_effect);
                     }
-                    self.relinearize(
+                    let node = res.parameter_node();
+                    let dirty_set = self.downstream_dirty_set(&node.node_graph(), &[node.clone()]);
+                    self.relinearize_dirty(
                         &mut response,
-                        &res.parameter_node().node_graph(),
+                        &node.node_graph(),
                         Some(&self.active_graph),
+                        dirty_set,
                     );
                 }
             }
@@ -545,6 +706,7 @@ impl NodeManager {
                         response.push(Lang::GraphEvent(GraphEvent::Recompute(
                             self.active_graph.clone(),
                             Vec::new(),
+                            None,
                         )));
                     };
                 }
@@ -553,6 +715,12 @@ impl NodeManager {
                 response.push(Lang::GraphEvent(GraphEvent::Recompute(
                     self.active_graph.clone(),
                     Vec::new(),
+                    None,
+                )));
+            }
+            UserNodeEvent::SearchNodes(query) => {
+                response.push(Lang::GraphEvent(GraphEvent::SearchResults(
+                    self.find_nodes(query),
                 )));
             }
         }
@@ -610,6 +778,66 @@ impl NodeManager {
                     response.append(&mut self.update_complex_operators(&from, &operator));
                 }
             }
+            UserGraphEvent::CloneGraph(res, new_name) => {
+                // Avoid clobbering existing graphs by picking a free name
+                let new_name = std::iter::once(new_name.clone())
+                    .chain((1..).map(|i| format!("{}.{}", new_name, i)))
+                    .find(|n| !self.graphs.contains_key(n))
+                    .unwrap();
+
+                if let Some(graph) = self.graphs.get(res.path_str().unwrap()) {
+                    log::trace!("Cloning graph {} as {}", res, new_name);
+                    let clone = graph.clone_as(&new_name);
+                    let new_res = clone.graph_resource();
+
+                    response.push(match &clone {
+                        ManagedNodeCollection::NodeGraph(_) => {
+                            Lang::GraphEvent(GraphEvent::GraphAdded(new_res.clone()))
+                        }
+                        ManagedNodeCollection::LayerStack(l) => {
+                            Lang::LayersEvent(LayersEvent::LayersAdded(
+                                new_res.clone(),
+                                self.parent_size,
+                                l.output_resources(),
+                            ))
+                        }
+                    });
+
+                    let mut evs = clone.rebuild_events(self.parent_size);
+                    for ev in evs.iter_mut() {
+                        match ev {
+                            Lang::GraphEvent(GraphEvent::NodeAdded(res, op, pbox, _, _)) => {
+                                *pbox = self.element_param_box(&op, res)
+                            }
+                            Lang::LayersEvent(LayersEvent::LayerPushed(
+                                res,
+                                _,
+                                _,
+                                op,
+                                _,
+                                _,
+                                pbox,
+                                _,
+                            )) => *pbox = self.element_param_box(&op, res),
+                            Lang::LayersEvent(LayersEvent::MaskPushed(
+                                _,
+                                res,
+                                _,
+                                op,
+                                _,
+                                _,
+                                pbox,
+                                _,
+                            )) => *pbox = self.element_param_box(&op, res),
+                            _ => {}
+                        }
+                    }
+                    response.extend(evs.drain(0..));
+
+                    self.graphs.insert(new_name, clone);
+                    self.relinearize(&mut response, &new_res, None);
+                }
+            }
             UserGraphEvent::DeleteGraph(res) => {
                 if self.graphs.len() > 1 {
                     self.graphs.remove(res.path_str().unwrap());
@@ -678,6 +906,33 @@ impl NodeManager {
                     .expect("Node Graph not found");
                 graph.refield_parameter(graph_field, new_field);
             }
+            UserGraphEvent::AutoLayout(graph) => {
+                if let Some(ManagedNodeCollection::NodeGraph(graph)) =
+                    self.graphs.get_mut(graph.path_str().unwrap())
+                {
+                    response.append(&mut graph.auto_layout());
+                }
+            }
+            UserGraphEvent::RequestStatistics(graph) => {
+                if let Some(collection) = self.graphs.get(graph.path_str().unwrap()) {
+                    response.push(Lang::GraphEvent(GraphEvent::Statistics(
+                        graph.clone(),
+                        collection.statistics(),
+                    )));
+                }
+            }
+            UserGraphEvent::ExportDot(graph, path) => {
+                match self.graphs.get(graph.path_str().unwrap()) {
+                    Some(ManagedNodeCollection::NodeGraph(g)) => {
+                        response.push(Lang::GraphEvent(GraphEvent::DotExported(
+                            path.clone(),
+                            g.export_dot(),
+                        )));
+                    }
+                    Some(_) => log::warn!("DOT export is only supported for node graphs"),
+                    None => {}
+                }
+            }
             UserGraphEvent::Extract(ress) => {
                 use itertools::Itertools;
 
@@ -737,11 +992,19 @@ impl NodeManager {
                 let other = other_graph.path_str().unwrap();
 
                 // Fetch other graph in a move, to sidestep double borrow
-                if let Some(ManagedNodeCollection::NodeGraph(other_graph)) =
-                    self.graphs.remove(other)
-                {
+                if let Some(other_collection) = self.graphs.remove(other) {
+                    // Layer stacks are injected via their equivalent node graph
+                    let other_graph = match &other_collection {
+                        ManagedNodeCollection::NodeGraph(g) => Some(Cow::Borrowed(g)),
+                        ManagedNodeCollection::LayerStack(ls) => {
+                            ls.to_graph(self.parent_size).map(Cow::Owned)
+                        }
+                    };
+
                     // Perform inject
-                    if let Some(ManagedNodeCollection::NodeGraph(g)) = self.graphs.get_mut(graph) {
+                    if let Some((ManagedNodeCollection::NodeGraph(g), other_graph)) =
+                        self.graphs.get_mut(graph).zip(other_graph)
+                    {
                         match g.inject(self.parent_size, node, &other_graph, *reposition) {
                             Ok(mut evs) => {
                                 // Rebuild parameter boxes for node added events before publishing
@@ -764,10 +1027,7 @@ impl NodeManager {
                     }
 
                     // Reinsert other graph
-                    self.graphs.insert(
-                        other.to_string(),
-                        ManagedNodeCollection::NodeGraph(other_graph),
-                    );
+                    self.graphs.insert(other.to_string(), other_collection);
                 }
 
                 // Relinearize
@@ -902,7 +1162,15 @@ impl NodeManager {
                 if let Some(ManagedNodeCollection::LayerStack(ls)) =
                     self.graphs.get_mut(layer_res.directory().unwrap())
                 {
-                    if ls.remove_layer(layer_res).is_some() {
+                    if let Some(element) = ls.remove_layer(layer_res) {
+                        if let layers::StackElement::Group(group) = element {
+                            response.extend(
+                                group
+                                    .nested_resources()
+                                    .drain(0..)
+                                    .map(|res| Lang::LayersEvent(LayersEvent::LayerRemoved(res))),
+                            );
+                        }
                         response.push(Lang::LayersEvent(LayersEvent::LayerRemoved(
                             layer_res.clone(),
                         )));
@@ -911,6 +1179,107 @@ impl NodeManager {
                     self.relinearize(&mut response, &self.active_graph, Some(&self.active_graph));
                 }
             }
+            UserLayersEvent::DuplicateLayer(layer_res) => {
+                if let Some(ManagedNodeCollection::LayerStack(ls)) =
+                    self.graphs.get_mut(layer_res.directory().unwrap())
+                {
+                    if let Some((res, layer, from, to)) = ls.duplicate_layer(layer_res) {
+                        log::debug!("Duplicated layer {} to {}", layer_res, res);
+
+                        let mut sockets = ls.layer_sockets(&res);
+                        let mut blend_sockets = ls.blend_sockets(&res);
+                        let op = layer.operator().clone();
+                        let pbox = self.element_param_box(&op, &res);
+                        let size = op.size_request().unwrap_or(self.parent_size);
+
+                        response.push(Lang::LayersEvent(LayersEvent::LayerPushed(
+                            res.clone(),
+                            layer.layer_type(),
+                            layer.title().to_owned(),
+                            op,
+                            layer.get_blend_mode(),
+                            layer.get_opacity(),
+                            pbox,
+                            size,
+                        )));
+                        response.push(Lang::LayersEvent(LayersEvent::LayerPositioned(
+                            res, from, to,
+                        )));
+                        response.extend(sockets.drain(0..).map(|(s, t, e)| {
+                            Lang::GraphEvent(GraphEvent::OutputSocketAdded(s, t, e, size))
+                        }));
+                        response.extend(blend_sockets.drain(0..).map(|(s, t)| {
+                            Lang::GraphEvent(GraphEvent::OutputSocketAdded(
+                                s,
+                                t,
+                                false,
+                                self.parent_size,
+                            ))
+                        }));
+                        self.relinearize(
+                            &mut response,
+                            &layer_res.node_graph(),
+                            Some(&self.active_graph),
+                        );
+                    }
+                }
+            }
+            UserLayersEvent::GroupLayers(layer_resources) => {
+                let graph = layer_resources.first().and_then(|res| res.directory());
+                let parent_size = self.parent_size;
+
+                if let Some(ManagedNodeCollection::LayerStack(ls)) =
+                    graph.and_then(|g| self.graphs.get_mut(g))
+                {
+                    if let Some((res, members)) = ls.group_layers(layer_resources, "group", "Group")
+                    {
+                        log::debug!("Grouped {} layers into {}", members.len(), res);
+
+                        response.push(Lang::LayersEvent(LayersEvent::LayersGrouped(
+                            res.clone(),
+                            "Group".to_owned(),
+                            members,
+                            BlendMode::Mix,
+                            1.0,
+                            parent_size,
+                        )));
+                        response.extend(ls.blend_sockets(&res).drain(0..).map(|(s, t)| {
+                            Lang::GraphEvent(GraphEvent::OutputSocketAdded(
+                                s,
+                                t,
+                                false,
+                                parent_size,
+                            ))
+                        }));
+                        self.relinearize(
+                            &mut response,
+                            &res.node_graph(),
+                            Some(&self.active_graph),
+                        );
+                    }
+                }
+            }
+            UserLayersEvent::UngroupLayers(group_res) => {
+                if let Some(ManagedNodeCollection::LayerStack(ls)) =
+                    self.graphs.get_mut(group_res.directory().unwrap())
+                {
+                    if let Some(members) = ls.ungroup_layers(group_res) {
+                        response.push(Lang::LayersEvent(LayersEvent::LayersUngrouped(
+                            group_res.clone(),
+                            members,
+                        )));
+                        response.push(Lang::LayersEvent(LayersEvent::LayerRemoved(
+                            group_res.clone(),
+                        )));
+
+                        self.relinearize(
+                            &mut response,
+                            &group_res.node_graph(),
+                            Some(&self.active_graph),
+                        );
+                    }
+                }
+            }
             UserLayersEvent::RemoveMask(mask_res) => {
                 if let Some(ManagedNodeCollection::LayerStack(ls)) =
                     self.graphs.get_mut(mask_res.directory().unwrap())
@@ -1015,14 +1384,15 @@ impl NodeManager {
                     self.relinearize(&mut response, &self.active_graph, Some(&self.active_graph));
                 }
             }
-            UserLayersEvent::PositionLayer(layer_res, position) => {
+            UserLayersEvent::PositionLayer(layer_res, index) => {
                 if let Some(ManagedNodeCollection::LayerStack(ls)) =
                     self.graphs.get_mut(layer_res.directory().unwrap())
                 {
-                    if ls.position_layer(layer_res, position).is_some() {
+                    if let Some(from) = ls.position_layer(layer_res, *index) {
                         response.push(Lang::LayersEvent(LayersEvent::LayerPositioned(
                             layer_res.clone(),
-                            position.clone(),
+                            from,
+                            *index,
                         )));
 
                         self.relinearize(
@@ -1076,6 +1446,72 @@ impl NodeManager {
                 let data = self.serialize().ok()?;
                 response.push(lang::Lang::GraphEvent(lang::GraphEvent::Serialized(data)));
             }
+            UserIOEvent::ExportGraphJson(path) => match self.serialize_json() {
+                Ok(data) => {
+                    if let Err(e) = std::fs::write(path, data) {
+                        log::error!("Failed to write JSON graph data: {}", e);
+                    }
+                }
+                Err(e) => log::error!("Failed to serialize graph data to JSON: {}", e),
+            },
+            UserIOEvent::ImportGraphJson(path) => {
+                match std::fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|data| self.deserialize_json(&data).map_err(|e| e.to_string()))
+                {
+                    Ok(mut evs) => {
+                        response.push(Lang::GraphEvent(GraphEvent::Imported));
+                        response.push(Lang::GraphEvent(GraphEvent::Cleared));
+                        response.append(&mut evs);
+                    }
+                    Err(e) => log::error!("Failed to import JSON graph data: {}", e),
+                }
+            }
+            UserIOEvent::ApplyMaterialPreset(graph, preset) => {
+                let mut changed = Vec::new();
+
+                if let Some(collection) = self.graphs.get_mut(graph.path_str().unwrap()) {
+                    for (param, data) in preset
+                        .iter()
+                        .filter(|(p, _)| &p.parameter_node().node_graph() == graph)
+                    {
+                        let old = match collection.parameter_value(param) {
+                            Some(old) => old,
+                            None => {
+                                log::warn!(
+                                    "Material preset references unknown parameter {}",
+                                    param
+                                );
+                                continue;
+                            }
+                        };
+
+                        if let Some(side_effect) = collection.parameter_change(param, data) {
+                            response.push(side_effect);
+                        }
+
+                        // Inform other components of the change. The node
+                        // manager does not receive its own events, so this
+                        // does not cause further recomputes.
+                        response.push(Lang::UserNodeEvent(UserNodeEvent::ParameterChange(
+                            param.clone(),
+                            old,
+                            data.clone(),
+                        )));
+                        changed.push(param.parameter_node());
+                    }
+                }
+
+                if !changed.is_empty() {
+                    let dirty_set = self.downstream_dirty_set(graph, &changed);
+                    self.relinearize_dirty(
+                        &mut response,
+                        graph,
+                        Some(&self.active_graph),
+                        dirty_set,
+                    );
+                }
+            }
             UserIOEvent::NewSurface => {
                 self.graphs.clear();
                 self.graphs.insert(
@@ -1102,23 +1538,32 @@ impl NodeManager {
             }
             UserIOEvent::NewExportSpec(new, keep_name) => {
                 let mut new = new.clone();
-
-                if let Some(out_ty) = self
-                    .graphs
-                    .get(new.node.node_graph().file().unwrap())
-                    .and_then(|graph| graph.output_type(&new.node))
-                {
+                new.sanitize();
+
+                // Both node graphs and layer stacks can be export sources,
+                // the collection is responsible for resolving the output type.
+                let out_ty = new
+                    .node
+                    .node_graph()
+                    .file()
+                    .and_then(|graph| self.graphs.get(graph))
+                    .and_then(|graph| graph.output_type(&new.node));
+
+                if let Some(out_ty) = out_ty {
                     if !keep_name {
                         new.name = out_ty.to_string();
                     }
                     self.export_specs.push(new.clone());
                     response.push(Lang::SurfaceEvent(SurfaceEvent::ExportSpecDeclared(new)));
+                } else {
+                    log::warn!("Cannot declare export spec for non-output {}", new.node);
                 }
             }
             UserIOEvent::UpdateExportSpec(name, new) => {
                 if let Some(idx) = self.export_specs.iter().position(|spec| &spec.name == name) {
                     let old = self.export_specs[idx].clone();
                     self.export_specs[idx] = new.clone();
+                    self.export_specs[idx].sanitize();
                     response.push(Lang::SurfaceEvent(SurfaceEvent::ExportSpecUpdated(
                         old,
                         self.export_specs[idx].clone(),
@@ -1139,42 +1584,54 @@ impl NodeManager {
                 response.push(Lang::GraphEvent(GraphEvent::Recompute(
                     self.active_graph.clone(),
                     Vec::new(),
+                    None,
                 )));
             }
             UserIOEvent::RunExports(base) => {
-                use itertools::Itertools;
+                self.run_exports(&mut response, |spec| {
+                    let mut path = base.clone();
+                    path.set_file_name(format!(
+                        "{}_{}.{}",
+                        path.file_name().unwrap().to_str().unwrap(),
+                        spec.name,
+                        spec.format.file_extension(),
+                    ));
+                    ExportTarget::File(path)
+                });
+            }
+            UserIOEvent::RunExportsZip(path) => {
+                if !self.export_specs.is_empty() {
+                    response.push(Lang::SurfaceEvent(SurfaceEvent::ArchiveExportStarted(
+                        path.clone(),
+                        self.export_specs.len(),
+                    )));
+                }
 
-                // Temporarily change parent size if required to meet export size.
-                let mut export_size_set = false;
-                let es = self.export_size.absolute(self.parent_size);
-                if es != self.parent_size {
-                    self.update_parent_size(&mut response, es, false, true);
-                    export_size_set = true;
+                self.run_exports(&mut response, |spec| {
+                    ExportTarget::Archive(
+                        path.clone(),
+                        format!("{}.{}", spec.name, spec.format.file_extension()),
+                    )
+                });
+            }
+            UserIOEvent::ExportAtlas(path, regions) => {
+                if !regions.is_empty() {
+                    response.push(Lang::SurfaceEvent(SurfaceEvent::AtlasExportStarted(
+                        path.clone(),
+                        regions.len(),
+                    )));
                 }
 
-                for (graph, export) in self
-                    .export_specs
+                let exports = regions
                     .iter()
-                    .map(|spec| {
-                        let mut path = base.clone();
-                        path.set_file_name(format!(
-                            "{}_{}.{}",
-                            path.file_name().unwrap().to_str().unwrap(),
-                            spec.name,
-                            spec.format.file_extension(),
-                        ));
-                        (spec.node.node_graph(), (spec.clone(), path))
+                    .map(|(node, region)| {
+                        (
+                            ExportSpec::from(node),
+                            ExportTarget::Atlas(path.clone(), *region),
+                        )
                     })
-                    .into_group_map()
-                    .drain()
-                {
-                    response.push(Lang::GraphEvent(GraphEvent::Recompute(graph, export)))
-                }
-
-                // Change back parent size if it was previously altered.
-                if export_size_set {
-                    self.update_parent_size(&mut response, self.parent_size, false, true);
-                }
+                    .collect();
+                self.export_to(&mut response, exports);
             }
             _ => {}
         }
@@ -1182,6 +1639,69 @@ impl NodeManager {
         Some(response)
     }
 
+    /// Push messages required to recompute all graphs with export
+    /// specifications, exporting each specification to the target given by
+    /// the supplied function.
+    fn run_exports<F: Fn(&ExportSpec) -> ExportTarget>(
+        &mut self,
+        response: &mut Vec<Lang>,
+        target: F,
+    ) {
+        let exports = self
+            .export_specs
+            .iter()
+            .map(|spec| (spec.clone(), target(spec)))
+            .collect();
+        self.export_to(response, exports);
+    }
+
+    /// Push messages required to recompute all graphs containing the given
+    /// exports, at export size.
+    fn export_to(&mut self, response: &mut Vec<Lang>, exports: Vec<(ExportSpec, ExportTarget)>) {
+        use itertools::Itertools;
+
+        // Temporarily change parent size if required to meet export size.
+        let mut export_size_set = false;
+        let es = self.export_size.absolute(self.parent_size);
+        if es != self.parent_size {
+            self.update_parent_size(response, es, false, true);
+            export_size_set = true;
+        }
+
+        for (graph, export) in exports
+            .into_iter()
+            .filter(|(spec, _)| {
+                let exportable = spec
+                    .node
+                    .node_graph()
+                    .file()
+                    .and_then(|graph| self.graphs.get(graph))
+                    .and_then(|graph| graph.output_type(&spec.node))
+                    .is_some();
+                if !exportable {
+                    log::warn!(
+                        "Skipping export of {}, {} has no output",
+                        spec.name,
+                        spec.node
+                    );
+                }
+                exportable
+            })
+            .map(|(spec, target)| (spec.node.node_graph(), (spec, target)))
+            .into_group_map()
+            .drain()
+        {
+            response.push(Lang::GraphEvent(GraphEvent::Recompute(graph, export, None)))
+        }
+
+        // Change back parent size if it was previously altered.
+        if export_size_set {
+            self.update_parent_size(response, self.parent_size, false, true);
+        }
+
+        response.push(Lang::SurfaceEvent(SurfaceEvent::ExportsQueued));
+    }
+
     // Push messages required for a parent size change. This does *not* change
     // the actual parent size data.
     fn update_parent_size(
@@ -1203,6 +1723,7 @@ impl NodeManager {
             response.push(Lang::GraphEvent(GraphEvent::Recompute(
                 self.active_graph.clone(),
                 Vec::new(),
+                None,
             )));
         }
     }
@@ -1231,6 +1752,10 @@ impl NodeManager {
                         instructions,
                         last_use,
                     )));
+                    response.push(Lang::GraphEvent(GraphEvent::ValidationReport(
+                        graph.graph_resource(),
+                        nodegraph::ValidationError::report(&graph.validate()),
+                    )));
                 }
             }
 
@@ -1268,28 +1793,62 @@ impl NodeManager {
         graph: &lang::Resource<lang::Graph>,
         recompute: Option<&lang::Resource<lang::Graph>>,
     ) {
-        if let Some(relin) = self
-            .graphs
-            .get(graph.path_str().unwrap())
-            .and_then(|g| g.linearize(LinearizationMode::TopoSort))
-            .map(|(instructions, last_use)| {
-                lang::Lang::GraphEvent(lang::GraphEvent::Relinearized(
-                    graph.clone(),
-                    instructions,
-                    last_use,
-                ))
-            })
-        {
-            response.push(relin);
+        self.relinearize_dirty(response, graph, recompute, None)
+    }
+
+    /// Run the linearization procedure on a graph like `relinearize`, passing
+    /// the given set of dirty nodes along with the recompute request.
+    fn relinearize_dirty(
+        &self,
+        response: &mut Vec<lang::Lang>,
+        graph: &lang::Resource<lang::Graph>,
+        recompute: Option<&lang::Resource<lang::Graph>>,
+        dirty_set: Option<HashSet<lang::Resource<lang::Node>>>,
+    ) {
+        let collection = match self.graphs.get(graph.path_str().unwrap()) {
+            Some(c) => c,
+            None => return,
+        };
+
+        if let Some((instructions, last_use)) = collection.linearize(LinearizationMode::TopoSort) {
+            response.push(Lang::GraphEvent(GraphEvent::Relinearized(
+                graph.clone(),
+                instructions,
+                last_use,
+            )));
+            response.push(Lang::GraphEvent(GraphEvent::ValidationReport(
+                graph.clone(),
+                nodegraph::ValidationError::report(&collection.validate()),
+            )));
             if let Some(g) = recompute {
                 response.push(Lang::GraphEvent(GraphEvent::Recompute(
                     g.clone(),
                     Vec::new(),
+                    dirty_set,
                 )));
             }
         }
     }
 
+    /// Determine the set of nodes downstream of the given sources, to be
+    /// passed along with a recompute of the active graph. This only applies
+    /// if the given graph is the active graph and a node graph, otherwise
+    /// there is no useful set to pass.
+    fn downstream_dirty_set(
+        &self,
+        graph: &lang::Resource<lang::Graph>,
+        sources: &[lang::Resource<lang::Node>],
+    ) -> Option<HashSet<lang::Resource<lang::Node>>> {
+        if graph != &self.active_graph {
+            return None;
+        }
+
+        match self.graphs.get(graph.path_str().unwrap())? {
+            ManagedNodeCollection::NodeGraph(g) => Some(g.subgraph_reachable_from(sources)),
+            _ => None,
+        }
+    }
+
     fn complete_operator(&self, op: &Operator) -> Operator {
         match op {
             lang::Operator::ComplexOperator(co) => {
@@ -1316,7 +1875,13 @@ impl Default for NodeManager {
 /// Designed to exist once in the system.
 pub fn start_nodes_thread(broker: &mut broker::Broker<lang::Lang>) -> thread::JoinHandle<()> {
     log::info!("Starting Node Manager");
-    let (sender, receiver, disconnector) = broker.subscribe("nodes");
+    let (sender, receiver, disconnector) = broker.subscribe_with_config(
+        "nodes",
+        broker::BrokerConfig {
+            high_water: 256,
+            drop_policy: broker::DropPolicy::DropOldest,
+        },
+    );
 
     thread::Builder::new()
         .name("nodes".to_string())
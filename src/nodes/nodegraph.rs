@@ -68,7 +68,7 @@ impl Node {
     }
 
     pub fn absolutely_sized(&self) -> bool {
-        matches!(self.size, OperatorSize::AbsoluteSize(..))
+        self.size.is_absolute()
     }
 }
 
@@ -90,8 +90,54 @@ pub enum NodeGraphError {
     SocketNotFound(String),
     #[error("Invalid connection")]
     InvalidConnection,
+    #[error("Connection would create a cycle")]
+    WouldCreateCycle,
     #[error("Monomorphization Error")]
     MonomorphizationError(#[from] MonomorphizationError),
+    #[error("Extraction Error: {0}")]
+    ExtractionError(#[from] ExtractionError),
+}
+
+/// Reasons for a selection of nodes not being extractable into a new graph.
+#[derive(Error, Debug)]
+pub enum ExtractionError {
+    #[error("Selected nodes do not form a connected subgraph")]
+    DisconnectedSubgraph,
+    #[error("Output socket {0} feeds more than one node outside the selection")]
+    SharedOutput(Resource<r::Socket>),
+}
+
+/// Problems found during validation of a node graph, referring to the
+/// offending sockets.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    #[error("Type mismatch between {0} and {1}")]
+    TypeMismatch(Resource<r::Socket>, Resource<r::Socket>),
+    #[error("Socket not found: {0}")]
+    SocketNotFound(Resource<r::Socket>),
+}
+
+impl ValidationError {
+    /// Obtain the sockets affected by this error.
+    pub fn sockets(&self) -> Vec<&Resource<r::Socket>> {
+        match self {
+            Self::TypeMismatch(source, sink) => vec![source, sink],
+            Self::SocketNotFound(socket) => vec![socket],
+        }
+    }
+
+    /// Convert validation errors into the nodes affected by them, along with
+    /// a description, for reporting.
+    pub fn report(errors: &[Self]) -> Vec<(Resource<r::Node>, String)> {
+        errors
+            .iter()
+            .flat_map(|e| {
+                e.sockets()
+                    .into_iter()
+                    .map(move |s| (s.socket_node(), e.to_string()))
+            })
+            .collect()
+    }
 }
 
 /// Container type for a node graph. Contains the actual graph, as well as
@@ -117,6 +163,79 @@ impl NodeGraph {
         }
     }
 
+    /// Produce a representation of the graph in Graphviz DOT format, for
+    /// debugging. Nodes are labelled with their name and operator, edges
+    /// with the connected sockets and the type of the source socket.
+    pub fn export_dot(&self) -> String {
+        use std::fmt::Write;
+
+        fn escape(s: &str) -> String {
+            s.replace('\\', "\\\\").replace('"', "\\\"")
+        }
+
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph \"{}\" {{", escape(&self.name)).unwrap();
+        writeln!(dot, "    node [shape=box];").unwrap();
+
+        for (name, idx) in self.indices.iter() {
+            let node = &self.graph[*idx];
+            writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\\n{}\"];",
+                escape(name),
+                escape(name),
+                escape(node.operator.title())
+            )
+            .unwrap();
+        }
+
+        for edge in self.graph.edge_references() {
+            let (source_socket, sink_socket) = edge.weight();
+            let source = self.indices.get_by_right(&edge.source()).unwrap();
+            let sink = self.indices.get_by_right(&edge.target()).unwrap();
+            let source_node = &self.graph[edge.source()];
+            let ty = match source_node
+                .operator
+                .monomorphic_type(source_socket, &source_node.type_variables)
+            {
+                Ok(t) => format!("{:?}", t),
+                Err(MonomorphizationError::PolymorphicSocket(v)) => {
+                    format!("{:?}", OperatorType::Polymorphic(v))
+                }
+                Err(_) => String::new(),
+            };
+
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{} -> {}\\n{}\"];",
+                escape(source),
+                escape(sink),
+                escape(source_socket),
+                escape(sink_socket),
+                ty
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Create a deep copy of this graph under a new name. Node resources are
+    /// derived from the graph name, such that only exposed parameters need to
+    /// be rewritten.
+    pub fn clone_as(&self, name: &str) -> Self {
+        let mut new = self.clone();
+        new.name = name.to_string();
+
+        for param in new.parameters.values_mut() {
+            param.parameter.set_graph(name);
+        }
+
+        new
+    }
+
     /// Obtain the resource corresponding to a node by graph index
     fn node_resource(&self, idx: &petgraph::graph::NodeIndex) -> Resource<r::Node> {
         Resource::node(
@@ -201,6 +320,36 @@ impl NodeGraph {
         (node_id, size)
     }
 
+    /// Duplicate the node with the given name, including its current parameter
+    /// values and size settings, placing the copy at the given position.
+    /// Connections are not duplicated. Returns the name of the new node, its
+    /// operator, and its absolute size.
+    ///
+    /// **Errors** if the node does not exist.
+    pub fn duplicate_node(
+        &mut self,
+        name: &str,
+        parent_size: u32,
+        position: (f64, f64),
+    ) -> Result<(String, Operator, u32), NodeGraphError> {
+        let idx = *self
+            .indices
+            .get_by_left(&name.to_string())
+            .ok_or_else(|| NodeGraphError::NodeNotFound(name.to_string()))?;
+        let source = self.graph.node_weight(idx).unwrap();
+        let op = source.operator.clone();
+        let op_size = source.size;
+
+        let (node_id, _) = self.new_node(&op, parent_size, None);
+        let new_idx = *self.indices.get_by_left(&node_id).unwrap();
+        let node = self.graph.node_weight_mut(new_idx).unwrap();
+        node.position = position;
+        node.size = op_size;
+        let size = node.node_size(parent_size);
+
+        Ok((node_id, op, size))
+    }
+
     /// Remove a node with the given Resource if it exists. Returns the node, a
     /// list of connections that have been removed, as well as a boolean
     /// determining whether the complex operators associated with this graph
@@ -409,6 +558,12 @@ impl NodeGraph {
             return Err(NodeGraphError::InvalidConnection);
         }
 
+        // Check that the connection does not close a cycle, i.e. the source
+        // is not reachable from the sink already
+        if petgraph::algo::has_path_connecting(&self.graph, sink_idx, source_idx, None) {
+            return Err(NodeGraphError::WouldCreateCycle);
+        }
+
         // Disconnect sink
         response.append(&mut self.disconnect_sink_socket(sink_node, sink_socket)?);
 
@@ -422,7 +577,7 @@ impl NodeGraph {
                 ));
             }
             (OperatorType::Monomorphic(ty1), OperatorType::Monomorphic(ty2)) => {
-                if ty1 != ty2 {
+                if !ty1.connects_to(ty2) {
                     return Err(NodeGraphError::ConnectionTypeError(
                         SocketTypeError::Mismatch,
                     ));
@@ -469,6 +624,48 @@ impl NodeGraph {
         Ok(response)
     }
 
+    /// Find all cycles in the graph, returned as the sets of nodes involved in
+    /// each. Connections closing a cycle are rejected, so this is empty for
+    /// all graphs built through `connect_sockets`, but graphs obtained
+    /// otherwise, e.g. from deserialization, may contain cycles.
+    pub fn find_cycles(&self) -> Vec<Vec<Resource<r::Node>>> {
+        petgraph::algo::tarjan_scc(&self.graph)
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|component| {
+                component
+                    .iter()
+                    .map(|idx| self.node_resource(idx))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Find all nodes reachable from the given source nodes by following
+    /// connections downstream, including the sources themselves. Sources not
+    /// in this graph are ignored.
+    pub fn subgraph_reachable_from(
+        &self,
+        sources: &[Resource<r::Node>],
+    ) -> HashSet<Resource<r::Node>> {
+        let graph = self.graph_resource();
+        let mut dfs = petgraph::visit::Dfs::empty(&self.graph);
+        dfs.stack.extend(
+            sources
+                .iter()
+                .filter(|res| res.is_node_of(&graph))
+                .filter_map(|res| self.indices.get_by_left(&res.file()?.to_string()))
+                .copied(),
+        );
+
+        let mut reachable = HashSet::new();
+        while let Some(idx) = dfs.next(&self.graph) {
+            reachable.insert(self.node_resource(&idx));
+        }
+
+        reachable
+    }
+
     /// Insert a node between two sockets.
     pub fn connect_between(
         &mut self,
@@ -686,7 +883,52 @@ impl NodeGraph {
         Ok(response)
     }
 
-    /// Attempt to automatically connect a socket to a node, on a best guess basis.
+    /// Find parameter substitutions of complex operators in this graph that
+    /// no longer correspond to a parameter of the given complex operator. Only
+    /// nodes calling the same graph as `co` are considered. Returns the stale
+    /// fields along with the node they were found on.
+    pub fn validate_parameter_substitutions(
+        &self,
+        co: &ComplexOperator,
+    ) -> Vec<(Resource<r::Node>, String)> {
+        self.graph
+            .node_indices()
+            .filter_map(|idx| match &self.graph.node_weight(idx).unwrap().operator {
+                Operator::ComplexOperator(complex) if complex.graph == co.graph => {
+                    Some((idx, complex))
+                }
+                _ => None,
+            })
+            .flat_map(|(idx, complex)| {
+                complex
+                    .parameters
+                    .keys()
+                    .filter(|field| !co.parameters.contains_key(*field))
+                    .map(move |field| (self.node_resource(&idx), field.clone()))
+            })
+            .collect()
+    }
+
+    /// Pick the socket to connect to from a list of compatible candidates.
+    /// When there is a choice, a socket with the same name as the socket
+    /// being connected is preferred, e.g. a `normal` output goes to a
+    /// `normal` input. Otherwise the first candidate is chosen.
+    fn best_socket_match(candidates: Vec<String>, other_socket: &str) -> Option<String> {
+        if candidates.len() > 1 {
+            if let Some(matching) = candidates
+                .iter()
+                .find(|s| s.eq_ignore_ascii_case(other_socket))
+            {
+                return Some(matching.clone());
+            }
+        }
+
+        candidates.into_iter().next()
+    }
+
+    /// Attempt to automatically connect a socket to a node, on a best guess
+    /// basis. Among all compatible sockets on the node, one with a matching
+    /// name is preferred.
     pub fn auto_connect(
         &mut self,
         node: &str,
@@ -723,18 +965,20 @@ impl NodeGraph {
         {
             // The socket is an input socket and thus to be used a sink. First
             // find a corresponding source on node
-            let socket = node_data
+            let candidates = node_data
                 .operator
                 .outputs()
                 .iter()
                 .sorted_by_key(|x| x.0)
-                .find_map(|(s, t)| {
+                .filter_map(|(s, t)| {
                     if t.can_unify_with(sink_ty, &node_data.type_variables, &other_tyvars) {
                         Some(s.clone())
                     } else {
                         None
                     }
                 })
+                .collect::<Vec<_>>();
+            let socket = Self::best_socket_match(candidates, other_socket)
                 .ok_or(NodeGraphError::InvalidConnection)?;
 
             // Perform connection
@@ -749,18 +993,20 @@ impl NodeGraph {
         {
             // The socket is an output socket and thus to be used a source.
             // First find a corresponding sink on node
-            let socket = node_data
+            let candidates = node_data
                 .operator
                 .inputs()
                 .iter()
                 .sorted_by_key(|x| x.0)
-                .find_map(|(s, (t, _))| {
+                .filter_map(|(s, (t, _))| {
                     if t.can_unify_with(source_ty, &node_data.type_variables, &other_tyvars) {
                         Some(s.clone())
                     } else {
                         None
                     }
                 })
+                .collect::<Vec<_>>();
+            let socket = Self::best_socket_match(candidates, other_socket)
                 .ok_or(NodeGraphError::InvalidConnection)?;
 
             // Perform connection
@@ -892,6 +1138,81 @@ impl NodeGraph {
         }
     }
 
+    /// Compute a layered layout for the whole graph and reposition all nodes
+    /// accordingly. Nodes are assigned to columns by their longest path from a
+    /// source in topological order, and ordered within each column by the
+    /// barycenter of their predecessors to reduce edge crossings.
+    ///
+    /// Returns position events for all nodes, such that other components can
+    /// update their positions.
+    pub fn auto_layout(&mut self) -> Vec<Lang> {
+        const COLUMN_SPACING: f64 = 192.;
+        const ROW_SPACING: f64 = 160.;
+
+        // Linearization only covers nodes reachable from outputs, so we sort
+        // the entire graph instead. Cycles can not occur in node graphs.
+        let order = match petgraph::algo::toposort(&self.graph, None) {
+            Ok(order) => order,
+            Err(_) => return Vec::new(),
+        };
+
+        // Layer assignment by longest path
+        let mut layers: HashMap<graph::NodeIndex, usize> = HashMap::new();
+        for idx in order.iter() {
+            let layer = self
+                .graph
+                .neighbors_directed(*idx, petgraph::Direction::Incoming)
+                .map(|pred| layers[&pred] + 1)
+                .max()
+                .unwrap_or(0);
+            layers.insert(*idx, layer);
+        }
+
+        let n_layers = layers.values().max().map(|x| x + 1).unwrap_or(0);
+        let mut columns: Vec<Vec<graph::NodeIndex>> = vec![Vec::new(); n_layers];
+        for idx in order.iter() {
+            columns[layers[idx]].push(*idx);
+        }
+
+        // Order nodes within columns and assign positions column by column
+        let mut rows: HashMap<graph::NodeIndex, f64> = HashMap::new();
+        for (x, column) in columns.iter_mut().enumerate() {
+            let barycenter = |idx: &graph::NodeIndex| {
+                let (sum, count) = self
+                    .graph
+                    .neighbors_directed(*idx, petgraph::Direction::Incoming)
+                    .fold((0., 0), |(s, c), pred| (s + rows[&pred], c + 1));
+                if count > 0 {
+                    sum / count as f64
+                } else {
+                    0.
+                }
+            };
+            column.sort_by(|a, b| {
+                barycenter(a)
+                    .partial_cmp(&barycenter(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+            let offset = (column.len() as f64 - 1.) / 2.;
+            for (y, idx) in column.iter().enumerate() {
+                rows.insert(*idx, y as f64 - offset);
+                let node = self.graph.node_weight_mut(*idx).unwrap();
+                node.position = (x as f64 * COLUMN_SPACING, (y as f64 - offset) * ROW_SPACING);
+            }
+        }
+
+        self.graph
+            .node_indices()
+            .map(|idx| {
+                Lang::UserNodeEvent(UserNodeEvent::PositionNode(
+                    self.node_resource(&idx),
+                    self.graph.node_weight(idx).unwrap().position,
+                ))
+            })
+            .collect()
+    }
+
     /// Rename a node from a given name to a new name.
     pub fn rename_node(&mut self, from: &str, to: &str) -> Option<Lang> {
         log::trace!("Renaming node {} to {}", from, to);
@@ -945,6 +1266,67 @@ impl NodeGraph {
         required_inputs.all(|s| connected_inputs.contains(&s))
     }
 
+    /// Merge another graph into this one, inserting all of its nodes and
+    /// connections as is. Nodes are renamed where their names conflict with
+    /// nodes already present. Exposed parameters are merged as well, with
+    /// conflicting fields suffixed by `_1`, `_2`, etc.
+    ///
+    /// Returns a mapping from the node resources in the other graph to the
+    /// node resources in this graph.
+    pub fn merge(&mut self, other: NodeGraph) -> HashMap<Resource<r::Node>, Resource<r::Node>> {
+        let mut mapping = HashMap::new();
+        let mut indices = HashMap::new();
+
+        for idx in other.graph.node_indices() {
+            let node = other.graph.node_weight(idx).unwrap().clone();
+            let old_name = other.indices.get_by_right(&idx).unwrap();
+            let new_name = if self.indices.contains_left(old_name) {
+                self.next_free_name(node.operator.default_name())
+            } else {
+                old_name.clone()
+            };
+
+            let new_idx = self.graph.add_node(node);
+            self.indices.insert(new_name, new_idx);
+            if other.outputs.contains(&idx) {
+                self.outputs.insert(new_idx);
+            }
+
+            indices.insert(idx, new_idx);
+            mapping.insert(other.node_resource(&idx), self.node_resource(&new_idx));
+        }
+
+        for edge in other.graph.edge_references() {
+            self.graph.add_edge(
+                indices[&edge.source()],
+                indices[&edge.target()],
+                edge.weight().clone(),
+            );
+        }
+
+        for (field, mut param) in other.parameters {
+            if let Some((new_node, fragment)) = mapping
+                .get(&param.parameter.parameter_node())
+                .zip(param.parameter.fragment())
+            {
+                param.parameter = new_node.node_parameter(fragment);
+            }
+
+            let mut new_field = field.clone();
+            for i in 1.. {
+                if !self.parameters.contains_key(&new_field) {
+                    break;
+                }
+                new_field = format!("{}_{}", field, i);
+            }
+
+            param.graph_field = new_field.clone();
+            self.parameters.insert(new_field, param);
+        }
+
+        mapping
+    }
+
     /// Extract the nodes determined by the iterator and construct a new graph
     /// from them. Edges going into or out of the subgraph will be terminated
     /// with inputs and outputs in the new graph respectively. Finally, the
@@ -955,6 +1337,10 @@ impl NodeGraph {
     /// transformation of *this* graph and construction of the *new* graph.
     ///
     /// The iterator is assumed to be nonempty!
+    ///
+    /// **Errors** without modifying the graph if the selection is not
+    /// connected, or if an output of a selected node feeds more than one node
+    /// outside the selection.
     pub fn extract<'a, I>(
         &mut self,
         name: &str,
@@ -968,6 +1354,8 @@ impl NodeGraph {
         let mut evs = vec![];
         let mut conns = vec![];
 
+        self.validate_extraction(nodes.clone())?;
+
         let mut complex_pos = (0., 0.);
         let nodes_count = nodes.clone().count() as f64;
 
@@ -1117,6 +1505,58 @@ impl NodeGraph {
         Ok((new, evs))
     }
 
+    /// Check whether the given nodes can be extracted into a new graph.
+    fn validate_extraction<'a, I>(&self, nodes: I) -> Result<(), NodeGraphError>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let selection = nodes
+            .map(|node| {
+                self.indices
+                    .get_by_left(&node.to_string())
+                    .copied()
+                    .ok_or_else(|| NodeGraphError::NodeNotFound(node.to_string()))
+            })
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        // Flood fill the selection, ignoring edge directions
+        let mut reached = HashSet::new();
+        let mut stack: Vec<_> = selection.iter().take(1).copied().collect();
+        while let Some(idx) = stack.pop() {
+            if reached.insert(idx) {
+                stack.extend(
+                    self.graph
+                        .neighbors_undirected(idx)
+                        .filter(|n| selection.contains(n) && !reached.contains(n)),
+                );
+            }
+        }
+
+        if reached.len() != selection.len() {
+            return Err(ExtractionError::DisconnectedSubgraph.into());
+        }
+
+        // Each outgoing socket may only be terminated by a single output
+        let mut external_outputs = HashSet::new();
+        for idx in selection.iter() {
+            for edge in self
+                .graph
+                .edges_directed(*idx, petgraph::Direction::Outgoing)
+            {
+                if selection.contains(&edge.target()) {
+                    continue;
+                }
+
+                let socket = self.node_resource(idx).node_socket(&edge.weight().0);
+                if !external_outputs.insert(socket.clone()) {
+                    return Err(ExtractionError::SharedOutput(socket).into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Inject the nodes in the given node if possible. For this to work the
     /// node has to contain a complex operator. Otherwise, this is a no
     /// operation but will not error.
@@ -1485,9 +1925,7 @@ impl NodeCollection for NodeGraph {
                                 creation: step,
                             });
 
-                        if let Some(thumbnail_output) =
-                            node.operator.outputs().keys().sorted().next()
-                        {
+                        for thumbnail_output in node.operator.outputs().keys().sorted() {
                             traversal
                                 .push(Instruction::Thumbnail(res.node_socket(thumbnail_output)));
                         }
@@ -1535,6 +1973,17 @@ impl NodeCollection for NodeGraph {
         None
     }
 
+    fn parameter_value(&self, resource: &Resource<Param>) -> Option<Vec<u8>> {
+        let res = resource.file()?;
+        let field = resource.fragment()?;
+
+        let node = self.indices.get_by_left(&res.to_string())?;
+        self.graph
+            .node_weight(*node)?
+            .operator
+            .parameter_value(field)
+    }
+
     /// Update all the complex operators matching a call to the old graph.
     /// Returns a vector of all node resources that have been updated.
     fn update_complex_operators(
@@ -1583,12 +2032,37 @@ impl NodeCollection for NodeGraph {
                         subs.resource_mut().set_graph(new.graph.path())
                     }
 
-                    let params = complex.parameters.clone();
-                    updated.push((self.node_resource(&idx), params));
+                    updated.push(idx);
                 }
             }
         }
 
+        // Purge substitutions that no longer correspond to any exposed
+        // parameter, e.g. after renaming a parameter in the called graph.
+        for (node, field) in self.validate_parameter_substitutions(new) {
+            let idx = *self
+                .indices
+                .get_by_left(&node.file().unwrap().to_string())
+                .unwrap();
+            if let Operator::ComplexOperator(complex) =
+                &mut self.graph.node_weight_mut(idx).unwrap().operator
+            {
+                complex.parameters.remove(&field);
+            }
+            evs.push(GraphEvent::StaleParameterRemoved(node, field));
+        }
+
+        let updated = updated
+            .drain(0..)
+            .map(|idx| {
+                let params = match &self.graph.node_weight(idx).unwrap().operator {
+                    Operator::ComplexOperator(complex) => complex.parameters.clone(),
+                    _ => HashMap::new(),
+                };
+                (self.node_resource(&idx), params)
+            })
+            .collect();
+
         (updated, evs)
     }
 
@@ -1673,6 +2147,75 @@ impl NodeCollection for NodeGraph {
         events
     }
 
+    fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for edge in self.graph.edge_references() {
+            let source = self.graph.node_weight(edge.source()).unwrap();
+            let sink = self.graph.node_weight(edge.target()).unwrap();
+            let (source_socket, sink_socket) = edge.weight();
+            let source_res = self
+                .node_resource(&edge.source())
+                .node_socket(source_socket);
+            let sink_res = self.node_resource(&edge.target()).node_socket(sink_socket);
+
+            let source_ty = source.operator.outputs().get(source_socket).copied();
+            let sink_ty = sink.operator.inputs().get(sink_socket).map(|x| x.0);
+
+            match (source_ty, sink_ty) {
+                (Some(source_ty), Some(sink_ty)) => {
+                    if !source_ty.can_unify_with(
+                        &sink_ty,
+                        &source.type_variables,
+                        &sink.type_variables,
+                    ) {
+                        errors.push(ValidationError::TypeMismatch(source_res, sink_res));
+                    }
+                }
+                (None, _) => errors.push(ValidationError::SocketNotFound(source_res)),
+                (_, None) => errors.push(ValidationError::SocketNotFound(sink_res)),
+            }
+        }
+
+        errors
+    }
+
+    fn find_nodes(&self, query: &NodeQuery) -> Vec<Resource<r::Node>> {
+        self.graph
+            .node_indices()
+            .map(|idx| (self.node_resource(&idx), idx))
+            .filter(|(res, idx)| {
+                query.matches(res, &self.graph.node_weight(*idx).unwrap().operator)
+            })
+            .map(|(res, _)| res)
+            .collect()
+    }
+
+    fn statistics(&self) -> GraphStatistics {
+        // Longest path by dynamic programming over the topological order
+        let mut depths: HashMap<graph::NodeIndex, usize> = HashMap::new();
+        if let Ok(order) = petgraph::algo::toposort(&self.graph, None) {
+            for idx in order.iter() {
+                let depth = self
+                    .graph
+                    .neighbors_directed(*idx, petgraph::Direction::Incoming)
+                    .map(|pred| depths[&pred] + 1)
+                    .max()
+                    .unwrap_or(1);
+                depths.insert(*idx, depth);
+            }
+        }
+
+        GraphStatistics {
+            node_count: self.graph.node_count(),
+            edge_count: self.graph.edge_count(),
+            max_depth: depths.values().copied().max().unwrap_or(0),
+            operator_histogram: super::operator_histogram(
+                self.graph.raw_nodes().iter().map(|n| &n.weight.operator),
+            ),
+        }
+    }
+
     fn element_param_box(
         &self,
         element: &Resource<r::Node>,
@@ -0,0 +1,60 @@
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FrameExportError {
+    #[error("Failed to download render result")]
+    Download(#[from] crate::gpu::DownloadError),
+    #[error("Failed to write PNG file")]
+    Png(#[from] image::ImageError),
+    #[error("Failed to write OpenEXR file")]
+    Exr(#[from] exr::error::Error),
+    #[error("No renderer with the given ID")]
+    UnknownRenderer,
+}
+
+/// Save a downloaded frame to the given path. Frames are stored as raw RGBA
+/// data in half precision. Paths with an `exr` extension are written as
+/// OpenEXR files, everything else as 16 bit PNG.
+///
+/// The frame holds display referred values, after tone mapping, such that the
+/// file looks the same as the viewport.
+pub fn save_frame<P: AsRef<Path>>(
+    path: P,
+    width: u32,
+    height: u32,
+    raw: &[u8],
+) -> Result<(), FrameExportError> {
+    #[allow(clippy::cast_ptr_alignment)]
+    let data =
+        unsafe { std::slice::from_raw_parts(raw.as_ptr() as *const half::f16, raw.len() / 2) };
+
+    let is_exr = path
+        .as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("exr"))
+        .unwrap_or(false);
+
+    if is_exr {
+        exr::prelude::write_rgba_file(path, width as usize, height as usize, |x, y| {
+            let px = &data[(y * width as usize + x) * 4..];
+            (
+                px[0].to_f32(),
+                px[1].to_f32(),
+                px[2].to_f32(),
+                px[3].to_f32(),
+            )
+        })?;
+    } else {
+        let quantized: Vec<u16> = data
+            .iter()
+            .map(|x| (x.to_f32().clamp(0., 1.) * 65535.) as u16)
+            .collect();
+        image::ImageBuffer::<image::Rgba<u16>, _>::from_raw(width, height, quantized)
+            .expect("Frame data does not match frame size")
+            .save(path)?;
+    }
+
+    Ok(())
+}
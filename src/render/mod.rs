@@ -3,10 +3,15 @@ use smallvec::SmallVec;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use strum::IntoEnumIterator;
 
+mod frame;
+
+pub use frame::FrameExportError;
+
 const DEFAULT_SAMPLES: usize = 24;
+const MAX_ADAPTIVE_SAMPLES: usize = 256;
 const DEFAULT_IMAGE_SIZE: u32 = 1024;
 const TIMING_DECAY: f64 = 0.85;
 
@@ -14,14 +19,34 @@ const TIMING_DECAY: f64 = 0.85;
 pub fn start_render_thread<B: gpu::Backend>(
     broker: &mut broker::Broker<Lang>,
     gpu: Arc<Mutex<gpu::GPU<B>>>,
+    config: &config::Configuration,
 ) -> thread::JoinHandle<()> {
-    let (sender, receiver, disconnector) = broker.subscribe("render");
+    let sample_budget = Duration::from_millis(config.sample_budget_ms);
+    let sampling_sequence = config.sampling_sequence;
+    let default_assets = gpu::render::DefaultAssets {
+        hdri: config.hdri_default_path.clone(),
+        matcap: config.matcap_default_path.clone(),
+    };
+    let (sender, receiver, disconnector) = broker.subscribe_filtered("render", |ev| {
+        matches!(
+            ev,
+            Lang::UserIOEvent(..)
+                | Lang::IOEvent(..)
+                | Lang::UIEvent(..)
+                | Lang::GraphEvent(..)
+                | Lang::SurfaceEvent(..)
+                | Lang::ComputeEvent(..)
+                | Lang::UserRenderEvent(..)
+                | Lang::UserNodeEvent(UserNodeEvent::ViewSocket(..))
+        )
+    });
     thread::Builder::new()
         .name("render".to_string())
         .spawn(move || {
             log::info!("Starting Renderer");
 
-            let mut render_manager = RenderManager::new(gpu);
+            let mut render_manager =
+                RenderManager::new(gpu, sample_budget, sampling_sequence, default_assets);
 
             loop {
                 let res = if let Ok(message) = receiver.try_recv() {
@@ -117,6 +142,22 @@ where
         .expect("Rendering failed")
     }
 
+    /// Take the GPU time of the last frame from the contained renderer
+    pub fn take_frame_time(&mut self) -> Option<std::time::Duration> {
+        match self {
+            ManagedRenderer::RendererSDF3D(r) => r.take_frame_time(),
+            ManagedRenderer::Renderer2D(r) => r.take_frame_time(),
+        }
+    }
+
+    /// Download the current render result from the contained renderer
+    pub fn download_accum(&mut self) -> Result<(u32, u32, Vec<u8>), gpu::DownloadError> {
+        match self {
+            ManagedRenderer::RendererSDF3D(r) => r.download_accum(),
+            ManagedRenderer::Renderer2D(r) => r.download_accum(),
+        }
+    }
+
     /// Obtain the render target view from the contained renderer
     pub fn target_view(&self) -> &Arc<Mutex<B::ImageView>> {
         match self {
@@ -173,36 +214,62 @@ where
     }
 }
 
+/// How the number of samples taken by a renderer is determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleMode {
+    /// Take as many samples as fit into the sample budget, given the measured
+    /// frame times
+    Adaptive(Duration),
+    /// Take a fixed number of samples
+    Fixed(usize),
+    /// Keep sampling indefinitely
+    Unlimited,
+}
+
 /// A renderer contains a managed renderer, which is the GPU side component, as
 /// well as extra information such as samples left to go and frame timings for
 /// statistics output.
 struct Renderer<B: gpu::Backend> {
     gpu: ManagedRenderer<B>,
     samples_to_go: usize,
-    max_samples: usize,
+    sample_mode: SampleMode,
     frametime_ema: EMA<f64>,
 }
 
 impl<B: gpu::Backend> Renderer<B> {
     /// Create a new renderer, given a managed renderer.
-    pub fn new(gpu: ManagedRenderer<B>, max_samples: usize) -> Self {
+    pub fn new(gpu: ManagedRenderer<B>, sample_mode: SampleMode) -> Self {
         Self {
             gpu,
             samples_to_go: 0,
-            max_samples,
+            sample_mode,
             frametime_ema: EMA::new(TIMING_DECAY),
         }
     }
 
     /// Reset the sampling of this renderer
     pub fn reset_sampling(&mut self) {
-        self.samples_to_go = self.max_samples;
+        self.samples_to_go = self.max_samples();
         self.gpu.reset_sampling();
     }
 
-    /// Get the renderer's max samples.
+    /// Get the renderer's max samples. In adaptive mode this is derived from
+    /// the frame time EMA, falling back to a default before any frames have
+    /// been timed.
     fn max_samples(&self) -> usize {
-        self.max_samples
+        match self.sample_mode {
+            SampleMode::Adaptive(budget) => {
+                let frametime = self.frametime_ema.get();
+                if frametime.is_finite() && frametime > 0. {
+                    ((budget.as_micros() as f64 / frametime) as usize)
+                        .clamp(1, MAX_ADAPTIVE_SAMPLES)
+                } else {
+                    DEFAULT_SAMPLES
+                }
+            }
+            SampleMode::Fixed(samples) => samples,
+            SampleMode::Unlimited => usize::MAX,
+        }
     }
 }
 
@@ -234,6 +301,9 @@ struct RenderManager<B: gpu::Backend> {
     gpu: Arc<Mutex<gpu::GPU<B>>>,
     image_slots: gpu::render::ImageSlots<B>,
     renderers: HashMap<RendererID, Renderer<B>>,
+    sample_budget: Duration,
+    sampling_sequence: SamplingSequence,
+    default_assets: gpu::render::DefaultAssets,
 }
 
 impl<B> RenderManager<B>
@@ -241,13 +311,21 @@ where
     B: gpu::Backend,
 {
     /// Spawn a new render manager. No renderers will be registered after creation.
-    pub fn new(gpu: Arc<Mutex<gpu::GPU<B>>>) -> Self {
+    pub fn new(
+        gpu: Arc<Mutex<gpu::GPU<B>>>,
+        sample_budget: Duration,
+        sampling_sequence: SamplingSequence,
+        default_assets: gpu::render::DefaultAssets,
+    ) -> Self {
         let image_slots = gpu::render::ImageSlots::new(gpu.clone(), DEFAULT_IMAGE_SIZE)
             .expect("Failed to build image slots");
         RenderManager {
             gpu,
             image_slots,
             renderers: HashMap::new(),
+            sample_budget,
+            sampling_sequence,
+            default_assets,
         }
     }
 
@@ -277,6 +355,11 @@ where
             }
         }
 
+        response.extend(self.renderers.iter_mut().filter_map(|(id, r)| {
+            r.take_frame_time()
+                .map(|t| Lang::RenderEvent(RenderEvent::FrameTiming(*id, t)))
+        }));
+
         Some(response)
     }
 
@@ -295,14 +378,9 @@ where
                 );
             }
             Lang::IOEvent(IOEvent::RenderSettingsLoaded(data)) => {
-                self.renderers
-                    .values_mut()
-                    .next()?
-                    .deserialize_settings(data)
-                    .ok()?;
-                let id = *self.renderers.keys().next()?;
-                let pbox = self.parameter_box(id)?;
-                response.push(Lang::RenderEvent(RenderEvent::SettingsUpdated(id, pbox)));
+                if let Some(id) = self.renderers.keys().next().copied() {
+                    response.extend(self.load_settings(id, data));
+                }
             }
             Lang::UserIOEvent(UserIOEvent::SaveSurface(..)) => {
                 let data = self.renderers.values().next()?.serialize_settings().ok()?;
@@ -346,9 +424,21 @@ where
                 access,
                 size,
                 out_ty,
-            )) => self.transfer_output(img, *layout, *access, *size as i32, *out_ty),
-            Lang::ComputeEvent(ComputeEvent::SocketViewReady(img, layout, access, size, ty)) => {
+                receipt,
+            )) => {
+                self.transfer_output(img, *layout, *access, *size as i32, *out_ty);
+                receipt.acknowledge();
+            }
+            Lang::ComputeEvent(ComputeEvent::SocketViewReady(
+                img,
+                layout,
+                access,
+                size,
+                ty,
+                receipt,
+            )) => {
                 self.transfer_socket_view(img, *layout, *access, *size as i32, *ty);
+                receipt.acknowledge();
             }
             Lang::UserNodeEvent(UserNodeEvent::ViewSocket(None)) => {
                 self.disconnect_image(gpu::render::ImageUse::View(ImageType::Grayscale));
@@ -414,6 +504,16 @@ where
                 self.redraw(*id);
                 response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
             }
+            Lang::UserRenderEvent(UserRenderEvent::BackgroundColor(id, color)) => {
+                self.set_background_color(*id, *color);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::ShowHdriBackground(id, show)) => {
+                self.set_show_hdri_background(*id, *show);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
             Lang::UserRenderEvent(UserRenderEvent::DisplacementAmount(id, displ)) => {
                 self.set_displacement_amount(*id, *displ);
                 self.redraw(*id);
@@ -454,11 +554,36 @@ where
                 self.redraw(*id);
                 response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
             }
+            Lang::UserRenderEvent(UserRenderEvent::AddLight(id)) => {
+                self.add_light(*id);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::RemoveLight(id, index)) => {
+                self.remove_light(*id, *index);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::UpdateLight(id, index, light)) => {
+                self.update_light(*id, *index, *light);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
             Lang::UserRenderEvent(UserRenderEvent::FogStrength(id, strength)) => {
                 self.set_fog_strength(*id, *strength);
                 self.redraw(*id);
                 response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
             }
+            Lang::UserRenderEvent(UserRenderEvent::SssColor(id, color)) => {
+                self.set_sss_color(*id, *color);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::SssStrength(id, strength)) => {
+                self.set_sss_strength(*id, *strength);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
             Lang::UserRenderEvent(UserRenderEvent::SetShadow(id, shadow)) => {
                 self.set_shadow(*id, *shadow);
                 self.redraw(*id);
@@ -469,6 +594,36 @@ where
                 self.redraw(*id);
                 response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
             }
+            Lang::UserRenderEvent(UserRenderEvent::WireframeOverlay(id, wireframe)) => {
+                self.set_wireframe(*id, *wireframe);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::WireWidth(id, wire_width)) => {
+                self.set_wire_width(*id, *wire_width);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::CapsuleRadius(id, radius)) => {
+                self.set_capsule_radius(*id, *radius);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::CapsuleLength(id, length)) => {
+                self.set_capsule_length(*id, *length);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::ConeHeight(id, height)) => {
+                self.set_cone_height(*id, *height);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
+            Lang::UserRenderEvent(UserRenderEvent::ConeAngle(id, angle)) => {
+                self.set_cone_angle(*id, *angle);
+                self.redraw(*id);
+                response.push(Lang::RenderEvent(RenderEvent::RendererRedrawn(*id)));
+            }
             Lang::UserRenderEvent(UserRenderEvent::LoadHdri(id, Some(path))) => {
                 self.load_hdri(*id, path);
                 self.redraw(*id);
@@ -506,10 +661,28 @@ where
             }
             Lang::UserRenderEvent(UserRenderEvent::SampleCount(id, samples)) => {
                 self.set_sample_count(*id, *samples as usize);
+                if let Some(pbox) = self.parameter_box(*id) {
+                    response.push(Lang::RenderEvent(RenderEvent::SettingsUpdated(*id, pbox)));
+                }
+            }
+            Lang::UserRenderEvent(UserRenderEvent::AdaptiveSamples(id, adaptive)) => {
+                self.set_adaptive_samples(*id, *adaptive);
+                if let Some(pbox) = self.parameter_box(*id) {
+                    response.push(Lang::RenderEvent(RenderEvent::SettingsUpdated(*id, pbox)));
+                }
             }
             Lang::UserRenderEvent(UserRenderEvent::CenterCamera(id)) => {
                 self.center_camera(*id);
             }
+            Lang::UserRenderEvent(UserRenderEvent::ExportFrame(id, path)) => {
+                match self.export_frame(*id, path) {
+                    Ok(()) => response.push(Lang::RenderEvent(RenderEvent::FrameExported(
+                        *id,
+                        path.clone(),
+                    ))),
+                    Err(e) => log::error!("Failed to export frame: {}", e),
+                }
+            }
             _ => {}
         }
 
@@ -530,10 +703,12 @@ where
                         &self.gpu,
                         monitor_dimensions,
                         viewport_dimensions,
+                        self.sampling_sequence,
+                        &self.default_assets,
                     )
                     .map_err(|e| format!("{:?}", e))?,
                 ),
-                DEFAULT_SAMPLES,
+                SampleMode::Adaptive(self.sample_budget),
             ),
             RendererType::Renderer2D => Renderer::new(
                 ManagedRenderer::Renderer2D(
@@ -541,10 +716,12 @@ where
                         &self.gpu,
                         monitor_dimensions,
                         viewport_dimensions,
+                        self.sampling_sequence,
+                        &self.default_assets,
                     )
                     .map_err(|e| format!("{:?}", e))?,
                 ),
-                1,
+                SampleMode::Fixed(1),
             ),
         };
 
@@ -559,6 +736,25 @@ where
         Ok(view)
     }
 
+    /// Load serialized settings into the given renderer. On success, the
+    /// updated parameter box is returned in a `SettingsUpdated` event, such
+    /// that the UI can reflect the loaded values. Failure to deserialize is
+    /// logged and leaves the renderer untouched.
+    fn load_settings(&mut self, id: RendererID, data: &[u8]) -> Option<Lang> {
+        let renderer = self.renderers.get_mut(&id)?;
+
+        if let Err(e) = renderer.deserialize_settings(data) {
+            log::error!("Failed to load render settings: {}", e);
+            return None;
+        }
+
+        renderer.reset_sampling();
+        self.redraw(id);
+
+        let pbox = self.parameter_box(id)?;
+        Some(Lang::RenderEvent(RenderEvent::SettingsUpdated(id, pbox)))
+    }
+
     pub fn parameter_box(&self, id: RendererID) -> Option<ParamBoxDescription<RenderField>> {
         use crate::lang::parameters::*;
 
@@ -571,18 +767,33 @@ where
                 name: "renderer",
                 is_open: true,
                 visibility: VisibilityFunction::default(),
-                parameters: vec![Parameter {
-                    name: "sample-count".to_string(),
-                    control: Control::DiscreteSlider {
-                        value: renderer.max_samples() as i32,
-                        min: 0,
-                        max: 256,
+                parameters: vec![
+                    Parameter {
+                        name: "adaptive-samples".to_string(),
+                        control: Control::Toggle {
+                            def: matches!(renderer.sample_mode, SampleMode::Adaptive(..)),
+                        },
+                        transmitter: RenderField::AdaptiveSamples,
+                        expose_status: None,
+                        visibility: VisibilityFunction::default(),
+                        presetable: false,
+                    },
+                    Parameter {
+                        name: "sample-count".to_string(),
+                        control: Control::DiscreteSlider {
+                            value: match renderer.sample_mode {
+                                SampleMode::Unlimited => 0,
+                                _ => renderer.max_samples() as i32,
+                            },
+                            min: 0,
+                            max: 256,
+                        },
+                        transmitter: RenderField::SampleCount,
+                        expose_status: None,
+                        visibility: VisibilityFunction::default(),
+                        presetable: false,
                     },
-                    transmitter: RenderField::SampleCount,
-                    expose_status: None,
-                    visibility: VisibilityFunction::default(),
-                    presetable: false,
-                }],
+                ],
             }],
         });
 
@@ -625,9 +836,28 @@ where
 
     pub fn set_sample_count(&mut self, renderer_id: RendererID, samples: usize) {
         if let Some(r) = self.renderers.get_mut(&renderer_id) {
-            let previous = r.max_samples;
-            r.max_samples = if samples == 0 { usize::MAX } else { samples };
-            r.samples_to_go = r.max_samples.saturating_sub(previous);
+            let previous = r.max_samples();
+            r.sample_mode = if samples == 0 {
+                SampleMode::Unlimited
+            } else {
+                SampleMode::Fixed(samples)
+            };
+            r.samples_to_go = r.max_samples().saturating_sub(previous);
+        }
+    }
+
+    /// Switch between adapting the sample count to the sample budget and
+    /// keeping the current sample count fixed.
+    pub fn set_adaptive_samples(&mut self, renderer_id: RendererID, adaptive: bool) {
+        let sample_budget = self.sample_budget;
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            let previous = r.max_samples();
+            r.sample_mode = match (adaptive, r.sample_mode) {
+                (true, _) => SampleMode::Adaptive(sample_budget),
+                (false, SampleMode::Adaptive(..)) => SampleMode::Fixed(previous),
+                (false, mode) => mode,
+            };
+            r.samples_to_go = r.max_samples().saturating_sub(previous);
         }
     }
 
@@ -757,6 +987,13 @@ where
         }
     }
 
+    pub fn set_show_hdri_background(&mut self, renderer_id: RendererID, show: bool) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_2d(|r| r.set_show_hdri_background(show));
+            r.reset_sampling();
+        }
+    }
+
     pub fn switch_object_type(&mut self, renderer_id: RendererID, object_type: ObjectType) {
         if let Some(r) = self.renderers.get_mut(&renderer_id) {
             r.update_sdf3d(|r| {
@@ -787,6 +1024,19 @@ where
         }
     }
 
+    pub fn set_background_color(&mut self, renderer_id: RendererID, color: [f32; 4]) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            let [red, green, blue, alpha] = color;
+            match &mut r.gpu {
+                ManagedRenderer::RendererSDF3D(x) => {
+                    x.set_background_color(red, green, blue, alpha)
+                }
+                ManagedRenderer::Renderer2D(x) => x.set_background_color(red, green, blue, alpha),
+            }
+            r.reset_sampling();
+        }
+    }
+
     pub fn set_displacement_amount(&mut self, renderer_id: RendererID, displacement: f32) {
         if let Some(r) = self.renderers.get_mut(&renderer_id) {
             r.update_sdf3d(|r| r.set_displacement_amount(displacement));
@@ -822,6 +1072,27 @@ where
         }
     }
 
+    pub fn add_light(&mut self, renderer_id: RendererID) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.add_light());
+            r.reset_sampling();
+        }
+    }
+
+    pub fn remove_light(&mut self, renderer_id: RendererID, index: usize) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.remove_light(index));
+            r.reset_sampling();
+        }
+    }
+
+    pub fn update_light(&mut self, renderer_id: RendererID, index: usize, light: Light) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.update_light(index, light));
+            r.reset_sampling();
+        }
+    }
+
     pub fn set_fog_strength(&mut self, renderer_id: RendererID, strength: f32) {
         if let Some(r) = self.renderers.get_mut(&renderer_id) {
             r.update_sdf3d(|r| r.set_fog_strength(strength));
@@ -829,6 +1100,20 @@ where
         }
     }
 
+    pub fn set_sss_color(&mut self, renderer_id: RendererID, color: [f32; 3]) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.set_sss_color(color));
+            r.reset_sampling();
+        }
+    }
+
+    pub fn set_sss_strength(&mut self, renderer_id: RendererID, strength: f32) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.set_sss_strength(strength));
+            r.reset_sampling();
+        }
+    }
+
     pub fn set_environment_strength(&mut self, renderer_id: RendererID, strength: f32) {
         if let Some(r) = self.renderers.get_mut(&renderer_id) {
             r.update_sdf3d(|r| r.set_environment_strength(strength));
@@ -864,6 +1149,48 @@ where
         }
     }
 
+    pub fn set_wireframe(&mut self, renderer_id: RendererID, wireframe: ParameterBool) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.set_wireframe(wireframe));
+            r.reset_sampling();
+        }
+    }
+
+    pub fn set_wire_width(&mut self, renderer_id: RendererID, wire_width: f32) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.set_wire_width(wire_width));
+            r.reset_sampling();
+        }
+    }
+
+    pub fn set_capsule_radius(&mut self, renderer_id: RendererID, radius: f32) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.set_capsule_radius(radius));
+            r.reset_sampling();
+        }
+    }
+
+    pub fn set_capsule_length(&mut self, renderer_id: RendererID, length: f32) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.set_capsule_length(length));
+            r.reset_sampling();
+        }
+    }
+
+    pub fn set_cone_height(&mut self, renderer_id: RendererID, height: f32) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.set_cone_height(height));
+            r.reset_sampling();
+        }
+    }
+
+    pub fn set_cone_angle(&mut self, renderer_id: RendererID, angle: f32) {
+        if let Some(r) = self.renderers.get_mut(&renderer_id) {
+            r.update_sdf3d(|r| r.set_cone_angle(angle));
+            r.reset_sampling();
+        }
+    }
+
     pub fn set_focal_length(&mut self, renderer_id: RendererID, focal_length: f32) {
         if let Some(r) = self.renderers.get_mut(&renderer_id) {
             r.update_sdf3d(|r| r.set_focal_length(focal_length));
@@ -917,10 +1244,24 @@ where
         }
     }
 
+    /// Save the current render result of the given renderer to a file.
+    pub fn export_frame<P: AsRef<std::path::Path>>(
+        &mut self,
+        renderer_id: RendererID,
+        path: P,
+    ) -> Result<(), FrameExportError> {
+        let r = self
+            .renderers
+            .get_mut(&renderer_id)
+            .ok_or(FrameExportError::UnknownRenderer)?;
+        let (width, height, raw) = r.download_accum()?;
+        frame::save_frame(path, width, height, &raw)
+    }
+
     pub fn center_camera(&mut self, renderer_id: RendererID) {
         if let Some(r) = self.renderers.get_mut(&renderer_id) {
             match &mut r.gpu {
-                ManagedRenderer::RendererSDF3D(r) => r.set_center(0., 0.),
+                ManagedRenderer::RendererSDF3D(r) => r.center_camera(),
                 ManagedRenderer::Renderer2D(r) => r.set_center(0., 0.),
             };
             r.reset_sampling();
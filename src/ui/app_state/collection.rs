@@ -15,8 +15,8 @@ pub trait Collection {
     fn collection_parameters(&mut self) -> &mut ParamBoxDescription<GraphField>;
     fn expose_parameter(&mut self, param: GraphParameter);
     fn conceal_parameter(&mut self, field: &str);
-    fn register_thumbnail(&mut self, node: &Resource<r::Node>, thumbnail: image::Id);
-    fn unregister_thumbnail(&mut self, node: &Resource<r::Node>) -> Option<image::Id>;
+    fn register_thumbnail(&mut self, socket: &Resource<r::Socket>, thumbnail: image::Id);
+    fn unregister_thumbnail(&mut self, socket: &Resource<r::Socket>) -> Option<image::Id>;
     fn update_complex_operator(
         &mut self,
         node: &Resource<r::Node>,
@@ -292,13 +292,30 @@ impl NodeCollections {
                 .layers
                 .remove_node(n, DropChildren)
                 .expect("Removal unsuccessful");
+            target.selection.retain(|l| l != layer_res);
         }
     }
 
     /// Reposition a layer in the stack
-    pub fn position_layer(&mut self, layer: &Resource<Node>, pos: &LayerDropTarget) {
+    pub fn position_layer(&mut self, layer: &Resource<Node>, index: usize) {
         if let Some(target) = self.target_layers_from_node(&layer) {
-            target.position_layer(layer, pos);
+            target.position_layer(layer, index);
+        }
+    }
+
+    /// Group layers in a layer stack. This is a NOP if the parent graph is not
+    /// a layer stack.
+    pub fn group_layers(&mut self, group: layers::Layer, members: &[Resource<Node>]) {
+        if let Some(target) = self.target_layers_from_node(&group.resource) {
+            target.group_layers(group, members);
+        }
+    }
+
+    /// Dissolve a group in a layer stack. This is a NOP if the parent graph is
+    /// not a layer stack.
+    pub fn ungroup_layers(&mut self, group: &Resource<Node>) {
+        if let Some(target) = self.target_layers_from_node(group) {
+            target.ungroup_layers(group);
         }
     }
 
@@ -432,21 +449,21 @@ impl NodeCollections {
         }
     }
 
-    /// Register a thumbnail for a given "node". This works for both graphs and
-    /// layers. In the case of a layer, the node is a layer, according to the
-    /// resource scheme used for layers.
-    pub fn register_thumbnail(&mut self, node: &Resource<r::Node>, thumbnail: image::Id) {
-        if let Some(target) = self.target_collection_from_node(node) {
-            target.register_thumbnail(node, thumbnail);
+    /// Register a thumbnail for a given socket of a "node". This works for
+    /// both graphs and layers. In the case of a layer, the node is a layer,
+    /// according to the resource scheme used for layers.
+    pub fn register_thumbnail(&mut self, socket: &Resource<r::Socket>, thumbnail: image::Id) {
+        if let Some(target) = self.target_collection_from_node(&socket.socket_node()) {
+            target.register_thumbnail(socket, thumbnail);
         }
     }
 
-    /// Unregister a thumbnail for a given "node". This works for both graphs and
-    /// layers. In the case of a layer, the node is a layer, according to the
-    /// resource scheme used for layers.
-    pub fn unregister_thumbnail(&mut self, node: &Resource<r::Node>) -> Option<image::Id> {
-        if let Some(target) = self.target_collection_from_node(node) {
-            target.unregister_thumbnail(node)
+    /// Unregister a thumbnail for a given socket of a "node". This works for
+    /// both graphs and layers. In the case of a layer, the node is a layer,
+    /// according to the resource scheme used for layers.
+    pub fn unregister_thumbnail(&mut self, socket: &Resource<r::Socket>) -> Option<image::Id> {
+        if let Some(target) = self.target_collection_from_node(&socket.socket_node()) {
+            target.unregister_thumbnail(socket)
         } else {
             None
         }
@@ -459,6 +476,35 @@ impl NodeCollections {
         }
     }
 
+    /// Update the position of a node when set from outside the UI
+    pub fn update_node_position(&mut self, node: &Resource<r::Node>, position: (f64, f64)) {
+        if let Some(target) = self.target_graph_from_node(&node) {
+            target.update_node_position(node, [position.0, position.1]);
+        }
+    }
+
+    /// Set or clear the execution error of a node
+    pub fn set_node_error(&mut self, node: &Resource<r::Node>, error: Option<String>) {
+        if let Some(target) = self.target_graph_from_node(&node) {
+            target.set_node_error(node, error);
+        }
+    }
+
+    /// Set the validation problems of the nodes in a graph. This is a NOP if
+    /// the collection is a layer stack.
+    pub fn set_validation_report(
+        &mut self,
+        graph: &Resource<r::Graph>,
+        report: &[(Resource<r::Node>, String)],
+    ) {
+        if let Some(target) = self
+            .target_collection_from_collection(graph)
+            .and_then(|x| x.as_graph_mut())
+        {
+            target.set_validation_report(report);
+        }
+    }
+
     /// Update the opacity of a layer when set from outside the UI
     pub fn update_layer_opacity(&mut self, layer: &Resource<r::Node>, opacity: f32) {
         if let Some(target) = self.target_layers_from_node(&layer) {
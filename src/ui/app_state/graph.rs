@@ -67,7 +67,13 @@ pub struct NodeData {
     pub resource: Resource<Node>,
     pub position: Point,
     pub callee: Option<Resource<r::Graph>>,
-    pub thumbnail: Option<image::Id>,
+    /// Thumbnails by socket name
+    pub thumbnails: HashMap<String, image::Id>,
+    /// Description of the last failure to execute this node, if it has not
+    /// executed successfully since
+    pub error: Option<String>,
+    /// Description of problems found by the last validation of the graph
+    pub invalid: Option<String>,
     pub title: String,
     pub inputs: Vec<(String, (OperatorType, bool))>,
     pub outputs: Vec<(String, OperatorType)>,
@@ -108,7 +114,9 @@ impl NodeData {
                 Operator::AtomicOperator(AtomicOperator::Output(..))
             ),
             param_box,
-            thumbnail: None,
+            thumbnails: HashMap::new(),
+            error: None,
+            invalid: None,
             type_variables: HashMap::new(),
         }
     }
@@ -228,6 +236,38 @@ impl Graph {
         self.node_count -= 1;
     }
 
+    /// Move a node to the given position if it exists, without snapping. For
+    /// positions set from outside the UI.
+    pub fn update_node_position(&mut self, res: &Resource<Node>, to: Point) {
+        if self.nodes.contains_key(res) {
+            self.move_node(res, to, false);
+        }
+    }
+
+    /// Set or clear the execution error of a node
+    pub fn set_node_error(&mut self, res: &Resource<Node>, error: Option<String>) {
+        if let Some(node) = self.nodes.get_mut(res) {
+            node.error = error;
+        }
+    }
+
+    /// Replace the validation problems of all nodes with the given report.
+    /// Multiple problems with the same node are joined.
+    pub fn set_validation_report(&mut self, report: &[(Resource<Node>, String)]) {
+        for node in self.nodes.values_mut() {
+            node.invalid = None;
+        }
+
+        for (res, problem) in report {
+            if let Some(node) = self.nodes.get_mut(res) {
+                node.invalid = Some(match node.invalid.take() {
+                    Some(problems) => format!("{}; {}", problems, problem),
+                    None => problem.clone(),
+                });
+            }
+        }
+    }
+
     /// Move a node position, updating acceleration structures. Returns new
     /// position. Snapping can be enabled via the boolean parameter.
     ///
@@ -555,21 +595,17 @@ impl Collection for Graph {
         }
     }
 
-    fn register_thumbnail(&mut self, node: &Resource<r::Node>, thumbnail: image::Id) {
-        if let Some(node) = self.nodes.get_mut(node) {
-            node.thumbnail = Some(thumbnail);
+    fn register_thumbnail(&mut self, socket: &Resource<r::Socket>, thumbnail: image::Id) {
+        if let Some(node) = self.nodes.get_mut(&socket.socket_node()) {
+            node.thumbnails
+                .insert(socket.fragment().unwrap().to_string(), thumbnail);
         }
     }
 
-    fn unregister_thumbnail(&mut self, node: &Resource<r::Node>) -> Option<image::Id> {
-        let mut old_id = None;
-
-        if let Some(node) = self.nodes.get_mut(node) {
-            old_id = node.thumbnail;
-            node.thumbnail = None;
-        }
-
-        old_id
+    fn unregister_thumbnail(&mut self, socket: &Resource<r::Socket>) -> Option<image::Id> {
+        self.nodes
+            .get_mut(&socket.socket_node())
+            .and_then(|node| node.thumbnails.remove(socket.fragment().unwrap()))
     }
 
     fn update_complex_operator(
@@ -19,6 +19,7 @@ pub struct Layer {
     pub blend_mode: usize,
     pub enabled: bool,
     pub is_mask: bool,
+    pub is_group: bool,
     pub expanded: bool,
     pub type_variables: HashMap<TypeVariable, ImageType>,
 }
@@ -35,6 +36,7 @@ impl Layer {
             blend_mode: 0,
             enabled: false,
             is_mask: false,
+            is_group: false,
             expanded: true,
             type_variables: HashMap::new(),
         }
@@ -61,6 +63,7 @@ impl Layer {
             blend_mode,
             enabled: true,
             is_mask: false,
+            is_group: false,
             expanded: true,
             type_variables: HashMap::new(),
         }
@@ -83,11 +86,31 @@ impl Layer {
             blend_mode,
             enabled: true,
             is_mask: true,
+            is_group: false,
             expanded: true,
             type_variables: HashMap::new(),
         }
     }
 
+    /// Create a group. Groups start out collapsed, and have no parameters of
+    /// their own.
+    pub fn group(resource: Resource<Node>, title: &str, blend_mode: usize, opacity: f32) -> Self {
+        Self {
+            resource,
+            title: title.to_owned(),
+            icon: crate::ui::util::IconName::FOLDER,
+            thumbnail: None,
+            operator_pbox: ParamBoxDescription::empty(),
+            opacity,
+            blend_mode,
+            enabled: true,
+            is_mask: false,
+            is_group: true,
+            expanded: false,
+            type_variables: HashMap::new(),
+        }
+    }
+
     pub fn update(&mut self, param_box: ParamBoxDescription<MessageWriters>) {
         self.operator_pbox = param_box;
     }
@@ -111,6 +134,8 @@ pub struct Layers {
     exposed_parameters: Vec<(String, GraphParameter)>,
     param_box: ParamBoxDescription<GraphField>,
     pub active_element: Option<id_tree::NodeId>,
+    /// Layers selected in addition to the active element, e.g. for grouping
+    pub selection: Vec<Resource<Node>>,
 }
 
 impl Layers {
@@ -123,6 +148,7 @@ impl Layers {
             exposed_parameters: Vec::new(),
             param_box: ParamBoxDescription::graph_parameters(name),
             active_element: None,
+            selection: Vec::new(),
         }
     }
 
@@ -308,141 +334,157 @@ impl Layers {
         queue.into_iter()
     }
 
-    /// Return a LayerDropTarget describing the given resource in a given
-    /// desired target position relative to the current stack.
-    pub fn drag_target(&self, res: &Resource<r::Node>, target: usize) -> LayerDropTarget {
+    /// Find the tree node holding the given resource, if any.
+    fn find_node(&self, res: &Resource<r::Node>) -> Option<id_tree::NodeId> {
+        self.layers
+            .traverse_pre_order_ids(self.layers.root_node_id()?)
+            .unwrap()
+            .find(|i| &self.layers.get(i).unwrap().data().resource == res)
+    }
+
+    /// Return the stack index the given resource should be moved to, when
+    /// dropped at a given desired target position relative to the currently
+    /// visible tree.
+    pub fn drag_index(&self, res: &Resource<r::Node>, target: usize) -> usize {
         let canonical = super::super::widgets::tree::visible_tree_items_queue(&self.layers, true);
+        let node_id = self.find_node(res).expect("Dragging unknown layer");
+        let parent = self.layers.get(&node_id).unwrap().parent().unwrap().clone();
+        let siblings = self.layers.children_ids(&parent).unwrap().count();
 
-        if target == 0 {
-            LayerDropTarget::Above(
-                self.layers
-                    .get(&canonical[target].0)
-                    .unwrap()
-                    .data()
-                    .resource
-                    .clone(),
-            )
-        } else {
-            let target_res = &self
-                .layers
-                .get(&canonical[target - 1].0)
-                .unwrap()
-                .data()
-                .resource;
+        // The tree is displayed top to bottom, whereas stacks are indexed
+        // from the bottom, so count the siblings displayed above the target.
+        let above = canonical
+            .iter()
+            .take(target)
+            .filter(|(n, _)| n != &node_id && self.layers.get(n).unwrap().parent() == Some(&parent))
+            .count();
 
-            if res.path_str().unwrap().contains("mask")
-                && !target_res.path_str().unwrap().contains("mask")
-            {
-                LayerDropTarget::Above(
-                    self.layers
-                        .get(&canonical[target].0)
-                        .unwrap()
-                        .data()
-                        .resource
-                        .clone(),
-                )
-            } else {
-                LayerDropTarget::Below(target_res.clone())
-            }
+        siblings - 1 - above
+    }
+
+    /// Move a layer or mask to the given index among its siblings. Children
+    /// are stored in stack order, such that the index corresponds to the
+    /// index in the layer or mask stack.
+    pub fn position_layer(&mut self, layer: &Resource<Node>, index: usize) {
+        let node_id = match self.find_node(layer) {
+            Some(n) => n,
+            None => return,
+        };
+        let parent = self.layers.get(&node_id).unwrap().parent().unwrap().clone();
+
+        let mut siblings: Vec<_> = self
+            .layers
+            .children_ids(&parent)
+            .unwrap()
+            .filter(|n| *n != &node_id)
+            .cloned()
+            .collect();
+        siblings.insert(index.min(siblings.len()), node_id);
+
+        for sibling in siblings {
+            self.layers
+                .make_last_sibling(&sibling)
+                .expect("Invalid node ID in tree");
         }
     }
 
-    /// Return contents of layer tree in canonical order, with all drawers
-    /// expanded.
-    fn canonical_order(&self) -> VecDeque<id_tree::NodeId> {
-        let mut stack: Vec<(id_tree::NodeId, usize)> = Vec::new();
-        let mut queue: VecDeque<_> = VecDeque::new();
+    /// Toggle whether a layer is part of the selection. Masks cannot be
+    /// selected.
+    pub fn toggle_selected(&mut self, layer: &Resource<Node>) {
+        if let Some(idx) = self.selection.iter().position(|l| l == layer) {
+            self.selection.remove(idx);
+        } else if !self
+            .find_node(layer)
+            .map(|n| self.layers.get(&n).unwrap().data().is_mask)
+            .unwrap_or(true)
+        {
+            self.selection.push(layer.clone());
+        }
+    }
 
-        if let Some(root) = self.layers.root_node_id() {
-            stack.push((root.clone(), 0));
+    /// All selected layers, including the active element if it is not a mask.
+    pub fn selected_layers(&self) -> Vec<Resource<Node>> {
+        let mut selected = self.selection.clone();
 
-            while !stack.is_empty() {
-                let (current, level) = stack.pop().unwrap();
-                if level > 0 {
-                    queue.push_back(current.clone());
-                }
-                stack.extend(
-                    self.layers
-                        .children_ids(&current)
-                        .unwrap()
-                        .cloned()
-                        .map(|n| (n, level + 1)),
-                );
+        if let Some(active) = self
+            .active_element
+            .as_ref()
+            .and_then(|n| self.layers.get(n).ok())
+            .map(|n| n.data())
+            .filter(|data| !data.is_mask)
+        {
+            if !selected.contains(&active.resource) {
+                selected.push(active.resource.clone());
             }
         }
 
-        queue
+        selected
     }
 
-    pub fn position_layer(&mut self, layer: &Resource<Node>, target: &LayerDropTarget) {
-        use id_tree::*;
+    /// Insert a group in place of the given member layers, which are moved
+    /// underneath it, retaining their order. The members are expected to be
+    /// adjacent siblings.
+    pub fn group_layers(&mut self, group: Layer, members: &[Resource<Node>]) -> Option<()> {
+        use id_tree::{InsertBehavior::*, MoveBehavior::*, Node};
 
-        // Convert Tree to linear list
-        let mut linear: Vec<Layer> = self
-            .canonical_order()
+        let group_res = group.resource.clone();
+        let member_ids = members
             .iter()
-            .map(|n| self.layers.get(n).unwrap().data().clone())
-            .collect();
+            .map(|m| self.find_node(m))
+            .collect::<Option<Vec<_>>>()?;
+        let parent = self.layers.get(member_ids.first()?).ok()?.parent()?.clone();
+        let index = self
+            .layers
+            .children_ids(&parent)
+            .ok()?
+            .position(|n| member_ids.contains(n))?;
 
-        // Modify list to reflect changes
-        let layer_idx = linear.iter().position(|l| &l.resource == layer).unwrap();
+        let group_id = self
+            .layers
+            .insert(Node::new(group), UnderNode(&parent))
+            .ok()?;
 
-        let mut last_mask_idx = layer_idx;
-        if !linear[layer_idx].is_mask {
-            while linear
-                .get(last_mask_idx + 1)
-                .map(|l| l.is_mask)
-                .unwrap_or(false)
-            {
-                last_mask_idx += 1;
-            }
+        // Reinsert members in stack order, such that the group preserves it
+        let mut ordered: Vec<_> = self
+            .layers
+            .children_ids(&parent)
+            .ok()?
+            .filter(|n| member_ids.contains(n))
+            .cloned()
+            .collect();
+        for member in ordered.drain(0..) {
+            self.layers.move_node(&member, ToParent(&group_id)).ok()?;
         }
 
-        let mut layers: Vec<_> = linear.drain(layer_idx..last_mask_idx + 1).collect();
-
-        let mut target_idx = linear
-            .iter()
-            .position(|l| target.target() == &l.resource)
-            .unwrap();
-
-        if let LayerDropTarget::Below(_) = target {
-            target_idx += 1;
+        self.position_layer(&group_res, index);
+        self.selection.clear();
 
-            if !layers[0].is_mask {
-                while linear.get(target_idx).map(|l| l.is_mask).unwrap_or(false) {
-                    target_idx += 1;
-                }
-            }
-        }
+        Some(())
+    }
 
-        linear.splice(target_idx..target_idx, layers.drain(0..));
+    /// Move the children of a group into its place among its siblings,
+    /// retaining their order. The group itself is left empty.
+    pub fn ungroup_layers(&mut self, group: &Resource<Node>) -> Option<()> {
+        use id_tree::MoveBehavior::*;
 
-        // Recreate tree
-        let mut tree = TreeBuilder::new()
-            .with_root(Node::new(Layer::root_layer()))
-            .with_node_capacity(linear.len() + 1)
-            .build();
-        let mut mask_buf = Vec::new();
-        let root = tree.root_node_id().unwrap().clone();
+        let group_id = self.find_node(group)?;
+        let parent = self.layers.get(&group_id).ok()?.parent()?.clone();
+        let mut siblings: Vec<_> = self.layers.children_ids(&parent).ok()?.cloned().collect();
+        let index = siblings.iter().position(|n| n == &group_id)?;
+        let members: Vec<_> = self.layers.children_ids(&group_id).ok()?.cloned().collect();
 
-        for layer in linear.drain(0..).rev() {
-            if !layer.is_mask {
-                let cursor = tree
-                    .insert(Node::new(layer), InsertBehavior::UnderNode(&root))
-                    .unwrap()
-                    .clone();
+        for member in members.iter() {
+            self.layers.move_node(member, ToParent(&parent)).ok()?;
+        }
 
-                for mask in mask_buf.drain(0..) {
-                    tree.insert(Node::new(mask), InsertBehavior::UnderNode(&cursor))
-                        .unwrap();
-                }
-            } else {
-                mask_buf.push(layer);
-            }
+        siblings.splice(index..=index, members);
+        for sibling in siblings {
+            self.layers
+                .make_last_sibling(&sibling)
+                .expect("Invalid node ID in tree");
         }
 
-        // Replace original tree
-        self.layers = tree;
+        Some(())
     }
 }
 
@@ -509,7 +551,10 @@ impl Collection for Layers {
         }
     }
 
-    fn register_thumbnail(&mut self, node: &Resource<r::Node>, thumbnail: image::Id) {
+    /// Layers display a single thumbnail, so the socket only serves to
+    /// identify the layer.
+    fn register_thumbnail(&mut self, socket: &Resource<r::Socket>, thumbnail: image::Id) {
+        let node = &socket.socket_node();
         if let Some(root) = self.layers.root_node_id() {
             if let Some(node_id) = self
                 .layers
@@ -523,7 +568,8 @@ impl Collection for Layers {
         }
     }
 
-    fn unregister_thumbnail(&mut self, node: &Resource<r::Node>) -> Option<image::Id> {
+    fn unregister_thumbnail(&mut self, socket: &Resource<r::Socket>) -> Option<image::Id> {
+        let node = &socket.socket_node();
         let mut old_thumbnail = None;
 
         if let Some(root) = self.layers.root_node_id() {
@@ -159,34 +159,15 @@ where
             }
         }
 
-        // Catch undo and redo events globally
+        // Translate global shortcuts into logical events
         for ev in ui.global_input().events().ui() {
-            match ev {
-                event::Ui::Press(
-                    _,
-                    event::Press {
-                        button: event::Button::Keyboard(input::Key::Z),
-                        modifiers: input::ModifierKey::CTRL,
-                    },
-                ) => {
+            if let event::Ui::Press(_, press) = ev {
+                if let Some(shortcut) = shortcut_event(press) {
                     self.app_data
                         .sender
-                        .send(Lang::UserIOEvent(UserIOEvent::Undo))
+                        .send(Lang::UserShortcutEvent(shortcut))
                         .unwrap();
                 }
-                event::Ui::Press(
-                    _,
-                    event::Press {
-                        button: event::Button::Keyboard(input::Key::Z),
-                        modifiers: input::ModifierKey::CTRL_SHIFT,
-                    },
-                ) => {
-                    self.app_data
-                        .sender
-                        .send(Lang::UserIOEvent(UserIOEvent::Redo))
-                        .unwrap();
-                }
-                _ => {}
             }
         }
 
@@ -313,21 +294,27 @@ where
     /// Handle UI event
     fn handle_event(&mut self, state: &mut widget::State<State>, event: &Lang) {
         match event {
-            Lang::ComputeEvent(ComputeEvent::ThumbnailCreated(res, thmb)) => {
+            Lang::ComputeEvent(ComputeEvent::ThumbnailCreated(_, socket, thmb)) => {
                 if let Some(t) = thmb.clone().to::<B>() {
                     if let Some(img) = self.renderer.create_image(t, 128, 128) {
                         let id = self.app_data.image_map.insert(img);
-                        state.update(|state| state.graphs.register_thumbnail(&res, id));
+                        state.update(|state| state.graphs.register_thumbnail(&socket, id));
                     }
                 }
             }
-            Lang::ComputeEvent(ComputeEvent::ThumbnailDestroyed(res)) => {
+            Lang::ComputeEvent(ComputeEvent::ThumbnailDestroyed(_, socket)) => {
                 state.update(|state| {
-                    if let Some(id) = state.graphs.unregister_thumbnail(&res) {
+                    if let Some(id) = state.graphs.unregister_thumbnail(&socket) {
                         self.app_data.image_map.remove(id);
                     }
                 });
             }
+            Lang::ComputeEvent(ComputeEvent::ThumbnailUpdated(node, _)) => {
+                state.update(|state| state.graphs.set_node_error(node, None));
+            }
+            Lang::ComputeEvent(ComputeEvent::OperatorError(node, msg)) => {
+                state.update(|state| state.graphs.set_node_error(node, Some(msg.clone())));
+            }
             Lang::ComputeEvent(ComputeEvent::ImageResourceAdded(res, cs, packed)) => {
                 state.update(|state| {
                     state.image_resources.push((res.clone(), (*cs, *packed)));
@@ -382,6 +369,11 @@ where
                     state.graphs.update_parameter(param, value);
                 })
             }
+            Lang::UserNodeEvent(UserNodeEvent::PositionNode(node, position)) => {
+                state.update(|state| {
+                    state.graphs.update_node_position(node, *position);
+                })
+            }
             Lang::UserLayersEvent(UserLayersEvent::SetOpacity(layer, _, opacity)) => {
                 state.update(|state| {
                     state.graphs.update_layer_opacity(layer, *opacity);
@@ -448,6 +440,13 @@ where
             GraphEvent::ComplexOperatorUpdated(node, op, pbox) => {
                 state.update(|state| state.graphs.update_complex_operator(node, op, pbox));
             }
+            GraphEvent::StaleParameterRemoved(node, field) => {
+                log::warn!(
+                    "Removed stale parameter {} from {}, no longer exposed by the called graph",
+                    field,
+                    node
+                );
+            }
             GraphEvent::ConnectedSockets(from, to) => {
                 state.update(|state| state.graphs.connect_sockets(from, to))
             }
@@ -460,6 +459,9 @@ where
             GraphEvent::SocketDemonomorphized(socket) => {
                 state.update(|state| state.graphs.demonomorphize_socket(socket))
             }
+            GraphEvent::ValidationReport(graph, report) => {
+                state.update(|state| state.graphs.set_validation_report(graph, report))
+            }
             GraphEvent::Cleared => {
                 state.update(|state| state.graphs.clear_all());
             }
@@ -514,8 +516,15 @@ where
                     Layer::mask(res.clone(), title, pbox.clone(), *bmode as usize, *opacity);
                 state.update(|state| state.graphs.push_layer_under(layer, for_layer));
             }
-            LayersEvent::LayerPositioned(res, target) => {
-                state.update(|state| state.graphs.position_layer(res, target));
+            LayersEvent::LayerPositioned(res, _, index) => {
+                state.update(|state| state.graphs.position_layer(res, *index));
+            }
+            LayersEvent::LayersGrouped(res, title, members, bmode, opacity, _) => {
+                let group = Layer::group(res.clone(), title, *bmode as usize, *opacity);
+                state.update(|state| state.graphs.group_layers(group, members));
+            }
+            LayersEvent::LayersUngrouped(res, _) => {
+                state.update(|state| state.graphs.ungroup_layers(res));
             }
         }
     }
@@ -697,3 +706,18 @@ where
         });
     }
 }
+
+/// Map a key press to the logical shortcut action it is bound to, if any.
+fn shortcut_event(press: &event::Press) -> Option<UserShortcutEvent> {
+    match press {
+        event::Press {
+            button: event::Button::Keyboard(input::Key::Z),
+            modifiers: input::ModifierKey::CTRL,
+        } => Some(UserShortcutEvent::Undo),
+        event::Press {
+            button: event::Button::Keyboard(input::Key::Z),
+            modifiers: input::ModifierKey::CTRL_SHIFT,
+        } => Some(UserShortcutEvent::Redo),
+        _ => None,
+    }
+}
@@ -43,6 +43,7 @@ pub struct Style {
 widget_ids! {
     pub struct Ids {
         param_box,
+        graph_clone,
         layer_convert,
         exposed_param_title,
         exposed_param_list,
@@ -68,23 +69,41 @@ impl<'a> Widget for GraphSection<'a> {
 
         let active_graph = self.graphs.get_active().clone();
 
+        // Space taken up by buttons on top, and extra space for the layer
+        // stack parameter box
+        let mut header = 32.0;
         let mut offset = 0.0;
 
+        for _click in widget::Button::new()
+            .label(&self.language.get_message("clone-graph"))
+            .label_font_size(10)
+            .parent(args.id)
+            .padded_w_of(args.id, 16.0)
+            .h(16.0)
+            .mid_top_with_margin(16.0)
+            .set(args.state.graph_clone, args.ui)
+        {
+            self.sender
+                .send(Lang::UserGraphEvent(UserGraphEvent::CloneGraph(
+                    active_graph.clone(),
+                    format!("{}.copy", active_graph.file().unwrap()),
+                )))
+                .unwrap();
+        }
+
         if self
             .graphs
             .get_active_collection_mut()
             .as_layers_mut()
             .is_some()
         {
-            offset = 32.0;
-
             for _click in widget::Button::new()
                 .label(&self.language.get_message("convert-to-graph"))
                 .label_font_size(10)
                 .parent(args.id)
                 .padded_w_of(args.id, 16.0)
                 .h(16.0)
-                .mid_top_with_margin(16.0)
+                .mid_top_with_margin(16.0 + header)
                 .set(args.state.layer_convert, args.ui)
             {
                 self.sender
@@ -93,6 +112,9 @@ impl<'a> Widget for GraphSection<'a> {
                     )))
                     .unwrap();
             }
+
+            header += 32.0;
+            offset = 32.0;
         }
 
         for ev in param_box::ParamBox::new(
@@ -102,7 +124,7 @@ impl<'a> Widget for GraphSection<'a> {
         )
         .parent(args.id)
         .w_of(args.id)
-        .mid_top_with_margin(32.0)
+        .mid_top_with_margin(header)
         .text_color(color::WHITE)
         .icon_font(args.style.icon_font(&args.ui.theme))
         .text_size(10)
@@ -117,7 +139,7 @@ impl<'a> Widget for GraphSection<'a> {
             .parent(args.id)
             .color(color::WHITE)
             .font_size(12)
-            .mid_top_with_margin(96.0 + offset)
+            .mid_top_with_margin(64.0 + header + offset)
             .set(args.state.exposed_param_title, args.ui);
 
         let exposed_params = self.graphs.get_exposed_parameters_mut();
@@ -127,7 +149,7 @@ impl<'a> Widget for GraphSection<'a> {
             .padded_w_of(args.id, 8.0)
             .item_size(160.0)
             .h(320.0)
-            .mid_top_with_margin(112.0 + offset)
+            .mid_top_with_margin(80.0 + header + offset)
             .scrollbar_on_top()
             .set(args.state.exposed_param_list, args.ui);
 
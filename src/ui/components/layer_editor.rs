@@ -84,6 +84,7 @@ pub struct State {
 pub enum MainTool {
     NewFill,
     NewFx,
+    Group,
 }
 
 impl<'a> Widget for LayerEditor<'a> {
@@ -126,6 +127,7 @@ impl<'a> Widget for LayerEditor<'a> {
             [
                 (IconName::SOLID, MainTool::NewFill),
                 (IconName::FX, MainTool::NewFx),
+                (IconName::FOLDER_PLUS, MainTool::Group),
             ]
             .iter()
             .copied(),
@@ -134,7 +136,7 @@ impl<'a> Widget for LayerEditor<'a> {
         .icon_color(color::WHITE)
         .button_color(color::DARK_CHARCOAL)
         .parent(id)
-        .w(64.0 + 8.0)
+        .w(96.0 + 8.0)
         .h(32.0)
         .top_left_with_margins(8.0, 0.0)
         .set(state.ids.main_toolbar, ui)
@@ -145,6 +147,18 @@ impl<'a> Widget for LayerEditor<'a> {
             Some(MainTool::NewFx) => {
                 state.update(|state| state.modal = Some(LayerFilter::Layer(LayerType::Fx)));
             }
+            Some(MainTool::Group) => {
+                if let NodeCollection::Layers(l) = self.graphs.get_active_collection_mut() {
+                    let selected = l.selected_layers();
+                    if !selected.is_empty() {
+                        self.sender
+                            .send(Lang::UserLayersEvent(UserLayersEvent::GroupLayers(
+                                selected,
+                            )))
+                            .unwrap();
+                    }
+                }
+            }
             _ => {}
         }
 
@@ -241,6 +255,14 @@ impl<'a> Widget for LayerEditor<'a> {
             let i = row.i;
             let is_base = active_collection.is_base_layer(&node_id);
             let expandable = active_collection.expandable(&node_id);
+            let selected = active_collection.selection.contains(
+                &active_collection
+                    .layers
+                    .get(&node_id)
+                    .unwrap()
+                    .data()
+                    .resource,
+            );
             let data = &mut active_collection
                 .layers
                 .get_mut(&node_id)
@@ -252,6 +274,7 @@ impl<'a> Widget for LayerEditor<'a> {
                 Some(row.node_id) == active_collection.active_element,
             )
             .toggleable(!is_base)
+            .selected(selected)
             .expandable(expandable)
             .color(color::WHITE)
             .icon_size(10)
@@ -262,6 +285,11 @@ impl<'a> Widget for LayerEditor<'a> {
                 match event {
                     layer_row::Event::ActiveElement => {
                         active_collection.active_element = Some(node_id);
+                        active_collection.selection.clear();
+                    }
+                    layer_row::Event::ToggleSelected => {
+                        let resource = data.resource.clone();
+                        active_collection.toggle_selected(&resource);
                     }
                     layer_row::Event::Retitled(old, new) => {
                         self.sender
@@ -310,7 +338,7 @@ impl<'a> Widget for LayerEditor<'a> {
                             self.sender
                                 .send(Lang::UserLayersEvent(UserLayersEvent::PositionLayer(
                                     drag.element.clone(),
-                                    active_collection.drag_target(&drag.element, pos),
+                                    active_collection.drag_index(&drag.element, pos),
                                 )))
                                 .unwrap();
                             state.update(|state| state.drag = None);
@@ -329,6 +357,13 @@ impl<'a> Widget for LayerEditor<'a> {
                             })
                             .unwrap();
                     }
+                    layer_row::Event::Ungroup => {
+                        self.sender
+                            .send(Lang::UserLayersEvent(UserLayersEvent::UngroupLayers(
+                                data.resource.clone(),
+                            )))
+                            .unwrap();
+                    }
                     layer_row::Event::AddMask => {
                         state.update(|state| {
                             state.modal = Some(LayerFilter::Mask(data.resource.clone()))
@@ -53,12 +53,14 @@ widget_ids! {
         graph_tools,
         graph_selector,
         status_line,
+        error_line,
     }
 }
 
 pub struct State {
     ids: Ids,
     vram_usage: (f32, f32, f32),
+    last_error: Option<(String, String)>,
 }
 
 #[derive(Clone, Copy)]
@@ -80,6 +82,7 @@ impl<'a> Widget for TopBar<'a> {
         State {
             ids: Ids::new(id_gen),
             vram_usage: (0., 0., 0.),
+            last_error: None,
         }
     }
 
@@ -213,6 +216,16 @@ impl<'a> Widget for TopBar<'a> {
             .left(8.0)
             .align_middle_y()
             .set(state.ids.status_line, ui);
+
+        if let Some((subject, msg)) = &state.last_error {
+            widget::Text::new(&format!("{}: {}", subject, msg))
+                .color(color::LIGHT_RED)
+                .font_size(10)
+                .parent(id)
+                .right_from(state.ids.status_line, 16.0)
+                .align_middle_y()
+                .set(state.ids.error_line, ui);
+        }
     }
 }
 
@@ -225,6 +238,17 @@ impl<'a> TopBar<'a> {
                 let total = *total as f32 / MEGABYTES;
                 state.update(|state| state.vram_usage = (used, total, 100. * used / total));
             }
+            Lang::ComputeEvent(ComputeEvent::OperatorError(node, msg)) => {
+                state.update(|state| state.last_error = Some((node.to_string(), msg.clone())));
+            }
+            Lang::IOEvent(IOEvent::ImageResourceLoadFailed(res, _, msg)) => {
+                state.update(|state| state.last_error = Some((res.to_string(), msg.clone())));
+            }
+            Lang::ComputeEvent(ComputeEvent::ThumbnailUpdated(node, _)) => {
+                if matches!(&state.last_error, Some((n, _)) if *n == node.to_string()) {
+                    state.update(|state| state.last_error = None);
+                }
+            }
             _ => {}
         }
     }
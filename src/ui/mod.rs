@@ -54,9 +54,7 @@ fn ui_loop<B: gpu::Backend>(
     let mut renderer =
         gpu::ui::Renderer::new(gpu, &window, dims, [1024, 1024]).expect("Error building renderer");
     let mut ui = conrod_core::UiBuilder::new([dims.width as f64, dims.height as f64]).build();
-    let assets = find_folder::Search::KidsThenParents(3, 5)
-        .for_folder("assets")
-        .unwrap();
+    let assets = crate::lang::config::asset_dir();
 
     let icon_font = ui
         .fonts
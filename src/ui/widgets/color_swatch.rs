@@ -0,0 +1,137 @@
+use super::color_picker::ColorPicker;
+use crate::ui::i18n::Language;
+
+use conrod_core::*;
+use palette::{Hsv, LinSrgb};
+
+/// Height of the popout color picker, excluding the alpha slider
+const PICKER_HEIGHT: f64 = 256.0;
+
+/// A compact color swatch for RGBA colors. Clicking the swatch toggles a
+/// popout with a color picker and an alpha slider, floating above other
+/// widgets such that it takes up no space while closed.
+#[derive(WidgetCommon)]
+pub struct ColorSwatch<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    style: Style,
+    color: [f32; 4],
+    language: &'a Language,
+}
+
+impl<'a> ColorSwatch<'a> {
+    pub fn new(color: [f32; 4], language: &'a Language) -> Self {
+        Self {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            color,
+            language,
+        }
+    }
+
+    builder_methods! {
+        pub text_size { style.text_size = Some(FontSize) }
+        pub popout_color { style.popout_color = Some(Color) }
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug, WidgetStyle, PartialEq)]
+pub struct Style {
+    #[conrod(default = "theme.font_size_small")]
+    text_size: Option<FontSize>,
+    #[conrod(default = "theme.background_color")]
+    popout_color: Option<Color>,
+}
+
+widget_ids! {
+    pub struct Ids {
+        swatch,
+        popout,
+        picker,
+        alpha,
+    }
+}
+
+pub struct State {
+    ids: Ids,
+    open: bool,
+}
+
+impl<'a> Widget for ColorSwatch<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = Option<[f32; 4]>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        Self::State {
+            ids: Ids::new(id_gen),
+            open: false,
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            ui,
+            id,
+            state,
+            style,
+            rect,
+            ..
+        } = args;
+
+        let mut ev = None;
+        let [r, g, b, a] = self.color;
+
+        for _click in widget::Button::new()
+            .color(color::Color::Rgba(r, g, b, a))
+            .parent(id)
+            .middle_of(id)
+            .wh_of(id)
+            .set(state.ids.swatch, ui)
+        {
+            state.update(|state| state.open = !state.open);
+        }
+
+        if !state.open {
+            return ev;
+        }
+
+        widget::Canvas::new()
+            .floating(true)
+            .color(style.popout_color(&ui.theme))
+            .border(0.)
+            .down_from(state.ids.swatch, 4.0)
+            .w(rect.w())
+            .h(PICKER_HEIGHT + 32.0)
+            .set(state.ids.popout, ui);
+
+        if let Some(new_color) = ColorPicker::new(Hsv::from(LinSrgb::new(r, g, b)))
+            .parent(state.ids.popout)
+            .top_left_of(state.ids.popout)
+            .padded_w_of(state.ids.popout, 0.0)
+            .h(PICKER_HEIGHT)
+            .set(state.ids.picker, ui)
+        {
+            let rgb = LinSrgb::from(new_color);
+            ev = Some([rgb.red, rgb.green, rgb.blue, a]);
+        }
+
+        if let Some(new_alpha) = widget::Slider::new(a, 0.0, 1.0)
+            .label(&self.language.get_message("alpha"))
+            .label_font_size(style.text_size(&ui.theme))
+            .parent(state.ids.popout)
+            .down_from(state.ids.picker, 8.0)
+            .padded_w_of(state.ids.popout, 8.0)
+            .h(16.0)
+            .set(state.ids.alpha, ui)
+        {
+            ev = Some([r, g, b, new_alpha]);
+        }
+
+        ev
+    }
+}
@@ -163,9 +163,11 @@ impl<'a> Widget for ExposedParamRow<'a> {
 fn control_name(control: &Control) -> &'static str {
     match control {
         Control::Slider { .. } => "f32",
+        Control::LogSlider { .. } => "f32",
         Control::DiscreteSlider { .. } => "i32",
         Control::XYPad { .. } => "xy",
         Control::RgbColor { .. } => "rgb",
+        Control::Color { .. } => "rgba",
         Control::Enum { .. } => "list",
         Control::File { .. } => "file",
         Control::ImageResource { .. } => "img",
@@ -327,12 +327,13 @@ impl<'a> Widget for Graph<'a> {
                     .title_color(style.node_title_color(&ui.theme))
                     .title_size(style.node_title_size(&ui.theme))
                     .selected(selection_state)
+                    .error(node.error.is_some() || node.invalid.is_some())
                     .view_socket(view_socket)
                     .active_color(style.node_active_color(&ui.theme))
                     .selection_color(style.node_selection_color(&ui.theme))
                     .parent(id)
                     .xy_relative_to(id, state.camera.transform(node.position))
-                    .thumbnail(node.thumbnail)
+                    .thumbnails(&node.thumbnails)
                     .wh([
                         STANDARD_NODE_SIZE * state.camera.zoom,
                         node_height(socket_count, 16., 8.) * state.camera.zoom,
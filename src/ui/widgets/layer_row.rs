@@ -8,6 +8,7 @@ pub struct LayerRow<'a> {
     common: widget::CommonBuilder,
     layer: &'a mut Layer,
     active: bool,
+    selected: bool,
     style: Style,
     toggleable: bool,
     expandable: bool,
@@ -19,6 +20,7 @@ impl<'a> LayerRow<'a> {
             common: widget::CommonBuilder::default(),
             layer,
             active,
+            selected: false,
             style: Style::default(),
             toggleable: true,
             expandable: false,
@@ -27,6 +29,7 @@ impl<'a> LayerRow<'a> {
 
     builder_methods! {
         pub toggleable { toggleable = bool }
+        pub selected { selected = bool }
         pub expandable { expandable = bool }
         pub icon_font { style.icon_font = Some(text::font::Id) }
         pub icon_size { style.icon_size = Some(FontSize) }
@@ -76,11 +79,13 @@ pub struct State {
 pub enum ContextAction {
     Delete,
     AddMask,
+    Ungroup,
     ToggleExpanded,
 }
 
 fn context_actions(
-    is_mask: bool,
+    maskable: bool,
+    group: bool,
     base_layer: bool,
     expanded: Option<bool>,
 ) -> Vec<(util::IconName, ContextAction)> {
@@ -96,9 +101,12 @@ fn context_actions(
             ContextAction::ToggleExpanded,
         ))
     }
-    if !is_mask && !base_layer {
+    if maskable && !base_layer {
         actions.push((util::IconName::MASK, ContextAction::AddMask));
     }
+    if group {
+        actions.push((util::IconName::FOLDER_OPEN, ContextAction::Ungroup));
+    }
 
     actions.push((util::IconName::TRASH, ContextAction::Delete));
 
@@ -107,12 +115,14 @@ fn context_actions(
 
 pub enum Event {
     ActiveElement,
+    ToggleSelected,
     Retitled(String, String),
     ToggleEnabled,
     ToggleExpanded,
     Drag(Point),
     Drop,
     AddMask,
+    Ungroup,
     Delete,
 }
 
@@ -171,6 +181,11 @@ impl<'a> Widget for LayerRow<'a> {
                     state.update(|state| state.dragging = false);
                     event = Some(Event::Drop);
                 }
+                event::Widget::Click(click)
+                    if click.modifiers.contains(input::ModifierKey::SHIFT) =>
+                {
+                    event = Some(Event::ToggleSelected);
+                }
                 event::Widget::Click(_) => {
                     event = Some(Event::ActiveElement);
                 }
@@ -237,7 +252,7 @@ impl<'a> Widget for LayerRow<'a> {
             }
         } else {
             widget::Text::new(&self.layer.title)
-                .color(if self.active {
+                .color(if self.active || self.selected {
                     style.selection_color(&ui.theme)
                 } else {
                     style.color(&ui.theme)
@@ -263,7 +278,8 @@ impl<'a> Widget for LayerRow<'a> {
         if hovering {
             match toolbar::Toolbar::flow_left(
                 context_actions(
-                    self.layer.is_mask,
+                    !self.layer.is_mask && !self.layer.is_group,
+                    self.layer.is_group,
                     !self.toggleable,
                     if self.expandable {
                         Some(self.layer.expanded)
@@ -284,6 +300,7 @@ impl<'a> Widget for LayerRow<'a> {
             .set(state.ids.toolbar, ui)
             {
                 Some(ContextAction::AddMask) => event = Some(Event::AddMask),
+                Some(ContextAction::Ungroup) => event = Some(Event::Ungroup),
                 Some(ContextAction::Delete) => event = Some(Event::Delete),
                 Some(ContextAction::ToggleExpanded) => event = Some(Event::ToggleExpanded),
                 None => {}
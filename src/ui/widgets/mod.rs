@@ -1,6 +1,7 @@
 pub mod bezier;
 pub mod color_picker;
 pub mod color_ramp;
+pub mod color_swatch;
 pub mod export_row;
 pub mod exposed_param_row;
 pub mod filtered_list;
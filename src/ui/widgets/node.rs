@@ -16,8 +16,9 @@ pub struct Node<'a> {
     common: widget::CommonBuilder,
     style: Style,
     selected: SelectionState,
+    error: bool,
     view_socket: Option<String>,
-    thumbnail: Option<image::Id>,
+    thumbnails: Option<&'a HashMap<String, image::Id>>,
     inputs: &'a [(String, (OperatorType, bool))],
     outputs: &'a [(String, OperatorType)],
     title: &'a str,
@@ -36,6 +37,8 @@ pub struct Style {
     active_color: Option<Color>,
     #[conrod(default = "color::YELLOW")]
     selection_color: Option<Color>,
+    #[conrod(default = "color::RED")]
+    error_color: Option<Color>,
     #[conrod(default = "1.")]
     zoom: Option<f64>,
 }
@@ -71,8 +74,9 @@ impl<'a> Node<'a> {
             common: widget::CommonBuilder::default(),
             style: Style::default(),
             selected: SelectionState::None,
+            error: false,
             view_socket: None,
-            thumbnail: None,
+            thumbnails: None,
             inputs,
             outputs,
             title,
@@ -80,8 +84,9 @@ impl<'a> Node<'a> {
         }
     }
 
-    pub fn thumbnail(mut self, thumbnail: Option<image::Id>) -> Self {
-        self.thumbnail = thumbnail;
+    /// Set the thumbnails of this node, by socket name.
+    pub fn thumbnails(mut self, thumbnails: &'a HashMap<String, image::Id>) -> Self {
+        self.thumbnails = Some(thumbnails);
         self
     }
 
@@ -92,11 +97,13 @@ impl<'a> Node<'a> {
 
     builder_methods! {
         pub selected { selected = SelectionState }
+        pub error { error = bool }
         pub title_color { style.title_color = Some(Color) }
         pub title_size { style.title_size = Some(FontSize) }
         pub border_color { style.border_color = Some(Color) }
         pub active_color { style.active_color = Some(Color) }
         pub selection_color { style.selection_color = Some(Color) }
+        pub error_color { style.error_color = Some(Color) }
         pub zoom { style.zoom = Some(f64) }
     }
 }
@@ -216,6 +223,7 @@ impl<'a> Widget for Node<'a> {
             .border_color(match self.selected {
                 SelectionState::Active => style.active_color(&ui.theme),
                 SelectionState::Selected => style.selection_color(&ui.theme),
+                _ if self.error => style.error_color(&ui.theme),
                 _ => style.border_color(&ui.theme),
             })
             .color(color::CHARCOAL)
@@ -232,7 +240,26 @@ impl<'a> Widget for Node<'a> {
             .up(2.0)
             .set(state.ids.title, ui);
 
-        if let Some(thumbnail) = self.thumbnail {
+        // Show the thumbnail of a hovered output socket if there is any,
+        // otherwise that of the first output.
+        let hovered_output = self.outputs.iter().map(|(o, _)| o).find(|o| {
+            state
+                .output_sockets
+                .get(*o)
+                .and_then(|w_id| ui.rect_of(*w_id))
+                .map(|rect| rect.is_over(ui.global_input().current.mouse.xy))
+                .unwrap_or(false)
+        });
+        let thumbnail = self.thumbnails.and_then(|thumbnails| {
+            hovered_output
+                .and_then(|o| thumbnails.get(o))
+                .or_else(|| self.outputs.iter().find_map(|(o, _)| thumbnails.get(o)))
+                .or_else(|| thumbnails.values().next())
+                .copied()
+        });
+
+        // Thumbnails of failed nodes are stale, and therefore hidden
+        if let Some(thumbnail) = thumbnail.filter(|_| !self.error) {
             let thumbnail_size = rect.w() - (8. * zoom) * 2.;
             widget::Image::new(thumbnail)
                 .parent(state.ids.rectangle)
@@ -422,9 +449,11 @@ fn operator_type_color(
     let base_color = match optype {
         OperatorType::Monomorphic(ImageType::Grayscale) => color::LIGHT_GREEN,
         OperatorType::Monomorphic(ImageType::Rgb) => color::LIGHT_ORANGE,
+        OperatorType::Monomorphic(ImageType::Rgba) => color::LIGHT_YELLOW,
         OperatorType::Polymorphic(v) => match variables.get(v) {
             Some(ImageType::Grayscale) => color::LIGHT_GREEN,
             Some(ImageType::Rgb) => color::LIGHT_ORANGE,
+            Some(ImageType::Rgba) => color::LIGHT_YELLOW,
             None => match v {
                 0 => color::DARK_RED,
                 1 => color::DARK_ORANGE,
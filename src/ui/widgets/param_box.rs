@@ -1,5 +1,6 @@
 use super::color_picker::ColorPicker;
 use super::color_ramp::ColorRamp;
+use super::color_swatch::ColorSwatch;
 use super::resource_editor::ResourceEditor;
 use super::size_control::SizeControl;
 
@@ -21,6 +22,7 @@ pub struct ControlCounts {
     pub discrete_sliders: usize,
     pub xy_pads: usize,
     pub rgb_colors: usize,
+    pub colors: usize,
     pub enums: usize,
     pub files: usize,
     pub imgs: usize,
@@ -46,7 +48,7 @@ where
             .flatten()
         {
             match parameter.control {
-                Control::Slider { .. } => {
+                Control::Slider { .. } | Control::LogSlider { .. } => {
                     counts.sliders += 1;
                 }
                 Control::DiscreteSlider { .. } => {
@@ -58,6 +60,9 @@ where
                 Control::RgbColor { .. } => {
                     counts.rgb_colors += 1;
                 }
+                Control::Color { .. } => {
+                    counts.colors += 1;
+                }
                 Control::Enum { .. } => {
                     counts.enums += 1;
                 }
@@ -161,6 +166,11 @@ impl<'a, T: MessageWriter> ParamBox<'a, T> {
                 .get_mut(&TypeId::of::<ColorPicker<Hsv>>())
                 .unwrap()
                 .resize(counts.rgb_colors, id_gen);
+            state
+                .controls
+                .get_mut(&TypeId::of::<ColorSwatch>())
+                .unwrap()
+                .resize(counts.colors, id_gen);
             state
                 .controls
                 .get_mut(&TypeId::of::<ColorRamp>())
@@ -228,6 +238,12 @@ impl<'a, T: MessageWriter> ParamBox<'a, T> {
                 .unwrap()
                 .len()
                 < (counts.rgb_colors)
+            || state
+                .controls
+                .get(&TypeId::of::<ColorSwatch>())
+                .unwrap()
+                .len()
+                < (counts.colors)
             || state
                 .controls
                 .get(&TypeId::of::<ColorRamp>())
@@ -342,6 +358,7 @@ where
                 TypeId::of::<widget::DropDownList<String>>() => widget::id::List::new(),
                 TypeId::of::<widget::XYPad<f32,f32>>() => widget::id::List::new(),
                 TypeId::of::<ColorPicker<Hsv>>() => widget::id::List::new(),
+                TypeId::of::<ColorSwatch>() => widget::id::List::new(),
                 TypeId::of::<ColorRamp>() => widget::id::List::new(),
                 TypeId::of::<widget::Button<widget::button::Flat>>() => widget::id::List::new(),
                 TypeId::of::<ResourceEditor<Img>>() => widget::id::List::new(),
@@ -561,6 +578,32 @@ where
                         }
                         control_idx.sliders += 1;
                     }
+                    Control::LogSlider { value, min, max } => {
+                        let control_id = state
+                            .controls
+                            .get(&TypeId::of::<widget::Slider<f32>>())
+                            .unwrap()[control_idx.sliders + control_idx.discrete_sliders];
+                        let (log_min, log_max) = (min.ln(), max.ln());
+                        if let Some(new) =
+                            widget::Slider::new(value.max(*min).ln(), log_min, log_max)
+                                .label(&format!("{:.2}", *value))
+                                .label_font_size(style.text_size(&ui.theme))
+                                .padded_w_of(id, 16.0)
+                                .h(16.0)
+                                .set(control_id, ui)
+                        {
+                            let new = new.exp();
+                            if (new - *value).abs() > std::f32::EPSILON {
+                                ev.push(Event::ChangeParameter(parameter.transmitter.transmit(
+                                    self.resource,
+                                    &value.to_data(),
+                                    &new.to_data(),
+                                )));
+                                *value = new;
+                            }
+                        }
+                        control_idx.sliders += 1;
+                    }
                     Control::DiscreteSlider { value, min, max } => {
                         let control_id = state
                             .controls
@@ -636,6 +679,24 @@ where
                         top_margin += 256.0;
                         control_idx.rgb_colors += 1;
                     }
+                    Control::Color { value } => {
+                        let control_id = state.controls.get(&TypeId::of::<ColorSwatch>()).unwrap()
+                            [control_idx.colors];
+                        if let Some(new) = ColorSwatch::new(*value, language)
+                            .text_size(style.text_size(&ui.theme))
+                            .padded_w_of(id, 16.0)
+                            .h(16.0)
+                            .set(control_id, ui)
+                        {
+                            ev.push(Event::ChangeParameter(parameter.transmitter.transmit(
+                                self.resource,
+                                &ColorParam(*value).to_data(),
+                                &ColorParam(new).to_data(),
+                            )));
+                            *value = new;
+                        }
+                        control_idx.colors += 1;
+                    }
                     Control::Enum { selected, variants } => {
                         let control_id = state
                             .controls
@@ -43,11 +43,14 @@ widget_ids! {
         absolute_toggle,
         relative_slider,
         absolute_slider,
+        absolute_entry,
     }
 }
 
 pub struct State {
     ids: Ids,
+    /// Text of the size entry while it is being edited
+    entry: Option<String>,
 }
 
 pub enum Event {
@@ -64,6 +67,7 @@ impl Widget for SizeControl {
     fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
         Self::State {
             ids: Ids::new(id_gen),
+            entry: None,
         }
     }
 
@@ -128,7 +132,7 @@ impl Widget for SizeControl {
                     }
                 }
             }
-            OperatorSize::AbsoluteSize(s) => {
+            OperatorSize::AbsoluteSize(s) | OperatorSize::NonPow2(s) => {
                 if self.allow_relative {
                     for _click in widget::Toggle::new(true)
                         .parent(id)
@@ -152,9 +156,9 @@ impl Widget for SizeControl {
                         .h(16.);
 
                 if self.allow_relative {
-                    ctrl = ctrl.padded_w_of(id, 20.).right(8.);
+                    ctrl = ctrl.padded_w_of(id, 52.).right(8.);
                 } else {
-                    ctrl = ctrl.w_of(id).mid_left_of(id);
+                    ctrl = ctrl.w(ui.w_of(id).unwrap_or(0.) - 64.).mid_left_of(id);
                 }
 
                 if let Some(new) = ctrl.set(state.ids.absolute_slider, ui) {
@@ -163,6 +167,31 @@ impl Widget for SizeControl {
                         ev = Some(Event::NewSize(new));
                     }
                 }
+
+                // Exact sizes can be entered directly, which allows sizes
+                // that the slider would snap to a normal texture size.
+                let entry = state.entry.clone().unwrap_or_else(|| s.to_string());
+                for event in widget::TextBox::new(&entry)
+                    .font_size(style.text_size(&ui.theme))
+                    .right(8.)
+                    .wh([56., 16.])
+                    .set(state.ids.absolute_entry, ui)
+                {
+                    match event {
+                        widget::text_box::Event::Update(new) => {
+                            state.update(|state| state.entry = Some(new));
+                        }
+                        widget::text_box::Event::Enter => {
+                            if let Ok(new) = entry.parse::<u32>() {
+                                let new = OperatorSize::abs_nearest(new.clamp(32, 16384) as f32);
+                                if new != self.size {
+                                    ev = Some(Event::NewSize(new));
+                                }
+                            }
+                            state.update(|state| state.entry = None);
+                        }
+                    }
+                }
             }
         }
 
@@ -134,7 +134,59 @@ impl<R, T> UndoBuilder for CallMultiResponseAction<R, T> {
     }
 }
 
+/// An action composed of several other actions, as created when a whole undo or
+/// redo step is replayed. Events are distributed to the first child accepting
+/// them. When built, the children are built in reverse order such that the
+/// resulting events undo the children last to first.
+pub struct CompoundAction {
+    children: Vec<Box<dyn UndoBuilder + Send>>,
+}
+
+impl CompoundAction {
+    pub fn new(children: Vec<Box<dyn UndoBuilder + Send>>) -> Self {
+        Self { children }
+    }
+}
+
+impl UndoBuilder for CompoundAction {
+    fn build(&self) -> Option<Vec<Lang>> {
+        let mut result = Vec::new();
+
+        for child in self.children.iter().rev() {
+            result.append(&mut child.build()?);
+        }
+
+        Some(result)
+    }
+
+    fn next(&mut self, event: &Lang) -> bool {
+        self.children
+            .iter_mut()
+            .any(|child| child.more() && child.next(event))
+    }
+
+    fn more(&self) -> bool {
+        self.children.iter().any(|child| child.more())
+    }
+}
+
 impl UndoAction {
+    pub fn compound_action(events: &[Lang]) -> Option<Self> {
+        let children: Vec<_> = events
+            .iter()
+            .filter_map(|ev| match Self::from_event(ev)? {
+                Self::Building(b) => Some(b),
+                Self::Complete(..) => None,
+            })
+            .collect();
+
+        if children.is_empty() {
+            None
+        } else {
+            Some(Self::Building(Box::new(CompoundAction::new(children))))
+        }
+    }
+
     pub fn parameter_change_action(res: &Resource<Param>, from: &[u8], to: &[u8]) -> Self {
         Self::Building(Box::new(IncrementalChangeAction::new(
             res.clone(),
@@ -654,6 +706,61 @@ impl UndoAction {
         ))])
     }
 
+    pub fn position_layer_action(layer: &Resource<Node>) -> UndoAction {
+        Self::Building(Box::new(CallResponseAction::new(
+            layer.clone(),
+            |layer, event| match event {
+                Lang::LayersEvent(LayersEvent::LayerPositioned(l, from, _)) if l == layer => {
+                    Some(*from)
+                }
+                _ => None,
+            },
+            |layer, from| {
+                vec![Lang::UserLayersEvent(UserLayersEvent::PositionLayer(
+                    layer.clone(),
+                    *from,
+                ))]
+            },
+        )))
+    }
+
+    pub fn group_layers_action(layers: &[Resource<Node>]) -> UndoAction {
+        Self::Building(Box::new(CallResponseAction::new(
+            layers.to_vec(),
+            |layers, event| match event {
+                Lang::LayersEvent(LayersEvent::LayersGrouped(res, _, members, _, _, _))
+                    if members.len() == layers.len()
+                        && members.iter().all(|m| layers.contains(m)) =>
+                {
+                    Some(res.clone())
+                }
+                _ => None,
+            },
+            |_, group| {
+                vec![Lang::UserLayersEvent(UserLayersEvent::UngroupLayers(
+                    group.clone(),
+                ))]
+            },
+        )))
+    }
+
+    pub fn ungroup_layers_action(group: &Resource<Node>) -> UndoAction {
+        Self::Building(Box::new(CallResponseAction::new(
+            group.clone(),
+            |group, event| match event {
+                Lang::LayersEvent(LayersEvent::LayersUngrouped(g, members)) if g == group => {
+                    Some(members.clone())
+                }
+                _ => None,
+            },
+            |_, members| {
+                vec![Lang::UserLayersEvent(UserLayersEvent::GroupLayers(
+                    members.clone(),
+                ))]
+            },
+        )))
+    }
+
     pub fn expose_parameter_action(param: &Resource<Param>) -> UndoAction {
         Self::Building(Box::new(CallResponseAction::new(
             param.clone(),
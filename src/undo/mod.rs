@@ -6,24 +6,31 @@ pub mod actions;
 
 pub use actions::*;
 
-pub fn start_undo_thread(broker: &mut broker::Broker<Lang>) -> thread::JoinHandle<()> {
+pub fn start_undo_thread(
+    broker: &mut broker::Broker<Lang>,
+    config: &config::Configuration,
+) -> thread::JoinHandle<()> {
     let (sender, receiver, disconnector) = broker.subscribe("undo");
+    let limit = config.undo_limit;
     thread::Builder::new()
         .name("undo".to_string())
         .spawn(move || {
             log::info!("Starting Undo manager");
 
-            let mut undo_stack = UndoStack::new();
+            let mut undo_stack = UndoStack::new(limit);
 
             for event in receiver {
                 match &*event {
                     Lang::UserIOEvent(UserIOEvent::OpenSurface(..))
+                    | Lang::GraphEvent(GraphEvent::Imported)
                     | Lang::UserIOEvent(UserIOEvent::NewSurface) => {
                         undo_stack.clear();
                     }
-                    Lang::UserIOEvent(UserIOEvent::Undo) => {
+                    Lang::UserIOEvent(UserIOEvent::Undo)
+                    | Lang::UserShortcutEvent(UserShortcutEvent::Undo) => {
                         if let Some(mut evs) = undo_stack.pop() {
                             log::debug!("Performing undo");
+                            undo_stack.record_redo(&evs);
                             for ev in evs.drain(0..) {
                                 sender.send(ev).unwrap()
                             }
@@ -31,6 +38,18 @@ pub fn start_undo_thread(broker: &mut broker::Broker<Lang>) -> thread::JoinHandl
                             log::debug!("Undo stack empty");
                         }
                     }
+                    Lang::UserIOEvent(UserIOEvent::Redo)
+                    | Lang::UserShortcutEvent(UserShortcutEvent::Redo) => {
+                        if let Some(mut evs) = undo_stack.pop_redo() {
+                            log::debug!("Performing redo");
+                            undo_stack.record_undo(&evs);
+                            for ev in evs.drain(0..) {
+                                sender.send(ev).unwrap()
+                            }
+                        } else {
+                            log::debug!("Redo stack empty");
+                        }
+                    }
                     event => undo_stack.notify_event(event),
                 }
             }
@@ -125,6 +144,9 @@ impl UndoAction {
             Lang::UserLayersEvent(UserLayersEvent::PushLayer(g, _, _)) => {
                 Some(Self::push_layer_action(g))
             }
+            Lang::UserLayersEvent(UserLayersEvent::DuplicateLayer(layer)) => {
+                Some(Self::push_layer_action(&layer.node_graph()))
+            }
             Lang::UserLayersEvent(UserLayersEvent::PushMask(parent, _)) => {
                 Some(Self::push_mask_action(parent))
             }
@@ -140,6 +162,15 @@ impl UndoAction {
             Lang::UserLayersEvent(UserLayersEvent::SetEnabled(layer, from, to)) => {
                 Some(Self::set_layer_enabled_action(layer, *from, *to))
             }
+            Lang::UserLayersEvent(UserLayersEvent::PositionLayer(layer, _)) => {
+                Some(Self::position_layer_action(layer))
+            }
+            Lang::UserLayersEvent(UserLayersEvent::GroupLayers(layers)) => {
+                Some(Self::group_layers_action(layers))
+            }
+            Lang::UserLayersEvent(UserLayersEvent::UngroupLayers(group)) => {
+                Some(Self::ungroup_layers_action(group))
+            }
             _ => None,
         }
     }
@@ -166,33 +197,71 @@ impl UndoAction {
     }
 }
 
+/// Determines which stack receives responses to events sent out by the undo
+/// manager itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Recording {
+    Undo,
+    Redo,
+}
+
+/// Undo stack, holding undo actions for user events, as well as redo actions
+/// for undone events. The undo stack is capped at a fixed limit, discarding
+/// the oldest actions first.
 pub struct UndoStack {
     stack: Vec<UndoAction>,
+    redo_stack: Vec<UndoAction>,
+    recording: Recording,
+    limit: usize,
 }
 
 impl UndoStack {
-    pub fn new() -> Self {
-        Self { stack: Vec::new() }
+    pub fn new(limit: usize) -> Self {
+        Self {
+            stack: Vec::new(),
+            redo_stack: Vec::new(),
+            recording: Recording::Undo,
+            limit,
+        }
     }
 
     pub fn clear(&mut self) {
-        self.stack.clear()
+        self.stack.clear();
+        self.redo_stack.clear();
+        self.recording = Recording::Undo;
     }
 
     /// Notify undo stack of a new event from the bus.
     pub fn notify_event(&mut self, event: &Lang) {
+        // Responses to a replayed undo step are used to build the redo action.
+        if self.recording == Recording::Redo {
+            if let Some(UndoAction::Building(builder)) = self.redo_stack.last_mut() {
+                if builder.more() && builder.next(event) {
+                    return;
+                }
+            }
+
+            // Any new user action invalidates the redo stack
+            if UndoAction::from_event(event).is_some() {
+                self.recording = Recording::Undo;
+                self.redo_stack.clear();
+            } else {
+                return;
+            }
+        }
+
         match self.stack.last_mut() {
             Some(UndoAction::Building(builder)) => {
                 if !builder.next(event) {
                     if let Some(new) = UndoAction::from_event(event) {
                         self.stack.last_mut().unwrap().build();
-                        self.stack.push(new);
+                        self.push(new);
                     }
                 }
             }
             _ => {
                 if let Some(new) = UndoAction::from_event(event) {
-                    self.stack.push(new);
+                    self.push(new);
                 }
             }
         }
@@ -202,6 +271,42 @@ impl UndoStack {
         }
     }
 
+    /// Push a new action onto the undo stack, clearing the redo stack and
+    /// discarding the oldest actions if the limit is exceeded.
+    fn push(&mut self, action: UndoAction) {
+        self.redo_stack.clear();
+        self.stack.push(action);
+
+        if self.stack.len() > self.limit {
+            let excess = self.stack.len() - self.limit;
+            self.stack.drain(0..excess);
+        }
+    }
+
+    /// Record the inverse of the given undo events as a redo action. Responses
+    /// to these events will be used to build the action.
+    pub fn record_redo(&mut self, events: &[Lang]) {
+        if let Some(action) = UndoAction::compound_action(events) {
+            self.redo_stack.push(action);
+            self.recording = Recording::Redo;
+        }
+    }
+
+    /// Record the inverse of the given redo events as an undo action, without
+    /// invalidating the remaining redo stack.
+    pub fn record_undo(&mut self, events: &[Lang]) {
+        if let Some(last) = self.stack.last_mut() {
+            last.build();
+        }
+
+        if let Some(action) = UndoAction::compound_action(events) {
+            let redo_stack = std::mem::take(&mut self.redo_stack);
+            self.push(action);
+            self.redo_stack = redo_stack;
+            self.recording = Recording::Undo;
+        }
+    }
+
     /// Pop an element off the undo stack.
     pub fn pop(&mut self) -> Option<Vec<Lang>> {
         // Attempt building the topmost action first
@@ -213,4 +318,14 @@ impl UndoStack {
             _ => None,
         })
     }
+
+    /// Pop an element off the redo stack.
+    pub fn pop_redo(&mut self) -> Option<Vec<Lang>> {
+        self.redo_stack.last_mut().and_then(|x| x.build());
+
+        self.redo_stack.pop().and_then(|x| match x {
+            UndoAction::Complete(evs) => Some(evs),
+            _ => None,
+        })
+    }
 }
@@ -1,3 +1,5 @@
+use serde_derive::{Deserialize, Serialize};
+
 /// Macro to include a shader SPIR-V file. Note that the file extension is not
 /// required!
 ///
@@ -58,6 +60,36 @@ where
     }
 }
 
+/// Low discrepancy sequences available for sampling, e.g. for supersampling
+/// jitter in renderers.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SamplingSequence {
+    Halton,
+    Sobol,
+}
+
+impl Default for SamplingSequence {
+    fn default() -> Self {
+        Self::Halton
+    }
+}
+
+impl SamplingSequence {
+    /// Create a new 2D sequence of this kind.
+    pub fn sequence_2d(self) -> Box<dyn SamplingSequence2D> {
+        match self {
+            Self::Halton => Box::new(HaltonSequence2D::default()),
+            Self::Sobol => Box::new(SobolSequence2D::default()),
+        }
+    }
+}
+
+/// An infinite sequence of 2D points in the unit square, that can be restarted.
+pub trait SamplingSequence2D: Iterator<Item = (f32, f32)> + Send {
+    /// Restart the sequence from its first point.
+    fn reset(&mut self);
+}
+
 /// An iterator over a 2D (2,3)-Halton sequence for QMC, except index 0 is added
 /// as (0.5, 0.5) to get a clean center sample first.
 pub struct HaltonSequence2D {
@@ -96,6 +128,12 @@ impl HaltonSequence2D {
     }
 }
 
+impl SamplingSequence2D for HaltonSequence2D {
+    fn reset(&mut self) {
+        self.idx = 0;
+    }
+}
+
 impl Iterator for HaltonSequence2D {
     type Item = (f32, f32);
 
@@ -112,6 +150,69 @@ impl Iterator for HaltonSequence2D {
     }
 }
 
+/// Direction numbers for the first two dimensions of the Sobol sequence, taken
+/// from the Joe-Kuo tables. The first dimension is the van der Corput sequence
+/// in base 2, the second has primitive polynomial x + 1 with m_1 = 1.
+static SOBOL_DIRECTIONS: [[u32; 32]; 2] = [
+    [
+        0x80000000, 0x40000000, 0x20000000, 0x10000000, 0x08000000, 0x04000000, 0x02000000,
+        0x01000000, 0x00800000, 0x00400000, 0x00200000, 0x00100000, 0x00080000, 0x00040000,
+        0x00020000, 0x00010000, 0x00008000, 0x00004000, 0x00002000, 0x00001000, 0x00000800,
+        0x00000400, 0x00000200, 0x00000100, 0x00000080, 0x00000040, 0x00000020, 0x00000010,
+        0x00000008, 0x00000004, 0x00000002, 0x00000001,
+    ],
+    [
+        0x80000000, 0xc0000000, 0xa0000000, 0xf0000000, 0x88000000, 0xcc000000, 0xaa000000,
+        0xff000000, 0x80800000, 0xc0c00000, 0xa0a00000, 0xf0f00000, 0x88880000, 0xcccc0000,
+        0xaaaa0000, 0xffff0000, 0x80008000, 0xc000c000, 0xa000a000, 0xf000f000, 0x88008800,
+        0xcc00cc00, 0xaa00aa00, 0xff00ff00, 0x80808080, 0xc0c0c0c0, 0xa0a0a0a0, 0xf0f0f0f0,
+        0x88888888, 0xcccccccc, 0xaaaaaaaa, 0xffffffff,
+    ],
+];
+
+/// An iterator over a 2D Sobol sequence for QMC, generated in Gray code order.
+/// Like the Halton sequence, index 0 is added as (0.5, 0.5) to get a clean
+/// center sample first.
+#[derive(Default)]
+pub struct SobolSequence2D {
+    idx: u32,
+    state: [u32; 2],
+}
+
+impl SamplingSequence2D for SobolSequence2D {
+    fn reset(&mut self) {
+        self.idx = 0;
+        self.state = [0, 0];
+    }
+}
+
+impl Iterator for SobolSequence2D {
+    type Item = (f32, f32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx == 0 {
+            self.idx += 1;
+            return Some((0.5, 0.5));
+        }
+
+        // Keep only 24 bits, such that the conversion to f32 is exact and
+        // points stay below 1
+        let point = (
+            (self.state[0] >> 8) as f32 / 16777216.0,
+            (self.state[1] >> 8) as f32 / 16777216.0,
+        );
+
+        // Advance by flipping the direction number of the lowest zero bit of
+        // the current index, wrapping around after 2^32 points.
+        let bit = ((self.idx - 1).trailing_ones() as usize).min(31);
+        self.state[0] ^= SOBOL_DIRECTIONS[0][bit];
+        self.state[1] ^= SOBOL_DIRECTIONS[1][bit];
+        self.idx = self.idx.wrapping_add(1).max(1);
+
+        Some(point)
+    }
+}
+
 pub fn f16_from_u8(sample: u8) -> u16 {
     half::f16::from_f32(sample as f32 / 256.0).to_bits()
 }
@@ -20,8 +20,18 @@ pub fn derive_parameters(input: proc_macro::TokenStream) -> proc_macro::TokenStr
     let field_names = fields.iter().filter_map(|x| x.ident.clone());
 
     let field_consts2 = field_consts.clone();
-    let field_names2 = fields.iter().filter_map(|x| x.ident.clone());
-    let field_tys = fields.iter().map(|x| x.ty.clone());
+
+    // Booleans are transmitted as 4 byte integers, like ParameterBool, and
+    // therefore require special treatment.
+    let field_setters = fields.iter().filter_map(|x| {
+        let name = x.ident.clone()?;
+        let ty = &x.ty;
+        Some(if is_bool(ty) {
+            quote! { self.#name = <u32>::from_data(data) != 0; }
+        } else {
+            quote! { self.#name = <#ty>::from_data(data); }
+        })
+    });
 
     let expanded = quote! {
         impl #name {
@@ -31,7 +41,7 @@ pub fn derive_parameters(input: proc_macro::TokenStream) -> proc_macro::TokenStr
         impl Parameters for #name {
             fn set_parameter(&mut self, field: &str, data: &[u8]) {
                 match field {
-                    #( Self::#field_consts2 => { self.#field_names2 = <#field_tys>::from_data(data); })*
+                    #( Self::#field_consts2 => { #field_setters })*
                     _ => panic!("Unknown field {}", field),
                 }
             }
@@ -40,3 +50,11 @@ pub fn derive_parameters(input: proc_macro::TokenStream) -> proc_macro::TokenStr
 
     proc_macro::TokenStream::from(expanded)
 }
+
+/// Determine whether a type is the primitive `bool`.
+fn is_bool(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(p) => p.qself.is_none() && p.path.is_ident("bool"),
+        _ => false,
+    }
+}